@@ -1,10 +1,11 @@
-use bytesize::ByteSize;
 use clap::Parser;
 use mina_indexer::{
-    block::{parser::BlockParser, BlockHash},
+    block::{parser::BlockParser, precomputed::ProtocolVersion, BlockHash},
+    network::Network,
     state::{ledger::genesis, IndexerMode, IndexerState},
     store::IndexerStore,
-    CANONICAL_UPDATE_THRESHOLD, MAINNET_TRANSITION_FRONTIER_K, PRUNE_INTERVAL_DEFAULT,
+    CANONICAL_UPDATE_THRESHOLD, MAINNET_CANONICAL_THRESHOLD, MAINNET_TRANSITION_FRONTIER_K,
+    PRUNE_INTERVAL_DEFAULT,
 };
 use std::{path::PathBuf, sync::Arc, thread};
 use tokio::{
@@ -71,12 +72,16 @@ async fn main() -> anyhow::Result<()> {
     let total_time = Instant::now();
     let mut state = IndexerState::new(
         mode,
+        Network::Mainnet,
+        ProtocolVersion::default(),
         BlockHash(GENESIS_HASH.to_string()),
         genesis_root.ledger,
         indexer_store,
         MAINNET_TRANSITION_FRONTIER_K,
         PRUNE_INTERVAL_DEFAULT,
         CANONICAL_UPDATE_THRESHOLD,
+        MAINNET_CANONICAL_THRESHOLD,
+        false,
     )
     .unwrap();
 
@@ -216,35 +221,9 @@ async fn main() -> anyhow::Result<()> {
     );
 
     println!("\n~~~ DB stats ~~~");
-    println!(
-        "Estimate number of keys:    {:?}",
-        state
-            .indexer_store
-            .as_ref()
-            .unwrap()
-            .estimate_live_data_size()
-    );
-    println!(
-        "Estimate live data size:    {:?}",
-        ByteSize::b(
-            state
-                .indexer_store
-                .as_ref()
-                .unwrap()
-                .estimate_live_data_size()
-        )
-    );
-    println!(
-        "Current size all memtables: {:?}",
-        ByteSize::b(
-            state
-                .indexer_store
-                .as_ref()
-                .unwrap()
-                .cur_size_all_mem_tables()
-        )
-    );
-    println!("{}", state.indexer_store.as_ref().unwrap().db_stats());
+    if let Some(db_stats) = state.indexer_store.as_ref().unwrap().stats()? {
+        println!("{db_stats}");
+    }
 
     println!("Initial ingestion complete!");
     println!("Watching {} now", watch_dir.display());
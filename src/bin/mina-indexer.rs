@@ -3,7 +3,7 @@ use std::sync::Arc;
 use clap::{Parser, Subcommand};
 use mina_indexer::{
     client,
-    server::{self, handle_command_line_arguments},
+    server::{self, daemon, handle_command_line_arguments, runtime::QueryPool, ServerCommand},
     store::IndexerStore,
 };
 
@@ -21,21 +21,70 @@ enum IndexerCommand {
     Server(server::ServerArgs),
     /// Client commands
     Client {
+        #[command(flatten)]
+        connect_args: client::ConnectArgs,
         #[command(subcommand)]
         args: client::ClientCli,
     },
+    /// Rebuild derived indexes from the blocks in an existing database
+    Reindex(server::ReindexArgs),
+    /// Offline database commands
+    Db {
+        #[command(subcommand)]
+        command: server::DbCommand,
+    },
 }
 
-#[tokio::main]
-pub async fn main() -> anyhow::Result<()> {
+pub fn main() -> anyhow::Result<()> {
     match Cli::parse().command {
-        IndexerCommand::Client { args } => client::run(&args).await,
+        IndexerCommand::Client { connect_args, args } => {
+            tokio::runtime::Runtime::new()?.block_on(client::run(&args, &connect_args))
+        }
+        IndexerCommand::Reindex(args) => {
+            tokio::runtime::Runtime::new()?.block_on(server::reindex(args))
+        }
+        IndexerCommand::Db { command } => {
+            tokio::runtime::Runtime::new()?.block_on(server::db(command))
+        }
         IndexerCommand::Server(args) => {
-            let config = handle_command_line_arguments(args).await?;
-            let db = Arc::new(IndexerStore::new(&config.database_dir)?);
-            tokio::spawn(server::run(config, db.clone()));
-            mina_indexer::gql::start_gql(db).await.unwrap();
-            Ok(())
+            if let Some(ServerCommand::Stop(stop_args)) = &args.command {
+                return server::stop(stop_args);
+            }
+
+            // the server subcommand's args override those of its config file
+            let args = args.with_config_file(
+                std::env::args_os()
+                    .skip_while(|arg| arg != "server")
+                    .skip(1),
+            )?;
+
+            // only the forking thread survives, so the daemon detaches before any others start
+            let pid_file = args.daemonize()?;
+
+            // queries' heavy store work runs on its own threads, apart from block ingestion
+            let runtime_config = args.runtime_configuration()?;
+            let runtime = runtime_config.build_runtime()?;
+            let query_runtime = runtime_config.build_query_runtime()?;
+            let query_pool = QueryPool::new(query_runtime.handle().clone());
+            let stopped = runtime.block_on(async move {
+                let config = handle_command_line_arguments(args).await?;
+                let db = if config.in_memory {
+                    IndexerStore::new_in_memory()
+                } else {
+                    IndexerStore::new_with_tuning(&config.database_dir, &config.rocksdb_tuning)?
+                };
+                let db = Arc::new(db.with_value_codec(config.value_codec));
+                // exit once the server has shut down
+                let server = tokio::spawn(server::run(config, db.clone(), query_pool));
+                tokio::select! {
+                    stopped = server => stopped?,
+                    stopped = mina_indexer::gql::start_gql(db) => Ok(stopped?),
+                }
+            });
+            if let Some(pid_file) = pid_file {
+                daemon::remove_pid_file(&pid_file);
+            }
+            stopped
         }
     }
 }
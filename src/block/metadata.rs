@@ -0,0 +1,57 @@
+use crate::{
+    block::precomputed::PrecomputedBlock,
+    state::ledger::{
+        command::{SignedCommand, UserCommandWithStatus},
+        internal_command::InternalCommand,
+        snark_work::SnarkWorkSummary,
+    },
+};
+use mina_serialization_types::staged_ledger_diff::UserCommand;
+use serde::{Deserialize, Serialize};
+
+/// Per-block totals, stored so analytics don't have to deserialize full blocks
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub struct BlockMetadata {
+    /// Number of user commands, including failed ones
+    pub num_commands: u32,
+    /// Total fees of the user commands
+    pub total_fees: u64,
+    /// Coinbase, including the parts paid to SNARK workers
+    pub coinbase_amount: u64,
+    /// Number of completed SNARK works
+    pub num_snarks: u32,
+}
+
+impl BlockMetadata {
+    pub fn from_precomputed_block(precomputed_block: &PrecomputedBlock) -> Self {
+        let commands = precomputed_block.commands();
+        let total_fees = commands
+            .iter()
+            .map(|command| {
+                let UserCommand::SignedCommand(signed_command) =
+                    UserCommandWithStatus(command.clone()).data();
+                let fee: u64 = SignedCommand(signed_command)
+                    .payload_common()
+                    .fee
+                    .inner()
+                    .inner();
+                fee
+            })
+            .sum();
+        let coinbase_amount = InternalCommand::from_precomputed_block(precomputed_block)
+            .into_iter()
+            .map(|internal_command| match internal_command {
+                InternalCommand::Coinbase { amount, .. }
+                | InternalCommand::FeeTransferViaCoinbase { amount, .. } => amount.0,
+                InternalCommand::FeeTransfer { .. } => 0,
+            })
+            .sum();
+
+        Self {
+            num_commands: commands.len() as u32,
+            total_fees,
+            coinbase_amount,
+            num_snarks: SnarkWorkSummary::from_precomputed_block(precomputed_block).len() as u32,
+        }
+    }
+}
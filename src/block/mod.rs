@@ -1,17 +1,25 @@
-use std::{ffi::OsStr, path::Path};
+use std::{
+    ffi::OsStr,
+    path::{Path, PathBuf},
+};
 
 use mina_serialization_types::{common::Base58EncodableVersionedType, v1::HashV1, version_bytes};
 use serde::{Deserialize, Serialize};
 use tokio::io::AsyncReadExt;
 
-use self::precomputed::{BlockLogContents, PrecomputedBlock};
+use self::precomputed::{BlockLogContents, PrecomputedBlock, PrecomputedBlockHeader};
+use crate::network::Network;
 
+pub mod metadata;
 pub mod parser;
 pub mod precomputed;
+pub mod production;
 pub mod receiver;
 pub mod signed_command;
 pub mod store;
 
+/// Witness tree node data, only the hashes, heights, and fork choice data of the block,
+/// its commands and ledger diff are fetched from the store when needed
 #[derive(Hash, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub struct Block {
     pub parent_hash: BlockHash,
@@ -19,6 +27,9 @@ pub struct Block {
     pub height: u32,
     pub blockchain_length: Option<u32>,
     pub global_slot_since_genesis: u32,
+    /// Blake2b digest of the block's last VRF output, all zeros for roots without a
+    /// precomputed block
+    pub last_vrf_output_digest: [u8; 32],
 }
 
 #[derive(Hash, PartialEq, Eq, Clone, Serialize, Deserialize)]
@@ -70,6 +81,7 @@ impl Block {
                 .t
                 .t,
             blockchain_length: precomputed_block.blockchain_length,
+            last_vrf_output_digest: precomputed_block.last_vrf_output_digest(),
         }
     }
 
@@ -116,25 +128,40 @@ impl BlockWithoutHeight {
             blockchain_length: precomputed_block.blockchain_length,
         }
     }
+
+    pub fn from_header(header: &PrecomputedBlockHeader) -> Self {
+        Self {
+            parent_hash: header.parent_hash(),
+            state_hash: BlockHash(header.state_hash.clone()),
+            global_slot_since_genesis: header.global_slot_since_genesis(),
+            blockchain_length: header.blockchain_length,
+        }
+    }
 }
 
 impl std::cmp::PartialOrd for Block {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        if self.state_hash == other.state_hash {
-            Some(std::cmp::Ordering::Equal)
-        } else if self.height > other.height
-            || self.height == other.height && self.state_hash.0 > other.state_hash.0
-        {
-            Some(std::cmp::Ordering::Greater)
-        } else {
-            Some(std::cmp::Ordering::Less)
-        }
+        Some(self.cmp(other))
     }
 }
 
+/// Mina's short-range fork choice, the greater block is the preferred tip: the longer
+/// chain, then the greater last VRF output digest, then the greater state hash
+///
+/// Forks in the witness tree are within the transition frontier of each other, so the
+/// long-range rule comparing the chains' min window densities never applies
 impl std::cmp::Ord for Block {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        self.partial_cmp(other).unwrap()
+        if self.state_hash == other.state_hash {
+            return std::cmp::Ordering::Equal;
+        }
+        self.height
+            .cmp(&other.height)
+            .then_with(|| {
+                self.last_vrf_output_digest
+                    .cmp(&other.last_vrf_output_digest)
+            })
+            .then_with(|| self.state_hash.0.cmp(&other.state_hash.0))
     }
 }
 
@@ -171,8 +198,14 @@ impl std::fmt::Debug for BlockHash {
     }
 }
 
-pub async fn parse_file(filename: &Path) -> anyhow::Result<PrecomputedBlock> {
+/// Parses the precomputed block file, `network` is the network the indexer is configured
+/// for, see [Network::from_file_name]
+pub async fn parse_file(filename: &Path, network: &Network) -> anyhow::Result<PrecomputedBlock> {
     if is_valid_block_file(filename) {
+        let network = Network::from_file_name(
+            filename.file_name().expect("filename already checked"),
+            network,
+        );
         let blockchain_length =
             get_blockchain_length(filename.file_name().expect("filename already checked"));
         let state_hash = get_state_hash(filename.file_name().expect("filename already checked"))
@@ -184,6 +217,7 @@ pub async fn parse_file(filename: &Path) -> anyhow::Result<PrecomputedBlock> {
         log_file.read_to_end(&mut log_file_contents).await?;
 
         let precomputed_block = PrecomputedBlock::from_log_contents(BlockLogContents {
+            network,
             state_hash,
             blockchain_length,
             contents: log_file_contents,
@@ -201,6 +235,22 @@ pub async fn parse_file(filename: &Path) -> anyhow::Result<PrecomputedBlock> {
     }
 }
 
+/// Path of the block's file in the blocks directory, found by the state hash in its name
+pub fn find_block_file(
+    blocks_dir: &Path,
+    state_hash: &BlockHash,
+) -> anyhow::Result<Option<PathBuf>> {
+    for entry in std::fs::read_dir(blocks_dir)? {
+        let path = entry?.path();
+        if is_valid_block_file(&path)
+            && path.file_name().and_then(get_state_hash).as_ref() == Some(&state_hash.0)
+        {
+            return Ok(Some(path));
+        }
+    }
+    Ok(None)
+}
+
 /// Extracts a state hash from an OS file name
 pub fn get_state_hash(file_name: &OsStr) -> Option<String> {
     let last_part = file_name.to_str()?.split('-').last()?.to_string();
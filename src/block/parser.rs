@@ -3,6 +3,7 @@ use crate::{
         get_blockchain_length, get_state_hash, is_valid_block_file,
         precomputed::{BlockLogContents, PrecomputedBlock},
     },
+    network::Network,
     BLOCK_REPORTING_FREQ_NUM, MAINNET_CANONICAL_THRESHOLD,
 };
 use glob::glob;
@@ -13,7 +14,7 @@ use std::{
     time::Instant,
     vec::IntoIter,
 };
-use tokio::io::AsyncReadExt;
+use tokio::{io::AsyncReadExt, sync::watch};
 use tracing::{debug, info};
 
 pub enum SearchRecursion {
@@ -31,15 +32,55 @@ pub struct BlockParser {
     pub recursion: SearchRecursion,
     canonical_paths: IntoIter<PathBuf>,
     successive_paths: IntoIter<PathBuf>,
+    /// No block is parsed while this is `true`
+    paused: Option<watch::Receiver<bool>>,
+    /// Network the indexer is configured for, see [Network::from_file_name]
+    network: Network,
 }
 
 impl BlockParser {
     pub fn new(blocks_dir: &Path) -> anyhow::Result<Self> {
-        Self::new_internal(blocks_dir, SearchRecursion::None)
+        Self::new_internal(
+            blocks_dir,
+            SearchRecursion::None,
+            0,
+            MAINNET_CANONICAL_THRESHOLD,
+        )
     }
 
     pub fn new_recursive(blocks_dir: &Path) -> anyhow::Result<Self> {
-        Self::new_internal(blocks_dir, SearchRecursion::Recursive)
+        Self::new_internal(
+            blocks_dir,
+            SearchRecursion::Recursive,
+            0,
+            MAINNET_CANONICAL_THRESHOLD,
+        )
+    }
+
+    /// Only parses the blocks of length at least `min_length`, e.g. those newer
+    /// than the state restored from a database
+    pub fn new_with_min_length(blocks_dir: &Path, min_length: u32) -> anyhow::Result<Self> {
+        Self::new_internal(
+            blocks_dir,
+            SearchRecursion::None,
+            min_length,
+            MAINNET_CANONICAL_THRESHOLD,
+        )
+    }
+
+    /// Only parses the blocks of length at least `min_length`, the canonical chain is found
+    /// with the given number of confirmations
+    pub fn new_with_canonical_threshold(
+        blocks_dir: &Path,
+        canonical_threshold: u32,
+        min_length: u32,
+    ) -> anyhow::Result<Self> {
+        Self::new_internal(
+            blocks_dir,
+            SearchRecursion::None,
+            min_length,
+            canonical_threshold,
+        )
     }
 
     pub fn new_testing(blocks_dir: &Path) -> anyhow::Result<Self> {
@@ -57,6 +98,8 @@ impl BlockParser {
                 recursion: SearchRecursion::None,
                 canonical_paths: vec![].into_iter(),
                 successive_paths: paths.into_iter(),
+                paused: None,
+                network: Network::default(),
             })
         } else {
             Err(anyhow::Error::msg(format!(
@@ -65,7 +108,12 @@ impl BlockParser {
         }
     }
 
-    fn new_internal(blocks_dir: &Path, recursion: SearchRecursion) -> anyhow::Result<Self> {
+    fn new_internal(
+        blocks_dir: &Path,
+        recursion: SearchRecursion,
+        min_length: u32,
+        canonical_threshold: u32,
+    ) -> anyhow::Result<Self> {
         debug!("Building parser");
         if blocks_dir.exists() {
             let pattern = match &recursion {
@@ -76,6 +124,7 @@ impl BlockParser {
             let mut paths: Vec<PathBuf> = glob(&pattern)
                 .expect("Failed to read glob pattern")
                 .filter_map(|x| x.ok())
+                .filter(|path| length_from_path_or_max(path) >= min_length)
                 .collect();
 
             let mut successive_paths = vec![];
@@ -126,7 +175,7 @@ impl BlockParser {
                     }
                 }
 
-                if last_contiguous_idx < MAINNET_CANONICAL_THRESHOLD as usize {
+                if last_contiguous_idx < canonical_threshold as usize {
                     info!("No canoncial blocks can be confidently found. Adding all blocks to the witness tree.");
                     return Ok(Self {
                         num_canonical: 0,
@@ -135,6 +184,8 @@ impl BlockParser {
                         recursion,
                         canonical_paths: vec![].into_iter(),
                         successive_paths: paths.into_iter(),
+                        paused: None,
+                        network: Network::default(),
                     });
                 }
 
@@ -144,7 +195,7 @@ impl BlockParser {
                 let mut curr_path = paths.get(curr_length_idx).unwrap();
                 let time = Instant::now();
 
-                for _ in 1..=MAINNET_CANONICAL_THRESHOLD {
+                for _ in 1..=canonical_threshold {
                     if curr_start_idx > 0 {
                         let prev_length_idx = length_start_indices[curr_start_idx - 1];
 
@@ -236,6 +287,8 @@ impl BlockParser {
                 recursion,
                 canonical_paths: canonical_paths.into_iter(),
                 successive_paths: successive_paths.into_iter(),
+                paused: None,
+                network: Network::default(),
             })
         } else {
             Err(anyhow::Error::msg(format!(
@@ -244,21 +297,52 @@ impl BlockParser {
         }
     }
 
+    /// Parsing waits while `paused` is `true`
+    pub fn with_pause(mut self, paused: watch::Receiver<bool>) -> Self {
+        self.paused = Some(paused);
+        self
+    }
+
+    /// Blocks are parsed for the configured `network`, see [Network::from_file_name]
+    pub fn with_network(mut self, network: Network) -> Self {
+        self.network = network;
+        self
+    }
+
+    async fn wait_while_paused(&mut self) {
+        if let Some(paused) = self.paused.as_mut() {
+            while *paused.borrow_and_update() {
+                debug!("Block parsing paused");
+                if paused.changed().await.is_err() {
+                    break;
+                }
+            }
+        }
+    }
+
     /// Traverses the internal paths. First canonical, then successive.
     pub async fn next(&mut self) -> anyhow::Result<Option<PrecomputedBlock>> {
+        self.wait_while_paused().await;
         if let Some(next_path) = self.canonical_paths.next() {
-            return Self::handle_path(&next_path).await;
+            return Self::handle_path(&next_path, &self.network).await;
         }
 
         if let Some(next_path) = self.successive_paths.next() {
-            return Self::handle_path(&next_path).await;
+            return Self::handle_path(&next_path, &self.network).await;
         }
 
         Ok(None)
     }
 
-    async fn handle_path(path: &Path) -> anyhow::Result<Option<PrecomputedBlock>> {
+    async fn handle_path(
+        path: &Path,
+        network: &Network,
+    ) -> anyhow::Result<Option<PrecomputedBlock>> {
         if is_valid_block_file(path) {
+            let network = Network::from_file_name(
+                path.file_name().expect("filename already checked"),
+                network,
+            );
             let blockchain_length =
                 get_blockchain_length(path.file_name().expect("filename already checked"));
             let state_hash = get_state_hash(path.file_name().expect("filename already checked"))
@@ -270,6 +354,7 @@ impl BlockParser {
             log_file.read_to_end(&mut log_file_contents).await?;
 
             let precomputed_block = PrecomputedBlock::from_log_contents(BlockLogContents {
+                network,
                 state_hash,
                 blockchain_length,
                 contents: log_file_contents,
@@ -316,6 +401,10 @@ impl BlockParser {
 
     pub async fn parse_file(&mut self, filename: &Path) -> anyhow::Result<PrecomputedBlock> {
         if is_valid_block_file(filename) {
+            let network = Network::from_file_name(
+                filename.file_name().expect("filename already checked"),
+                &self.network,
+            );
             let blockchain_length =
                 get_blockchain_length(filename.file_name().expect("filename already checked"));
             let state_hash =
@@ -328,6 +417,7 @@ impl BlockParser {
             log_file.read_to_end(&mut log_file_contents).await?;
 
             let precomputed_block = PrecomputedBlock::from_log_contents(BlockLogContents {
+                network,
                 state_hash,
                 blockchain_length,
                 contents: log_file_contents,
@@ -1,30 +1,50 @@
-use crate::state::{
-    ledger::{
+use crate::{
+    block::BlockHash,
+    network::Network,
+    state::ledger::{
         command::{PaymentPayload, SignedCommand, UserCommandWithStatus},
         public_key::PublicKey,
     },
-    Canonicity,
 };
+use blake2::{digest::consts::U32, Blake2b, Digest};
 use mina_serialization_types::{
     json::DeltaTransitionChainProofJson,
     protocol_state::{ProtocolState, ProtocolStateJson},
     protocol_state_proof::ProtocolStateProofBase64Json,
     staged_ledger_diff::{
-        self, SignedCommandPayloadBody, StagedLedgerDiff, StagedLedgerDiffJson, StakeDelegation,
+        self, CoinBase, SignedCommandPayloadBody, StagedLedgerDiff, StagedLedgerDiffJson,
+        StakeDelegation,
+    },
+    v1::{
+        DeltaTransitionChainProof, ProtocolStateProofV1, TransactionSnarkWorkV1,
+        UserCommandWithStatusV1,
     },
-    v1::{DeltaTransitionChainProof, ProtocolStateProofV1, UserCommandWithStatusV1},
 };
 use serde::{Deserialize, Serialize};
+use std::str::FromStr;
 
 pub struct BlockLogContents {
+    pub(crate) network: Network,
     pub(crate) state_hash: String,
     pub(crate) blockchain_length: Option<u32>,
     pub(crate) contents: Vec<u8>,
 }
 
+/// Protocol version of a precomputed block
+///
+/// Blocks which predate the `protocol_version` field are version `1.0.0`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ProtocolVersion {
+    pub transaction: u32,
+    pub network: u32,
+    pub patch: u32,
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 pub struct BlockLog {
     scheduled_time: String,
+    #[serde(default)]
+    protocol_version: Option<ProtocolVersion>,
     protocol_state: ProtocolStateJson,
     protocol_state_proof: ProtocolStateProofBase64Json,
     staged_ledger_diff: StagedLedgerDiffJson,
@@ -33,9 +53,10 @@ pub struct BlockLog {
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct PrecomputedBlock {
-    pub canonicity: Option<Canonicity>,
+    pub network: Network,
     pub state_hash: String,
     pub scheduled_time: String,
+    pub protocol_version: ProtocolVersion,
     pub protocol_state: ProtocolState,
     pub blockchain_length: Option<u32>,
     pub protocol_state_proof: ProtocolStateProofV1,
@@ -45,20 +66,23 @@ pub struct PrecomputedBlock {
 
 impl PrecomputedBlock {
     pub fn from_log_contents(log_contents: BlockLogContents) -> serde_json::Result<Self> {
+        let network = log_contents.network;
         let state_hash = log_contents.state_hash;
         let blockchain_length = log_contents.blockchain_length;
         let str = String::from_utf8_lossy(&log_contents.contents);
         let BlockLog {
             scheduled_time,
+            protocol_version,
             protocol_state,
             protocol_state_proof,
             staged_ledger_diff,
             delta_transition_chain_proof,
         } = serde_json::from_str::<BlockLog>(&str).unwrap();
         Ok(Self {
-            canonicity: None,
+            network,
             state_hash,
             scheduled_time,
+            protocol_version: protocol_version.unwrap_or_default(),
             blockchain_length,
             protocol_state: protocol_state.into(),
             protocol_state_proof: protocol_state_proof.into(),
@@ -78,6 +102,62 @@ impl PrecomputedBlock {
             .commands
     }
 
+    pub fn completed_works(&self) -> Vec<TransactionSnarkWorkV1> {
+        self.staged_ledger_diff
+            .diff
+            .clone()
+            .inner()
+            .0
+            .inner()
+            .inner()
+            .completed_works
+    }
+
+    pub fn coinbase(&self) -> CoinBase {
+        self.staged_ledger_diff
+            .diff
+            .clone()
+            .inner()
+            .0
+            .inner()
+            .inner()
+            .coinbase
+            .inner()
+    }
+
+    pub fn supercharge_coinbase(&self) -> bool {
+        self.protocol_state
+            .body
+            .t
+            .t
+            .consensus_state
+            .t
+            .t
+            .supercharge_coinbase
+    }
+
+    /// Block reward in nanomina on the block's network
+    pub fn coinbase_amount(&self) -> u64 {
+        self.network.coinbase_amount(self.supercharge_coinbase())
+    }
+
+    /// Blake2b digest of the last VRF output, the tie-breaker of Mina's fork choice
+    /// between chains of the same length
+    pub fn last_vrf_output_digest(&self) -> [u8; 32] {
+        let last_vrf_output = &self
+            .protocol_state
+            .body
+            .t
+            .t
+            .consensus_state
+            .t
+            .t
+            .last_vrf_output
+            .t
+            .0;
+        Blake2b::<U32>::digest(last_vrf_output).into()
+    }
+
     pub fn block_public_keys(&self) -> Vec<PublicKey> {
         let mut public_keys: Vec<PublicKey> = vec![];
         let consenesus_state = self
@@ -123,30 +203,213 @@ impl PrecomputedBlock {
         public_keys
     }
 
+    pub fn block_creator(&self) -> PublicKey {
+        block_creator(&self.protocol_state)
+    }
+
+    pub fn coinbase_receiver(&self) -> PublicKey {
+        coinbase_receiver(&self.protocol_state)
+    }
+
+    pub fn genesis_state_hash(&self) -> BlockHash {
+        BlockHash::from_hashv1(self.protocol_state.body.t.t.genesis_state_hash.clone())
+    }
+
     pub fn global_slot_since_genesis(&self) -> u32 {
-        self.protocol_state
-            .body
-            .t
-            .t
-            .consensus_state
-            .t
-            .t
-            .global_slot_since_genesis
-            .t
-            .t
+        global_slot_since_genesis(&self.protocol_state)
+    }
+
+    /// Epoch of the block's global slot
+    pub fn epoch(&self) -> u32 {
+        self.global_slot_since_genesis() / self.network.slots_per_epoch()
     }
 
     pub fn timestamp(&self) -> u64 {
-        self.protocol_state
-            .body
-            .clone()
-            .inner()
-            .inner()
-            .blockchain_state
-            .inner()
-            .inner()
-            .timestamp
-            .inner()
-            .inner()
+        timestamp(&self.protocol_state)
+    }
+
+    /// Slim part of the block, everything but the proofs and staged ledger diff
+    pub fn header(&self) -> PrecomputedBlockHeader {
+        PrecomputedBlockHeader {
+            network: self.network.clone(),
+            state_hash: self.state_hash.clone(),
+            scheduled_time: self.scheduled_time.clone(),
+            protocol_version: self.protocol_version,
+            protocol_state: self.protocol_state.clone(),
+            blockchain_length: self.blockchain_length,
+        }
+    }
+
+    /// Heavyweight part of the block, serializes like [PrecomputedBlockBody]
+    pub(crate) fn body_ref(&self) -> PrecomputedBlockBodyRef<'_> {
+        PrecomputedBlockBodyRef {
+            protocol_state_proof: &self.protocol_state_proof,
+            staged_ledger_diff: &self.staged_ledger_diff,
+            delta_transition_chain_proof: &self.delta_transition_chain_proof,
+        }
+    }
+
+    pub fn from_parts(header: PrecomputedBlockHeader, body: PrecomputedBlockBody) -> Self {
+        Self {
+            network: header.network,
+            state_hash: header.state_hash,
+            scheduled_time: header.scheduled_time,
+            protocol_version: header.protocol_version,
+            protocol_state: header.protocol_state,
+            blockchain_length: header.blockchain_length,
+            protocol_state_proof: body.protocol_state_proof,
+            staged_ledger_diff: body.staged_ledger_diff,
+            delta_transition_chain_proof: body.delta_transition_chain_proof,
+        }
+    }
+}
+
+/// Precomputed block without its proofs and staged ledger diff, stored
+/// separately so chain walks and canonicity updates stay cheap
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PrecomputedBlockHeader {
+    pub network: Network,
+    pub state_hash: String,
+    pub scheduled_time: String,
+    pub protocol_version: ProtocolVersion,
+    pub protocol_state: ProtocolState,
+    pub blockchain_length: Option<u32>,
+}
+
+impl PrecomputedBlockHeader {
+    pub fn parent_hash(&self) -> BlockHash {
+        BlockHash::from_hashv1(self.protocol_state.previous_state_hash.clone())
+    }
+
+    pub fn block_creator(&self) -> PublicKey {
+        block_creator(&self.protocol_state)
+    }
+
+    pub fn coinbase_receiver(&self) -> PublicKey {
+        coinbase_receiver(&self.protocol_state)
+    }
+
+    pub fn timestamp(&self) -> u64 {
+        timestamp(&self.protocol_state)
+    }
+
+    pub fn global_slot_since_genesis(&self) -> u32 {
+        global_slot_since_genesis(&self.protocol_state)
+    }
+
+    /// Epoch of the block's global slot
+    pub fn epoch(&self) -> u32 {
+        self.global_slot_since_genesis() / self.network.slots_per_epoch()
+    }
+}
+
+/// Proofs and staged ledger diff of a precomputed block
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PrecomputedBlockBody {
+    pub protocol_state_proof: ProtocolStateProofV1,
+    pub staged_ledger_diff: StagedLedgerDiff,
+    pub delta_transition_chain_proof: DeltaTransitionChainProof,
+}
+
+/// Borrowed [PrecomputedBlockBody], so the body is serialized without being cloned
+#[derive(Debug, Serialize)]
+pub(crate) struct PrecomputedBlockBodyRef<'a> {
+    protocol_state_proof: &'a ProtocolStateProofV1,
+    staged_ledger_diff: &'a StagedLedgerDiff,
+    delta_transition_chain_proof: &'a DeltaTransitionChainProof,
+}
+
+fn block_creator(protocol_state: &ProtocolState) -> PublicKey {
+    protocol_state
+        .body
+        .t
+        .t
+        .consensus_state
+        .t
+        .t
+        .block_creator
+        .clone()
+        .into()
+}
+
+fn coinbase_receiver(protocol_state: &ProtocolState) -> PublicKey {
+    protocol_state
+        .body
+        .t
+        .t
+        .consensus_state
+        .t
+        .t
+        .coinbase_receiver
+        .clone()
+        .into()
+}
+
+fn global_slot_since_genesis(protocol_state: &ProtocolState) -> u32 {
+    protocol_state
+        .body
+        .t
+        .t
+        .consensus_state
+        .t
+        .t
+        .global_slot_since_genesis
+        .t
+        .t
+}
+
+fn timestamp(protocol_state: &ProtocolState) -> u64 {
+    protocol_state
+        .body
+        .clone()
+        .inner()
+        .inner()
+        .blockchain_state
+        .inner()
+        .inner()
+        .timestamp
+        .inner()
+        .inner()
+}
+
+impl ProtocolVersion {
+    /// Blocks are compatible if their transaction versions agree
+    pub fn is_compatible(&self, other: &Self) -> bool {
+        self.transaction == other.transaction
+    }
+}
+
+impl Default for ProtocolVersion {
+    fn default() -> Self {
+        Self {
+            transaction: 1,
+            network: 0,
+            patch: 0,
+        }
+    }
+}
+
+impl std::fmt::Display for ProtocolVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}.{}", self.transaction, self.network, self.patch)
+    }
+}
+
+impl FromStr for ProtocolVersion {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<&str> = s.split('.').collect();
+        if parts.len() != 3 {
+            anyhow::bail!(
+                "Invalid protocol version: {s}, expected <transaction>.<network>.<patch>"
+            );
+        }
+
+        Ok(Self {
+            transaction: u32::from_str(parts[0])?,
+            network: u32::from_str(parts[1])?,
+            patch: u32::from_str(parts[2])?,
+        })
     }
 }
@@ -0,0 +1,76 @@
+use super::precomputed::PrecomputedBlockHeader;
+use serde::{Deserialize, Serialize};
+
+/// Inclusive range of blocks, by blockchain length or by epoch
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BlockRange {
+    Heights { start: u32, end: u32 },
+    Epochs { start: u32, end: u32 },
+}
+
+impl BlockRange {
+    pub fn contains(&self, header: &PrecomputedBlockHeader) -> bool {
+        match *self {
+            Self::Heights { start, end } => header
+                .blockchain_length
+                .map_or(false, |length| start <= length && length <= end),
+            Self::Epochs { start, end } => (start..=end).contains(&header.epoch()),
+        }
+    }
+}
+
+/// Blocks produced by a block creator, by canonicity
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockProduction {
+    pub creator: String,
+    pub canonical: u32,
+    pub orphaned: u32,
+    /// Neither canonical nor orphaned yet
+    pub pending: u32,
+}
+
+impl BlockProduction {
+    pub fn new(creator: String) -> Self {
+        Self {
+            creator,
+            canonical: 0,
+            orphaned: 0,
+            pending: 0,
+        }
+    }
+
+    pub fn total(&self) -> u32 {
+        self.canonical + self.orphaned + self.pending
+    }
+}
+
+/// Slots of an epoch of the canonical chain, with and without a canonical block
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EpochSlots {
+    pub epoch: u32,
+    /// Slots with a canonical block
+    pub canonical: u32,
+    /// Slots without a canonical block
+    pub skipped: u32,
+    /// The skipped global slots, if listed
+    pub skipped_slots: Vec<u32>,
+}
+
+impl EpochSlots {
+    pub fn new(epoch: u32) -> Self {
+        Self {
+            epoch,
+            canonical: 0,
+            skipped: 0,
+            skipped_slots: vec![],
+        }
+    }
+
+    /// Fraction of the epoch's counted slots without a canonical block
+    pub fn skip_rate(&self) -> f64 {
+        match self.canonical + self.skipped {
+            0 => 0.0,
+            slots => self.skipped as f64 / slots as f64,
+        }
+    }
+}
@@ -1,6 +1,12 @@
-use crate::block::{parse_file, parser::BlockParser, precomputed::PrecomputedBlock};
+use crate::{
+    block::{parse_file, parser::BlockParser, precomputed::PrecomputedBlock},
+    network::Network,
+};
 use async_priority_channel as priority;
-use std::path::{Path, PathBuf};
+use std::{
+    collections::{HashSet, VecDeque},
+    path::{Path, PathBuf},
+};
 use tokio::sync::{
     mpsc,
     watch::{self, Sender},
@@ -18,8 +24,41 @@ use watchexec::{
     fs::{worker, WorkingData},
 };
 
+/// Number of the most recently received state hashes remembered to skip duplicates, a block
+/// copied to several watched directories arrives in all of them within moments
+const RECEIVED_STATE_HASHES_CAPACITY: usize = 1000;
+
+/// The most recently received state hashes, the oldest are forgotten first
+#[derive(Debug, Default)]
+struct ReceivedStateHashes {
+    state_hashes: HashSet<String>,
+    order: VecDeque<String>,
+}
+
+impl ReceivedStateHashes {
+    /// Remembers the state hash, returns whether it's new
+    fn insert(&mut self, state_hash: String) -> bool {
+        if !self.state_hashes.insert(state_hash.clone()) {
+            return false;
+        }
+        self.order.push_back(state_hash);
+        if self.order.len() > RECEIVED_STATE_HASHES_CAPACITY {
+            if let Some(oldest) = self.order.pop_front() {
+                self.state_hashes.remove(&oldest);
+            }
+        }
+        true
+    }
+}
+
 pub struct BlockReceiver {
     parsers: Vec<BlockParser>,
+    watched_directories: Vec<PathBuf>,
+    /// State hashes of recently received blocks, blocks seen in multiple directories are only
+    /// received once
+    received_state_hashes: ReceivedStateHashes,
+    /// Network the indexer is configured for, see [Network::from_file_name]
+    network: Network,
     worker_command_sender: Sender<WorkingData>,
     worker_event_receiver: priority::Receiver<Event, Priority>,
     worker_error_receiver: mpsc::Receiver<RuntimeError>,
@@ -46,12 +85,21 @@ impl BlockReceiver {
         let parsers = Vec::new();
         Ok(BlockReceiver {
             parsers,
+            watched_directories: Vec::new(),
+            received_state_hashes: ReceivedStateHashes::default(),
+            network: Network::default(),
             worker_command_sender,
             worker_event_receiver,
             worker_error_receiver,
         })
     }
 
+    /// Blocks are received for the configured `network`, see [Network::from_file_name]
+    pub fn with_network(mut self, network: Network) -> Self {
+        self.network = network;
+        self
+    }
+
     pub async fn load_directory(&mut self, directory: &Path) -> anyhow::Result<()> {
         debug!("Loading directory");
 
@@ -66,19 +114,71 @@ impl BlockReceiver {
             )));
         }
 
-        let mut wkd = WorkingData::default();
-        wkd.pathset = vec![directory.into()];
-        self.worker_command_sender.send_replace(wkd);
+        if self.watched_directories.iter().any(|dir| dir == directory) {
+            debug!("Already watching directory {}", directory.display());
+            return Ok(());
+        }
 
         match BlockParser::new(directory) {
-            Ok(block_parser) => self.parsers.push(block_parser),
+            Ok(block_parser) => self
+                .parsers
+                .push(block_parser.with_network(self.network.clone())),
             Err(err) => return Err(err),
         }
 
+        self.watched_directories.push(directory.to_path_buf());
+        self.update_worker_pathset();
+
         info!("Set to watch directory {}", directory.display());
         Ok(())
     }
 
+    /// Stops watching the directory, if it's watched
+    pub fn unload_directory(&mut self, directory: &Path) {
+        if let Some(index) = self
+            .watched_directories
+            .iter()
+            .position(|dir| dir == directory)
+        {
+            self.watched_directories.remove(index);
+            self.parsers.remove(index);
+            self.update_worker_pathset();
+            info!("Stopped watching directory {}", directory.display());
+        }
+    }
+
+    /// Watches exactly the given directories, e.g. after a configuration reload
+    pub async fn set_directories(&mut self, directories: &[PathBuf]) -> anyhow::Result<()> {
+        for directory in self.watched_directories.clone() {
+            if !directories.contains(&directory) {
+                self.unload_directory(&directory);
+            }
+        }
+        self.load_directories(directories).await
+    }
+
+    fn update_worker_pathset(&self) {
+        let mut wkd = WorkingData::default();
+        wkd.pathset = self
+            .watched_directories
+            .iter()
+            .map(|dir| dir.as_path().into())
+            .collect();
+        self.worker_command_sender.send_replace(wkd);
+    }
+
+    /// Watches all of the given directories, merging their block events
+    pub async fn load_directories(&mut self, directories: &[PathBuf]) -> anyhow::Result<()> {
+        for directory in directories {
+            self.load_directory(directory).await?;
+        }
+        Ok(())
+    }
+
+    pub fn watched_directories(&self) -> &[PathBuf] {
+        &self.watched_directories
+    }
+
     pub async fn recv(&mut self) -> Option<anyhow::Result<PrecomputedBlock>> {
         loop {
             tokio::select! {
@@ -110,9 +210,25 @@ impl BlockReceiver {
                             }
 
                             if let Some((path, Some(_filetype))) = path_and_filetype {
-                                match parse_file(path.as_path()).await {
-                                    Ok(block) => return Some(Ok(block)),
-                                    Err(_) => continue,
+                                match parse_file(path.as_path(), &self.network).await {
+                                    Ok(block) => {
+                                        let state_hash = block.state_hash.clone();
+                                        if self.received_state_hashes.insert(state_hash) {
+                                            return Some(Ok(block));
+                                        }
+
+                                        debug!(
+                                            "Block {} already received, skipping {}",
+                                            block.state_hash,
+                                            path.display()
+                                        );
+                                        continue;
+                                    }
+                                    // the file may still be being written, it's parsed again once modified
+                                    Err(e) => {
+                                        debug!("Unable to parse {}: {e}", path.display());
+                                        continue;
+                                    }
                                 }
                             }
                         }
@@ -123,3 +239,22 @@ impl BlockReceiver {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{ReceivedStateHashes, RECEIVED_STATE_HASHES_CAPACITY};
+
+    #[test]
+    fn received_state_hashes_are_bounded() {
+        let mut received = ReceivedStateHashes::default();
+        assert!(received.insert("0".to_string()));
+        assert!(!received.insert("0".to_string()));
+
+        for n in 1..=RECEIVED_STATE_HASHES_CAPACITY {
+            assert!(received.insert(n.to_string()));
+        }
+        assert_eq!(received.state_hashes.len(), RECEIVED_STATE_HASHES_CAPACITY);
+        // the oldest is forgotten
+        assert!(received.insert("0".to_string()));
+    }
+}
@@ -1,11 +1,36 @@
 use crate::{
-    block::{precomputed::PrecomputedBlock, BlockHash},
-    state::Canonicity,
+    block::{
+        metadata::BlockMetadata,
+        precomputed::{PrecomputedBlock, PrecomputedBlockHeader},
+        BlockHash, BlockWithoutHeight,
+    },
+    state::ledger::public_key::PublicKey,
 };
 
+/// Iterator over stored blocks, in order of increasing length
+pub type BlockIterator<'a> = Box<dyn Iterator<Item = anyhow::Result<PrecomputedBlock>> + 'a>;
+
 pub trait BlockStore {
     fn add_block(&self, block: &PrecomputedBlock) -> anyhow::Result<()>;
     fn get_block(&self, state_hash: &BlockHash) -> anyhow::Result<Option<PrecomputedBlock>>;
-    fn set_canonicity(&self, state_hash: &BlockHash, canonicity: Canonicity) -> anyhow::Result<()>;
-    fn get_canonicity(&self, state_hash: &BlockHash) -> anyhow::Result<Option<Canonicity>>;
+    fn get_block_header(
+        &self,
+        state_hash: &BlockHash,
+    ) -> anyhow::Result<Option<PrecomputedBlockHeader>>;
+    fn get_block_metadata(&self, state_hash: &BlockHash) -> anyhow::Result<Option<BlockMetadata>>;
+    fn exists(&self, state_hash: &BlockHash) -> anyhow::Result<bool>;
+    fn get_blocks_at_height(&self, blockchain_length: u32) -> anyhow::Result<Vec<BlockHash>>;
+    fn get_blocks_by_creator(&self, creator: &PublicKey) -> anyhow::Result<Vec<BlockHash>>;
+    fn prune_orphaned_blocks(&self, height: u32) -> anyhow::Result<u32>;
+    fn get_block_ancestry(
+        &self,
+        state_hash: &BlockHash,
+    ) -> anyhow::Result<Option<BlockWithoutHeight>>;
+    fn get_ancestor(
+        &self,
+        state_hash: &BlockHash,
+        generations: u32,
+    ) -> anyhow::Result<Option<BlockHash>>;
+    fn blocks_iter(&self, start_height: u32, limit: usize) -> BlockIterator<'_>;
+    fn blocks_after(&self, state_hash: &BlockHash) -> anyhow::Result<BlockIterator<'_>>;
 }
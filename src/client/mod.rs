@@ -1,18 +1,18 @@
 use crate::{
-    block::{precomputed::PrecomputedBlock, Block},
-    state::{
-        ledger::account::Account,
-        summary::{SummaryShort, SummaryVerbose},
+    block::{precomputed::PrecomputedBlock, production::BlockRange, Block},
+    error::IndexerError,
+    ipc::{
+        read_auth_token, read_frame, write_frame, BlockId, IpcRequest, IpcRequestFrame, IpcResponse,
     },
+    server::rest::TRANSACTIONS_PAGE_LEN_DEFAULT,
     SOCKET_NAME,
 };
 use clap::Parser;
-use futures::{
-    io::{AsyncWriteExt, BufReader},
-    AsyncReadExt,
-};
+use futures::io::{AsyncRead, AsyncWrite, BufReader};
 use interprocess::local_socket::tokio::LocalSocketStream;
-use std::{path::PathBuf, process};
+use std::{net::SocketAddr, path::PathBuf, process};
+use tokio::net::TcpStream;
+use tokio_util::compat::{TokioAsyncReadCompatExt, TokioAsyncWriteCompatExt};
 use tracing::instrument;
 
 #[derive(Parser, Debug)]
@@ -20,12 +20,59 @@ use tracing::instrument;
 pub enum ClientCli {
     /// Display the account info for the given public key
     Account(AccountArgs),
+    /// Display a page of the user commands sent or received by the given public key
+    Transactions(TransactionsArgs),
+    /// Display the stake delegated to each block producer
+    StakeDistribution(StakeDistributionArgs),
+    /// Display the accounts of the best ledger with the largest balances
+    TopAccounts(TopAccountsArgs),
+    /// Display the coinbase and fee transfers of the given block
+    InternalCommands(InternalCommandsArgs),
+    /// Display the SNARK fees per epoch earned by the given prover in canonical blocks
+    SnarkWork(SnarkWorkArgs),
+    /// Display the number of blocks produced per block creator, canonical and orphaned
+    BlockProduction(BlockProductionArgs),
+    /// Display the number of canonical blocks and skipped slots, those without a canonical block, per epoch
+    SkippedSlots(SkippedSlotsArgs),
     /// Display the best chain
     BestChain(ChainArgs),
+    /// Display the stored block of the given state hash
+    Block(BlockArgs),
+    /// Display the canonicity and confirmations of the block of the given state hash
+    Canonicity(CanonicityArgs),
+    /// Display the stored blocks of the given length with their canonicity
+    BlocksAtHeight(HeightArgs),
     /// Dump the best ledger to a file
     BestLedger(LedgerArgs),
+    /// Display the ledger as of a canonical block, or write it to a file
+    Ledger(HistoricalLedgerArgs),
+    /// Compact the database to reclaim space
+    Compact(CompactArgs),
+    /// Write a consistent copy of the live database to a new directory
+    Checkpoint(CheckpointArgs),
     /// Show summary of indexer state
     Summary(SummaryArgs),
+    /// Pause block ingestion, e.g. during backups or disk maintenance
+    Pause,
+    /// Resume paused block ingestion
+    Resume,
+    /// Reload the log levels, watch directories, and pruning settings of the server's config file
+    Reload,
+    /// Snapshot the canonical tip's ledger now, speeding up historical ledger queries near it
+    SnapshotLedger,
+    /// Show the server's health, fails if the server isn't alive
+    Health(HealthArgs),
+    /// Print the best and canonical tips as a JSON line now and whenever either changes,
+    /// until the server stops
+    Subscribe,
+}
+
+#[derive(clap::Args, Debug)]
+#[command(author, version, about, long_about = None)]
+pub struct HealthArgs {
+    /// Also fail if the server isn't ready to serve queries (default: false)
+    #[arg(long, default_value_t = false)]
+    ready: bool,
 }
 
 #[derive(clap::Args, Debug)]
@@ -36,6 +83,76 @@ pub struct AccountArgs {
     public_key: String,
 }
 
+#[derive(clap::Args, Debug)]
+#[command(author, version, about, long_about = None)]
+pub struct TransactionsArgs {
+    /// Public key of the account
+    public_key: String,
+    /// Number of transactions to skip, oldest first
+    #[arg(long, default_value_t = 0)]
+    offset: usize,
+    /// Max number of transactions to display
+    #[arg(long, default_value_t = TRANSACTIONS_PAGE_LEN_DEFAULT)]
+    limit: usize,
+}
+
+#[derive(clap::Args, Debug)]
+#[command(author, version, about, long_about = None)]
+pub struct StakeDistributionArgs {
+    /// Epoch of the staking ledger (default: the best ledger)
+    #[arg(short, long)]
+    epoch: Option<u32>,
+}
+
+#[derive(clap::Args, Debug)]
+#[command(author, version, about, long_about = None)]
+pub struct InternalCommandsArgs {
+    /// State hash of the block
+    state_hash: String,
+}
+
+#[derive(clap::Args, Debug)]
+#[command(author, version, about, long_about = None)]
+pub struct SnarkWorkArgs {
+    /// Public key of the prover
+    prover: String,
+    /// Also display each SNARK work (default: false)
+    #[arg(short, long, default_value_t = false)]
+    verbose: bool,
+}
+
+#[derive(clap::Args, Debug)]
+#[command(author, version, about, long_about = None)]
+pub struct TopAccountsArgs {
+    /// Number of accounts to display
+    num: usize,
+}
+
+#[derive(clap::Args, Debug)]
+#[command(author, version, about, long_about = None)]
+pub struct BlockProductionArgs {
+    /// Start of the range, inclusive
+    #[arg(long)]
+    start: u32,
+    /// End of the range, inclusive
+    #[arg(long)]
+    end: u32,
+    /// The range is of epochs instead of blockchain lengths (default: false)
+    #[arg(long, default_value_t = false)]
+    epochs: bool,
+}
+
+#[derive(clap::Args, Debug)]
+#[command(author, version, about, long_about = None)]
+pub struct SkippedSlotsArgs {
+    /// Epoch to display (default: all epochs)
+    #[arg(long)]
+    epoch: Option<u32>,
+    /// Also display each skipped slot (default: false)
+    #[arg(short, long, default_value_t = false)]
+    verbose: bool,
+}
+
 #[derive(clap::Args, Debug)]
 #[command(author, version, about, long_about = None)]
 pub struct ChainArgs {
@@ -50,6 +167,30 @@ pub struct ChainArgs {
     verbose: bool,
 }
 
+#[derive(clap::Args, Debug)]
+#[command(author, version, about, long_about = None)]
+pub struct BlockArgs {
+    /// State hash of the block
+    state_hash: String,
+    /// Verbose also displays the entire precomputed block (default: false)
+    #[arg(short, long, default_value_t = false)]
+    verbose: bool,
+}
+
+#[derive(clap::Args, Debug)]
+#[command(author, version, about, long_about = None)]
+pub struct CanonicityArgs {
+    /// State hash of the block
+    state_hash: String,
+}
+
+#[derive(clap::Args, Debug)]
+#[command(author, version, about, long_about = None)]
+pub struct HeightArgs {
+    /// Blockchain length of the blocks
+    blockchain_length: u32,
+}
+
 #[derive(clap::Args, Debug)]
 #[command(author, version, about, long_about = None)]
 pub struct LedgerArgs {
@@ -58,76 +199,450 @@ pub struct LedgerArgs {
     path: PathBuf,
 }
 
+#[derive(clap::Args, Debug)]
+#[command(author, version, about, long_about = None)]
+pub struct HistoricalLedgerArgs {
+    /// State hash or canonical blockchain length of the block
+    block: BlockId,
+    /// Path to write the ledger, on the server's host (default: display it)
+    #[arg(short, long)]
+    path: Option<PathBuf>,
+}
+
+#[derive(clap::Args, Debug)]
+#[command(author, version, about, long_about = None)]
+pub struct CompactArgs {
+    /// Column family to compact (default: all column families)
+    #[arg(short, long)]
+    column_family: Option<String>,
+}
+
+#[derive(clap::Args, Debug)]
+#[command(author, version, about, long_about = None)]
+pub struct CheckpointArgs {
+    /// Path of the checkpoint directory in the server's checkpoint directory, must not exist
+    #[arg(short, long)]
+    path: PathBuf,
+}
+
 #[derive(clap::Args, Debug)]
 #[command(author, version, about, long_about = None)]
 pub struct SummaryArgs {
-    /// Verbose output should be redirected to a file
-    #[arg(short, long, default_value_t = false)]
-    verbose: bool,
+    /// Add the dangling branch details, repeat for the witness tree near the best tip
+    #[arg(short, long, action = clap::ArgAction::Count)]
+    verbose: u8,
+}
+
+/// Where the client connects to the server
+#[derive(clap::Args, Debug, Clone)]
+pub struct ConnectArgs {
+    /// Address of the server's TCP listener (default: the server's local socket)
+    #[arg(long)]
+    pub connect: Option<SocketAddr>,
+    /// Name of the server's local socket
+    #[arg(long, default_value = SOCKET_NAME)]
+    pub socket: String,
+    /// Path to a file of the auth token the server was started with
+    #[arg(long)]
+    pub auth_token_file: Option<PathBuf>,
+}
+
+/// Connection to the server which can be used for any number of requests
+pub struct Client {
+    reader: BufReader<Box<dyn AsyncRead + Unpin + Send>>,
+    writer: Box<dyn AsyncWrite + Unpin + Send>,
+    /// Presented with every request
+    auth_token: Option<String>,
+}
+
+impl Client {
+    /// Connects to the server's local socket
+    pub async fn connect(socket_name: &str) -> std::io::Result<Self> {
+        let (reader, writer) = LocalSocketStream::connect(socket_name).await?.into_split();
+        Ok(Self {
+            reader: BufReader::new(Box::new(reader)),
+            writer: Box::new(writer),
+            auth_token: None,
+        })
+    }
+
+    /// Connects to the server's TCP listener
+    pub async fn connect_tcp(address: SocketAddr) -> std::io::Result<Self> {
+        let (reader, writer) = TcpStream::connect(address).await?.into_split();
+        Ok(Self {
+            reader: BufReader::new(Box::new(reader.compat())),
+            writer: Box::new(writer.compat_write()),
+            auth_token: None,
+        })
+    }
+
+    /// Presents the token with every request, for servers started with an auth token
+    pub fn with_auth_token(mut self, auth_token: String) -> Self {
+        self.auth_token = Some(auth_token);
+        self
+    }
+
+    /// Sends the request and awaits its response, requests are answered in order
+    pub async fn request(&mut self, request: &IpcRequest) -> anyhow::Result<IpcResponse> {
+        self.send(request).await?;
+        self.read_response().await
+    }
+
+    /// Sends the request of a streamed response and hands each of its chunks to
+    /// `on_chunk` as it arrives, until the end of the stream
+    pub async fn request_stream<F>(
+        &mut self,
+        request: &IpcRequest,
+        mut on_chunk: F,
+    ) -> anyhow::Result<()>
+    where
+        F: FnMut(IpcResponse) -> anyhow::Result<()>,
+    {
+        self.send(request).await?;
+        loop {
+            match self.read_response().await? {
+                IpcResponse::EndOfStream => return Ok(()),
+                IpcResponse::Error(msg) => return Err(anyhow::Error::msg(msg)),
+                IpcResponse::Failed { error, request_id } => {
+                    return Err(failure(error, &request_id))
+                }
+                chunk => on_chunk(chunk)?,
+            }
+        }
+    }
+
+    async fn send(&mut self, request: &IpcRequest) -> anyhow::Result<()> {
+        let frame = IpcRequestFrame::new(self.auth_token.clone(), request.clone());
+        write_frame(&mut self.writer, &frame).await
+    }
+
+    /// Next response, a protocol version mismatch is an error
+    async fn read_response(&mut self) -> anyhow::Result<IpcResponse> {
+        match read_frame(&mut self.reader).await? {
+            Some(IpcResponse::UnsupportedVersion {
+                server_version,
+                client_version,
+            }) => {
+                let upgrade = if server_version > client_version {
+                    "upgrade your client"
+                } else {
+                    "upgrade the server"
+                };
+                Err(anyhow::Error::msg(format!(
+                    "Server speaks IPC protocol version {server_version}, the client version {client_version}, {upgrade}"
+                )))
+            }
+            Some(response) => Ok(response),
+            None => Err(anyhow::Error::msg(
+                "Server closed the connection without responding",
+            )),
+        }
+    }
 }
 
 #[instrument]
-pub async fn run(command: &ClientCli) -> Result<(), anyhow::Error> {
-    let conn = match LocalSocketStream::connect(SOCKET_NAME).await {
-        Ok(conn) => conn,
+pub async fn run(command: &ClientCli, connect_args: &ConnectArgs) -> Result<(), anyhow::Error> {
+    let auth_token = connect_args
+        .auth_token_file
+        .as_deref()
+        .map(read_auth_token)
+        .transpose()?;
+    let client = match connect_args.connect {
+        Some(address) => Client::connect_tcp(address).await,
+        None => Client::connect(&connect_args.socket).await,
+    };
+    let mut client = match client {
+        Ok(client) => match auth_token {
+            Some(auth_token) => client.with_auth_token(auth_token),
+            None => client,
+        },
         Err(e) => {
-            println!(
-                "Make sure the server has been started and initial block ingestion has completed."
-            );
-            println!(
-                "Initial block ingestion takes several minutes if ingesting all mainnet blocks."
-            );
+            println!("Make sure the server has been started.");
             println!("Error: {e}");
             process::exit(111);
         }
     };
-    let (reader, mut writer) = conn.into_split();
-    let mut reader = BufReader::new(reader);
-
-    let mut buffer = Vec::with_capacity(1280000);
-
-    match command {
-        ClientCli::Account(account_args) => {
-            let command = format!("account {}\0", account_args.public_key);
-            writer.write_all(command.as_bytes()).await?;
-            reader.read_to_end(&mut buffer).await?;
-            let account: Account = bcs::from_bytes(&buffer)?;
-            println!("{account:?}");
-        }
-        ClientCli::BestChain(chain_args) => {
-            let command = format!("best_chain {}\0", chain_args.num);
-            writer.write_all(command.as_bytes()).await?;
-            reader.read_to_end(&mut buffer).await?;
-            let blocks: Vec<PrecomputedBlock> = bcs::from_bytes(&buffer)?;
-            blocks.iter().for_each(|block| {
-                if chain_args.verbose {
-                    println!("{}", serde_json::to_string(block).unwrap());
+    let request = match command {
+        ClientCli::Account(account_args) => IpcRequest::Account {
+            public_key: account_args.public_key.clone(),
+        },
+        ClientCli::Transactions(transactions_args) => IpcRequest::Transactions {
+            public_key: transactions_args.public_key.clone(),
+            offset: transactions_args.offset,
+            limit: transactions_args.limit,
+        },
+        ClientCli::StakeDistribution(stake_args) => IpcRequest::StakeDistribution {
+            epoch: stake_args.epoch,
+        },
+        ClientCli::InternalCommands(internal_commands_args) => IpcRequest::InternalCommands {
+            state_hash: internal_commands_args.state_hash.clone(),
+        },
+        ClientCli::SnarkWork(snark_work_args) => IpcRequest::SnarkWork {
+            prover: snark_work_args.prover.clone(),
+        },
+        ClientCli::TopAccounts(top_accounts_args) => IpcRequest::TopAccounts {
+            num: top_accounts_args.num,
+        },
+        ClientCli::BlockProduction(production_args) => {
+            let BlockProductionArgs { start, end, epochs } = *production_args;
+            IpcRequest::BlockProduction {
+                range: if epochs {
+                    BlockRange::Epochs { start, end }
                 } else {
-                    let block = Block::from_precomputed(block, block.blockchain_length.unwrap());
-                    println!("{}", block.summary());
+                    BlockRange::Heights { start, end }
+                },
+            }
+        }
+        ClientCli::SkippedSlots(skipped_slots_args) => IpcRequest::SkippedSlots {
+            epoch: skipped_slots_args.epoch,
+            verbose: skipped_slots_args.verbose,
+        },
+        ClientCli::BestChain(chain_args) => IpcRequest::BestChain {
+            num: chain_args.num,
+        },
+        ClientCli::Block(block_args) => IpcRequest::Block {
+            state_hash: block_args.state_hash.clone(),
+            verbose: block_args.verbose,
+        },
+        ClientCli::Canonicity(canonicity_args) => IpcRequest::Canonicity {
+            state_hash: canonicity_args.state_hash.clone(),
+        },
+        ClientCli::BlocksAtHeight(height_args) => IpcRequest::BlocksAtHeight {
+            blockchain_length: height_args.blockchain_length,
+        },
+        ClientCli::BestLedger(ledger_args) => IpcRequest::BestLedger {
+            path: ledger_args.path.clone(),
+        },
+        ClientCli::Ledger(ledger_args) => IpcRequest::Ledger {
+            block: ledger_args.block.clone(),
+            path: ledger_args.path.clone(),
+        },
+        ClientCli::Compact(compact_args) => IpcRequest::Compact {
+            column_family: compact_args.column_family.clone(),
+        },
+        ClientCli::Checkpoint(checkpoint_args) => IpcRequest::Checkpoint {
+            path: checkpoint_args.path.clone(),
+        },
+        ClientCli::Summary(summary_args) => IpcRequest::Summary {
+            verbosity: summary_args.verbose,
+        },
+        ClientCli::Pause => IpcRequest::Pause,
+        ClientCli::Resume => IpcRequest::Resume,
+        ClientCli::Reload => IpcRequest::Reload,
+        ClientCli::SnapshotLedger => IpcRequest::SnapshotLedger,
+        ClientCli::Health(_) => IpcRequest::Health,
+        ClientCli::Subscribe => IpcRequest::Subscribe,
+    };
+
+    // blocks are printed as they arrive
+    if let ClientCli::BestChain(chain_args) = command {
+        return client
+            .request_stream(&request, |chunk| match chunk {
+                IpcResponse::BestChain(blocks) => {
+                    print_blocks(&blocks, chain_args.verbose);
+                    Ok(())
+                }
+                chunk => Err(anyhow::Error::msg(format!("Unexpected chunk {chunk:?}"))),
+            })
+            .await;
+    }
+    if let ClientCli::Subscribe = command {
+        return client
+            .request_stream(&request, |chunk| match chunk {
+                IpcResponse::Tips(tips) => {
+                    println!("{}", serde_json::to_string(&tips)?);
+                    Ok(())
+                }
+                chunk => Err(anyhow::Error::msg(format!("Unexpected chunk {chunk:?}"))),
+            })
+            .await;
+    }
+    if let ClientCli::Ledger(HistoricalLedgerArgs { path: None, .. }) = command {
+        return client
+            .request_stream(&request, |chunk| match chunk {
+                IpcResponse::Accounts(accounts) => {
+                    for account in accounts {
+                        print!("{account:?}");
+                    }
+                    Ok(())
+                }
+                chunk => Err(anyhow::Error::msg(format!("Unexpected chunk {chunk:?}"))),
+            })
+            .await;
+    }
+    let (response, initializing) = client.request(&request).await?.unwrap_initializing();
+    if let Some(blocks_processed) = initializing {
+        eprintln!(
+            "The server is still ingesting its startup blocks ({blocks_processed} so far), the response may be incomplete"
+        );
+    }
+
+    match response {
+        IpcResponse::Account(Some(account)) => println!("{account:?}"),
+        IpcResponse::Account(None) => println!("Account not found"),
+        IpcResponse::Block {
+            block: Some(block),
+            precomputed,
+        } => {
+            println!("{}", serde_json::to_string_pretty(&block)?);
+            if let Some(precomputed) = precomputed {
+                println!("{}", serde_json::to_string(&precomputed)?);
+            }
+        }
+        IpcResponse::Block { block: None, .. } => println!("Block not found"),
+        IpcResponse::BlockStatus(Some(status)) => match status.confirmations {
+            Some(confirmations) => {
+                println!("{:?} with {confirmations} confirmations", status.canonicity)
+            }
+            None => println!("{:?}, not on the best chain", status.canonicity),
+        },
+        IpcResponse::BlockStatus(None) => println!("Block not found"),
+        IpcResponse::Transactions(transactions) if transactions.is_empty() => {
+            println!("No transactions found")
+        }
+        IpcResponse::Transactions(transactions) => {
+            for transaction in transactions {
+                let direction = if transaction.sent { "to" } else { "from" };
+                let amount = transaction
+                    .amount
+                    .map_or(String::new(), |amount| format!(" {amount}"));
+                println!(
+                    "{} {}{amount} {direction} {} (fee {}, {}) in {} at length {}",
+                    transaction.hash,
+                    transaction.kind,
+                    transaction.counterparty,
+                    transaction.fee,
+                    transaction.status,
+                    transaction.state_hash,
+                    transaction.blockchain_length.unwrap_or_default(),
+                );
+            }
+        }
+        IpcResponse::Accounts(accounts) => {
+            for account in accounts {
+                println!("{} {}", account.public_key.to_address(), account.balance.0);
+            }
+        }
+        IpcResponse::BlockProduction(production) if production.is_empty() => {
+            println!("No blocks found")
+        }
+        IpcResponse::BlockProduction(production) => {
+            for creator_production in production {
+                println!(
+                    "{} {} canonical, {} orphaned, {} pending",
+                    creator_production.creator,
+                    creator_production.canonical,
+                    creator_production.orphaned,
+                    creator_production.pending
+                );
+            }
+        }
+        IpcResponse::EpochSlots(epoch_slots) if epoch_slots.is_empty() => {
+            println!("No canonical blocks found")
+        }
+        IpcResponse::EpochSlots(epoch_slots) => {
+            for slots in epoch_slots {
+                println!(
+                    "Epoch {}: {} canonical blocks, {} skipped slots ({:.2}%)",
+                    slots.epoch,
+                    slots.canonical,
+                    slots.skipped,
+                    100.0 * slots.skip_rate()
+                );
+                for skipped_slot in slots.skipped_slots {
+                    println!("  {skipped_slot}");
+                }
+            }
+        }
+        IpcResponse::InternalCommands(Some(internal_commands)) => {
+            for internal_command in internal_commands {
+                println!("{internal_command}");
+            }
+        }
+        IpcResponse::InternalCommands(None) => println!("Block not found"),
+        IpcResponse::SnarkWork(snark_work) => {
+            if let ClientCli::SnarkWork(SnarkWorkArgs { verbose: true, .. }) = command {
+                for work in &snark_work.snark_work {
+                    println!(
+                        "{} at length {} fee {}",
+                        work.state_hash.0,
+                        work.blockchain_length.unwrap_or_default(),
+                        work.fee.0
+                    );
                 }
-            });
-        }
-        ClientCli::BestLedger(ledger_args) => {
-            let command = format!("best_ledger {}\0", ledger_args.path.display());
-            writer.write_all(command.as_bytes()).await?;
-            reader.read_to_end(&mut buffer).await?;
-            let msg: String = bcs::from_bytes(&buffer)?;
-            println!("{msg}");
-        }
-        ClientCli::Summary(summary_args) => {
-            let command = format!("summary {}\0", summary_args.verbose);
-            writer.write_all(command.as_bytes()).await?;
-            reader.read_to_end(&mut buffer).await?;
-            if summary_args.verbose {
-                let summary: SummaryVerbose = bcs::from_bytes(&buffer)?;
-                println!("{summary}");
-            } else {
-                let summary: SummaryShort = bcs::from_bytes(&buffer)?;
-                println!("{summary}");
             }
+            if snark_work.epoch_fees.is_empty() {
+                println!("No canonical SNARK work found");
+            }
+            for fees in &snark_work.epoch_fees {
+                println!(
+                    "Epoch {}: {} works, {} fees",
+                    fees.epoch, fees.num_works, fees.total_fees
+                );
+            }
+        }
+        IpcResponse::StakeDistribution(distribution) => {
+            println!("Total stake: {}", distribution.total_stake);
+            for stake in &distribution.delegates {
+                println!(
+                    "{} {} from {} delegators",
+                    stake.delegate, stake.total_delegated, stake.num_delegators
+                );
+            }
+        }
+        IpcResponse::Blocks(blocks) if blocks.is_empty() => println!("No blocks found"),
+        IpcResponse::Blocks(blocks) => {
+            for block in blocks {
+                let canonicity = block
+                    .canonicity
+                    .map_or("Unknown".to_string(), |canonicity| {
+                        format!("{canonicity:?}")
+                    });
+                println!(
+                    "{} {canonicity} (parent {}, creator {})",
+                    block.state_hash, block.parent_hash, block.block_creator
+                );
+            }
+        }
+        IpcResponse::BestChain(_) | IpcResponse::Tips(_) | IpcResponse::EndOfStream => {
+            return Err(anyhow::Error::msg("Unexpected streamed response"))
+        }
+        IpcResponse::SummaryShort(summary) => println!("{summary}"),
+        IpcResponse::SummaryVerbose(summary) => println!("{summary}"),
+        IpcResponse::Health(report) => {
+            println!("{report}");
+            if matches!(command, ClientCli::Health(HealthArgs { ready: true })) && !report.ready {
+                return Err(anyhow::Error::msg("Server isn't ready"));
+            }
+        }
+        IpcResponse::Message(msg) => println!("{msg}"),
+        IpcResponse::Error(msg) => return Err(anyhow::Error::msg(msg)),
+        IpcResponse::Failed { error, request_id } => return Err(failure(error, &request_id)),
+        IpcResponse::UnsupportedVersion { .. } => {
+            return Err(anyhow::Error::msg("Unexpected protocol version mismatch"))
+        }
+        IpcResponse::Initializing { .. } => {
+            return Err(anyhow::Error::msg(
+                "Unexpected nested initializing response",
+            ))
         }
     }
 
     Ok(())
 }
+
+/// The typed error of a failed request, in the context of its ID
+fn failure(error: IndexerError, request_id: &str) -> anyhow::Error {
+    anyhow::Error::new(error).context(format!("Request {request_id} failed"))
+}
+
+fn print_blocks(blocks: &[PrecomputedBlock], verbose: bool) {
+    for block in blocks {
+        if verbose {
+            println!("{}", serde_json::to_string(block).unwrap());
+        } else {
+            let block = Block::from_precomputed(block, block.blockchain_length.unwrap());
+            println!("{}", block.summary());
+        }
+    }
+}
@@ -0,0 +1,55 @@
+use id_tree::NodeIdError;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Failure of a state, store, or query operation, sent to IPC clients as is
+///
+/// Operations return it wrapped in an [anyhow::Error], failures without a more specific
+/// variant are [IndexerError::Other]
+#[derive(Debug, Clone, PartialEq, Eq, Error, Serialize, Deserialize)]
+pub enum IndexerError {
+    /// The block isn't in the store or the witness tree
+    #[error("Block {0} not found")]
+    MissingBlock(String),
+    /// The block's body was dropped along with its epoch
+    #[error(
+        "Missing body of block {state_hash} of epoch {epoch}, dropped epochs: {dropped_epochs:?}"
+    )]
+    DroppedBlockBody {
+        state_hash: String,
+        epoch: u32,
+        dropped_epochs: Vec<u32>,
+    },
+    #[error("No ledger for block {0}")]
+    MissingLedger(String),
+    #[error("No staking ledger of epoch {0}")]
+    MissingStakingLedger(u32),
+    #[error("No canonical block at length {0}")]
+    MissingCanonicalBlock(u32),
+    #[error("Block {0} isn't canonical")]
+    NotCanonical(String),
+    /// The witness tree lacks a node it should have, e.g. a tip of a branch
+    #[error("Inconsistent witness tree: {0}")]
+    WitnessTree(String),
+    /// The request's arguments are invalid, e.g. a malformed public key
+    #[error("Invalid request: {0}")]
+    InvalidRequest(String),
+    #[error("{0}")]
+    Other(String),
+}
+
+impl From<NodeIdError> for IndexerError {
+    fn from(e: NodeIdError) -> Self {
+        Self::WitnessTree(format!("{e:?}"))
+    }
+}
+
+/// The typed error the failure carries, other failures are kept as their message
+impl From<anyhow::Error> for IndexerError {
+    fn from(e: anyhow::Error) -> Self {
+        match e.downcast_ref::<IndexerError>() {
+            Some(indexer_error) => indexer_error.clone(),
+            None => Self::Other(e.to_string()),
+        }
+    }
+}
@@ -49,6 +49,8 @@ pub async fn start_gql(db: Arc<IndexerStore>) -> std::io::Result<()> {
             .wrap(middleware::Logger::default())
     })
     .bind(("0.0.0.0", 8080))?
+    // the indexer server shuts down on signals, then the process exits
+    .disable_signals()
     .run()
     .await
 }
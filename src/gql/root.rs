@@ -8,6 +8,7 @@ use mina_serialization_types::v1::UserCommandWithStatusV1;
 
 use crate::gql::schema::Transaction;
 use crate::gql::schema::TransactionQueryInput;
+use crate::store::codec;
 use crate::store::IndexerStore;
 use crate::store::TransactionKey;
 
@@ -32,6 +33,15 @@ impl QueryRoot {
         "0.1.1"
     }
 
+    #[graphql(description = "Network of the indexed blocks")]
+    fn network(ctx: &Context) -> Option<String> {
+        ctx.db
+            .network()
+            .ok()
+            .flatten()
+            .map(|network| network.to_string())
+    }
+
     #[graphql(description = "List of all transactions")]
     fn transactions(
         ctx: &Context,
@@ -47,7 +57,7 @@ impl QueryRoot {
             let (key, value) = entry.unwrap();
 
             let key = TransactionKey::from_slice(&key).unwrap();
-            let cmd = bcs::from_bytes::<UserCommandWithStatusV1>(&value)
+            let cmd = codec::decode::<UserCommandWithStatusV1>(&value)
                 .unwrap()
                 .inner();
 
@@ -0,0 +1,416 @@
+use crate::{
+    block::{
+        precomputed::PrecomputedBlock,
+        production::{BlockProduction, BlockRange, EpochSlots},
+    },
+    error::IndexerError,
+    server::{
+        health::HealthReport,
+        rest::{BlockJson, TransactionJson},
+        snapshot::{BlockStatus, Tips},
+    },
+    state::{
+        ledger::{
+            account::Account, internal_command::InternalCommand, snark_work::ProverSnarkWork,
+            staking::StakeDistribution,
+        },
+        summary::{SummaryShort, SummaryVerbose},
+    },
+};
+use futures::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+use tokio::{
+    sync::{OwnedSemaphorePermit, Semaphore},
+    time::{Interval, MissedTickBehavior},
+};
+
+/// Max length of a frame's payload, larger frames are rejected before being read
+pub const MAX_FRAME_LEN: u32 = 1 << 30;
+
+/// Max length of a request frame's payload, requests are read before the client is
+/// authenticated so they're kept small
+pub const MAX_REQUEST_FRAME_LEN: u32 = 16 * 1024;
+
+/// Max number of blocks or accounts in a chunk of a streamed response
+pub const STREAM_CHUNK_LEN: usize = 100;
+
+/// Version of the IPC protocol, bumped whenever the requests or responses change
+/// incompatibly so the client and server can tell they don't understand each other
+pub const IPC_PROTOCOL_VERSION: u32 = 2;
+
+/// Block identified by its state hash, or by its length on the canonical chain
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BlockId {
+    StateHash(String),
+    Length(u32),
+}
+
+impl std::str::FromStr for BlockId {
+    type Err = std::convert::Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s.parse() {
+            Ok(length) => Self::Length(length),
+            Err(_) => Self::StateHash(s.to_string()),
+        })
+    }
+}
+
+/// Request of a client to the server
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum IpcRequest {
+    /// Account of the public key in the best ledger
+    Account {
+        public_key: String,
+    },
+    /// Page of the user commands sent or received by the public key, ordered by blockchain length
+    Transactions {
+        public_key: String,
+        offset: usize,
+        limit: usize,
+    },
+    /// Stake per delegate of the epoch's staking ledger, of the best ledger if `None`
+    StakeDistribution {
+        epoch: Option<u32>,
+    },
+    /// The `num` accounts of the best ledger with the largest balances
+    TopAccounts {
+        num: usize,
+    },
+    /// Coinbase and fee transfers of the block
+    InternalCommands {
+        state_hash: String,
+    },
+    /// SNARK work of the prover included in canonical blocks, with its fees per epoch
+    SnarkWork {
+        prover: String,
+    },
+    /// Blocks produced per block creator in the range
+    BlockProduction {
+        range: BlockRange,
+    },
+    /// First `num` blocks of the best chain, streamed
+    BestChain {
+        num: usize,
+    },
+    /// Stored block of the state hash, with its precomputed block if `verbose`
+    Block {
+        state_hash: String,
+        verbose: bool,
+    },
+    /// Canonicity and confirmations of the block
+    Canonicity {
+        state_hash: String,
+    },
+    /// Stored blocks of the length, e.g. the forks at it
+    BlocksAtHeight {
+        blockchain_length: u32,
+    },
+    /// Write the best ledger to `path` on the server's host
+    BestLedger {
+        path: PathBuf,
+    },
+    /// Ledger as of the canonical block, written to `path` on the server's host or
+    /// streamed as accounts if `None`
+    Ledger {
+        block: BlockId,
+        path: Option<PathBuf>,
+    },
+    /// Compact the column family, all of them if `None`
+    Compact {
+        column_family: Option<String>,
+    },
+    /// Write a checkpoint of the live database to `path` in the server's checkpoint
+    /// directory
+    Checkpoint {
+        path: PathBuf,
+    },
+    /// Summary of the indexer state, verbosity 1 adds the store's column family and
+    /// operation stats and 2 a rendering of the witness tree near the best tip
+    Summary {
+        verbosity: u8,
+    },
+    /// Stop ingesting blocks until resumed
+    Pause,
+    Resume,
+    /// Apply the server's config file again, without restarting
+    Reload,
+    /// Store the canonical tip's ledger whole now, instead of waiting for the next interval
+    SnapshotLedger,
+    Health,
+    /// Canonical blocks and skipped slots per epoch, of all epochs if `None`, listing the
+    /// skipped slots if `verbose`
+    SkippedSlots {
+        epoch: Option<u32>,
+        verbose: bool,
+    },
+    /// Keep the connection open, pushing the tips now and whenever the best or canonical
+    /// tip changes, until the server stops
+    Subscribe,
+}
+
+/// Frame of a request, with the auth token the server was started with, if any
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IpcRequestFrame {
+    /// Comes first so it's readable whatever the rest of the frame looks like
+    pub protocol_version: u32,
+    pub auth_token: Option<String>,
+    pub request: IpcRequest,
+}
+
+impl IpcRequestFrame {
+    /// Frame of the request in this build's protocol version
+    pub fn new(auth_token: Option<String>, request: IpcRequest) -> Self {
+        Self {
+            protocol_version: IPC_PROTOCOL_VERSION,
+            auth_token,
+            request,
+        }
+    }
+
+    /// Protocol version of the frame's payload, read before the rest of the payload
+    /// since requests of other versions may not deserialize
+    pub fn protocol_version(payload: &[u8]) -> anyhow::Result<u32> {
+        let version_bytes = payload
+            .get(..4)
+            .ok_or_else(|| anyhow::Error::msg("Frame too short for a protocol version"))?;
+        Ok(bcs::from_bytes(version_bytes)?)
+    }
+}
+
+/// Response of the server to an [IpcRequest]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum IpcResponse {
+    Account(Option<Account>),
+    /// Chunk of the best chain, more chunks follow until [IpcResponse::EndOfStream]
+    BestChain(Vec<PrecomputedBlock>),
+    /// `None` if the block isn't stored
+    Block {
+        block: Option<Box<BlockJson>>,
+        precomputed: Option<Box<PrecomputedBlock>>,
+    },
+    Blocks(Vec<BlockJson>),
+    Transactions(Vec<TransactionJson>),
+    Accounts(Vec<Account>),
+    BlockProduction(Vec<BlockProduction>),
+    StakeDistribution(Box<StakeDistribution>),
+    SnarkWork(Box<ProverSnarkWork>),
+    /// `None` if the block isn't stored
+    InternalCommands(Option<Vec<InternalCommand>>),
+    /// `None` if the block isn't stored
+    BlockStatus(Option<BlockStatus>),
+    /// Last frame of a streamed response, a stream may also end with an [IpcResponse::Failed]
+    EndOfStream,
+    SummaryShort(Box<SummaryShort>),
+    SummaryVerbose(Box<SummaryVerbose>),
+    Health(HealthReport),
+    /// Outcome of a command with side effects
+    Message(String),
+    /// The request was malformed or refused
+    Error(String),
+    /// The request failed, the server's logs of it carry its ID
+    Failed {
+        error: IndexerError,
+        request_id: String,
+    },
+    /// The request's protocol version isn't the server's, nothing else of it was read
+    UnsupportedVersion {
+        server_version: u32,
+        client_version: u32,
+    },
+    /// Response answered from the partially built state while the server still ingests
+    /// its startup blocks
+    Initializing {
+        blocks_processed: u32,
+        response: Box<IpcResponse>,
+    },
+    EpochSlots(Vec<EpochSlots>),
+    /// Pushed to subscribers, more follow until [IpcResponse::EndOfStream]
+    Tips(Tips),
+}
+
+impl IpcResponse {
+    /// Response without the initializing flag, and the number of blocks processed so
+    /// far if it was answered during initialization
+    pub fn unwrap_initializing(self) -> (IpcResponse, Option<u32>) {
+        match self {
+            IpcResponse::Initializing {
+                blocks_processed,
+                response,
+            } => (*response, Some(blocks_processed)),
+            response => (response, None),
+        }
+    }
+}
+
+/// Reads the auth token from the file, surrounding whitespace isn't part of it
+pub fn read_auth_token(path: &Path) -> anyhow::Result<String> {
+    let token = std::fs::read_to_string(path).map_err(|e| {
+        anyhow::Error::msg(format!(
+            "Unable to read the auth token file {}: {e}",
+            path.display()
+        ))
+    })?;
+    let token = token.trim();
+    if token.is_empty() {
+        return Err(anyhow::Error::msg(format!(
+            "Auth token file {} is empty",
+            path.display()
+        )));
+    }
+    Ok(token.to_string())
+}
+
+/// Compares the tokens in time independent of where they differ
+pub fn auth_token_matches(expected: &str, given: Option<&str>) -> bool {
+    let given = match given {
+        Some(given) => given.as_bytes(),
+        None => return false,
+    };
+    let expected = expected.as_bytes();
+    expected.len() == given.len()
+        && expected
+            .iter()
+            .zip(given)
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+/// Limits on the client connections, shared by all of them: the number of open
+/// connections, and the rate of each connection's requests
+#[derive(Debug, Clone)]
+pub struct ConnLimits {
+    /// A permit per open connection
+    connections: Arc<Semaphore>,
+    min_request_interval: Option<Duration>,
+}
+
+impl ConnLimits {
+    pub fn new(max_connections: usize, min_request_interval: Option<Duration>) -> Self {
+        Self {
+            connections: Arc::new(Semaphore::new(max_connections)),
+            min_request_interval,
+        }
+    }
+
+    /// Min time between two requests of a connection, none if `max_requests_per_sec` is 0
+    pub fn min_request_interval(max_requests_per_sec: u32) -> Option<Duration> {
+        (max_requests_per_sec > 0).then(|| Duration::from_secs(1) / max_requests_per_sec)
+    }
+
+    /// A permit for a new connection, released when dropped, none if the max number of
+    /// connections are open
+    pub fn try_open(&self) -> Option<OwnedSemaphorePermit> {
+        self.connections.clone().try_acquire_owned().ok()
+    }
+
+    /// Ticks pacing a connection's requests, requests beyond the rate limit wait for the
+    /// next tick. None if unlimited
+    pub fn request_ticks(&self) -> Option<Interval> {
+        self.min_request_interval.map(|period| {
+            let mut request_ticks = tokio::time::interval(period);
+            request_ticks.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            request_ticks
+        })
+    }
+}
+
+/// Answers the first request of a connection beyond the max number of connections with
+/// an error, the request is read so the client gets the error instead of a reset
+pub async fn refuse_conn<R, W>(reader: &mut R, writer: &mut W)
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    read_request_payload(reader).await.ok();
+    let err_msg = "Too many connections, try again later".to_string();
+    write_frame(writer, &IpcResponse::Error(err_msg)).await.ok();
+}
+
+/// Writes `value` as a frame, its bcs serialization prefixed by its big-endian `u32` length
+pub async fn write_frame<W, T>(writer: &mut W, value: &T) -> anyhow::Result<()>
+where
+    W: AsyncWrite + Unpin,
+    T: Serialize,
+{
+    let payload = bcs::to_bytes(value)?;
+    let len = u32::try_from(payload.len())
+        .ok()
+        .filter(|len| *len <= MAX_FRAME_LEN)
+        .ok_or_else(|| {
+            anyhow::Error::msg(format!("Frame of {} bytes is too large", payload.len()))
+        })?;
+    writer.write_all(&len.to_be_bytes()).await?;
+    writer.write_all(&payload).await?;
+    writer.flush().await?;
+    Ok(())
+}
+
+/// Reads a frame written by [write_frame], `None` if the peer closed the
+/// connection before the frame started
+pub async fn read_frame<R, T>(reader: &mut R) -> anyhow::Result<Option<T>>
+where
+    R: AsyncRead + Unpin,
+    T: DeserializeOwned,
+{
+    match read_frame_payload(reader).await? {
+        Some(payload) => {
+            Ok(Some(bcs::from_bytes(&payload).map_err(|e| {
+                anyhow::Error::msg(format!("Malformed frame: {e}"))
+            })?))
+        }
+        None => Ok(None),
+    }
+}
+
+/// Reads the payload of a frame written by [write_frame] without deserializing it,
+/// `None` if the peer closed the connection before the frame started
+pub async fn read_frame_payload<R>(reader: &mut R) -> anyhow::Result<Option<Vec<u8>>>
+where
+    R: AsyncRead + Unpin,
+{
+    read_payload(reader, MAX_FRAME_LEN).await
+}
+
+/// Reads the payload of a request frame like [read_frame_payload], with requests longer
+/// than [MAX_REQUEST_FRAME_LEN] rejected before anything is allocated for them
+pub async fn read_request_payload<R>(reader: &mut R) -> anyhow::Result<Option<Vec<u8>>>
+where
+    R: AsyncRead + Unpin,
+{
+    read_payload(reader, MAX_REQUEST_FRAME_LEN).await
+}
+
+async fn read_payload<R>(reader: &mut R, max_len: u32) -> anyhow::Result<Option<Vec<u8>>>
+where
+    R: AsyncRead + Unpin,
+{
+    let mut len_bytes = [0; 4];
+    let mut num_read = 0;
+    while num_read < len_bytes.len() {
+        match reader.read(&mut len_bytes[num_read..]).await? {
+            0 if num_read == 0 => return Ok(None),
+            0 => {
+                return Err(anyhow::Error::msg(
+                    "Connection closed within a frame length",
+                ))
+            }
+            n => num_read += n,
+        }
+    }
+
+    let len = u32::from_be_bytes(len_bytes);
+    if len > max_len {
+        return Err(anyhow::Error::msg(format!(
+            "Frame of {len} bytes is too large"
+        )));
+    }
+    let mut payload = vec![0; len as usize];
+    reader.read_exact(&mut payload).await?;
+    Ok(Some(payload))
+}
@@ -1,15 +1,50 @@
 pub mod block;
 pub mod client;
+pub mod error;
 pub mod gql;
+pub mod ipc;
+pub mod network;
 pub mod server;
 pub mod state;
 pub mod store;
 
+pub const BACKUP_INTERVAL_DEFAULT_SEC: u64 = 6 * 60 * 60;
+pub const BACKUP_RETENTION_DEFAULT: usize = 3;
 pub const BLOCK_REPORTING_FREQ_NUM: u32 = 5000;
 pub const BLOCK_REPORTING_FREQ_SEC: u64 = 180;
+pub const BERKELEY_CANONICAL_THRESHOLD: u32 = 10;
+pub const BERKELEY_COINBASE_AMOUNT: u64 = 720_000_000_000;
+pub const BERKELEY_SLOTS_PER_EPOCH: u32 = 7140;
+pub const BERKELEY_SLOT_DURATION_MS: u64 = 180_000;
+pub const BERKELEY_SUPERCHARGED_COINBASE_AMOUNT: u64 = 1_440_000_000_000;
+pub const BERKELEY_TRANSITION_FRONTIER_K: u32 = 290;
 pub const CANONICAL_UPDATE_THRESHOLD: u32 = PRUNE_INTERVAL_DEFAULT / 5;
+pub const CLEANUP_INTERVAL_DEFAULT_SEC: u64 = 60 * 60;
+pub const DANGLING_MAX_AGE_SLOTS_DEFAULT: u32 = MAINNET_SLOTS_PER_EPOCH;
+pub const DANGLING_MAX_DEPTH_DEFAULT: u32 = MAINNET_TRANSITION_FRONTIER_K;
+pub const DEVNET_CANONICAL_THRESHOLD: u32 = 10;
+pub const DEVNET_COINBASE_AMOUNT: u64 = 720_000_000_000;
+pub const DEVNET_SLOTS_PER_EPOCH: u32 = 7140;
+pub const DEVNET_SLOT_DURATION_MS: u64 = 180_000;
+pub const DEVNET_SUPERCHARGED_COINBASE_AMOUNT: u64 = 1_440_000_000_000;
+pub const DEVNET_TRANSITION_FRONTIER_K: u32 = 290;
+pub const DIFFS_MAP_CAPACITY_DEFAULT: usize = 1000;
+pub const LEDGER_SNAPSHOT_INTERVAL_DEFAULT: u32 = 1000;
 pub const MAINNET_CANONICAL_THRESHOLD: u32 = 10;
+pub const MAINNET_COINBASE_AMOUNT: u64 = 720_000_000_000;
+pub const MAINNET_SLOTS_PER_EPOCH: u32 = 7140;
+pub const MAINNET_SLOT_DURATION_MS: u64 = 180_000;
+pub const MAINNET_GENESIS_CREATOR: &str = "B62qiy32p8kAKnny8ZFwoMhYpBppM1DWVCqAPBYNcXnsAHhnfAAuXgg";
 pub const MAINNET_GENESIS_HASH: &str = "3NKeMoncuHab5ScarV5ViyF16cJPT4taWNSaTLS64Dp67wuXigPZ";
+pub const MAINNET_GENESIS_TIMESTAMP_MS: u64 = 1_615_939_200_000;
+pub const MAINNET_SUPERCHARGED_COINBASE_AMOUNT: u64 = 1_440_000_000_000;
 pub const MAINNET_TRANSITION_FRONTIER_K: u32 = 290;
+pub const MAX_CONNECTIONS_DEFAULT: usize = 64;
+pub const MAX_REQUESTS_PER_SEC_DEFAULT: u32 = 20;
 pub const PRUNE_INTERVAL_DEFAULT: u32 = 10;
+pub const QUERY_THREADS_DEFAULT: usize = 4;
+pub const REST_ADDRESS_DEFAULT: &str = "127.0.0.1:8081";
 pub const SOCKET_NAME: &str = "@mina-indexer.sock";
+pub const STOP_TIMEOUT_DEFAULT_SEC: u64 = 60;
+pub const SUMMARY_WITNESS_TREE_DEPTH: u32 = 10;
+pub const WITNESS_TREE_INTERVAL_DEFAULT: u32 = 10;
@@ -0,0 +1,316 @@
+use crate::{
+    BERKELEY_CANONICAL_THRESHOLD, BERKELEY_COINBASE_AMOUNT, BERKELEY_SLOTS_PER_EPOCH,
+    BERKELEY_SLOT_DURATION_MS, BERKELEY_SUPERCHARGED_COINBASE_AMOUNT,
+    BERKELEY_TRANSITION_FRONTIER_K, DEVNET_CANONICAL_THRESHOLD, DEVNET_COINBASE_AMOUNT,
+    DEVNET_SLOTS_PER_EPOCH, DEVNET_SLOT_DURATION_MS, DEVNET_SUPERCHARGED_COINBASE_AMOUNT,
+    DEVNET_TRANSITION_FRONTIER_K, MAINNET_CANONICAL_THRESHOLD, MAINNET_COINBASE_AMOUNT,
+    MAINNET_GENESIS_CREATOR, MAINNET_GENESIS_HASH, MAINNET_GENESIS_TIMESTAMP_MS,
+    MAINNET_SLOTS_PER_EPOCH, MAINNET_SLOT_DURATION_MS, MAINNET_SUPERCHARGED_COINBASE_AMOUNT,
+    MAINNET_TRANSITION_FRONTIER_K,
+};
+use serde::{Deserialize, Serialize};
+use std::{ffi::OsStr, path::PathBuf, str::FromStr};
+use tracing::warn;
+
+/// Prefix of custom chain ids when parsing a network
+const CUSTOM_PREFIX: &str = "custom:";
+
+/// Mina network a block, store, or query belongs to
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Network {
+    #[default]
+    Mainnet,
+    Devnet,
+    Berkeley,
+    Custom {
+        chain_id: String,
+    },
+}
+
+/// Genesis and consensus parameters of a network
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NetworkProfile {
+    /// State hash of the genesis block, if known
+    pub genesis_hash: Option<&'static str>,
+    /// Creator and coinbase receiver of the genesis block, if known
+    pub genesis_creator: Option<&'static str>,
+    /// Milliseconds since the UNIX epoch at the start of the genesis slot, if known
+    pub genesis_timestamp_ms: Option<u64>,
+    /// Transition frontier length, aka `k`
+    pub transition_frontier_k: u32,
+    /// Number of confirmations after which a block is considered canonical
+    pub canonical_threshold: u32,
+    pub slots_per_epoch: u32,
+    pub slot_duration_ms: u64,
+    /// Block reward in nanomina
+    pub coinbase_amount: u64,
+    /// Block reward in nanomina of blocks whose producer's stake is unlocked
+    pub supercharged_coinbase_amount: u64,
+}
+
+impl Network {
+    /// Parameters of the network, those not known to the indexer are left out
+    ///
+    /// Custom chains use mainnet's consensus constants
+    pub fn profile(&self) -> NetworkProfile {
+        match self {
+            Network::Mainnet => NetworkProfile {
+                genesis_hash: Some(MAINNET_GENESIS_HASH),
+                genesis_creator: Some(MAINNET_GENESIS_CREATOR),
+                genesis_timestamp_ms: Some(MAINNET_GENESIS_TIMESTAMP_MS),
+                transition_frontier_k: MAINNET_TRANSITION_FRONTIER_K,
+                canonical_threshold: MAINNET_CANONICAL_THRESHOLD,
+                slots_per_epoch: MAINNET_SLOTS_PER_EPOCH,
+                slot_duration_ms: MAINNET_SLOT_DURATION_MS,
+                coinbase_amount: MAINNET_COINBASE_AMOUNT,
+                supercharged_coinbase_amount: MAINNET_SUPERCHARGED_COINBASE_AMOUNT,
+            },
+            Network::Devnet => NetworkProfile {
+                genesis_hash: None,
+                genesis_creator: None,
+                genesis_timestamp_ms: None,
+                transition_frontier_k: DEVNET_TRANSITION_FRONTIER_K,
+                canonical_threshold: DEVNET_CANONICAL_THRESHOLD,
+                slots_per_epoch: DEVNET_SLOTS_PER_EPOCH,
+                slot_duration_ms: DEVNET_SLOT_DURATION_MS,
+                coinbase_amount: DEVNET_COINBASE_AMOUNT,
+                supercharged_coinbase_amount: DEVNET_SUPERCHARGED_COINBASE_AMOUNT,
+            },
+            Network::Berkeley => NetworkProfile {
+                genesis_hash: None,
+                genesis_creator: None,
+                genesis_timestamp_ms: None,
+                transition_frontier_k: BERKELEY_TRANSITION_FRONTIER_K,
+                canonical_threshold: BERKELEY_CANONICAL_THRESHOLD,
+                slots_per_epoch: BERKELEY_SLOTS_PER_EPOCH,
+                slot_duration_ms: BERKELEY_SLOT_DURATION_MS,
+                coinbase_amount: BERKELEY_COINBASE_AMOUNT,
+                supercharged_coinbase_amount: BERKELEY_SUPERCHARGED_COINBASE_AMOUNT,
+            },
+            Network::Custom { .. } => NetworkProfile {
+                genesis_hash: None,
+                genesis_creator: None,
+                genesis_timestamp_ms: None,
+                ..Network::Mainnet.profile()
+            },
+        }
+    }
+
+    /// Name used as the precomputed block file name prefix, e.g. `mainnet-2-3N...json`
+    pub fn name(&self) -> &str {
+        match self {
+            Network::Mainnet => "mainnet",
+            Network::Devnet => "devnet",
+            Network::Berkeley => "berkeley",
+            Network::Custom { chain_id } => chain_id,
+        }
+    }
+
+    /// State hash of the network's genesis block, if known
+    pub fn genesis_hash(&self) -> Option<&'static str> {
+        self.profile().genesis_hash
+    }
+
+    /// Transition frontier length, aka `k`
+    pub fn transition_frontier_k(&self) -> u32 {
+        self.profile().transition_frontier_k
+    }
+
+    /// Number of slots in an epoch
+    pub fn slots_per_epoch(&self) -> u32 {
+        self.profile().slots_per_epoch
+    }
+
+    /// Number of confirmations after which a block is considered canonical
+    pub fn canonical_threshold(&self) -> u32 {
+        self.profile().canonical_threshold
+    }
+
+    /// Block reward in nanomina, doubled when the coinbase is supercharged
+    pub fn coinbase_amount(&self, supercharge_coinbase: bool) -> u64 {
+        let profile = self.profile();
+        match supercharge_coinbase {
+            true => profile.supercharged_coinbase_amount,
+            false => profile.coinbase_amount,
+        }
+    }
+
+    /// Milliseconds since the UNIX epoch at the start of the global slot, if the
+    /// network's genesis timestamp is known
+    pub fn slot_timestamp_ms(&self, global_slot: u32) -> Option<u64> {
+        let profile = self.profile();
+        profile
+            .genesis_timestamp_ms
+            .map(|genesis| genesis + global_slot as u64 * profile.slot_duration_ms)
+    }
+
+    /// Global slot at the time, in milliseconds since the UNIX epoch, if the network's
+    /// genesis timestamp is known. Times before genesis are in slot 0
+    pub fn global_slot_at(&self, timestamp_ms: u64) -> Option<u32> {
+        let profile = self.profile();
+        profile
+            .genesis_timestamp_ms
+            .map(|genesis| (timestamp_ms.saturating_sub(genesis) / profile.slot_duration_ms) as u32)
+    }
+
+    /// Default genesis ledger of the network, e.g. `~/.mina-indexer/genesis-ledgers/devnet.json`
+    pub fn genesis_ledger_path(&self) -> PathBuf {
+        PathBuf::from(concat!(env!("HOME"), "/.mina-indexer/genesis-ledgers"))
+            .join(format!("{}.json", self.name()))
+    }
+
+    /// Extracts the network from a precomputed block's OS file name, `configured` is the
+    /// network the indexer is configured for
+    ///
+    /// Files without a network prefix are assumed to be mainnet blocks. Custom chains' files
+    /// are prefixed with `custom:<chain id>`, or with the configured custom chain id. Unknown
+    /// prefixes, e.g. typos, are assumed to be mainnet blocks with a warning
+    pub fn from_file_name(file_name: &OsStr, configured: &Network) -> Self {
+        file_name
+            .to_str()
+            .and_then(|file_name| {
+                let mut parts = file_name.split('-');
+                let prefix = parts.next()?;
+                parts.next()?;
+                if prefix.is_empty() || prefix.parse::<u32>().is_ok() {
+                    return None;
+                }
+                if let Network::Custom { chain_id } = configured {
+                    if prefix == chain_id {
+                        return Some(configured.clone());
+                    }
+                }
+                match Network::from_str(prefix) {
+                    Ok(network) => Some(network),
+                    Err(e) => {
+                        warn!("Assuming {file_name} is a mainnet block: {e}");
+                        None
+                    }
+                }
+            })
+            .unwrap_or_default()
+    }
+}
+
+impl std::fmt::Display for Network {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Network::Custom { chain_id } => write!(f, "{CUSTOM_PREFIX}{chain_id}"),
+            _ => write!(f, "{}", self.name()),
+        }
+    }
+}
+
+impl FromStr for Network {
+    type Err = anyhow::Error;
+
+    /// Parses `mainnet`, `devnet`, `berkeley`, or `custom:<chain id>`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "mainnet" => Ok(Network::Mainnet),
+            "devnet" => Ok(Network::Devnet),
+            "berkeley" => Ok(Network::Berkeley),
+            _ => match s.strip_prefix(CUSTOM_PREFIX) {
+                Some("") => anyhow::bail!("Custom chain id cannot be empty"),
+                Some(chain_id) => Ok(Network::Custom {
+                    chain_id: chain_id.to_string(),
+                }),
+                None => Err(anyhow::Error::msg(format!(
+                    "Unknown network {s:?}, expected mainnet, devnet, berkeley, or {CUSTOM_PREFIX}<chain id>"
+                ))),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Network;
+    use std::{ffi::OsString, str::FromStr};
+
+    #[test]
+    fn network_from_file_name() {
+        let mainnet =
+            OsString::from("mainnet-2-3NLyWnjZqUECniE1q719CoLmes6WDQAod4vrTeLfN7XXJbHv6EHH.json");
+        let berkeley =
+            OsString::from("berkeley-42-3NLyWnjZqUECniE1q719CoLmes6WDQAod4vrTeLfN7XXJbHv6EHH.json");
+        let no_prefix = OsString::from("3NLyWnjZqUECniE1q719CoLmes6WDQAod4vrTeLfN7XXJbHv6EHH.json");
+
+        let mainnet_configured = Network::Mainnet;
+        assert_eq!(
+            Network::from_file_name(&mainnet, &mainnet_configured),
+            Network::Mainnet
+        );
+        assert_eq!(
+            Network::from_file_name(&berkeley, &mainnet_configured),
+            Network::Berkeley
+        );
+        assert_eq!(
+            Network::from_file_name(&no_prefix, &mainnet_configured),
+            Network::Mainnet
+        );
+
+        // custom chains are explicitly prefixed or configured
+        let testworld = Network::Custom {
+            chain_id: "testworld".to_string(),
+        };
+        let custom = OsString::from("custom:testworld-42-3NLy.json");
+        let configured = OsString::from("testworld-42-3NLy.json");
+        assert_eq!(
+            Network::from_file_name(&custom, &mainnet_configured),
+            testworld
+        );
+        assert_eq!(Network::from_file_name(&configured, &testworld), testworld);
+
+        // unknown prefixes aren't custom chains
+        assert_eq!(
+            Network::from_file_name(&configured, &mainnet_configured),
+            Network::Mainnet
+        );
+        assert_eq!(
+            Network::from_file_name(&OsString::from("mainet-42-3NLy.json"), &testworld),
+            Network::Mainnet
+        );
+    }
+
+    #[test]
+    fn network_profiles() {
+        let mainnet = Network::Mainnet.profile();
+        assert_eq!(mainnet.genesis_hash, Some(crate::MAINNET_GENESIS_HASH));
+        assert_eq!(
+            Network::Mainnet.slot_timestamp_ms(1),
+            Some(crate::MAINNET_GENESIS_TIMESTAMP_MS + mainnet.slot_duration_ms)
+        );
+        assert_eq!(Network::Mainnet.coinbase_amount(false), 720_000_000_000);
+        assert_eq!(Network::Mainnet.coinbase_amount(true), 1_440_000_000_000);
+
+        let devnet = Network::Devnet;
+        assert_eq!(devnet.genesis_hash(), None);
+        assert_eq!(devnet.slot_timestamp_ms(1), None);
+        assert!(devnet.genesis_ledger_path().ends_with("devnet.json"));
+        assert_eq!(
+            devnet.transition_frontier_k(),
+            crate::DEVNET_TRANSITION_FRONTIER_K
+        );
+        assert_eq!(
+            Network::Berkeley.canonical_threshold(),
+            crate::BERKELEY_CANONICAL_THRESHOLD
+        );
+    }
+
+    #[test]
+    fn network_round_trip() {
+        for name in ["mainnet", "devnet", "berkeley", "custom:testworld"] {
+            assert_eq!(Network::from_str(name).unwrap().to_string(), name);
+        }
+        assert_eq!(
+            Network::from_str("custom:testworld").unwrap(),
+            Network::Custom {
+                chain_id: "testworld".to_string()
+            }
+        );
+        assert!(Network::from_str("").is_err());
+        assert!(Network::from_str("mainet").is_err());
+        assert!(Network::from_str("custom:").is_err());
+    }
+}
@@ -0,0 +1,92 @@
+use daemonize::Daemonize;
+use nix::{
+    errno::Errno,
+    sys::signal::{kill, Signal},
+    unistd::Pid,
+};
+use std::{
+    fs::File,
+    path::Path,
+    time::{Duration, Instant},
+};
+use tracing::warn;
+
+/// Interval between the checks whether a stopped daemon has exited
+const STOP_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Detaches the process from its terminal and session and writes its pid to `pid_file`,
+/// its stdout and stderr go to `output`
+///
+/// Must be called before any threads are spawned, only the calling thread survives the fork.
+/// The working directory is kept so relative paths of the args still resolve
+pub fn daemonize(pid_file: &Path, output: &Path) -> anyhow::Result<()> {
+    let stdout = File::options().create(true).append(true).open(output)?;
+    let stderr = stdout.try_clone()?;
+
+    // the pid file stays locked while the daemon runs, a second one fails to start
+    Daemonize::new()
+        .pid_file(pid_file)
+        .working_directory(std::env::current_dir()?)
+        .stdout(stdout)
+        .stderr(stderr)
+        .start()
+        .map_err(|e| anyhow::Error::msg(format!("Unable to daemonize: {e}")))
+}
+
+/// Removes the daemon's pid file when it exits
+pub fn remove_pid_file(pid_file: &Path) {
+    if let Err(e) = std::fs::remove_file(pid_file) {
+        warn!("Unable to remove the pid file {}: {e}", pid_file.display());
+    }
+}
+
+/// Signals the daemon of the pid file to shut down and waits up to `timeout` for it to exit
+pub fn stop(pid_file: &Path, timeout: Duration) -> anyhow::Result<()> {
+    let pid = read_pid(pid_file)?;
+    match kill(pid, Signal::SIGTERM) {
+        Ok(()) => (),
+        Err(Errno::ESRCH) => {
+            std::fs::remove_file(pid_file)?;
+            return Err(anyhow::Error::msg(format!(
+                "No daemon with pid {pid} is running, removed the stale pid file {}",
+                pid_file.display()
+            )));
+        }
+        Err(e) => {
+            return Err(anyhow::Error::msg(format!(
+                "Unable to signal the daemon with pid {pid}: {e}"
+            )))
+        }
+    }
+
+    // the daemon shuts down cleanly, e.g. flushing the database, before it exits
+    let signaled = Instant::now();
+    while is_running(pid) {
+        if signaled.elapsed() >= timeout {
+            return Err(anyhow::Error::msg(format!(
+                "Daemon with pid {pid} is still shutting down after {timeout:?}"
+            )));
+        }
+        std::thread::sleep(STOP_POLL_INTERVAL);
+    }
+    Ok(())
+}
+
+/// Pid written to the file by [daemonize]
+pub fn read_pid(pid_file: &Path) -> anyhow::Result<Pid> {
+    let contents = std::fs::read_to_string(pid_file).map_err(|e| {
+        anyhow::Error::msg(format!(
+            "Unable to read the pid file {}, is a daemon running? {e}",
+            pid_file.display()
+        ))
+    })?;
+    let pid = contents.trim().parse().map_err(|e| {
+        anyhow::Error::msg(format!("Malformed pid file {}: {e}", pid_file.display()))
+    })?;
+    Ok(Pid::from_raw(pid))
+}
+
+fn is_running(pid: Pid) -> bool {
+    // signal 0 only checks that the process exists
+    !matches!(kill(pid, None), Err(Errno::ESRCH))
+}
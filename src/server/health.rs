@@ -0,0 +1,90 @@
+use crate::{
+    state::{metadata::store::MetadataStore, IndexerPhase},
+    store::IndexerStore,
+};
+use nix::sys::statvfs::statvfs;
+use serde_derive::{Deserialize, Serialize};
+use std::{
+    path::PathBuf,
+    sync::RwLock,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Health of the server, it's alive if it reports at all
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HealthReport {
+    /// Initialization is over and the store answers, so queries see the whole state
+    pub ready: bool,
+    pub phase: String,
+    /// Seconds since the UNIX epoch, when the last block was received
+    pub last_block_received: Option<u64>,
+    pub seconds_since_last_block: Option<u64>,
+    /// The store answered a read
+    pub store_connected: bool,
+    /// Bytes available to the server on the database's filesystem, none for an in-memory database
+    pub disk_available_bytes: Option<u64>,
+}
+
+/// Tracks the server's phase and received blocks for its health reports
+pub struct HealthMonitor {
+    /// `None` for an in-memory database
+    database_dir: Option<PathBuf>,
+    phase: RwLock<IndexerPhase>,
+    last_block_received: RwLock<Option<SystemTime>>,
+}
+
+impl HealthMonitor {
+    pub fn new(database_dir: Option<PathBuf>, phase: IndexerPhase) -> Self {
+        Self {
+            database_dir,
+            phase: RwLock::new(phase),
+            last_block_received: RwLock::new(None),
+        }
+    }
+
+    pub fn set_phase(&self, phase: IndexerPhase) {
+        *self.phase.write().unwrap() = phase;
+    }
+
+    pub fn block_received(&self) {
+        *self.last_block_received.write().unwrap() = Some(SystemTime::now());
+    }
+
+    pub fn report(&self, db: &IndexerStore) -> HealthReport {
+        let phase = self.phase.read().unwrap().clone();
+        let last_block_received = *self.last_block_received.read().unwrap();
+        let store_connected = db.get_metadata().is_ok();
+        HealthReport {
+            ready: phase == IndexerPhase::Watching && store_connected,
+            phase: phase.to_string(),
+            last_block_received: last_block_received
+                .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                .map(|since_epoch| since_epoch.as_secs()),
+            seconds_since_last_block: last_block_received
+                .and_then(|time| time.elapsed().ok())
+                .map(|elapsed| elapsed.as_secs()),
+            store_connected,
+            disk_available_bytes: self
+                .database_dir
+                .as_ref()
+                .and_then(|dir| statvfs(dir).ok())
+                .map(|stats| stats.blocks_available() as u64 * stats.fragment_size() as u64),
+        }
+    }
+}
+
+impl std::fmt::Display for HealthReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Ready:                {}", self.ready)?;
+        writeln!(f, "Phase:                {}", self.phase)?;
+        match self.seconds_since_last_block {
+            Some(seconds) => writeln!(f, "Last block received:  {seconds}s ago")?,
+            None => writeln!(f, "Last block received:  never")?,
+        }
+        writeln!(f, "Store connected:      {}", self.store_connected)?;
+        match self.disk_available_bytes {
+            Some(bytes) => write!(f, "Disk available:       {}", bytesize::ByteSize::b(bytes)),
+            None => write!(f, "Disk available:       unknown"),
+        }
+    }
+}
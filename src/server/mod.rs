@@ -1,51 +1,151 @@
+use self::{
+    health::HealthMonitor,
+    runtime::{QueryPool, RuntimeConfiguration},
+    snapshot::StateSnapshot,
+};
 use crate::{
     block::{
-        parser::BlockParser, precomputed::PrecomputedBlock, receiver::BlockReceiver,
+        parser::BlockParser, precomputed::ProtocolVersion, receiver::BlockReceiver,
         store::BlockStore, BlockHash, BlockWithoutHeight,
     },
+    error::IndexerError,
+    ipc::{
+        auth_token_matches, read_auth_token, read_request_payload, refuse_conn, write_frame,
+        BlockId, ConnLimits, IpcRequest, IpcRequestFrame, IpcResponse, IPC_PROTOCOL_VERSION,
+        STREAM_CHUNK_LEN,
+    },
+    network::Network,
     state::{
-        ledger::{self, genesis::GenesisRoot, public_key::PublicKey, Ledger},
-        summary::{SummaryShort, SummaryVerbose},
-        IndexerMode, IndexerState,
+        canonicity::store::CanonicityStore,
+        event::store::EventStore,
+        ledger::{
+            self, genesis::GenesisRoot, internal_command::store::InternalCommandStore,
+            public_key::PublicKey, staking::StakeDistribution, store::LedgerStore, Ledger,
+        },
+        metadata::store::MetadataStore,
+        summary::WitnessTreeSummaryVerbose,
+        Canonicity, IndexerMode, IndexerState,
     },
-    store::IndexerStore,
-    CANONICAL_UPDATE_THRESHOLD, MAINNET_GENESIS_HASH, MAINNET_TRANSITION_FRONTIER_K,
-    PRUNE_INTERVAL_DEFAULT, SOCKET_NAME,
+    store::{codec::ValueCodec, rocks_db::RocksDbTuningConfiguration, IndexerStore},
+    BACKUP_INTERVAL_DEFAULT_SEC, BACKUP_RETENTION_DEFAULT, CANONICAL_UPDATE_THRESHOLD,
+    CLEANUP_INTERVAL_DEFAULT_SEC, DANGLING_MAX_AGE_SLOTS_DEFAULT, DANGLING_MAX_DEPTH_DEFAULT,
+    DIFFS_MAP_CAPACITY_DEFAULT, LEDGER_SNAPSHOT_INTERVAL_DEFAULT, MAX_CONNECTIONS_DEFAULT,
+    MAX_REQUESTS_PER_SEC_DEFAULT, PRUNE_INTERVAL_DEFAULT, QUERY_THREADS_DEFAULT,
+    REST_ADDRESS_DEFAULT, SOCKET_NAME, STOP_TIMEOUT_DEFAULT_SEC, WITNESS_TREE_INTERVAL_DEFAULT,
 };
-use clap::Parser;
-use futures::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use clap::{Parser, Subcommand};
+use futures::io::{AsyncRead, AsyncWrite, BufReader};
 use interprocess::local_socket::tokio::{LocalSocketListener, LocalSocketStream};
 use log::trace;
-use std::{path::PathBuf, process, sync::Arc};
-use tokio::fs::{self, create_dir_all, metadata};
-use tracing::{debug, error, info, instrument, level_filters::LevelFilter};
-use tracing_subscriber::prelude::*;
-use uuid::Uuid;
+use nix::unistd::{chown, Group};
+use std::{
+    ffi::OsString,
+    io::Write,
+    net::SocketAddr,
+    os::unix::fs::PermissionsExt,
+    path::{Component, Path, PathBuf},
+    process,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use tokio::{
+    fs::{self, create_dir_all, metadata},
+    net::TcpListener,
+    signal::unix::{signal, Signal, SignalKind},
+    sync::{mpsc, oneshot, watch},
+    time::Interval,
+};
+use tokio_util::compat::{TokioAsyncReadCompatExt, TokioAsyncWriteCompatExt};
+use tracing::{
+    debug, error, info, info_span, instrument, level_filters::LevelFilter, warn, Instrument,
+};
+use tracing_subscriber::{prelude::*, reload};
+
+pub mod daemon;
+pub mod health;
+pub mod rest;
+pub mod rosetta;
+pub mod runtime;
+pub mod snapshot;
+pub mod websocket;
+
+/// Requests of the client connections which change the indexer state, or read what's too
+/// costly to snapshot after every block, answered by the server between blocks
+///
+/// Queries are answered from [StateSnapshot]s otherwise, without waiting on the server
+#[derive(Debug)]
+pub enum StateRequest {
+    /// Reload the config file, replying with the applied settings
+    Reload(oneshot::Sender<anyhow::Result<String>>),
+    /// Snapshot the canonical tip's ledger, replying with its state hash if it was taken
+    SnapshotLedger(oneshot::Sender<anyhow::Result<Option<BlockHash>>>),
+    /// Summary of the witness tree, which isn't part of the [StateSnapshot]s
+    WitnessTree(oneshot::Sender<WitnessTreeSummaryVerbose>),
+}
+
+/// Sends the request made by `request` to the indexer state and awaits the reply
+async fn request_state<T>(
+    state_requests: &mpsc::Sender<StateRequest>,
+    request: impl FnOnce(oneshot::Sender<T>) -> StateRequest,
+) -> anyhow::Result<T> {
+    let (reply, response) = oneshot::channel();
+    state_requests
+        .send(request(reply))
+        .await
+        .map_err(|_| anyhow::Error::msg("Indexer state unavailable"))?;
+    response
+        .await
+        .map_err(|_| anyhow::Error::msg("Indexer state unavailable"))
+}
 
 #[derive(Parser, Debug, Clone)]
-#[command(author, version, about, long_about = None)]
+#[command(author, version, about, long_about = None, args_override_self = true)]
 pub struct ServerArgs {
-    /// Path to the root ledger (if non-genesis, set --non-genesis-ledger and --root-hash)
+    /// Path to a TOML file of server args, e.g. `database_dir = "..."`, overridden by the
+    /// command line's
+    #[arg(long)]
+    config: Option<PathBuf>,
+    /// Path to the root ledger (default: the network's genesis ledger in
+    /// ~/.mina-indexer/genesis-ledgers, if non-genesis, set --non-genesis-ledger and --root-hash)
     #[arg(short, long)]
-    ledger: PathBuf,
+    ledger: Option<PathBuf>,
     /// Use a non-genesis ledger
     #[arg(short, long, default_value_t = false)]
     non_genesis_ledger: bool,
-    /// Hash of the base ledger
-    #[arg(
-        long,
-        default_value = MAINNET_GENESIS_HASH
-    )]
-    root_hash: String,
+    /// Network of the indexed blocks (mainnet, devnet, berkeley, or a custom chain id)
+    #[arg(long, default_value_t = Network::Mainnet)]
+    network: Network,
+    /// Protocol version of the indexed blocks, incompatible blocks are rejected
+    #[arg(long, default_value_t = ProtocolVersion::default())]
+    protocol_version: ProtocolVersion,
+    /// Hash of the base ledger (default: the network's genesis hash)
+    #[arg(long)]
+    root_hash: Option<String>,
     /// Path to startup blocks directory
     #[arg(short, long, default_value = concat!(env!("HOME"), "/.mina-indexer/startup-blocks"))]
     startup_dir: PathBuf,
-    /// Path to directory to watch for new blocks
+    /// Path to directory to watch for new blocks (repeat to watch multiple directories)
     #[arg(short, long, default_value = concat!(env!("HOME"), "/.mina-indexer/watch-blocks"))]
-    watch_dir: PathBuf,
+    watch_dir: Vec<PathBuf>,
     /// Path to directory for rocksdb
     #[arg(short, long, default_value = concat!(env!("HOME"), "/.mina-indexer/database"))]
     database_dir: PathBuf,
+    /// Path to a JSON file of RocksDB tuning options (default: built-in defaults)
+    #[arg(long)]
+    rocksdb_config: Option<PathBuf>,
+    /// Codec of newly written database values, existing values stay readable
+    #[arg(long, value_enum, default_value_t = ValueCodec::Bcs)]
+    value_codec: ValueCodec,
+    /// Keep the database in memory only, nothing is persisted
+    #[arg(long, default_value_t = false)]
+    in_memory: bool,
+    /// Wipe and recreate the database directory before starting, instead of restoring
+    /// the indexer state from it
+    #[arg(long, default_value_t = false)]
+    db_override: bool,
+    /// Restore the database from the latest backup in this directory before starting
+    #[arg(long)]
+    restore_from: Option<PathBuf>,
     /// Path to directory for logs
     #[arg(long, default_value = concat!(env!("HOME"), "/.mina-indexer/logs"))]
     log_dir: PathBuf,
@@ -64,21 +164,374 @@ pub struct ServerArgs {
     /// Threshold for updating the canonical tip/ledger
     #[arg(short, long, default_value_t = CANONICAL_UPDATE_THRESHOLD)]
     canonical_update_threshold: u32,
+    /// Number of confirmations before a block is marked canonical (default: the network's)
+    #[arg(long)]
+    canonical_threshold: Option<u32>,
+    /// Delete orphaned blocks more than k blocks below the canonical tip
+    #[arg(long, default_value_t = false)]
+    prune_orphaned_blocks: bool,
+    /// Number of blocks added between persisting the witness tree, a restarted server only
+    /// re-ingests the blocks added since (0: only persisted at shutdown)
+    #[arg(long, default_value_t = WITNESS_TREE_INTERVAL_DEFAULT)]
+    witness_tree_interval: u32,
+    /// Height difference between canonical ledger snapshots, historical ledgers in between
+    /// are reconstructed from the nearest snapshot below
+    #[arg(long, default_value_t = LEDGER_SNAPSHOT_INTERVAL_DEFAULT)]
+    ledger_snapshot_interval: u32,
+    /// Max number of pending blocks' ledger diffs kept in memory, older ones are spilled to
+    /// the database
+    #[arg(long, default_value_t = DIFFS_MAP_CAPACITY_DEFAULT)]
+    max_diffs_in_memory: usize,
+    /// Drop dangling branches whose blocks are all more than this many blocks below the
+    /// canonical tip, recording their blocks as orphaned
+    #[arg(long, default_value_t = DANGLING_MAX_DEPTH_DEFAULT)]
+    dangling_max_depth: u32,
+    /// Drop dangling branches whose blocks are all more than this many slots older than the
+    /// best tip, recording their blocks as orphaned
+    #[arg(long, default_value_t = DANGLING_MAX_AGE_SLOTS_DEFAULT)]
+    dangling_max_age_slots: u32,
+    /// Path to a blocks directory searched for the missing ancestors of dangling branches
+    /// taller than the transition frontier (default: no backfill)
+    #[arg(long)]
+    backfill_dir: Option<PathBuf>,
+    /// Path to directory for periodic database backups (default: no backups)
+    #[arg(long)]
+    backup_dir: Option<PathBuf>,
+    /// Path to directory for database checkpoints, checkpoint paths are relative to it
+    #[arg(long, default_value = concat!(env!("HOME"), "/.mina-indexer/checkpoints"))]
+    checkpoint_dir: PathBuf,
+    /// Interval between database backups in seconds
+    #[arg(long, default_value_t = BACKUP_INTERVAL_DEFAULT_SEC)]
+    backup_interval: u64,
+    /// Number of most recent database backups to keep
+    #[arg(long, default_value_t = BACKUP_RETENTION_DEFAULT)]
+    backup_retention: usize,
+    /// Remove pending canonicity entries, orphaned ledger snapshots, and leftover read-only
+    /// instance directories older than this many seconds (default: no cleanup)
+    #[arg(long)]
+    stale_data_max_age: Option<u64>,
+    /// Interval between stale data cleanups in seconds
+    #[arg(long, default_value_t = CLEANUP_INTERVAL_DEFAULT_SEC)]
+    cleanup_interval: u64,
+    /// Address of the REST API
+    #[arg(long, default_value = REST_ADDRESS_DEFAULT)]
+    rest_address: SocketAddr,
+    /// Also accept client connections at this TCP address, e.g. from other hosts or containers
+    /// (requires --auth-token-file)
+    #[arg(long)]
+    listen: Option<SocketAddr>,
+    /// Name of the local socket for client connections, a filesystem path unless it starts with
+    /// '@' (set a distinct one per instance to run several on one host)
+    #[arg(long, default_value = SOCKET_NAME)]
+    socket: String,
+    /// Octal filesystem mode of the socket file, e.g. 660 for its owner and group only
+    /// (default: as created, per the umask), not for namespaced sockets
+    #[arg(long, value_parser = parse_socket_mode)]
+    socket_mode: Option<u32>,
+    /// Group owning the socket file, e.g. of the client users, not for namespaced sockets
+    #[arg(long)]
+    socket_group: Option<String>,
+    /// Max number of concurrent client connections, further connections are refused
+    #[arg(long, default_value_t = MAX_CONNECTIONS_DEFAULT)]
+    max_connections: usize,
+    /// Max number of requests per second answered on a client connection, further requests
+    /// wait their turn (0: unlimited)
+    #[arg(long, default_value_t = MAX_REQUESTS_PER_SEC_DEFAULT)]
+    max_requests_per_sec: u32,
+    /// Path to a file of the token clients must present with every request, as the client's
+    /// --auth-token-file or the REST API's bearer token (default: no authentication)
+    #[arg(long)]
+    auth_token_file: Option<PathBuf>,
+    /// Worker threads ingesting blocks and serving connections (default: one per CPU core)
+    #[arg(long)]
+    worker_threads: Option<usize>,
+    /// Threads dedicated to the heavy store work of queries, e.g. ledger reconstruction
+    /// and large scans, apart from block ingestion
+    #[arg(long, default_value_t = QUERY_THREADS_DEFAULT)]
+    query_threads: usize,
+    /// Detach from the terminal and run in the background, stop it with `server stop`
+    #[arg(long, default_value_t = false)]
+    daemonize: bool,
+    /// Path to the pid file of the daemon, it's locked while the daemon runs
+    #[arg(long, default_value = concat!(env!("HOME"), "/.mina-indexer/mina-indexer.pid"))]
+    pid_file: PathBuf,
+    #[command(subcommand)]
+    pub command: Option<ServerCommand>,
+    /// Command line args overriding those of the config file, kept for reloads
+    #[arg(skip)]
+    cli_args: Vec<OsString>,
+}
+
+impl ServerArgs {
+    /// Threads of the server's runtimes, which are built before the server starts
+    pub fn runtime_configuration(&self) -> anyhow::Result<RuntimeConfiguration> {
+        if self.worker_threads == Some(0) || self.query_threads == 0 {
+            return Err(anyhow::Error::msg(
+                "--worker-threads and --query-threads must be positive",
+            ));
+        }
+        Ok(RuntimeConfiguration {
+            worker_threads: self.worker_threads,
+            query_threads: self.query_threads,
+        })
+    }
+
+    /// Detaches the server from the terminal if started with --daemonize, returning the pid
+    /// file to remove once it exits
+    ///
+    /// Must be called before the server's runtimes are built, its stdout and stderr go
+    /// to a file in the log directory
+    pub fn daemonize(&self) -> anyhow::Result<Option<PathBuf>> {
+        if !self.daemonize {
+            return Ok(None);
+        }
+        std::fs::create_dir_all(&self.log_dir)?;
+        if let Some(parent) = self.pid_file.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let output = self.log_dir.join(DAEMON_OUTPUT_FILE_NAME);
+        println!(
+            "Starting the daemon, its pid is written to {} and its output to {}",
+            self.pid_file.display(),
+            output.display()
+        );
+        daemon::daemonize(&self.pid_file, &output)?;
+        Ok(Some(self.pid_file.clone()))
+    }
+
+    /// Args of the config file, if there is one, overridden by `cli_args`, the
+    /// command line args these server args were parsed from
+    pub fn with_config_file<I>(self, cli_args: I) -> anyhow::Result<Self>
+    where
+        I: IntoIterator<Item = OsString>,
+    {
+        let config = match &self.config {
+            Some(config) => config,
+            None => return Ok(self),
+        };
+        let cli_args: Vec<OsString> = cli_args.into_iter().collect();
+        let mut args = parse_with_config_file(config, &cli_args)?;
+        args.cli_args = cli_args;
+        Ok(args)
+    }
+}
+
+/// Parses an octal permission mode, e.g. `660`
+fn parse_socket_mode(mode: &str) -> Result<u32, String> {
+    match u32::from_str_radix(mode, 8) {
+        Ok(mode) if mode <= 0o777 => Ok(mode),
+        _ => Err(format!("{mode} isn't an octal permission mode, e.g. 660")),
+    }
+}
+
+fn parse_with_config_file(config: &Path, cli_args: &[OsString]) -> anyhow::Result<ServerArgs> {
+    let mut args = vec![OsString::from("server")];
+    args.extend(config_file_args(config)?);
+
+    // args given later take precedence
+    args.extend(cli_args.iter().cloned());
+    Ok(ServerArgs::try_parse_from(args)?)
+}
+
+/// Command line args equivalent to the TOML config file at `path`, its keys
+/// are the names of the args, e.g. `database_dir` or `database-dir` for `--database-dir`
+fn config_file_args(path: &Path) -> anyhow::Result<Vec<OsString>> {
+    let contents = std::fs::read_to_string(path).map_err(|e| {
+        anyhow::Error::msg(format!(
+            "Unable to read config file {}: {e}",
+            path.display()
+        ))
+    })?;
+    let table: toml::Table = toml::from_str(&contents).map_err(|e| {
+        anyhow::Error::msg(format!("Malformed config file {}: {e}", path.display()))
+    })?;
+
+    let mut args = vec![];
+    for (key, value) in table {
+        if key == "config" {
+            return Err(anyhow::Error::msg("Config files can't include one another"));
+        }
+        let flag = OsString::from(format!("--{}", key.replace('_', "-")));
+        let values = match value {
+            toml::Value::Boolean(true) => {
+                args.push(flag);
+                continue;
+            }
+            toml::Value::Boolean(false) => continue,
+            // repeated args, e.g. watch directories
+            toml::Value::Array(values) => values,
+            value => vec![value],
+        };
+        for value in values {
+            let value = match value {
+                toml::Value::String(value) => value,
+                toml::Value::Integer(value) => value.to_string(),
+                toml::Value::Float(value) => value.to_string(),
+                value => {
+                    return Err(anyhow::Error::msg(format!(
+                        "Unsupported value of {key} in {}: {value}",
+                        path.display()
+                    )))
+                }
+            };
+            args.push(flag.clone());
+            args.push(value.into());
+        }
+    }
+    Ok(args)
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum ServerCommand {
+    /// Shut down the daemon started with --daemonize and wait for it to exit
+    Stop(StopArgs),
+}
+
+#[derive(Parser, Debug, Clone)]
+#[command(author, version, about, long_about = None)]
+pub struct StopArgs {
+    /// Path to the pid file of the daemon
+    #[arg(long, default_value = concat!(env!("HOME"), "/.mina-indexer/mina-indexer.pid"))]
+    pid_file: PathBuf,
+    /// Max seconds to wait for the daemon's clean shutdown
+    #[arg(long, default_value_t = STOP_TIMEOUT_DEFAULT_SEC)]
+    timeout: u64,
+}
+
+/// File in the log directory the daemon's stdout and stderr are appended to
+const DAEMON_OUTPUT_FILE_NAME: &str = "mina-indexer-daemon.out";
+
+/// Signals the daemon to shut down cleanly, as on SIGTERM, and waits for it to exit
+pub fn stop(args: &StopArgs) -> anyhow::Result<()> {
+    let pid = daemon::read_pid(&args.pid_file)?;
+    println!("Stopping the daemon with pid {pid}");
+    daemon::stop(&args.pid_file, Duration::from_secs(args.timeout))?;
+    println!("Stopped the daemon with pid {pid}");
+    Ok(())
+}
+
+#[derive(Parser, Debug, Clone)]
+#[command(author, version, about, long_about = None)]
+pub struct ReindexArgs {
+    /// Path to the root ledger (default: the network's genesis ledger in
+    /// ~/.mina-indexer/genesis-ledgers, if non-genesis, set --root-hash)
+    #[arg(short, long)]
+    ledger: Option<PathBuf>,
+    /// Network of the indexed blocks (mainnet, devnet, berkeley, or a custom chain id)
+    #[arg(long, default_value_t = Network::Mainnet)]
+    network: Network,
+    /// Hash of the base ledger (default: the network's genesis hash)
+    #[arg(long)]
+    root_hash: Option<String>,
+    /// Number of confirmations before a block is marked canonical (default: the network's)
+    #[arg(long)]
+    canonical_threshold: Option<u32>,
+    /// Path to directory for rocksdb
+    #[arg(short, long, default_value = concat!(env!("HOME"), "/.mina-indexer/database"))]
+    database_dir: PathBuf,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum DbCommand {
+    /// Check the database for corrupt blocks, dangling index entries, and gaps in the canonical chain,
+    /// optionally replaying the canonical ledger diffs
+    Verify(VerifyArgs),
+    /// Drop the block bodies of an epoch, e.g. after archiving a checkpoint of the database
+    DropEpoch(DropEpochArgs),
+    /// Export the stored blocks as SST files, to be imported by another database
+    ExportBlocks(ExportBlocksArgs),
+    /// Bulk load blocks exported by another database, reindex afterwards
+    ImportBlocks(ImportBlocksArgs),
+}
+
+#[derive(Parser, Debug, Clone)]
+#[command(author, version, about, long_about = None)]
+pub struct VerifyArgs {
+    /// Path to directory for rocksdb
+    #[arg(short, long, default_value = concat!(env!("HOME"), "/.mina-indexer/database"))]
+    database_dir: PathBuf,
+    /// Also replay the canonical ledger diffs, checking the stored ledger snapshots and balances
+    #[arg(long, default_value_t = false)]
+    replay: bool,
+}
+
+#[derive(Parser, Debug, Clone)]
+#[command(author, version, about, long_about = None)]
+pub struct DropEpochArgs {
+    /// Path to directory for rocksdb
+    #[arg(short, long, default_value = concat!(env!("HOME"), "/.mina-indexer/database"))]
+    database_dir: PathBuf,
+    /// Epoch whose block bodies are dropped
+    #[arg(short, long)]
+    epoch: u32,
+}
+
+#[derive(Parser, Debug, Clone)]
+#[command(author, version, about, long_about = None)]
+pub struct ExportBlocksArgs {
+    /// Path to directory for rocksdb
+    #[arg(short, long, default_value = concat!(env!("HOME"), "/.mina-indexer/database"))]
+    database_dir: PathBuf,
+    /// New directory the SST files are written to
+    #[arg(short, long)]
+    path: PathBuf,
+}
+
+#[derive(Parser, Debug, Clone)]
+#[command(author, version, about, long_about = None)]
+pub struct ImportBlocksArgs {
+    /// Path to directory for rocksdb
+    #[arg(short, long, default_value = concat!(env!("HOME"), "/.mina-indexer/database"))]
+    database_dir: PathBuf,
+    /// Directory of the exported SST files
+    #[arg(short, long)]
+    path: PathBuf,
 }
 
 pub struct IndexerConfiguration {
+    network: Network,
+    protocol_version: ProtocolVersion,
     ledger: GenesisRoot,
     non_genesis_ledger: bool,
     root_hash: BlockHash,
     startup_dir: PathBuf,
-    watch_dir: PathBuf,
+    watch_dirs: Vec<PathBuf>,
     pub database_dir: PathBuf,
+    pub in_memory: bool,
+    pub rocksdb_tuning: RocksDbTuningConfiguration,
+    pub value_codec: ValueCodec,
     keep_noncanonical_blocks: bool,
     log_file: PathBuf,
     log_level: LevelFilter,
     log_level_stdout: LevelFilter,
     prune_interval: u32,
     canonical_update_threshold: u32,
+    canonical_threshold: u32,
+    prune_orphaned_blocks: bool,
+    witness_tree_interval: u32,
+    ledger_snapshot_interval: u32,
+    max_diffs_in_memory: usize,
+    dangling_max_depth: u32,
+    dangling_max_age_slots: u32,
+    backfill_dir: Option<PathBuf>,
+    backup_dir: Option<PathBuf>,
+    checkpoint_dir: PathBuf,
+    backup_interval: Duration,
+    backup_retention: usize,
+    stale_data_max_age: Option<Duration>,
+    cleanup_interval: Duration,
+    rest_address: SocketAddr,
+    listen_address: Option<SocketAddr>,
+    socket_name: String,
+    socket_mode: Option<u32>,
+    socket_group: Option<String>,
+    max_connections: usize,
+    /// Min time between two requests answered on a client connection, none if unlimited
+    min_request_interval: Option<Duration>,
+    auth_token: Option<String>,
+    /// Reloads re-read the config file, overridden by the command line args
+    config_file: Option<PathBuf>,
+    cli_args: Vec<OsString>,
 }
 
 #[instrument(skip_all)]
@@ -86,36 +539,137 @@ pub async fn handle_command_line_arguments(
     args: ServerArgs,
 ) -> anyhow::Result<IndexerConfiguration> {
     trace!("Parsing server args");
-
     let non_genesis_ledger = args.non_genesis_ledger;
-    let root_hash = BlockHash(args.root_hash.to_string());
+    let network = args.network;
+    let ledger_path = match args.ledger {
+        Some(ledger_path) => ledger_path,
+        None if non_genesis_ledger => {
+            return Err(anyhow::Error::msg(
+                "--ledger must be given for a non-genesis ledger",
+            ))
+        }
+        None => network.genesis_ledger_path(),
+    };
+    let protocol_version = args.protocol_version;
+    let root_hash = root_hash_or_genesis(args.root_hash, &network)?;
     let startup_dir = args.startup_dir;
-    let watch_dir = args.watch_dir;
+    let watch_dirs = args.watch_dir;
     let database_dir = args.database_dir;
+    let in_memory = args.in_memory;
+    let db_override = args.db_override;
+    let rocksdb_tuning = match args.rocksdb_config {
+        Some(path) => {
+            info!("Loading RocksDB tuning options from {}", path.display());
+            RocksDbTuningConfiguration::from_file(&path)?
+        }
+        None => RocksDbTuningConfiguration::default(),
+    };
+    let value_codec = args.value_codec;
+    let restore_from = args.restore_from;
     let keep_noncanonical_blocks = args.keep_non_canonical_blocks;
     let log_dir = args.log_dir;
     let log_level = args.log_level;
     let log_level_stdout = args.log_level_stdout;
     let prune_interval = args.prune_interval;
     let canonical_update_threshold = args.canonical_update_threshold;
+    let canonical_threshold = args
+        .canonical_threshold
+        .unwrap_or_else(|| network.canonical_threshold());
+    let prune_orphaned_blocks = args.prune_orphaned_blocks;
+    let witness_tree_interval = args.witness_tree_interval;
+    let ledger_snapshot_interval = args.ledger_snapshot_interval;
+    let max_diffs_in_memory = args.max_diffs_in_memory;
+    let dangling_max_depth = args.dangling_max_depth;
+    let dangling_max_age_slots = args.dangling_max_age_slots;
+    let backfill_dir = args.backfill_dir;
+    let backup_dir = args.backup_dir;
+    let checkpoint_dir = args.checkpoint_dir;
+    let backup_interval = Duration::from_secs(args.backup_interval);
+    let backup_retention = args.backup_retention;
+    let stale_data_max_age = args.stale_data_max_age.map(Duration::from_secs);
+    let cleanup_interval = Duration::from_secs(args.cleanup_interval);
+    let rest_address = args.rest_address;
+    let listen_address = args.listen;
+    let socket_name = args.socket;
+    let socket_mode = args.socket_mode;
+    let socket_group = args.socket_group;
+    let max_connections = args.max_connections;
+    let min_request_interval = ConnLimits::min_request_interval(args.max_requests_per_sec);
+    let auth_token = args
+        .auth_token_file
+        .as_deref()
+        .map(read_auth_token)
+        .transpose()?;
+    let config_file = args.config;
+    let cli_args = args.cli_args;
 
     assert!(
         // bad things happen if this condition fails
-        canonical_update_threshold < MAINNET_TRANSITION_FRONTIER_K,
+        canonical_update_threshold < network.transition_frontier_k(),
         "canonical update threshold must be strictly less than the transition frontier length!"
     );
+    if ledger_snapshot_interval == 0 {
+        return Err(anyhow::Error::msg(
+            "Ledger snapshot interval must be positive",
+        ));
+    }
+    check_canonical_threshold(canonical_threshold, &network)?;
+
+    if in_memory && (backup_dir.is_some() || restore_from.is_some()) {
+        return Err(anyhow::Error::msg(
+            "An in-memory database cannot be backed up or restored",
+        ));
+    }
+    if let (Some(address), None) = (listen_address, auth_token.as_ref()) {
+        return Err(anyhow::Error::msg(format!(
+            "Listening at {address} over TCP needs --auth-token-file, requests from other hosts would be unauthenticated"
+        )));
+    }
+    if socket_name.starts_with('@') && (socket_mode.is_some() || socket_group.is_some()) {
+        return Err(anyhow::Error::msg(format!(
+            "Namespaced socket {socket_name} has no file, --socket-mode and --socket-group need a socket path"
+        )));
+    }
+    assert!(
+        backup_retention > 0,
+        "backup retention must keep at least one backup!"
+    );
+    assert!(
+        max_connections > 0,
+        "max connections must allow at least one connection!"
+    );
 
-    create_dir_if_non_existent(watch_dir.to_str().unwrap()).await;
+    if db_override && !in_memory && metadata(&database_dir).await.is_ok() {
+        info!(
+            "Removing database {} (--db-override)",
+            database_dir.display()
+        );
+        fs::remove_dir_all(&database_dir).await?;
+    }
+
+    if let Some(restore_dir) = restore_from {
+        info!(
+            "Restoring {} from the latest backup in {}",
+            database_dir.display(),
+            restore_dir.display()
+        );
+        IndexerStore::restore_from_latest_backup(&restore_dir, &database_dir)?;
+    }
+
+    for watch_dir in &watch_dirs {
+        create_dir_if_non_existent(watch_dir.to_str().unwrap()).await;
+    }
     create_dir_if_non_existent(log_dir.to_str().unwrap()).await;
+    create_dir_if_non_existent(checkpoint_dir.to_str().unwrap()).await;
 
-    info!("Parsing genesis ledger file at {}", args.ledger.display());
+    info!("Parsing genesis ledger file at {}", ledger_path.display());
 
-    match ledger::genesis::parse_file(&args.ledger).await {
+    match ledger::genesis::parse_file(&ledger_path).await {
         Err(err) => {
             error!(
                 reason = "Unable to parse genesis ledger",
                 error = err.to_string(),
-                path = &args.ledger.display().to_string()
+                path = &ledger_path.display().to_string()
             );
             process::exit(100)
         }
@@ -131,48 +685,127 @@ pub async fn handle_command_line_arguments(
             }
 
             Ok(IndexerConfiguration {
+                network,
+                protocol_version,
                 ledger,
                 non_genesis_ledger,
                 root_hash,
                 startup_dir,
-                watch_dir,
+                watch_dirs,
                 database_dir,
+                in_memory,
+                rocksdb_tuning,
+                value_codec,
                 keep_noncanonical_blocks,
                 log_file: PathBuf::from(&log_fname),
                 log_level,
                 log_level_stdout,
-
                 prune_interval,
                 canonical_update_threshold,
+                canonical_threshold,
+                prune_orphaned_blocks,
+                witness_tree_interval,
+                ledger_snapshot_interval,
+                max_diffs_in_memory,
+                dangling_max_depth,
+                dangling_max_age_slots,
+                backfill_dir,
+                backup_dir,
+                checkpoint_dir,
+                backup_interval,
+                backup_retention,
+                stale_data_max_age,
+                cleanup_interval,
+                rest_address,
+                listen_address,
+                socket_name,
+                socket_mode,
+                socket_group,
+                max_connections,
+                min_request_interval,
+                auth_token,
+                config_file,
+                cli_args,
             })
         }
     }
 }
 
+/// Directory of the server's read-only secondary instance, inside the database directory
+const SECONDARY_DIR_NAME: &str = "secondary";
+
+/// Min time between the snapshots published during initialization
+const STARTUP_SNAPSHOT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Time to wait before accepting connections again after failing to accept one,
+/// e.g. while out of file descriptors
+const ACCEPT_ERROR_BACKOFF: Duration = Duration::from_millis(100);
+
 #[instrument(skip_all)]
 pub async fn run(
     config: IndexerConfiguration,
     indexer_store: Arc<IndexerStore>,
+    query_pool: QueryPool,
 ) -> Result<(), anyhow::Error> {
-    debug!("Checking that a server instance isn't already running");
-    LocalSocketStream::connect(SOCKET_NAME)
+    debug!(
+        "Checking that a server instance isn't already running at {}",
+        config.socket_name
+    );
+    if LocalSocketStream::connect(config.socket_name.as_str())
         .await
-        .expect_err("Server is already running... Exiting.");
+        .is_ok()
+    {
+        return Err(anyhow::Error::msg(format!(
+            "Server is already running at {}... Exiting.",
+            config.socket_name
+        )));
+    }
+    // nobody is listening on it, left behind by a server which didn't shut down cleanly
+    remove_socket_file(&config.socket_name).await?;
 
     info!("Starting mina-indexer server");
     let IndexerConfiguration {
+        network,
+        protocol_version,
         ledger,
         non_genesis_ledger,
         root_hash,
         startup_dir,
-        watch_dir,
+        watch_dirs,
         database_dir,
+        in_memory,
+        rocksdb_tuning: _,
+        value_codec: _,
         keep_noncanonical_blocks,
         log_file,
         log_level,
         log_level_stdout,
         prune_interval,
         canonical_update_threshold,
+        canonical_threshold,
+        prune_orphaned_blocks,
+        witness_tree_interval,
+        ledger_snapshot_interval,
+        max_diffs_in_memory,
+        dangling_max_depth,
+        dangling_max_age_slots,
+        backfill_dir,
+        backup_dir,
+        checkpoint_dir,
+        backup_interval,
+        backup_retention,
+        stale_data_max_age,
+        cleanup_interval,
+        rest_address,
+        listen_address,
+        socket_name,
+        socket_mode,
+        socket_group,
+        max_connections,
+        min_request_interval,
+        auth_token,
+        config_file,
+        cli_args,
     } = config;
 
     // setup tracing
@@ -183,173 +816,1169 @@ pub async fn run(
     let log_file = std::fs::File::create(log_file)?;
     let file_layer = tracing_subscriber::fmt::layer().with_writer(log_file);
 
+    // log levels can be reloaded
+    let (log_level, log_level_handle) = reload::Layer::new(log_level);
+    let (log_level_stdout, log_level_stdout_handle) = reload::Layer::new(log_level_stdout);
     let stdout_layer = tracing_subscriber::fmt::layer();
     tracing_subscriber::registry()
         .with(stdout_layer.with_filter(log_level_stdout))
         .with(file_layer.with_filter(log_level))
         .init();
+    let reloader = ConfigReloader {
+        config_file,
+        cli_args,
+        set_log_level: Box::new(move |level| Ok(log_level_handle.reload(level)?)),
+        set_log_level_stdout: Box::new(move |level| Ok(log_level_stdout_handle.reload(level)?)),
+    };
+
+    // from here on SIGINT and SIGTERM shut the server down cleanly, SIGHUP reloads the config file
+    let mut terminate = signal(SignalKind::terminate())?;
+    let mut hangup = signal(SignalKind::hangup())?;
 
     let mode = if keep_noncanonical_blocks {
         IndexerMode::Full
     } else {
         IndexerMode::Light
     };
-    let mut indexer_state = {
+    // a database with a recorded state is picked up where the previous run left off,
+    // one of another network is never touched
+    check_database_network(&indexer_store, &database_dir, &network)?;
+    let transition_frontier_k = network.transition_frontier_k();
+    let restored = match indexer_store.get_metadata()? {
+        Some(metadata) => {
+            info!(
+                "Restoring indexer state from {}, left at best tip {:?}, canonical tip {:?} of length {} after {} blocks",
+                database_dir.display(),
+                metadata.best_tip,
+                metadata.canonical_tip,
+                metadata.max_canonical_height,
+                metadata.blocks_processed
+            );
+            true
+        }
+        None => false,
+    };
+    let mut indexer_state = if restored {
+        IndexerState::new_from_db(
+            mode,
+            network,
+            protocol_version,
+            indexer_store.clone(),
+            transition_frontier_k,
+            prune_interval,
+            canonical_update_threshold,
+            canonical_threshold,
+            prune_orphaned_blocks,
+        )
+        .map_err(|e| {
+            anyhow::Error::msg(format!(
+                "Unable to restore the indexer state from {}: {e}, start with --db-override to replace the database",
+                database_dir.display()
+            ))
+        })?
+    } else {
         info!(
             "Initializing indexer state from blocks in {}",
             startup_dir.display()
         );
         IndexerState::new(
             mode,
+            network,
+            protocol_version,
             root_hash.clone(),
             ledger.ledger,
-            indexer_store,
-            MAINNET_TRANSITION_FRONTIER_K,
+            indexer_store.clone(),
+            transition_frontier_k,
             prune_interval,
             canonical_update_threshold,
+            canonical_threshold,
+            prune_orphaned_blocks,
         )?
     };
-    let mut block_parser = BlockParser::new(&startup_dir)?;
-    if !non_genesis_ledger {
-        indexer_state
-            .initialize_with_contiguous_canonical(&mut block_parser)
-            .await?;
+
+    indexer_state.witness_tree_interval = witness_tree_interval;
+    indexer_state.ledger_snapshot_interval = ledger_snapshot_interval;
+    indexer_state.diffs_map.set_capacity(max_diffs_in_memory)?;
+    indexer_state.dangling_max_depth = dangling_max_depth;
+    indexer_state.dangling_max_age_slots = dangling_max_age_slots;
+
+    let health = Arc::new(HealthMonitor::new(
+        (!in_memory).then(|| database_dir.clone()),
+        indexer_state.phase.clone(),
+    ));
+
+    // operators pause block ingestion, e.g. during backups or disk maintenance
+    let (ingestion_paused_tx, mut ingestion_paused) = watch::channel(false);
+    let ingestion_paused_tx = Arc::new(ingestion_paused_tx);
+
+    // queries are answered from the start, against the partially built state until
+    // initialization is over, connections share one long-lived secondary instance which catches up with
+    // the primary on every read, an in-memory store is read directly
+    let readonly_store = if in_memory {
+        indexer_store.clone()
+    } else {
+        let secondary_path = database_dir.join(SECONDARY_DIR_NAME);
+        if secondary_path.exists() {
+            debug!(
+                "Removing previous readonly instance at {}",
+                secondary_path.display()
+            );
+            tokio::fs::remove_dir_all(&secondary_path).await?;
+        }
+        debug!("Spawning secondary readonly RocksDB instance");
+        Arc::new(IndexerStore::new_read_only(&database_dir, &secondary_path)?)
+    };
+
+    let listener = LocalSocketListener::bind(socket_name.as_str())?;
+    set_socket_permissions(&socket_name, socket_mode, socket_group.as_deref())?;
+    info!("Local socket listener started at {socket_name}");
+    let tcp_listener = match listen_address {
+        Some(address) => {
+            let tcp_listener = TcpListener::bind(address).await?;
+            info!("TCP listener started at {address}");
+            Some(tcp_listener)
+        }
+        None => None,
+    };
+
+    // the REST API reads blocks from the same store as the connections, both answer
+    // queries from the latest snapshot of the indexer state
+    let (state_requests_tx, mut state_requests) = mpsc::channel(64);
+    // the state isn't asked for the witness tree until initialization is over
+    let (snapshots_tx, snapshots) = watch::channel(Arc::new(
+        StateSnapshot::new(&indexer_state).with_witness_tree(&indexer_state),
+    ));
+    let snapshots_tx = Arc::new(snapshots_tx);
+    let (next_event_seq_nums_tx, next_event_seq_nums) =
+        watch::channel(indexer_store.get_next_event_seq_num()?);
+    let rest_store = readonly_store.clone();
+    let rest_snapshots = snapshots.clone();
+    let rest_state_requests = state_requests_tx.clone();
+    let rest_health = health.clone();
+    let rest_auth_token = auth_token.clone();
+    let rest_query_pool = query_pool.clone();
+    let conn_context = ConnContext {
+        primary_store: indexer_store.clone(),
+        checkpoint_dir,
+        db: readonly_store.clone(),
+        state_requests: state_requests_tx,
+        snapshots,
+        ingestion_paused: ingestion_paused_tx,
+        health: health.clone(),
+        limits: ConnLimits::new(max_connections, min_request_interval),
+        auth_token: auth_token.map(Arc::new),
+        query_pool,
+    };
+    tokio::spawn(async move {
+        if let Err(e) = rest::start_rest(
+            rest_address,
+            rest_store,
+            rest_snapshots,
+            rest_state_requests,
+            rest_health,
+            rest_auth_token,
+            next_event_seq_nums,
+            rest_query_pool,
+        )
+        .await
+        {
+            error!("REST API stopped: {e}");
+        }
+    });
+    let mut accepting = tokio::spawn(accept_conns(listener, tcp_listener, conn_context));
+
+    // after a restore only the startup blocks above the canonical tip are new
+    let mut block_parser = if restored {
+        let min_length = indexer_state
+            .canonical_tip_block()
+            .blockchain_length
+            .unwrap_or(0)
+            + 1;
+        BlockParser::new_with_canonical_threshold(
+            &startup_dir,
+            indexer_state.canonical_threshold,
+            min_length,
+        )?
     } else {
-        indexer_state
-            .initialize_without_contiguous_canonical(&mut block_parser)
-            .await?;
+        BlockParser::new_with_canonical_threshold(
+            &startup_dir,
+            indexer_state.canonical_threshold,
+            0,
+        )?
     }
+    .with_pause(ingestion_paused.clone())
+    .with_network(indexer_state.network.clone());
 
-    let mut block_receiver = BlockReceiver::new().await?;
-    block_receiver.load_directory(&watch_dir).await?;
-    info!("Block receiver set to watch {watch_dir:?}");
+    // snapshots of the partially built state are published at most once per interval
+    let startup_snapshots_tx = snapshots_tx.clone();
+    let last_startup_snapshot = Mutex::new(Instant::now());
+    indexer_state.set_progress_hook(Some(Box::new(move |state| {
+        let mut last_startup_snapshot = last_startup_snapshot.lock().unwrap();
+        if last_startup_snapshot.elapsed() >= STARTUP_SNAPSHOT_INTERVAL {
+            startup_snapshots_tx
+                .send_replace(Arc::new(StateSnapshot::new(state).with_witness_tree(state)));
+            *last_startup_snapshot = Instant::now();
+        }
+    })));
+    let initialization = async {
+        if restored {
+            indexer_state.add_blocks(&mut block_parser, 0).await
+        } else if !non_genesis_ledger {
+            indexer_state
+                .initialize_with_contiguous_canonical(&mut block_parser)
+                .await
+        } else {
+            indexer_state
+                .initialize_without_contiguous_canonical(&mut block_parser)
+                .await
+        }
+    };
+    let interrupted = tokio::select! {
+        initialized = initialization => {
+            initialized?;
+            false
+        }
+        signaled = shutdown_signal(&mut terminate) => {
+            signaled?;
+            true
+        }
+    };
+    if interrupted {
+        info!("Shutdown signal received during initialization");
+        accepting.abort();
+        return shut_down(&indexer_state, &indexer_store, &socket_name).await;
+    }
+    indexer_state.set_progress_hook(None);
+    health.set_phase(indexer_state.phase.clone());
+    snapshots_tx.send_replace(Arc::new(StateSnapshot::new(&indexer_state)));
+    next_event_seq_nums_tx.send_replace(indexer_store.get_next_event_seq_num()?);
+
+    if let Some(backup_dir) = backup_dir {
+        info!(
+            "Backing up the database to {} every {backup_interval:?}",
+            backup_dir.display()
+        );
+        tokio::spawn(indexer_store.clone().run_backups(
+            backup_dir,
+            backup_interval,
+            backup_retention,
+        ));
+    }
 
-    let listener = LocalSocketListener::bind(SOCKET_NAME)?;
-    info!("Local socket listener started");
+    if let Some(max_age) = stale_data_max_age {
+        info!("Removing data older than {max_age:?} every {cleanup_interval:?}");
+        tokio::spawn(indexer_store.clone().run_cleanup(cleanup_interval, max_age));
+    }
+
+    let mut block_receiver = BlockReceiver::new()
+        .await?
+        .with_network(indexer_state.network.clone());
+    block_receiver.load_directories(&watch_dirs).await?;
+    info!("Block receiver set to watch {watch_dirs:?}");
 
     loop {
         tokio::select! {
-            block_fut = block_receiver.recv() => {
+            block_fut = block_receiver.recv(), if !*ingestion_paused.borrow() => {
                 if let Some(block_result) = block_fut {
-                    let precomputed_block = block_result?;
+                    health.block_received();
+                    let precomputed_block = match block_result {
+                        Ok(precomputed_block) => precomputed_block,
+                        Err(e) => {
+                            error!("Unable to receive a block: {e}");
+                            continue;
+                        }
+                    };
                     let block = BlockWithoutHeight::from_precomputed(&precomputed_block);
                     debug!("Receiving block {block:?}");
 
-                    indexer_state.add_block(&precomputed_block)?;
+                    // a block which can't be added is rejected, the next ones are still ingested
+                    if let Err(e) = indexer_state.add_block(&precomputed_block) {
+                        error!("Unable to add {block:?}: {e}");
+                        let state_hash = BlockHash(precomputed_block.state_hash.clone());
+                        let reason = e.to_string();
+                        if let Err(e) = indexer_store.add_rejected_block(&state_hash, &reason) {
+                            error!("Unable to record the rejected block {state_hash:?}: {e}");
+                        }
+                        continue;
+                    }
                     info!("Added {block:?}");
+
+                    // look for the missing ancestors of dangling branches taller than k
+                    if let Some(backfill_dir) = &backfill_dir {
+                        if indexer_state.deep_dangling_branches().next().is_some() {
+                            match indexer_state.backfill_dangling_branches(backfill_dir).await {
+                                Ok(0) => (),
+                                Ok(num_added) => info!("Backfilled {num_added} ancestors of dangling branches"),
+                                Err(e) => error!("Unable to backfill dangling branches: {e}"),
+                            }
+                        }
+                    }
+                    let snapshot = snapshots_tx.borrow().next(&indexer_state);
+                    snapshots_tx.send_replace(Arc::new(snapshot));
+
+                    // wake up the event subscribers
+                    next_event_seq_nums_tx.send_replace(indexer_store.get_next_event_seq_num()?);
                 } else {
                     info!("Block receiver shutdown, system exit");
-                    return Ok(())
+                    break;
+                }
+            }
+
+            signaled = shutdown_signal(&mut terminate) => {
+                signaled?;
+                info!("Shutdown signal received");
+                break;
+            }
+
+            Some(()) = hangup.recv() => {
+                info!("Reloading the config file on SIGHUP");
+                match reloader.reload(&mut indexer_state, &mut block_receiver).await {
+                    Ok(reloaded) => info!("{reloaded}"),
+                    Err(e) => error!("Unable to reload the config file: {e}"),
+                }
+            }
+
+            Ok(()) = ingestion_paused.changed() => {
+                if *ingestion_paused.borrow() {
+                    info!("Block ingestion paused");
+                } else {
+                    info!("Block ingestion resumed");
+                }
+            }
+
+            Some(request) = state_requests.recv() => {
+                match request {
+                    StateRequest::Reload(reply) => {
+                        reply.send(reloader.reload(&mut indexer_state, &mut block_receiver).await).ok();
+                    }
+                    StateRequest::SnapshotLedger(reply) => {
+                        reply.send(indexer_state.snapshot_canonical_ledger()).ok();
+                    }
+                    StateRequest::WitnessTree(reply) => {
+                        reply.send(indexer_state.witness_tree_summary()).ok();
+                    }
                 }
             }
 
+            accepted = &mut accepting => {
+                accepted??;
+                break;
+            }
+        }
+    }
+
+    // no more blocks are ingested or connections accepted
+    drop(block_receiver);
+    accepting.abort();
+    shut_down(&indexer_state, &indexer_store, &socket_name).await
+}
+
+/// Accepts client connections, each is handled in its own task
+///
+/// Failing to accept a connection, e.g. while out of file descriptors, is logged and
+/// connections are accepted again after [ACCEPT_ERROR_BACKOFF]
+///
+/// Connections are numbered from 1, their requests' IDs are the connection's number
+/// and the request's number on it, e.g. `3.1`
+async fn accept_conns(
+    listener: LocalSocketListener,
+    tcp_listener: Option<TcpListener>,
+    conn_context: ConnContext,
+) -> anyhow::Result<()> {
+    let mut conn_id = 0;
+    loop {
+        tokio::select! {
             conn_fut = listener.accept() => {
-                let conn = conn_fut?;
-                info!("Receiving connection");
-                let best_chain = indexer_state.root_branch.longest_chain();
-
-                let primary_path = database_dir.clone();
-                let mut secondary_path = primary_path.clone();
-                secondary_path.push(Uuid::new_v4().to_string());
-
-                debug!("Spawning secondary readonly RocksDB instance");
-                let block_store_readonly = IndexerStore::new_read_only(&primary_path, &secondary_path)?;
-                let summary = indexer_state.summary_verbose();
-                let ledger = indexer_state.best_ledger()?.unwrap();
-
-                // handle the connection
-                tokio::spawn(async move {
-                    debug!("Handling connection");
-                    if let Err(e) = handle_conn(conn, block_store_readonly, best_chain, ledger, summary).await {
-                        error!("Error handling connection: {e}");
+                match conn_fut {
+                    Ok(conn) => {
+                        let (reader, writer) = conn.into_split();
+                        conn_id += 1;
+                        info!("Receiving connection {conn_id}");
+                        spawn_conn(reader, writer, conn_context.clone(), conn_id);
+                    }
+                    Err(e) => {
+                        error!("Unable to accept a connection: {e}");
+                        tokio::time::sleep(ACCEPT_ERROR_BACKOFF).await;
                     }
+                }
+            }
 
-                    debug!("Removing readonly instance at {}", secondary_path.display());
-                    tokio::fs::remove_dir_all(&secondary_path).await.ok();
-                });
+            conn_fut = async { tcp_listener.as_ref().unwrap().accept().await }, if tcp_listener.is_some() => {
+                match conn_fut {
+                    Ok((stream, peer)) => {
+                        conn_id += 1;
+                        info!("Receiving TCP connection {conn_id} from {peer}");
+                        let (reader, writer) = stream.into_split();
+                        let context = conn_context.clone();
+                        spawn_conn(reader.compat(), writer.compat_write(), context, conn_id);
+                    }
+                    Err(e) => {
+                        error!("Unable to accept a TCP connection: {e}");
+                        tokio::time::sleep(ACCEPT_ERROR_BACKOFF).await;
+                    }
+                }
             }
         }
     }
 }
 
-#[instrument(skip_all)]
-async fn handle_conn(
-    conn: LocalSocketStream,
-    db: IndexerStore,
-    best_chain: Vec<BlockHash>,
-    ledger: Ledger,
-    summary: SummaryVerbose,
-) -> Result<(), anyhow::Error> {
-    let (reader, mut writer) = conn.into_split();
+/// Resolves on the next SIGINT or SIGTERM
+async fn shutdown_signal(terminate: &mut Signal) -> std::io::Result<()> {
+    tokio::select! {
+        interrupted = tokio::signal::ctrl_c() => interrupted,
+        _ = terminate.recv() => Ok(()),
+    }
+}
+
+/// Sets a max log level at runtime
+type SetLogLevel = Box<dyn Fn(LevelFilter) -> anyhow::Result<()> + Send + Sync>;
+
+/// Re-reads the config file, overridden by the command line args, and applies
+/// the settings which don't require a restart: log levels, watch directories,
+/// and the prune interval and canonical update threshold
+struct ConfigReloader {
+    config_file: Option<PathBuf>,
+    cli_args: Vec<OsString>,
+    set_log_level: SetLogLevel,
+    set_log_level_stdout: SetLogLevel,
+}
+
+impl ConfigReloader {
+    async fn reload(
+        &self,
+        indexer_state: &mut IndexerState,
+        block_receiver: &mut BlockReceiver,
+    ) -> anyhow::Result<String> {
+        let config_file = self.config_file.as_ref().ok_or_else(|| {
+            anyhow::Error::msg("No config file to reload, start the server with --config")
+        })?;
+        let args = parse_with_config_file(config_file, &self.cli_args)?;
+        if args.canonical_update_threshold >= indexer_state.transition_frontier_length {
+            return Err(anyhow::Error::msg(
+                "canonical update threshold must be strictly less than the transition frontier length",
+            ));
+        }
+        if args.ledger_snapshot_interval == 0 {
+            return Err(anyhow::Error::msg(
+                "ledger snapshot interval must be positive",
+            ));
+        }
+
+        for watch_dir in &args.watch_dir {
+            create_dir_if_non_existent(watch_dir.to_str().unwrap()).await;
+        }
+        block_receiver.set_directories(&args.watch_dir).await?;
+        (self.set_log_level)(args.log_level)?;
+        (self.set_log_level_stdout)(args.log_level_stdout)?;
+        indexer_state.prune_interval = args.prune_interval;
+        indexer_state.canonical_update_threshold = args.canonical_update_threshold;
+        indexer_state.witness_tree_interval = args.witness_tree_interval;
+        indexer_state.ledger_snapshot_interval = args.ledger_snapshot_interval;
+
+        Ok(format!(
+            "Reloaded {}: log level {}, stdout log level {}, watching {:?}, prune interval {}, canonical update threshold {}, witness tree interval {}, ledger snapshot interval {}",
+            config_file.display(),
+            args.log_level,
+            args.log_level_stdout,
+            args.watch_dir,
+            args.prune_interval,
+            args.canonical_update_threshold,
+            args.witness_tree_interval,
+            args.ledger_snapshot_interval
+        ))
+    }
+}
+
+/// Records the indexer state's restart metadata and witness tree, persists the store's
+/// buffered writes, and removes the socket file before the server exits
+async fn shut_down(
+    indexer_state: &IndexerState,
+    indexer_store: &IndexerStore,
+    socket_name: &str,
+) -> anyhow::Result<()> {
+    info!("Shutting down");
+    indexer_state.record_metadata()?;
+    indexer_state.persist_witness_tree()?;
+    indexer_store.flush()?;
+    remove_socket_file(socket_name).await?;
+    info!("Shutdown complete");
+    Ok(())
+}
+
+/// Refuses a canonical threshold which isn't positive and less than the network's
+/// transition frontier length
+fn check_canonical_threshold(canonical_threshold: u32, network: &Network) -> anyhow::Result<()> {
+    if canonical_threshold == 0 || canonical_threshold >= network.transition_frontier_k() {
+        return Err(anyhow::Error::msg(format!(
+            "Canonical threshold {canonical_threshold} must be positive and less than the transition frontier length {}",
+            network.transition_frontier_k()
+        )));
+    }
+    Ok(())
+}
+
+/// Refuses a database holding the blocks of another network than `network`
+pub fn check_database_network(
+    indexer_store: &IndexerStore,
+    database_dir: &Path,
+    network: &Network,
+) -> anyhow::Result<()> {
+    match indexer_store.network()? {
+        Some(stored_network) if &stored_network != network => Err(anyhow::Error::msg(format!(
+            "Database {} holds {stored_network} blocks, not {network} blocks, start with --db-override to replace it",
+            database_dir.display()
+        ))),
+        _ => Ok(()),
+    }
+}
+
+/// Rebuilds the derived data of an existing database from its stored blocks
+///
+/// The server must not be running on the database
+pub async fn reindex(args: ReindexArgs) -> anyhow::Result<()> {
+    let canonical_threshold = args
+        .canonical_threshold
+        .unwrap_or_else(|| args.network.canonical_threshold());
+    check_canonical_threshold(canonical_threshold, &args.network)?;
+    let root_hash = root_hash_or_genesis(args.root_hash, &args.network)?;
+    let ledger_path = args
+        .ledger
+        .unwrap_or_else(|| args.network.genesis_ledger_path());
+    let ledger = ledger::genesis::parse_file(&ledger_path).await?;
+    let indexer_store = IndexerStore::new(&args.database_dir)?;
+
+    println!("Reindexing {}", args.database_dir.display());
+    let num_blocks =
+        indexer_store.reindex(&root_hash, ledger.ledger.into(), canonical_threshold)?;
+    println!("Reindexed {num_blocks} blocks");
+    Ok(())
+}
+
+/// Runs an offline database command
+///
+/// The server must not be running on the database
+pub async fn db(command: DbCommand) -> anyhow::Result<()> {
+    match command {
+        DbCommand::Verify(args) => {
+            let indexer_store = IndexerStore::new(&args.database_dir)?;
+
+            println!("Verifying {}", args.database_dir.display());
+            let mut problems = indexer_store.verify()?;
+            if args.replay {
+                println!("Replaying the canonical ledger diffs");
+                problems.extend(indexer_store.replay_ledgers()?);
+            }
+            for problem in &problems {
+                println!("{problem}");
+            }
+
+            if problems.is_empty() {
+                println!("No problems found");
+                Ok(())
+            } else {
+                Err(anyhow::Error::msg(format!(
+                    "Found {} problems in {}",
+                    problems.len(),
+                    args.database_dir.display()
+                )))
+            }
+        }
+        DbCommand::DropEpoch(args) => {
+            let indexer_store = IndexerStore::new(&args.database_dir)?;
+            indexer_store.drop_epoch(args.epoch)?;
+            println!(
+                "Dropped the block bodies of epoch {} from {}",
+                args.epoch,
+                args.database_dir.display()
+            );
+            Ok(())
+        }
+        DbCommand::ExportBlocks(args) => {
+            let indexer_store = IndexerStore::new(&args.database_dir)?;
+            let num_blocks = indexer_store.export_blocks(&args.path)?;
+            println!(
+                "Exported {num_blocks} blocks from {} to {}",
+                args.database_dir.display(),
+                args.path.display()
+            );
+            Ok(())
+        }
+        DbCommand::ImportBlocks(args) => {
+            let indexer_store = IndexerStore::new(&args.database_dir)?;
+            indexer_store.import_blocks(&args.path)?;
+            println!(
+                "Imported the blocks in {} into {}, reindex to derive their data",
+                args.path.display(),
+                args.database_dir.display()
+            );
+            Ok(())
+        }
+    }
+}
+
+/// Supplied root hash, or the network's genesis hash if none is supplied
+fn root_hash_or_genesis(root_hash: Option<String>, network: &Network) -> anyhow::Result<BlockHash> {
+    match root_hash {
+        Some(root_hash) => Ok(BlockHash(root_hash)),
+        None => match network.genesis_hash() {
+            Some(genesis_hash) => Ok(BlockHash(genesis_hash.to_string())),
+            None => Err(anyhow::Error::msg(format!(
+                "Unknown genesis hash for {network}, --root-hash must be supplied"
+            ))),
+        },
+    }
+}
+
+/// Sets the mode and owning group of the local socket's file, clients need its
+/// write permission to connect
+pub fn set_socket_permissions(
+    socket_name: &str,
+    mode: Option<u32>,
+    group: Option<&str>,
+) -> anyhow::Result<()> {
+    if let Some(group) = group {
+        let gid = Group::from_name(group)?
+            .ok_or_else(|| anyhow::Error::msg(format!("Unknown socket group {group}")))?
+            .gid;
+        chown(socket_name, None, Some(gid)).map_err(|e| {
+            anyhow::Error::msg(format!(
+                "Unable to set the group of {socket_name} to {group}: {e}"
+            ))
+        })?;
+        debug!("Socket {socket_name} owned by group {group}");
+    }
+    if let Some(mode) = mode {
+        std::fs::set_permissions(socket_name, std::fs::Permissions::from_mode(mode))?;
+        debug!("Socket {socket_name} mode set to {mode:o}");
+    }
+    Ok(())
+}
+
+/// Removes the file of the local socket, namespaced socket names (starting with '@') have none
+async fn remove_socket_file(socket_name: &str) -> anyhow::Result<()> {
+    if !socket_name.starts_with('@') && metadata(socket_name).await.is_ok() {
+        info!("Removing socket file {socket_name}");
+        fs::remove_file(socket_name).await?;
+    }
+    Ok(())
+}
+
+/// What the client connections' requests are answered with
+#[derive(Clone)]
+struct ConnContext {
+    /// Compactions and checkpoints are done on the primary instance
+    primary_store: Arc<IndexerStore>,
+    /// Checkpoint paths are relative to this directory
+    checkpoint_dir: PathBuf,
+    db: Arc<IndexerStore>,
+    state_requests: mpsc::Sender<StateRequest>,
+    /// Latest snapshot of the indexer state
+    snapshots: watch::Receiver<Arc<StateSnapshot>>,
+    /// Block ingestion is paused while `true`
+    ingestion_paused: Arc<watch::Sender<bool>>,
+    health: Arc<HealthMonitor>,
+    limits: ConnLimits,
+    /// Requests without this token are refused
+    auth_token: Option<Arc<String>>,
+    query_pool: QueryPool,
+}
+
+/// Handles the connection in its own task, its requests query the indexer
+/// state via the server's loop so the connection may stay open
+fn spawn_conn<R, W>(mut reader: R, mut writer: W, context: ConnContext, conn_id: u64)
+where
+    R: AsyncRead + Unpin + Send + 'static,
+    W: AsyncWrite + Unpin + Send + 'static,
+{
+    tokio::spawn(async move {
+        // the permit is released when the connection closes
+        let _permit = match context.limits.try_open() {
+            Some(permit) => permit,
+            None => {
+                warn!("Refusing connection {conn_id}, max number of connections reached");
+                refuse_conn(&mut reader, &mut writer).await;
+                return;
+            }
+        };
+        debug!("Handling connection {conn_id}");
+        if let Err(e) = handle_conn(reader, writer, context, conn_id).await {
+            error!("Error handling connection {conn_id}: {e}");
+        }
+    });
+}
+
+#[instrument(skip(reader, writer, context))]
+async fn handle_conn<R, W>(
+    reader: R,
+    mut writer: W,
+    context: ConnContext,
+    conn_id: u64,
+) -> Result<(), anyhow::Error>
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
     let mut reader = BufReader::new(reader);
-    let mut buffer = Vec::with_capacity(128);
-    let _read = reader.read_until(0, &mut buffer).await?;
-
-    let mut buffers = buffer.split(|byte| *byte == 32);
-    let command = buffers.next().unwrap();
-    let command_string = String::from_utf8(command.to_vec())?;
-
-    match command_string.as_str() {
-        "account" => {
-            let data_buffer = buffers.next().unwrap();
-            let public_key = PublicKey::from_address(&String::from_utf8(
-                data_buffer[..data_buffer.len() - 1].to_vec(),
-            )?)?;
-            info!("Received account command for {public_key:?}");
-            debug!("Using ledger {ledger:?}");
-            let account = ledger.accounts.get(&public_key);
-            if let Some(account) = account {
-                debug!("Writing account {account:?} to client");
-                let bytes = bcs::to_bytes(account)?;
-                writer.write_all(&bytes).await?;
+
+    // requests beyond the rate limit wait their turn
+    let mut request_ticks = context.limits.request_ticks();
+
+    // requests are answered in order until the client closes the connection
+    let mut num_requests = 0;
+    loop {
+        num_requests += 1;
+        let request_id = format!("{conn_id}.{num_requests}");
+        let span = info_span!("request", %request_id);
+        let answered = match read_request_payload(&mut reader).await {
+            Ok(Some(payload)) => {
+                handle_frame(
+                    &payload,
+                    &request_id,
+                    &context,
+                    &mut writer,
+                    request_ticks.as_mut(),
+                )
+                .instrument(span)
+                .await
+            }
+            Ok(None) => {
+                debug!("Client closed the connection");
+                return Ok(());
+            }
+            Err(e) => {
+                let err_msg = format!("Malformed request: {e}");
+                span.in_scope(|| error!("{err_msg}"));
+                Err(IpcResponse::Error(err_msg))
+            }
+        };
+        let (response, refused) = match answered {
+            Ok(response) => (response, false),
+            Err(response) => (response, true),
+        };
+
+        // errors carry the request ID, it's in the server's logs of the request
+        let response = match response {
+            IpcResponse::Error(err_msg) => {
+                IpcResponse::Error(format!("{err_msg} (request {request_id})"))
+            }
+            response => response,
+        };
+        write_frame(&mut writer, &response).await?;
+
+        // a refused client doesn't get to try again on the same connection
+        if refused {
+            return Err(anyhow::Error::msg(format!(
+                "Refused request {request_id}, closing the connection"
+            )));
+        }
+    }
+}
+
+/// Answers the request of the frame's payload, `Err` with the response to a request
+/// which is refused, e.g. a malformed or unauthorized one
+async fn handle_frame<W>(
+    payload: &[u8],
+    request_id: &str,
+    context: &ConnContext,
+    writer: &mut W,
+    request_ticks: Option<&mut Interval>,
+) -> Result<IpcResponse, IpcResponse>
+where
+    W: AsyncWrite + Unpin,
+{
+    // the rest of a request of another protocol version isn't read, it may not deserialize
+    let frame = match IpcRequestFrame::protocol_version(payload) {
+        Ok(IPC_PROTOCOL_VERSION) => bcs::from_bytes::<IpcRequestFrame>(payload)
+            .map_err(|e| anyhow::Error::msg(format!("Malformed request: {e}"))),
+        Ok(client_version) => {
+            warn!(
+                "Refusing request of IPC protocol version {client_version}, the server's is {IPC_PROTOCOL_VERSION}"
+            );
+            return Err(IpcResponse::UnsupportedVersion {
+                server_version: IPC_PROTOCOL_VERSION,
+                client_version,
+            });
+        }
+        Err(e) => Err(anyhow::Error::msg(format!("Malformed request: {e}"))),
+    };
+    let frame = match frame {
+        Ok(frame) => frame,
+        Err(e) => {
+            error!("{e}");
+            return Err(IpcResponse::Error(e.to_string()));
+        }
+    };
+
+    if let Some(auth_token) = &context.auth_token {
+        if !auth_token_matches(auth_token, frame.auth_token.as_deref()) {
+            let err_msg = "Unauthorized request, a valid auth token is required".to_string();
+            warn!("{err_msg}");
+            return Err(IpcResponse::Error(err_msg));
+        }
+    }
+    if let Some(request_ticks) = request_ticks {
+        request_ticks.tick().await;
+    }
+
+    let response = match handle_request(frame.request, context, writer).await {
+        Ok(response) => response,
+        Err(e) => {
+            error!("Error handling request: {e}");
+            IpcResponse::Failed {
+                error: e.into(),
+                request_id: request_id.to_string(),
             }
         }
-        "best_chain" => {
+    };
+
+    // answers from the partially built state are flagged
+    let (initializing, blocks_processed) = {
+        let snapshot = context.snapshots.borrow();
+        (snapshot.initializing, snapshot.blocks_processed)
+    };
+    Ok(match response {
+        IpcResponse::Error(_)
+        | IpcResponse::Failed { .. }
+        | IpcResponse::EndOfStream
+        | IpcResponse::UnsupportedVersion { .. } => response,
+        response if initializing => IpcResponse::Initializing {
+            blocks_processed,
+            response: Box::new(response),
+        },
+        response => response,
+    })
+}
+
+/// Writes the ledger to `path`, formatted straight into the file, not into a string first
+async fn write_ledger(ledger: Ledger, path: PathBuf) -> anyhow::Result<IpcResponse> {
+    debug!("Writing ledger to {}", path.display());
+    let ledger_path = path.clone();
+    tokio::task::spawn_blocking(move || -> anyhow::Result<()> {
+        let mut file = std::io::BufWriter::new(std::fs::File::create(ledger_path)?);
+        write!(file, "{ledger:?}")?;
+        file.flush()?;
+        Ok(())
+    })
+    .await??;
+    Ok(IpcResponse::Message(format!(
+        "Ledger written to {}",
+        path.display()
+    )))
+}
+
+/// Path of a requested checkpoint in the checkpoint directory, requests can't escape it
+pub fn checkpoint_path(checkpoint_dir: &Path, path: &Path) -> Result<PathBuf, IndexerError> {
+    if path.as_os_str().is_empty()
+        || !path
+            .components()
+            .all(|component| matches!(component, Component::Normal(_) | Component::CurDir))
+    {
+        return Err(IndexerError::InvalidRequest(format!(
+            "Checkpoint path {} must be relative to the checkpoint directory, without '..'",
+            path.display()
+        )));
+    }
+    Ok(checkpoint_dir.join(path))
+}
+
+/// Public key of the address of a request
+fn parse_public_key(address: &str) -> Result<PublicKey, IndexerError> {
+    PublicKey::from_address(address)
+        .map_err(|e| IndexerError::InvalidRequest(format!("Malformed public key {address}: {e}")))
+}
+
+/// Answers a single request, streamed responses are written to `writer` chunk by
+/// chunk before their end is returned
+async fn handle_request<W>(
+    request: IpcRequest,
+    context: &ConnContext,
+    writer: &mut W,
+) -> anyhow::Result<IpcResponse>
+where
+    W: AsyncWrite + Unpin,
+{
+    let ConnContext {
+        primary_store,
+        checkpoint_dir,
+        db,
+        state_requests,
+        snapshots,
+        ingestion_paused,
+        health,
+        query_pool,
+        ..
+    } = context;
+
+    // the snapshot is taken up front, later blocks don't affect the response
+    let snapshot = snapshots.borrow().clone();
+    Ok(match request {
+        IpcRequest::Account { public_key } => {
+            let public_key = parse_public_key(&public_key)?;
+            info!("Received account command for {public_key:?}");
+
+            // the ledger is reconstructed from the stored diffs
+            let account = query_pool
+                .run(move || snapshot.account(&public_key))
+                .await??;
+            debug!("Writing account {account:?} to client");
+            IpcResponse::Account(account)
+        }
+        IpcRequest::BestChain { num } => {
             info!("Received best_chain command");
-            let data_buffer = buffers.next().unwrap();
-            let num = String::from_utf8(data_buffer[..data_buffer.len() - 1].to_vec())?
-                .parse::<usize>()?;
-            let best_chain: Vec<PrecomputedBlock> = best_chain[..best_chain.len() - 1]
+            let best_chain = &snapshot.best_chain;
+            let best_chain = &best_chain[..best_chain.len().saturating_sub(1)];
+
+            // only a chunk of blocks is in memory at a time
+            for state_hashes in best_chain[..num.min(best_chain.len())].chunks(STREAM_CHUNK_LEN) {
+                let mut blocks = Vec::with_capacity(state_hashes.len());
+                for state_hash in state_hashes {
+                    let block = db
+                        .get_block(state_hash)?
+                        .ok_or_else(|| IndexerError::MissingBlock(state_hash.0.clone()))?;
+                    blocks.push(block);
+                }
+                write_frame(writer, &IpcResponse::BestChain(blocks)).await?;
+            }
+            IpcResponse::EndOfStream
+        }
+        IpcRequest::Subscribe => {
+            info!("Received subscribe command");
+            let mut snapshots = snapshots.clone();
+            let mut tips = snapshot.tips();
+            write_frame(writer, &IpcResponse::Tips(tips.clone())).await?;
+
+            // a client which went away is noticed at the next push
+            while snapshots.changed().await.is_ok() {
+                let new_tips = snapshots.borrow_and_update().tips();
+                if new_tips != tips {
+                    write_frame(writer, &IpcResponse::Tips(new_tips.clone())).await?;
+                    tips = new_tips;
+                }
+            }
+            IpcResponse::EndOfStream
+        }
+        IpcRequest::Block {
+            state_hash,
+            verbose,
+        } => {
+            info!("Received block command for {state_hash}");
+            let state_hash = BlockHash(state_hash);
+            let block = rest::block_json(db, &state_hash)?;
+            let precomputed = if verbose && block.is_some() {
+                db.get_block(&state_hash)?
+            } else {
+                None
+            };
+            IpcResponse::Block {
+                block: block.map(Box::new),
+                precomputed: precomputed.map(Box::new),
+            }
+        }
+        IpcRequest::Transactions {
+            public_key,
+            offset,
+            limit,
+        } => {
+            let public_key = parse_public_key(&public_key)?;
+            info!("Received transactions command for {public_key:?}");
+            IpcResponse::Transactions(rest::account_transactions(db, &public_key, offset, limit)?)
+        }
+        IpcRequest::StakeDistribution { epoch } => {
+            info!("Received stake_distribution command");
+            let best_tip = snapshot.best_tip.clone();
+            let ledger = match epoch {
+                Some(epoch) => db
+                    .get_staking_ledger(epoch)?
+                    .ok_or(IndexerError::MissingStakingLedger(epoch))?,
+                None => query_pool
+                    .run(move || snapshot.best_ledger())
+                    .await??
+                    .ok_or_else(|| IndexerError::MissingLedger(best_tip.0.clone()))?,
+            };
+            IpcResponse::StakeDistribution(Box::new(StakeDistribution::new(&ledger, epoch)))
+        }
+        IpcRequest::InternalCommands { state_hash } => {
+            info!("Received internal_commands command for {state_hash}");
+            let state_hash = BlockHash(state_hash);
+            IpcResponse::InternalCommands(match db.get_block_header(&state_hash)? {
+                Some(_) => Some(
+                    db.get_internal_commands(&state_hash)?
+                        .into_iter()
+                        .map(|internal_command| internal_command.command)
+                        .collect(),
+                ),
+                None => None,
+            })
+        }
+        IpcRequest::SnarkWork { prover } => {
+            let prover = parse_public_key(&prover)?;
+            info!("Received snark_work command for {prover:?}");
+            let db = db.clone();
+            let snark_work = query_pool
+                .run(move || db.get_canonical_snark_work_by_prover(&prover))
+                .await??;
+            IpcResponse::SnarkWork(Box::new(snark_work))
+        }
+        IpcRequest::TopAccounts { num } => {
+            info!("Received top_accounts command");
+            let best_tip = snapshot.best_tip.clone();
+            let ledger = query_pool
+                .run(move || snapshot.best_ledger())
+                .await??
+                .ok_or_else(|| IndexerError::MissingLedger(best_tip.0.clone()))?;
+            IpcResponse::Accounts(ledger.top_accounts(num))
+        }
+        IpcRequest::BlockProduction { range } => {
+            info!("Received block_production command for {range:?}");
+
+            // the whole blocks-by-creator index is read
+            let db = db.clone();
+            let production = query_pool
+                .run(move || db.get_block_production(&range))
+                .await??;
+            IpcResponse::BlockProduction(production)
+        }
+        IpcRequest::SkippedSlots { epoch, verbose } => {
+            info!("Received skipped_slots command for epoch {epoch:?}");
+
+            // the canonical chain is read up to the epoch
+            let db = db.clone();
+            let epoch_slots = query_pool
+                .run(move || db.get_epoch_slots(epoch, verbose))
+                .await??;
+            IpcResponse::EpochSlots(epoch_slots)
+        }
+        IpcRequest::Canonicity { state_hash } => {
+            info!("Received canonicity command for {state_hash}");
+            IpcResponse::BlockStatus(snapshot.block_status(&BlockHash(state_hash))?)
+        }
+        IpcRequest::BlocksAtHeight { blockchain_length } => {
+            info!("Received blocks_at_height command for {blockchain_length}");
+            let blocks = db
+                .get_blocks_at_height(blockchain_length)?
                 .iter()
-                .take(num)
-                .cloned()
-                .map(|state_hash| db.get_block(&state_hash).unwrap().unwrap())
-                .collect();
-            let bytes = bcs::to_bytes(&best_chain)?;
-            writer.write_all(&bytes).await?;
-        }
-        "best_ledger" => {
+                .filter_map(|state_hash| rest::block_json(db, state_hash).transpose())
+                .collect::<anyhow::Result<_>>()?;
+            IpcResponse::Blocks(blocks)
+        }
+        IpcRequest::BestLedger { path } => {
             info!("Received best_ledger command");
-            let data_buffer = buffers.next().unwrap();
-            let path = &String::from_utf8(data_buffer[..data_buffer.len() - 1].to_vec())?
-                .parse::<PathBuf>()?;
-            debug!("Writing ledger to {}", path.display());
-            fs::write(path, format!("{ledger:?}")).await?;
-            let bytes = bcs::to_bytes(&format!("Ledger written to {}", path.display()))?;
-            writer.write_all(&bytes).await?;
-        }
-        "summary" => {
+            let best_tip = snapshot.best_tip.clone();
+            let ledger = query_pool
+                .run(move || snapshot.best_ledger())
+                .await??
+                .ok_or_else(|| IndexerError::MissingLedger(best_tip.0.clone()))?;
+            write_ledger(ledger, path).await?
+        }
+        IpcRequest::Ledger { block, path } => {
+            info!("Received ledger command for {block:?}");
+            let state_hash = match block {
+                BlockId::Length(length) => snapshot
+                    .canonical_chain(length)
+                    .next()
+                    .transpose()?
+                    .map(|(_, state_hash)| state_hash)
+                    .ok_or(IndexerError::MissingCanonicalBlock(length))?,
+                BlockId::StateHash(state_hash) => {
+                    let state_hash = BlockHash(state_hash);
+                    if db.get_canonicity(&state_hash)? != Some(Canonicity::Canonical) {
+                        return Err(IndexerError::NotCanonical(state_hash.0).into());
+                    }
+                    state_hash
+                }
+            };
+
+            // reconstructed from the nearest snapshot and the diffs since
+            let db = db.clone();
+            let missing_ledger = IndexerError::MissingLedger(state_hash.0.clone());
+            let ledger = query_pool
+                .run(move || db.get_ledger(&state_hash))
+                .await??
+                .ok_or(missing_ledger)?;
+            match path {
+                Some(path) => write_ledger(ledger, path).await?,
+                None => {
+                    let accounts: Vec<_> = ledger.accounts.into_values().collect();
+                    for accounts in accounts.chunks(STREAM_CHUNK_LEN) {
+                        write_frame(writer, &IpcResponse::Accounts(accounts.to_vec())).await?;
+                    }
+                    IpcResponse::EndOfStream
+                }
+            }
+        }
+        IpcRequest::Compact { column_family } => {
+            info!("Received compact command");
+
+            // compaction runs on the primary instance and may take a while
+            let name = column_family.clone();
+            let primary_store = primary_store.clone();
+            let result =
+                tokio::task::spawn_blocking(move || primary_store.compact(name.as_deref())).await?;
+            let column_family = column_family.as_deref().unwrap_or("all");
+            IpcResponse::Message(match result {
+                Ok(()) => format!("Compacted {column_family}"),
+                Err(e) => format!("Compaction failed: {e}"),
+            })
+        }
+        IpcRequest::Checkpoint { path } => {
+            info!("Received checkpoint command for {}", path.display());
+
+            // checkpoints are taken of the primary instance
+            let path = checkpoint_path(checkpoint_dir, &path)?;
+            let dst_path = path.clone();
+            let primary_store = primary_store.clone();
+            let result =
+                tokio::task::spawn_blocking(move || primary_store.checkpoint(&dst_path)).await?;
+            IpcResponse::Message(match result {
+                Ok(()) => format!("Checkpoint written to {}", path.display()),
+                Err(e) => format!("Checkpoint failed: {e}"),
+            })
+        }
+        IpcRequest::Summary { verbosity } => {
             info!("Received summary command");
-            let data_buffer = buffers.next().unwrap();
-            let verbose = String::from_utf8(data_buffer[..data_buffer.len() - 1].to_vec())?
-                .parse::<bool>()?;
-            if verbose {
-                let bytes = bcs::to_bytes(&summary)?;
-                writer.write_all(&bytes).await?;
+            let summary = snapshot.summary(state_requests).await?;
+            if verbosity > 0 {
+                IpcResponse::SummaryVerbose(Box::new(summary.with_verbosity(verbosity)))
             } else {
-                let summary: SummaryShort = summary.into();
-                let bytes = bcs::to_bytes(&summary)?;
-                writer.write_all(&bytes).await?;
+                IpcResponse::SummaryShort(Box::new(summary.into()))
             }
         }
-        bad_request => {
-            let err_msg = format!("Malformed request: {bad_request}");
-            error!("{err_msg}");
-            return Err(anyhow::Error::msg(err_msg));
+        IpcRequest::Reload => {
+            info!("Received reload command");
+            IpcResponse::Message(request_state(state_requests, StateRequest::Reload).await??)
         }
-    }
-
-    Ok(())
+        IpcRequest::SnapshotLedger => {
+            info!("Received snapshot ledger command");
+            IpcResponse::Message(
+                match request_state(state_requests, StateRequest::SnapshotLedger).await?? {
+                    Some(state_hash) => {
+                        format!("Snapshotted the ledger of canonical tip {}", state_hash.0)
+                    }
+                    None => "The canonical tip's ledger is unavailable".to_string(),
+                },
+            )
+        }
+        IpcRequest::Health => {
+            debug!("Received health command");
+            IpcResponse::Health(health.report(db))
+        }
+        IpcRequest::Pause => {
+            info!("Received pause command");
+            IpcResponse::Message(if ingestion_paused.send_replace(true) {
+                "Block ingestion is already paused".to_string()
+            } else {
+                "Paused block ingestion".to_string()
+            })
+        }
+        IpcRequest::Resume => {
+            info!("Received resume command");
+            IpcResponse::Message(if ingestion_paused.send_replace(false) {
+                "Resumed block ingestion".to_string()
+            } else {
+                "Block ingestion isn't paused".to_string()
+            })
+        }
+    })
 }
 
 async fn create_dir_if_non_existent(path: &str) {
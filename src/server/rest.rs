@@ -0,0 +1,447 @@
+use super::{
+    health::HealthMonitor, rosetta, runtime::QueryPool, snapshot::StateSnapshot, websocket,
+    StateRequest,
+};
+use crate::{
+    block::{metadata::BlockMetadata, store::BlockStore, BlockHash},
+    ipc::auth_token_matches,
+    state::{
+        canonicity::store::CanonicityStore,
+        ledger::{
+            account::Account,
+            command::{
+                store::CommandStore, CommandStatusData, CommandWithStateHash, SignedCommand,
+            },
+            public_key::PublicKey,
+        },
+        summary::SummaryShort,
+        Canonicity,
+    },
+    store::IndexerStore,
+};
+use actix_web::{
+    dev::{Service, ServiceRequest},
+    get,
+    http::header::{HeaderName, HeaderValue, AUTHORIZATION},
+    middleware,
+    web::{Data, Path, Query},
+    App, HttpResponse, HttpServer, Responder,
+};
+use futures::{future::Either, FutureExt};
+use mina_serialization_types::staged_ledger_diff::{
+    SignedCommandPayloadBody, StakeDelegation, UserCommand,
+};
+use serde_derive::{Deserialize, Serialize};
+use std::{net::SocketAddr, sync::Arc};
+use tokio::sync::{mpsc, watch};
+use tracing::{error, info};
+
+/// Block header fields and per-block totals, without the block body
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockJson {
+    pub state_hash: String,
+    pub parent_hash: String,
+    pub blockchain_length: Option<u32>,
+    pub global_slot_since_genesis: u32,
+    pub epoch: u32,
+    pub timestamp: u64,
+    pub block_creator: String,
+    pub coinbase_receiver: String,
+    pub canonicity: Option<Canonicity>,
+    pub metadata: Option<BlockMetadata>,
+    /// Hashes of the block's user commands, in block order
+    pub command_hashes: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AccountJson {
+    pub public_key: String,
+    pub balance: u64,
+    pub nonce: u32,
+    pub delegate: Option<String>,
+}
+
+impl From<Account> for AccountJson {
+    fn from(account: Account) -> Self {
+        Self {
+            public_key: account.public_key.to_address(),
+            balance: account.balance.0,
+            nonce: account.nonce.0,
+            delegate: account.delegate.map(|delegate| delegate.to_address()),
+        }
+    }
+}
+
+/// User command sent or received by an account, from the account's side
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TransactionJson {
+    pub hash: String,
+    /// `payment` or `delegation`
+    pub kind: String,
+    /// The account is the command's source, i.e. the payer or delegator
+    pub sent: bool,
+    /// Receiver or new delegate of a sent command, source of a received one
+    pub counterparty: String,
+    /// `None` for delegations
+    pub amount: Option<u64>,
+    pub fee: u64,
+    pub state_hash: String,
+    pub blockchain_length: Option<u32>,
+    /// `applied` or `failed`
+    pub status: String,
+}
+
+/// Default and max number of transactions in a page
+pub const TRANSACTIONS_PAGE_LEN_DEFAULT: usize = 50;
+pub const TRANSACTIONS_PAGE_LEN_MAX: usize = 1000;
+
+#[derive(Debug, Serialize)]
+struct ErrorJson {
+    error: String,
+}
+
+fn error_response(mut response: actix_web::HttpResponseBuilder, error: String) -> HttpResponse {
+    response.json(ErrorJson { error })
+}
+
+fn internal_error(e: anyhow::Error) -> HttpResponse {
+    error!("REST API error: {e}");
+    error_response(HttpResponse::InternalServerError(), e.to_string())
+}
+
+/// JSON view of the stored block, if there is one
+pub fn block_json(db: &IndexerStore, state_hash: &BlockHash) -> anyhow::Result<Option<BlockJson>> {
+    let header = match db.get_block_header(state_hash)? {
+        Some(header) => header,
+        None => return genesis_block_json(db, state_hash),
+    };
+    Ok(Some(BlockJson {
+        state_hash: header.state_hash.clone(),
+        parent_hash: header.parent_hash().0,
+        blockchain_length: header.blockchain_length,
+        global_slot_since_genesis: header.global_slot_since_genesis(),
+        epoch: header.epoch(),
+        timestamp: header.timestamp(),
+        block_creator: header.block_creator().to_address(),
+        coinbase_receiver: header.coinbase_receiver().to_address(),
+        canonicity: db.get_canonicity(state_hash)?,
+        metadata: db.get_block_metadata(state_hash)?,
+        command_hashes: db.get_command_hashes_in_block(state_hash)?,
+    }))
+}
+
+/// JSON view of the genesis block, which has no precomputed block
+fn genesis_block_json(
+    db: &IndexerStore,
+    state_hash: &BlockHash,
+) -> anyhow::Result<Option<BlockJson>> {
+    let genesis = match db.get_genesis_block()? {
+        Some(genesis) if &genesis.state_hash == state_hash => genesis,
+        _ => return Ok(None),
+    };
+    let network = db.network()?.unwrap_or_default();
+    let creator = network.profile().genesis_creator.unwrap_or_default();
+    Ok(Some(BlockJson {
+        state_hash: genesis.state_hash.0.clone(),
+        parent_hash: genesis.parent_hash.0,
+        blockchain_length: genesis.blockchain_length,
+        global_slot_since_genesis: genesis.global_slot_since_genesis,
+        epoch: genesis.global_slot_since_genesis / network.slots_per_epoch(),
+        timestamp: network
+            .slot_timestamp_ms(genesis.global_slot_since_genesis)
+            .unwrap_or(0),
+        block_creator: creator.to_string(),
+        coinbase_receiver: creator.to_string(),
+        canonicity: db.get_canonicity(state_hash)?,
+        metadata: None,
+        command_hashes: vec![],
+    }))
+}
+
+/// JSON view of the command from the side of `pk`, its source or receiver
+pub fn transaction_json(
+    pk: &PublicKey,
+    command: &CommandWithStateHash,
+) -> anyhow::Result<TransactionJson> {
+    let UserCommand::SignedCommand(signed_command) = command.command.clone().data();
+    let signed_command = SignedCommand(signed_command);
+    let (kind, source, receiver, amount): (_, PublicKey, PublicKey, _) =
+        match signed_command.payload_body() {
+            SignedCommandPayloadBody::PaymentPayload(payment_payload) => {
+                let payment = payment_payload.inner().inner();
+                (
+                    "payment",
+                    payment.source_pk.into(),
+                    payment.receiver_pk.into(),
+                    Some(payment.amount.inner().inner()),
+                )
+            }
+            SignedCommandPayloadBody::StakeDelegation(delegation_payload) => {
+                let StakeDelegation::SetDelegate {
+                    delegator,
+                    new_delegate,
+                } = delegation_payload.inner();
+                ("delegation", delegator.into(), new_delegate.into(), None)
+            }
+        };
+    let sent = &source == pk;
+    let counterparty = if sent { receiver } else { source };
+    Ok(TransactionJson {
+        hash: signed_command.hash_signed_command()?,
+        kind: kind.to_string(),
+        sent,
+        counterparty: counterparty.to_address(),
+        amount,
+        fee: signed_command.payload_common().fee.inner().inner(),
+        state_hash: command.state_hash.0.clone(),
+        blockchain_length: command.blockchain_length,
+        status: match command.command.status_data() {
+            CommandStatusData::Applied { .. } => "applied".to_string(),
+            CommandStatusData::Failed => "failed".to_string(),
+        },
+    })
+}
+
+/// Page of the account's transactions, ordered by blockchain length
+pub fn account_transactions(
+    db: &IndexerStore,
+    pk: &PublicKey,
+    offset: usize,
+    limit: usize,
+) -> anyhow::Result<Vec<TransactionJson>> {
+    db.get_commands_for_public_key_page(pk, offset, limit.min(TRANSACTIONS_PAGE_LEN_MAX))?
+        .iter()
+        .map(|command| transaction_json(pk, command))
+        .collect()
+}
+
+#[get("/blocks/{state_hash}")]
+async fn block(db: Data<Arc<IndexerStore>>, state_hash: Path<String>) -> impl Responder {
+    let state_hash = BlockHash(state_hash.into_inner());
+    match block_json(&db, &state_hash) {
+        Ok(Some(block)) => HttpResponse::Ok().json(block),
+        Ok(None) => error_response(
+            HttpResponse::NotFound(),
+            format!("Block {} not found", state_hash.0),
+        ),
+        Err(e) => internal_error(e),
+    }
+}
+
+#[get("/blocks/height/{blockchain_length}")]
+async fn blocks_at_height(
+    db: Data<Arc<IndexerStore>>,
+    blockchain_length: Path<u32>,
+) -> impl Responder {
+    let blocks: anyhow::Result<Vec<BlockJson>> = db
+        .get_blocks_at_height(blockchain_length.into_inner())
+        .and_then(|state_hashes| {
+            state_hashes
+                .iter()
+                .filter_map(|state_hash| block_json(&db, state_hash).transpose())
+                .collect()
+        });
+    match blocks {
+        Ok(blocks) => HttpResponse::Ok().json(blocks),
+        Err(e) => internal_error(e),
+    }
+}
+
+#[get("/accounts/{public_key}")]
+async fn account(
+    snapshots: Data<watch::Receiver<Arc<StateSnapshot>>>,
+    query_pool: Data<QueryPool>,
+    public_key: Path<String>,
+) -> impl Responder {
+    let address = public_key.into_inner();
+    let public_key = match PublicKey::from_address(&address) {
+        Ok(public_key) => public_key,
+        Err(e) => {
+            return error_response(
+                HttpResponse::BadRequest(),
+                format!("Invalid public key {address}: {e}"),
+            )
+        }
+    };
+
+    let snapshot = snapshots.borrow().clone();
+    let account = query_pool.run(move || snapshot.account(&public_key)).await;
+    match account {
+        Ok(Ok(Some(account))) => HttpResponse::Ok().json(AccountJson::from(account)),
+        Ok(Ok(None)) => error_response(
+            HttpResponse::NotFound(),
+            format!("Account {address} not found"),
+        ),
+        Ok(Err(e)) | Err(e) => internal_error(e),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct PageParams {
+    #[serde(default)]
+    offset: usize,
+    limit: Option<usize>,
+}
+
+#[get("/accounts/{public_key}/transactions")]
+async fn transactions(
+    db: Data<Arc<IndexerStore>>,
+    public_key: Path<String>,
+    params: Query<PageParams>,
+) -> impl Responder {
+    let address = public_key.into_inner();
+    let public_key = match PublicKey::from_address(&address) {
+        Ok(public_key) => public_key,
+        Err(e) => {
+            return error_response(
+                HttpResponse::BadRequest(),
+                format!("Invalid public key {address}: {e}"),
+            )
+        }
+    };
+    let limit = params.limit.unwrap_or(TRANSACTIONS_PAGE_LEN_DEFAULT);
+    match account_transactions(&db, &public_key, params.offset, limit) {
+        Ok(transactions) => HttpResponse::Ok().json(transactions),
+        Err(e) => internal_error(e),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct SummaryParams {
+    #[serde(default)]
+    verbose: bool,
+    /// Takes precedence over `verbose`, which is verbosity 1
+    #[serde(default)]
+    verbosity: Option<u8>,
+}
+
+#[get("/summary")]
+async fn summary(
+    snapshots: Data<watch::Receiver<Arc<StateSnapshot>>>,
+    state_requests: Data<mpsc::Sender<StateRequest>>,
+    params: Query<SummaryParams>,
+) -> impl Responder {
+    let snapshot = snapshots.borrow().clone();
+    let summary = match snapshot.summary(&state_requests).await {
+        Ok(summary) => summary,
+        Err(e) => return internal_error(e),
+    };
+    let verbosity = params.verbosity.unwrap_or(params.verbose as u8);
+    if verbosity > 0 {
+        HttpResponse::Ok().json(summary.with_verbosity(verbosity))
+    } else {
+        HttpResponse::Ok().json(SummaryShort::from(summary))
+    }
+}
+
+/// Always answers while the server is alive
+#[get("/health")]
+async fn health(db: Data<Arc<IndexerStore>>, monitor: Data<Arc<HealthMonitor>>) -> impl Responder {
+    HttpResponse::Ok().json(monitor.report(&db))
+}
+
+/// Answers 503 until the server is ready to serve queries, to gate traffic during initialization
+#[get("/ready")]
+async fn ready(db: Data<Arc<IndexerStore>>, monitor: Data<Arc<HealthMonitor>>) -> impl Responder {
+    let report = monitor.report(&db);
+    if report.ready {
+        HttpResponse::Ok().json(report)
+    } else {
+        HttpResponse::ServiceUnavailable().json(report)
+    }
+}
+
+/// Health checks are answered without the auth token, for orchestrators
+fn is_authorized(req: &ServiceRequest, auth_token: Option<&str>) -> bool {
+    let auth_token = match auth_token {
+        Some(auth_token) => auth_token,
+        None => return true,
+    };
+    if matches!(req.path(), "/health" | "/ready") {
+        return true;
+    }
+    let bearer_token = req
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    auth_token_matches(auth_token, bearer_token)
+}
+
+/// Header of the responses answered from the partially built state, during initialization
+const INITIALIZING_HEADER: &str = "x-indexer-initializing";
+
+/// Serves the REST API, the WebSocket event subscriptions, and the Rosetta Data API at `address`,
+/// blocks and events are read from `db` and the rest from the latest of the
+/// indexer state's `snapshots`, or requested of it through `state_requests`
+///
+/// With an `auth_token`, requests must present it as a bearer token
+///
+/// Requests are answered during initialization too, flagged with an [INITIALIZING_HEADER]
+pub async fn start_rest(
+    address: SocketAddr,
+    db: Arc<IndexerStore>,
+    snapshots: watch::Receiver<Arc<StateSnapshot>>,
+    state_requests: mpsc::Sender<StateRequest>,
+    health_monitor: Arc<HealthMonitor>,
+    auth_token: Option<String>,
+    next_event_seq_nums: watch::Receiver<u64>,
+    query_pool: QueryPool,
+) -> std::io::Result<()> {
+    info!("REST API listening on {address}");
+    HttpServer::new(move || {
+        let auth_token = auth_token.clone();
+        let phase_snapshots = snapshots.clone();
+        App::new()
+            .app_data(Data::new(db.clone()))
+            .app_data(Data::new(snapshots.clone()))
+            .app_data(Data::new(state_requests.clone()))
+            .app_data(Data::new(health_monitor.clone()))
+            .app_data(Data::new(next_event_seq_nums.clone()))
+            .app_data(Data::new(query_pool.clone()))
+            .service(websocket::events)
+            .service(rosetta::scope())
+            .service(blocks_at_height)
+            .service(block)
+            .service(account)
+            .service(transactions)
+            .service(summary)
+            .service(health)
+            .service(ready)
+            .wrap_fn(move |req, srv| {
+                let initializing = phase_snapshots.borrow().initializing;
+                srv.call(req).map(move |res| {
+                    res.map(|mut res| {
+                        if initializing {
+                            res.headers_mut().insert(
+                                HeaderName::from_static(INITIALIZING_HEADER),
+                                HeaderValue::from_static("true"),
+                            );
+                        }
+                        res
+                    })
+                })
+            })
+            .wrap_fn(move |req, srv| {
+                if is_authorized(&req, auth_token.as_deref()) {
+                    Either::Left(
+                        srv.call(req)
+                            .map(|res| res.map(|res| res.map_into_left_body())),
+                    )
+                } else {
+                    let response = error_response(
+                        HttpResponse::Unauthorized(),
+                        "A valid bearer token is required".to_string(),
+                    );
+                    Either::Right(futures::future::ready(Ok(req
+                        .into_response(response)
+                        .map_into_right_body())))
+                }
+            })
+            .wrap(middleware::Logger::default())
+    })
+    .bind(address)?
+    .disable_signals()
+    .run()
+    .await
+}
@@ -0,0 +1,615 @@
+use crate::{
+    block::{precomputed::PrecomputedBlockHeader, store::BlockStore, BlockHash},
+    network::Network,
+    state::{
+        canonicity::store::CanonicityStore,
+        ledger::{
+            command::{
+                store::CommandStore, CommandStatusData, SignedCommand, UserCommandWithStatus,
+            },
+            internal_command::{store::InternalCommandStore, InternalCommand},
+            public_key::PublicKey,
+            store::LedgerStore,
+        },
+        metadata::store::MetadataStore,
+    },
+    store::IndexerStore,
+};
+use actix_web::{
+    post,
+    web::{self, Data, Json},
+    HttpResponse, Scope,
+};
+use mina_serialization_types::staged_ledger_diff::{
+    SignedCommandPayloadBody, StakeDelegation, UserCommand,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tracing::debug;
+
+/// Version of the Rosetta specification implemented
+pub const ROSETTA_VERSION: &str = "1.4.13";
+
+const BLOCKCHAIN: &str = "mina";
+const OPERATION_TYPES: [&str; 6] = [
+    "fee_payer_dec",
+    "payment_source_dec",
+    "payment_receiver_inc",
+    "delegate_change",
+    "coinbase_inc",
+    "fee_receiver_inc",
+];
+const SUCCESS: &str = "Success";
+const FAILED: &str = "Failed";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NetworkIdentifier {
+    pub blockchain: String,
+    pub network: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockIdentifier {
+    pub index: u64,
+    pub hash: String,
+}
+
+/// Block looked up by length, state hash, or both, the canonical tip if neither
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PartialBlockIdentifier {
+    pub index: Option<u64>,
+    pub hash: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AccountIdentifier {
+    pub address: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Currency {
+    pub symbol: String,
+    pub decimals: u32,
+}
+
+/// Amount in nanomina, negative for debits
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Amount {
+    pub value: String,
+    pub currency: Currency,
+}
+
+impl Amount {
+    fn mina(value: i128) -> Self {
+        Self {
+            value: value.to_string(),
+            currency: Currency {
+                symbol: "MINA".to_string(),
+                decimals: 9,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OperationIdentifier {
+    pub index: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Operation {
+    pub operation_identifier: OperationIdentifier,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub related_operations: Option<Vec<OperationIdentifier>>,
+    #[serde(rename = "type")]
+    pub operation_type: String,
+    pub status: Option<String>,
+    pub account: AccountIdentifier,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub amount: Option<Amount>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TransactionIdentifier {
+    pub hash: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Transaction {
+    pub transaction_identifier: TransactionIdentifier,
+    pub operations: Vec<Operation>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Block {
+    pub block_identifier: BlockIdentifier,
+    pub parent_block_identifier: BlockIdentifier,
+    /// Milliseconds since the Unix epoch
+    pub timestamp: u64,
+    pub transactions: Vec<Transaction>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RosettaError {
+    pub code: u32,
+    pub message: String,
+    pub retriable: bool,
+}
+
+impl RosettaError {
+    fn network_mismatch(network_identifier: &NetworkIdentifier) -> Self {
+        Self {
+            code: 1,
+            message: format!(
+                "Unsupported network {}/{}",
+                network_identifier.blockchain, network_identifier.network
+            ),
+            retriable: false,
+        }
+    }
+
+    fn block_not_found(block_identifier: &PartialBlockIdentifier) -> Self {
+        Self {
+            code: 2,
+            message: format!(
+                "Block not found (index {:?}, hash {:?})",
+                block_identifier.index, block_identifier.hash
+            ),
+            retriable: true,
+        }
+    }
+
+    fn store(e: anyhow::Error) -> Self {
+        Self {
+            code: 3,
+            message: format!("Store error: {e}"),
+            retriable: true,
+        }
+    }
+
+    fn mempool_unavailable() -> Self {
+        Self {
+            code: 4,
+            message: "The indexer doesn't track the mempool".to_string(),
+            retriable: false,
+        }
+    }
+
+    fn invalid_account(address: &str) -> Self {
+        Self {
+            code: 5,
+            message: format!("Invalid account address {address}"),
+            retriable: false,
+        }
+    }
+
+    /// Every error the API may return, for `/network/options`
+    fn all() -> Vec<Self> {
+        let network_identifier = NetworkIdentifier {
+            blockchain: BLOCKCHAIN.to_string(),
+            network: String::new(),
+        };
+        vec![
+            Self::network_mismatch(&network_identifier),
+            Self::block_not_found(&PartialBlockIdentifier::default()),
+            Self::store(anyhow::Error::msg("")),
+            Self::mempool_unavailable(),
+            Self::invalid_account(""),
+        ]
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct NetworkRequest {
+    pub network_identifier: NetworkIdentifier,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct BlockRequest {
+    pub network_identifier: NetworkIdentifier,
+    pub block_identifier: PartialBlockIdentifier,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct AccountBalanceRequest {
+    pub network_identifier: NetworkIdentifier,
+    pub account_identifier: AccountIdentifier,
+    pub block_identifier: Option<PartialBlockIdentifier>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NetworkStatusResponse {
+    pub current_block_identifier: BlockIdentifier,
+    pub current_block_timestamp: u64,
+    pub genesis_block_identifier: BlockIdentifier,
+    pub peers: Vec<serde_json::Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AccountBalanceResponse {
+    pub block_identifier: BlockIdentifier,
+    pub balances: Vec<Amount>,
+    pub metadata: serde_json::Value,
+}
+
+/// Network of the stored blocks, mainnet if none are stored yet
+fn network(db: &IndexerStore) -> Result<Network, RosettaError> {
+    Ok(db
+        .network()
+        .map_err(RosettaError::store)?
+        .unwrap_or_default())
+}
+
+fn network_identifier(network: &Network) -> NetworkIdentifier {
+    NetworkIdentifier {
+        blockchain: BLOCKCHAIN.to_string(),
+        network: network.to_string(),
+    }
+}
+
+/// Checks the request is for the network of the stored blocks
+fn check_network(
+    db: &IndexerStore,
+    network_identifier: &NetworkIdentifier,
+) -> Result<Network, RosettaError> {
+    let network = network(db)?;
+    if *network_identifier != self::network_identifier(&network) {
+        return Err(RosettaError::network_mismatch(network_identifier));
+    }
+    Ok(network)
+}
+
+fn identify_block(header: &PrecomputedBlockHeader) -> BlockIdentifier {
+    BlockIdentifier {
+        index: header.blockchain_length.unwrap_or_default() as u64,
+        hash: header.state_hash.clone(),
+    }
+}
+
+/// Header of the identified block, by state hash, or by length on the
+/// canonical chain, or the canonical tip if neither is given
+fn resolve_block(
+    db: &IndexerStore,
+    block_identifier: &PartialBlockIdentifier,
+) -> Result<PrecomputedBlockHeader, RosettaError> {
+    let not_found = || RosettaError::block_not_found(block_identifier);
+    let state_hash = match (&block_identifier.hash, block_identifier.index) {
+        (Some(hash), _) => BlockHash(hash.clone()),
+        (None, Some(index)) => {
+            let index = u32::try_from(index).map_err(|_| not_found())?;
+            db.get_canonical_hash_at_height(index)
+                .map_err(RosettaError::store)?
+                .ok_or_else(not_found)?
+        }
+        (None, None) => {
+            db.get_metadata()
+                .map_err(RosettaError::store)?
+                .ok_or_else(not_found)?
+                .canonical_tip
+        }
+    };
+
+    let header = db
+        .get_block_header(&state_hash)
+        .map_err(RosettaError::store)?
+        .ok_or_else(not_found)?;
+    match block_identifier.index {
+        Some(index) if index != header.blockchain_length.unwrap_or_default() as u64 => {
+            Err(not_found())
+        }
+        _ => Ok(header),
+    }
+}
+
+fn operation(
+    index: u64,
+    operation_type: &str,
+    status: &str,
+    account: &PublicKey,
+    amount: Option<i128>,
+) -> Operation {
+    Operation {
+        operation_identifier: OperationIdentifier { index },
+        related_operations: None,
+        operation_type: operation_type.to_string(),
+        status: Some(status.to_string()),
+        account: AccountIdentifier {
+            address: account.to_address(),
+        },
+        amount: amount.map(Amount::mina),
+        metadata: None,
+    }
+}
+
+/// Fee payment, then the payment or delegation, which only succeeds if the
+/// command was applied
+pub fn user_command_transaction(command: &UserCommandWithStatus) -> anyhow::Result<Transaction> {
+    let status = match command.status_data() {
+        CommandStatusData::Applied { .. } => SUCCESS,
+        CommandStatusData::Failed => FAILED,
+    };
+    let UserCommand::SignedCommand(signed_command) = command.clone().data();
+    let signed_command = SignedCommand(signed_command);
+    let fee: u64 = signed_command.payload_common().fee.inner().inner();
+
+    let mut operations = vec![operation(
+        0,
+        "fee_payer_dec",
+        SUCCESS,
+        &signed_command.fee_payer_pk(),
+        Some(-(fee as i128)),
+    )];
+    match signed_command.payload_body() {
+        SignedCommandPayloadBody::PaymentPayload(payment_payload) => {
+            let payment = payment_payload.inner().inner();
+            let amount: u64 = payment.amount.inner().inner();
+            operations.push(operation(
+                1,
+                "payment_source_dec",
+                status,
+                &payment.source_pk.into(),
+                Some(-(amount as i128)),
+            ));
+            let mut receiver_inc = operation(
+                2,
+                "payment_receiver_inc",
+                status,
+                &payment.receiver_pk.into(),
+                Some(amount as i128),
+            );
+            receiver_inc.related_operations = Some(vec![OperationIdentifier { index: 1 }]);
+            operations.push(receiver_inc);
+        }
+        SignedCommandPayloadBody::StakeDelegation(delegation_payload) => {
+            let StakeDelegation::SetDelegate {
+                delegator,
+                new_delegate,
+            } = delegation_payload.inner();
+            let mut delegate_change =
+                operation(1, "delegate_change", status, &delegator.into(), None);
+            delegate_change.metadata = Some(serde_json::json!({
+                "delegate_change_target": PublicKey::from(new_delegate).to_address(),
+            }));
+            operations.push(delegate_change);
+        }
+    }
+
+    Ok(Transaction {
+        transaction_identifier: TransactionIdentifier {
+            hash: signed_command.hash_signed_command()?,
+        },
+        operations,
+    })
+}
+
+/// Coinbases and fee transfers are credits only, their transaction hash is
+/// made up of the block's state hash and the command's position in the block
+pub fn internal_command_transaction(
+    state_hash: &BlockHash,
+    position: usize,
+    command: &InternalCommand,
+) -> Transaction {
+    let (kind, operation_type, amount) = match command {
+        InternalCommand::Coinbase { amount, .. } => ("coinbase", "coinbase_inc", amount),
+        InternalCommand::FeeTransfer { amount, .. } => ("fee_transfer", "fee_receiver_inc", amount),
+        InternalCommand::FeeTransferViaCoinbase { amount, .. } => {
+            ("fee_transfer_via_coinbase", "fee_receiver_inc", amount)
+        }
+    };
+    Transaction {
+        transaction_identifier: TransactionIdentifier {
+            hash: format!("{}:{position}:{kind}", state_hash.0),
+        },
+        operations: vec![operation(
+            0,
+            operation_type,
+            SUCCESS,
+            command.receiver(),
+            Some(amount.0 as i128),
+        )],
+    }
+}
+
+/// Rosetta block of the identified stored block, user commands first
+pub fn block(
+    db: &IndexerStore,
+    block_identifier: &PartialBlockIdentifier,
+) -> Result<Block, RosettaError> {
+    let header = resolve_block(db, block_identifier)?;
+    let state_hash = BlockHash(header.state_hash.clone());
+
+    let mut transactions = vec![];
+    for command in db
+        .get_commands_in_block(&state_hash)
+        .map_err(RosettaError::store)?
+    {
+        transactions.push(user_command_transaction(&command.command).map_err(RosettaError::store)?);
+    }
+    for (position, command) in db
+        .get_internal_commands(&state_hash)
+        .map_err(RosettaError::store)?
+        .iter()
+        .enumerate()
+    {
+        transactions.push(internal_command_transaction(
+            &state_hash,
+            position,
+            &command.command,
+        ));
+    }
+
+    let block_identifier = identify_block(&header);
+    let parent_block_identifier = if block_identifier.index <= 1 {
+        // the genesis block is its own parent
+        block_identifier.clone()
+    } else {
+        BlockIdentifier {
+            index: block_identifier.index - 1,
+            hash: header.parent_hash().0,
+        }
+    };
+    Ok(Block {
+        block_identifier,
+        parent_block_identifier,
+        timestamp: header.timestamp(),
+        transactions,
+    })
+}
+
+/// Canonical tip and genesis block of the stored chain
+pub fn network_status(
+    db: &IndexerStore,
+    network: &Network,
+) -> Result<NetworkStatusResponse, RosettaError> {
+    let current = resolve_block(db, &PartialBlockIdentifier::default())?;
+    let genesis_block_identifier = match network.genesis_hash() {
+        Some(genesis_hash) => BlockIdentifier {
+            index: 1,
+            hash: genesis_hash.to_string(),
+        },
+        None => identify_block(&resolve_block(
+            db,
+            &PartialBlockIdentifier {
+                index: Some(1),
+                hash: None,
+            },
+        )?),
+    };
+    Ok(NetworkStatusResponse {
+        current_block_identifier: identify_block(&current),
+        current_block_timestamp: current.timestamp(),
+        genesis_block_identifier,
+        peers: vec![],
+    })
+}
+
+/// Balance and nonce of the account in the ledger of the identified block
+pub fn account_balance(
+    db: &IndexerStore,
+    account_identifier: &AccountIdentifier,
+    block_identifier: &PartialBlockIdentifier,
+) -> Result<AccountBalanceResponse, RosettaError> {
+    let public_key = PublicKey::from_address(&account_identifier.address)
+        .map_err(|_| RosettaError::invalid_account(&account_identifier.address))?;
+    let header = resolve_block(db, block_identifier)?;
+    let ledger = db
+        .get_ledger(&BlockHash(header.state_hash.clone()))
+        .map_err(RosettaError::store)?
+        .ok_or_else(|| RosettaError::block_not_found(block_identifier))?;
+
+    // accounts missing from the ledger have a zero balance
+    let (balance, nonce) = ledger
+        .accounts
+        .get(&public_key)
+        .map_or((0, 0), |account| (account.balance.0, account.nonce.0));
+    Ok(AccountBalanceResponse {
+        block_identifier: identify_block(&header),
+        balances: vec![Amount::mina(balance as i128)],
+        metadata: serde_json::json!({ "nonce": nonce.to_string() }),
+    })
+}
+
+fn respond<T: Serialize>(result: Result<T, RosettaError>) -> HttpResponse {
+    match result {
+        Ok(response) => HttpResponse::Ok().json(response),
+        Err(e) => {
+            debug!("Rosetta API error {}: {}", e.code, e.message);
+            HttpResponse::InternalServerError().json(e)
+        }
+    }
+}
+
+#[post("/network/list")]
+async fn network_list(db: Data<Arc<IndexerStore>>) -> HttpResponse {
+    respond(network(&db).map(
+        |network| serde_json::json!({ "network_identifiers": [network_identifier(&network)] }),
+    ))
+}
+
+#[post("/network/status")]
+async fn network_status_handler(
+    db: Data<Arc<IndexerStore>>,
+    request: Json<NetworkRequest>,
+) -> HttpResponse {
+    respond(
+        check_network(&db, &request.network_identifier)
+            .and_then(|network| network_status(&db, &network)),
+    )
+}
+
+#[post("/network/options")]
+async fn network_options(
+    db: Data<Arc<IndexerStore>>,
+    request: Json<NetworkRequest>,
+) -> HttpResponse {
+    respond(check_network(&db, &request.network_identifier).map(|_| {
+        serde_json::json!({
+            "version": {
+                "rosetta_version": ROSETTA_VERSION,
+                "node_version": env!("CARGO_PKG_VERSION"),
+            },
+            "allow": {
+                "operation_statuses": [
+                    { "status": SUCCESS, "successful": true },
+                    { "status": FAILED, "successful": false },
+                ],
+                "operation_types": OPERATION_TYPES,
+                "errors": RosettaError::all(),
+                "historical_balance_lookup": true,
+            },
+        })
+    }))
+}
+
+#[post("/block")]
+async fn block_handler(db: Data<Arc<IndexerStore>>, request: Json<BlockRequest>) -> HttpResponse {
+    respond(
+        check_network(&db, &request.network_identifier)
+            .and_then(|_| block(&db, &request.block_identifier))
+            .map(|block| serde_json::json!({ "block": block })),
+    )
+}
+
+#[post("/account/balance")]
+async fn account_balance_handler(
+    db: Data<Arc<IndexerStore>>,
+    request: Json<AccountBalanceRequest>,
+) -> HttpResponse {
+    respond(
+        check_network(&db, &request.network_identifier).and_then(|_| {
+            account_balance(
+                &db,
+                &request.account_identifier,
+                &request.block_identifier.clone().unwrap_or_default(),
+            )
+        }),
+    )
+}
+
+/// The indexer doesn't see pending transactions, the mempool is always empty
+#[post("/mempool")]
+async fn mempool(db: Data<Arc<IndexerStore>>, request: Json<NetworkRequest>) -> HttpResponse {
+    respond(
+        check_network(&db, &request.network_identifier)
+            .map(|_| serde_json::json!({ "transaction_identifiers": [] })),
+    )
+}
+
+#[post("/mempool/transaction")]
+async fn mempool_transaction() -> HttpResponse {
+    respond::<()>(Err(RosettaError::mempool_unavailable()))
+}
+
+/// Rosetta Data API endpoints, under `/rosetta`
+pub fn scope() -> Scope {
+    web::scope("/rosetta")
+        .service(network_list)
+        .service(network_status_handler)
+        .service(network_options)
+        .service(block_handler)
+        .service(account_balance_handler)
+        .service(mempool)
+        .service(mempool_transaction)
+}
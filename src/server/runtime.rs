@@ -0,0 +1,61 @@
+use std::io;
+use tokio::runtime::{Builder, Handle, Runtime};
+use tracing::Span;
+
+/// Threads of the server's runtimes, queries' heavy store work runs apart from block
+/// ingestion so neither delays the other
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuntimeConfiguration {
+    /// Worker threads of the runtime ingesting blocks and serving connections, one
+    /// per CPU core if `None`
+    pub worker_threads: Option<usize>,
+    /// Threads of the query pool
+    pub query_threads: usize,
+}
+
+impl RuntimeConfiguration {
+    /// Runtime ingesting blocks and serving connections
+    pub fn build_runtime(&self) -> io::Result<Runtime> {
+        let mut builder = Builder::new_multi_thread();
+        if let Some(worker_threads) = self.worker_threads {
+            builder.worker_threads(worker_threads);
+        }
+        builder.thread_name("mina-indexer").enable_all().build()
+    }
+
+    /// Runtime of the query pool, only its blocking threads do any work
+    pub fn build_query_runtime(&self) -> io::Result<Runtime> {
+        Builder::new_multi_thread()
+            .worker_threads(1)
+            .max_blocking_threads(self.query_threads)
+            .thread_name("mina-indexer-query")
+            .enable_all()
+            .build()
+    }
+}
+
+/// Threads for the heavy store work of queries, e.g. ledger reconstruction and large
+/// scans, so it doesn't take the blocking threads block ingestion relies on
+#[derive(Debug, Clone)]
+pub struct QueryPool {
+    handle: Handle,
+}
+
+impl QueryPool {
+    pub fn new(handle: Handle) -> Self {
+        Self { handle }
+    }
+
+    /// Runs the store work on the pool, within the caller's tracing span
+    pub async fn run<F, T>(&self, work: F) -> anyhow::Result<T>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let span = Span::current();
+        Ok(self
+            .handle
+            .spawn_blocking(move || span.in_scope(work))
+            .await?)
+    }
+}
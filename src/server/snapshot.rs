@@ -0,0 +1,209 @@
+use super::{request_state, StateRequest};
+use crate::{
+    block::{store::BlockStore, BlockHash},
+    network::Network,
+    state::{
+        canonicity::{chain::CanonicalChain, store::CanonicityStore, Canonicity},
+        ledger::{account::Account, public_key::PublicKey, store::LedgerStore, Ledger},
+        summary::{ConsensusTimeSummary, SummaryVerbose, WitnessTreeSummaryVerbose},
+        IndexerState,
+    },
+    store::IndexerStore,
+};
+use serde_derive::{Deserialize, Serialize};
+use std::{sync::Arc, time::Instant};
+use time::{OffsetDateTime, PrimitiveDateTime};
+use tokio::sync::mpsc;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockStatus {
+    pub canonicity: Canonicity,
+    /// Number of blocks above the block on the best chain, `None` if the block isn't on it
+    pub confirmations: Option<u32>,
+}
+
+/// Best and canonical tips, pushed to subscribers whenever either changes
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Tips {
+    pub best_tip: BlockHash,
+    pub best_tip_length: u32,
+    pub canonical_tip: BlockHash,
+    pub canonical_tip_length: u32,
+}
+
+/// What queries are answered from, taken of the indexer state after every change so the
+/// queries don't wait on block ingestion
+///
+/// Only what's cheap to take is part of the snapshot, summaries and store stats are built
+/// by the queries. Ledgers are reconstructed from the store at the best tip, so balances
+/// reflect every block up to it
+#[derive(Debug, Clone)]
+pub struct StateSnapshot {
+    /// State hashes of the best chain, from the best tip
+    pub best_chain: Arc<Vec<BlockHash>>,
+    /// Root branch blocks from its root to the canonical tip, lowest first
+    pub witness_canonical_chain: Arc<Vec<(u32, BlockHash)>>,
+    pub best_tip: BlockHash,
+    pub best_tip_length: u32,
+    pub canonical_tip: BlockHash,
+    pub canonical_tip_length: u32,
+    /// Taken of the partially built state, before startup ingestion is over
+    pub initializing: bool,
+    pub blocks_processed: u32,
+    best_tip_global_slot: u32,
+    network: Network,
+    started: Instant,
+    date_time: OffsetDateTime,
+    /// Summary of the witness tree as of the snapshot, only taken with the throttled
+    /// startup snapshots, it's requested of the indexer state otherwise
+    witness_tree: Option<WitnessTreeSummaryVerbose>,
+    indexer_store: Option<Arc<IndexerStore>>,
+}
+
+impl StateSnapshot {
+    pub fn new(state: &IndexerState) -> Self {
+        Self::with_chains(
+            state,
+            Arc::new(state.best_chain()),
+            Arc::new(state.witness_canonical_chain()),
+        )
+    }
+
+    /// Snapshot of the state after this one, the chains are shared with this snapshot
+    /// unless the tips moved
+    pub fn next(&self, state: &IndexerState) -> Self {
+        let best_tip_moved = state.best_tip.state_hash != self.best_tip;
+        let canonical_tip_moved = state.canonical_tip.state_hash != self.canonical_tip;
+        let best_chain = if best_tip_moved {
+            Arc::new(state.best_chain())
+        } else {
+            self.best_chain.clone()
+        };
+        // the root moves with the tips
+        let witness_canonical_chain = if best_tip_moved || canonical_tip_moved {
+            Arc::new(state.witness_canonical_chain())
+        } else {
+            self.witness_canonical_chain.clone()
+        };
+        Self::with_chains(state, best_chain, witness_canonical_chain)
+    }
+
+    fn with_chains(
+        state: &IndexerState,
+        best_chain: Arc<Vec<BlockHash>>,
+        witness_canonical_chain: Arc<Vec<(u32, BlockHash)>>,
+    ) -> Self {
+        Self {
+            best_chain,
+            witness_canonical_chain,
+            best_tip: state.best_tip.state_hash.clone(),
+            best_tip_length: state.best_tip_block().blockchain_length.unwrap_or(0),
+            canonical_tip: state.canonical_tip.state_hash.clone(),
+            canonical_tip_length: state.canonical_tip_block().blockchain_length.unwrap_or(0),
+            initializing: state.is_initializing(),
+            blocks_processed: state.blocks_processed,
+            best_tip_global_slot: state.best_tip_block().global_slot_since_genesis,
+            network: state.network.clone(),
+            started: state.time,
+            date_time: state.date_time,
+            witness_tree: None,
+            indexer_store: state.indexer_store.clone(),
+        }
+    }
+
+    /// Takes the summary of the witness tree along with the snapshot
+    pub fn with_witness_tree(mut self, state: &IndexerState) -> Self {
+        self.witness_tree = Some(state.witness_tree_summary());
+        self
+    }
+
+    pub fn tips(&self) -> Tips {
+        Tips {
+            best_tip: self.best_tip.clone(),
+            best_tip_length: self.best_tip_length,
+            canonical_tip: self.canonical_tip.clone(),
+            canonical_tip_length: self.canonical_tip_length,
+        }
+    }
+
+    /// Summary as of the snapshot, with the uptime, wall-clock slot, and store stats as of
+    /// now, the witness tree's summary is requested of the indexer state unless the snapshot
+    /// was taken with it
+    pub async fn summary(
+        &self,
+        state_requests: &mpsc::Sender<StateRequest>,
+    ) -> anyhow::Result<SummaryVerbose> {
+        let mut witness_tree = match &self.witness_tree {
+            Some(witness_tree) => witness_tree.clone(),
+            None => request_state(state_requests, StateRequest::WitnessTree).await?,
+        };
+        witness_tree.best_tip_hash = self.best_tip.0.clone();
+        witness_tree.best_tip_length = self.best_tip_length;
+        witness_tree.canonical_tip_hash = self.canonical_tip.0.clone();
+        witness_tree.canonical_tip_length = self.canonical_tip_length;
+
+        Ok(SummaryVerbose {
+            network: self.network.to_string(),
+            uptime: self.started.elapsed(),
+            date_time: PrimitiveDateTime::new(self.date_time.date(), self.date_time.time()),
+            blocks_processed: self.blocks_processed,
+            consensus_time: ConsensusTimeSummary::new(&self.network, self.best_tip_global_slot),
+            witness_tree,
+            db_stats: self
+                .indexer_store
+                .as_ref()
+                .and_then(|indexer_store| indexer_store.stats().ok().flatten()),
+            store_operations: self
+                .indexer_store
+                .as_ref()
+                .map(|indexer_store| indexer_store.operation_stats()),
+        })
+    }
+
+    /// Canonical blocks from the given length on, from the store and then the root branch
+    pub fn canonical_chain(&self, from_length: u32) -> CanonicalChain<'_> {
+        CanonicalChain::new(
+            self.indexer_store.as_deref(),
+            self.witness_canonical_chain.to_vec(),
+            from_length,
+        )
+    }
+
+    /// Ledger at the best tip, reconstructed from the store
+    pub fn best_ledger(&self) -> anyhow::Result<Option<Ledger>> {
+        match &self.indexer_store {
+            Some(indexer_store) => indexer_store.get_ledger(&self.best_tip),
+            None => Ok(None),
+        }
+    }
+
+    /// Canonicity of the stored block, blocks without one are still in the witness tree,
+    /// `None` if the block isn't stored
+    pub fn block_status(&self, state_hash: &BlockHash) -> anyhow::Result<Option<BlockStatus>> {
+        let indexer_store = match &self.indexer_store {
+            Some(indexer_store) => indexer_store,
+            None => return Ok(None),
+        };
+        let blockchain_length = match indexer_store.get_block_ancestry(state_hash)? {
+            Some(ancestry) => ancestry.blockchain_length.unwrap_or(0),
+            None => return Ok(None),
+        };
+        let canonicity = indexer_store
+            .get_canonicity(state_hash)?
+            .unwrap_or(Canonicity::Pending);
+        let on_best_chain =
+            canonicity == Canonicity::Canonical || self.best_chain.contains(state_hash);
+        Ok(Some(BlockStatus {
+            canonicity,
+            confirmations: on_best_chain
+                .then(|| self.best_tip_length.saturating_sub(blockchain_length)),
+        }))
+    }
+
+    /// Account of the public key in the best ledger
+    pub fn account(&self, public_key: &PublicKey) -> anyhow::Result<Option<Account>> {
+        Ok(self
+            .best_ledger()?
+            .and_then(|ledger| ledger.accounts.get(public_key).cloned()))
+    }
+}
@@ -0,0 +1,111 @@
+use crate::{
+    state::event::{store::EventStore, IndexerEvent},
+    store::IndexerStore,
+};
+use actix_web::{
+    get,
+    web::{Data, Payload, Query},
+    HttpRequest, HttpResponse,
+};
+use actix_ws::Message;
+use futures::StreamExt;
+use serde_derive::{Deserialize, Serialize};
+use std::sync::Arc;
+use tokio::sync::watch;
+use tracing::{debug, error};
+
+/// Max number of logged events read from the store at once
+const EVENTS_BATCH_SIZE: usize = 100;
+
+/// Event pushed to the WebSocket subscribers
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EventMessage {
+    pub seq_num: u64,
+    pub event: IndexerEvent,
+}
+
+#[derive(Debug, Deserialize)]
+struct EventsParams {
+    /// Sequence number of the first pushed event (default: the next logged event)
+    from: Option<u64>,
+}
+
+/// Pushes the logged events to the subscriber as JSON text messages, in order,
+/// as soon as the server logs them
+///
+/// `next_event_seq_nums` is updated by the server after every block it adds
+#[get("/events")]
+async fn events(
+    req: HttpRequest,
+    body: Payload,
+    db: Data<Arc<IndexerStore>>,
+    next_event_seq_nums: Data<watch::Receiver<u64>>,
+    params: Query<EventsParams>,
+) -> actix_web::Result<HttpResponse> {
+    let mut seq_num = match params.from {
+        Some(from) => from,
+        None => db
+            .get_next_event_seq_num()
+            .map_err(actix_web::error::ErrorInternalServerError)?,
+    };
+    let (response, mut session, mut messages) = actix_ws::handle(&req, body)?;
+    let db = db.get_ref().clone();
+    let mut next_event_seq_nums = next_event_seq_nums.get_ref().clone();
+
+    actix_web::rt::spawn(async move {
+        debug!("WebSocket subscriber connected from sequence number {seq_num}");
+        loop {
+            let logged = match db.get_events_from(seq_num, EVENTS_BATCH_SIZE) {
+                Ok(logged) => logged,
+                Err(e) => {
+                    error!("Error reading logged events: {e}");
+                    break;
+                }
+            };
+
+            let num_logged = logged.len();
+            for (logged_seq_num, event) in logged {
+                seq_num = logged_seq_num + 1;
+                let message = EventMessage {
+                    seq_num: logged_seq_num,
+                    event,
+                };
+                let text = match serde_json::to_string(&message) {
+                    Ok(text) => text,
+                    Err(e) => {
+                        error!("Error serializing event {logged_seq_num}: {e}");
+                        continue;
+                    }
+                };
+                if session.text(text).await.is_err() {
+                    debug!("WebSocket subscriber disconnected");
+                    return;
+                }
+            }
+            if num_logged == EVENTS_BATCH_SIZE {
+                continue;
+            }
+
+            // wait for the server to log more events, answering pings meanwhile
+            tokio::select! {
+                changed = next_event_seq_nums.changed() => {
+                    if changed.is_err() {
+                        break;
+                    }
+                }
+                message = messages.next() => match message {
+                    Some(Ok(Message::Ping(bytes))) => {
+                        if session.pong(&bytes).await.is_err() {
+                            return;
+                        }
+                    }
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                    Some(Ok(_)) => (),
+                },
+            }
+        }
+        session.close(None).await.ok();
+    });
+
+    Ok(response)
+}
@@ -1,4 +1,7 @@
-use crate::block::{precomputed::PrecomputedBlock, Block, BlockHash};
+use crate::{
+    block::{precomputed::PrecomputedBlock, Block, BlockHash},
+    error::IndexerError,
+};
 use id_tree::{
     InsertBehavior::{AsRoot, UnderNode},
     MoveBehavior::ToRoot,
@@ -6,74 +9,66 @@ use id_tree::{
     RemoveBehavior::{DropChildren, OrphanChildren},
     Tree,
 };
-use serde::ser::SerializeStruct;
-use serde::Serialize;
 use std::collections::HashMap;
 
+/// Tree of blocks of the witness tree, its nodes only hold the hashes, heights, and
+/// consensus data of [Block], anything else is fetched from the store
+///
+/// Operations on node ids the tree lacks fail with [IndexerError::WitnessTree]
 #[derive(Clone)]
 pub struct Branch {
     pub root: NodeId,
     pub branches: Tree<Block>,
 }
 
-pub type Path = Vec<Block>;
-
-#[derive(Clone)]
-pub struct Leaf<T> {
-    pub block: Block,
-    ledger: T,
-}
-
 impl Branch {
-    pub fn new_genesis(root_hash: BlockHash) -> Self {
+    pub fn new_genesis(root_hash: BlockHash) -> Result<Self, IndexerError> {
         let genesis_block = Block {
             state_hash: root_hash.clone(),
             parent_hash: root_hash,
             height: 0,
             blockchain_length: Some(1),
             global_slot_since_genesis: 0,
+            last_vrf_output_digest: [0; 32],
         };
         let mut branches = Tree::new();
 
-        let root = branches.insert(Node::new(genesis_block), AsRoot).unwrap();
+        let root = branches.insert(Node::new(genesis_block), AsRoot)?;
 
-        Self { root, branches }
+        Ok(Self { root, branches })
     }
 
     pub fn new_non_genesis(
         root_hash: BlockHash,
         blockchain_length: Option<u32>,
         global_slot_since_genesis: u32,
-    ) -> Self {
+    ) -> Result<Self, IndexerError> {
         let root_block = Block {
             state_hash: root_hash.clone(),
             parent_hash: root_hash,
             height: 0,
             global_slot_since_genesis,
             blockchain_length,
+            last_vrf_output_digest: [0; 32],
         };
         let mut branches = Tree::new();
-        let root = branches.insert(Node::new(root_block), AsRoot).unwrap();
+        let root = branches.insert(Node::new(root_block), AsRoot)?;
 
-        Self { root, branches }
+        Ok(Self { root, branches })
     }
 
-    pub fn new_testing(precomputed_block: &PrecomputedBlock) -> Self {
-        let root_block = Block::from_precomputed(precomputed_block, 0);
-        let mut branches = Tree::new();
-        let root = branches.insert(Node::new(root_block), AsRoot).unwrap();
-
-        Self { root, branches }
+    pub fn new_testing(precomputed_block: &PrecomputedBlock) -> Result<Self, IndexerError> {
+        Branch::new(precomputed_block)
     }
 
     // only the genesis block should work here
-    pub fn new_rooted(root_precomputed: &PrecomputedBlock) -> Self {
-        Branch::new(root_precomputed).unwrap()
+    pub fn new_rooted(root_precomputed: &PrecomputedBlock) -> Result<Self, IndexerError> {
+        Branch::new(root_precomputed)
     }
 }
 
 impl Branch {
-    pub fn new(root_precomputed: &PrecomputedBlock) -> anyhow::Result<Self> {
+    pub fn new(root_precomputed: &PrecomputedBlock) -> Result<Self, IndexerError> {
         let root_block = Block::from_precomputed(root_precomputed, 0);
         let mut branches = Tree::new();
         let root = branches.insert(Node::new(root_block), AsRoot)?;
@@ -86,44 +81,52 @@ impl Branch {
     }
 
     /// Returns the new node's id in the branch
-    pub fn simple_extension(&mut self, block: &PrecomputedBlock) -> Option<(NodeId, Block)> {
-        let root_node_id = self
+    pub fn simple_extension(
+        &mut self,
+        block: &PrecomputedBlock,
+    ) -> Result<Option<(NodeId, Block)>, IndexerError> {
+        let incoming_prev_hash = BlockHash::previous_state_hash(block);
+        let parent = self
             .branches
-            .root_node_id()
-            .expect("root_node_id guaranteed by constructor");
-        for node_id in self
-            .branches
-            .traverse_post_order_ids(root_node_id)
-            .expect("root_node_id is valid")
-        {
-            let node = self
-                .branches
-                .get(&node_id)
-                .expect("node_id comes from branches iterator, cannot be invalid");
-
-            // incoming block is a child of node
-            let incoming_prev_hash = BlockHash::previous_state_hash(block);
-            if incoming_prev_hash == node.data().state_hash {
-                let new_block = Block::from_precomputed(block, node.data().height + 1);
+            .traverse_post_order_ids(&self.root)?
+            .find_map(|node_id| match self.branches.get(&node_id) {
+                // incoming block is a child of node
+                Ok(node) if node.data().state_hash == incoming_prev_hash => {
+                    Some((node_id, node.data().clone()))
+                }
+                _ => None,
+            });
+
+        match parent {
+            Some((node_id, parent_block)) => {
+                let mut new_block = Block::from_precomputed(block, parent_block.height + 1);
+                if new_block.blockchain_length.is_none() {
+                    new_block.blockchain_length =
+                        parent_block.blockchain_length.map(|length| length + 1);
+                }
                 let new_node_id = self
                     .branches
-                    .insert(Node::new(new_block.clone()), UnderNode(&node_id))
-                    .expect("node_id comes from branches iterator, cannot be invalid");
-
-                return Some((new_node_id, new_block));
+                    .insert(Node::new(new_block.clone()), UnderNode(&node_id))?;
+                Ok(Some((new_node_id, new_block)))
             }
+            None => Ok(None),
         }
-        None
     }
 
     /// Prunes the tree and updates the root
-    pub fn prune_transition_frontier(&mut self, k: u32, best_tip: &Block) {
+    pub fn prune_transition_frontier(
+        &mut self,
+        k: u32,
+        best_tip: &Block,
+    ) -> Result<(), IndexerError> {
         let mut witness_length = 0;
         let mut new_root_id = None;
         let mut prune_point_id = None;
-        let best_tip_id = self.leaf_node_id(best_tip).unwrap();
+        let best_tip_id = self.leaf_node_id(best_tip)?.ok_or_else(|| {
+            IndexerError::WitnessTree(format!("best tip {:?} isn't a leaf", best_tip.state_hash.0))
+        })?;
 
-        for ancestor_id in self.branches.ancestor_ids(&best_tip_id).unwrap().cloned() {
+        for ancestor_id in self.branches.ancestor_ids(&best_tip_id)?.cloned() {
             witness_length += 1;
             if witness_length == k {
                 new_root_id = Some(ancestor_id.clone());
@@ -134,145 +137,108 @@ impl Branch {
         }
 
         // guaranteed to exist because of the height precondition
-        let new_root_id = new_root_id.unwrap();
-        let prune_point_id = prune_point_id.unwrap();
+        let (new_root_id, prune_point_id) = match (new_root_id, prune_point_id) {
+            (Some(new_root_id), Some(prune_point_id)) => (new_root_id, prune_point_id),
+            _ => {
+                return Err(IndexerError::WitnessTree(format!(
+                    "best tip {:?} has fewer than {} ancestors",
+                    best_tip.state_hash.0,
+                    k + 1
+                )))
+            }
+        };
 
         // remove all prune point siblings
-        for node_id in self
-            .branches
-            .get(&prune_point_id)
-            .unwrap()
-            .children()
-            .clone()
-        {
+        for node_id in self.branches.get(&prune_point_id)?.children().clone() {
             if node_id != new_root_id {
-                self.branches
-                    .remove_node(node_id.clone(), id_tree::RemoveBehavior::DropChildren)
-                    .unwrap();
+                self.branches.remove_node(node_id, DropChildren)?;
             }
         }
 
         // remove parent node + orphan children
-        self.branches
-            .remove_node(prune_point_id, OrphanChildren)
-            .unwrap();
+        self.branches.remove_node(prune_point_id, OrphanChildren)?;
 
         // remove original root + drop children
-        self.branches
-            .remove_node(self.branches.root_node_id().unwrap().clone(), DropChildren)
-            .unwrap();
+        self.branches.remove_node(self.root.clone(), DropChildren)?;
 
         // move prune node to root
-        self.branches.move_node(&new_root_id, ToRoot).unwrap();
+        self.branches.move_node(&new_root_id, ToRoot)?;
 
         // update node heights
-        let n = self.branches.get(&new_root_id).unwrap().data().height;
+        let n = self.branches.get(&new_root_id)?.data().height;
         let node_ids: Vec<NodeId> = self
             .branches
-            .traverse_level_order_ids(&new_root_id)
-            .unwrap()
+            .traverse_level_order_ids(&new_root_id)?
             .collect();
 
         for node_id in node_ids {
-            let node = self.branches.get_mut(&node_id).unwrap();
-            node.data_mut().height -= n;
+            self.branches.get_mut(&node_id)?.data_mut().height -= n;
         }
 
         // update root
-        self.root = new_root_id.clone();
+        self.root = new_root_id;
+        Ok(())
     }
 
-    /// block is guaranteed to exist in leaves
-    fn leaf_node_id(&self, block: &Block) -> Option<NodeId> {
-        self.branches
-            .traverse_post_order_ids(self.branches.root_node_id()?)
-            .unwrap()
+    /// The node id of the block, `None` if it isn't in the branch
+    fn leaf_node_id(&self, block: &Block) -> Result<Option<NodeId>, IndexerError> {
+        Ok(self
+            .branches
+            .traverse_post_order_ids(&self.root)?
             .find(|node_id| {
-                block.state_hash == self.branches.get(node_id).unwrap().data().state_hash
-            })
+                self.branches
+                    .get(node_id)
+                    .map_or(false, |node| node.data().state_hash == block.state_hash)
+            }))
     }
 
     /// Merges two trees:
     /// incoming is placed under junction_id in self
     ///
     /// Returns the id of the best tip in the merged subtree
-    pub fn merge_on(&mut self, junction_id: &NodeId, incoming: &mut Branch) -> Option<NodeId> {
-        let (merged_tip_id, _) = incoming.best_tip_with_id().unwrap();
+    pub fn merge_on(
+        &mut self,
+        junction_id: &NodeId,
+        incoming: &mut Branch,
+    ) -> Result<Option<NodeId>, IndexerError> {
+        let (merged_tip_id, _) = incoming.best_tip_with_id().ok_or_else(|| {
+            IndexerError::WitnessTree("the incoming branch has no best tip".to_string())
+        })?;
         let mut merge_id_map = HashMap::new();
 
         // associate the incoming tree's root node id with it's new id in the base tree
-        let incoming_root_id = incoming
-            .branches
-            .root_node_id()
-            .expect("branch always has root node");
-        let mut incoming_root_data = incoming
-            .branches
-            .get(incoming_root_id)
-            .expect("incoming_root_id valid, branch always has root node")
-            .data()
-            .clone();
-        let junction_height = self
-            .branches
-            .get(junction_id)
-            .expect("junction node exists in self")
-            .data()
-            .height;
-        let junction_length = self
-            .branches
-            .get(junction_id)
-            .expect("junction node exists in self")
-            .data()
-            .blockchain_length
-            .unwrap_or(0);
+        let incoming_root_id = &incoming.root;
+        let mut incoming_root_data = incoming.branches.get(incoming_root_id)?.data().clone();
+        let junction_height = self.branches.get(junction_id)?.data().height;
 
         // adjust the height of the incoming branch's root block
         incoming_root_data.height = junction_height + 1;
-        if incoming_root_data.blockchain_length.is_none() {
-            incoming_root_data.blockchain_length = Some(junction_length + 1)
-        }
 
         let new_node_id = self
             .branches
-            .insert(Node::new(incoming_root_data), UnderNode(junction_id))
-            .expect("merge_on called with valid junction_id");
+            .insert(Node::new(incoming_root_data), UnderNode(junction_id))?;
 
-        merge_id_map.insert(incoming_root_id, new_node_id);
+        merge_id_map.insert(incoming_root_id.clone(), new_node_id);
 
         for old_node_id in incoming
             .branches
-            .traverse_level_order_ids(incoming_root_id)
-            .expect("incoming_root_id guaranteed by root_id() call")
+            .traverse_level_order_ids(incoming_root_id)?
         {
-            let under_node_id = merge_id_map
-                .get(&old_node_id)
-                .expect("guaranteed by call structure");
-            let children_ids = incoming
-                .branches
-                .children_ids(&old_node_id)
-                .expect("old_node_id valid");
+            let under_node_id = merge_id_map.get(&old_node_id).cloned().ok_or_else(|| {
+                IndexerError::WitnessTree(format!("node {old_node_id:?} merged before its parent"))
+            })?;
             let mut merge_id_map_inserts = Vec::new();
 
-            for child_id in children_ids {
-                let mut child_node_data = incoming
-                    .branches
-                    .get(child_id)
-                    .expect("child_id valid")
-                    .data()
-                    .clone();
+            for child_id in incoming.branches.children_ids(&old_node_id)? {
+                let mut child_node_data = incoming.branches.get(child_id)?.data().clone();
 
                 child_node_data.height += junction_height + 1;
 
-                if child_node_data.blockchain_length.is_none() {
-                    child_node_data.blockchain_length =
-                        Some(junction_length + child_node_data.height + 1 - junction_height)
-                }
-
                 let new_child_id = self
                     .branches
-                    .insert(Node::new(child_node_data), UnderNode(under_node_id))
-                    .expect("under_node_id guaranteed by call structure");
+                    .insert(Node::new(child_node_data), UnderNode(&under_node_id))?;
 
-                merge_id_map_inserts.push((child_id, new_child_id));
+                merge_id_map_inserts.push((child_id.clone(), new_child_id));
             }
 
             for (child_id, new_child_id) in merge_id_map_inserts {
@@ -280,40 +246,76 @@ impl Branch {
             }
         }
 
-        merge_id_map.get(&merged_tip_id).cloned()
+        // the incoming blocks lacking a length get it from the junction
+        self.backfill_lengths()?;
+        Ok(merge_id_map.get(&merged_tip_id).cloned())
     }
 
-    pub fn new_root(&mut self, precomputed_block: &PrecomputedBlock) {
+    pub fn new_root(&mut self, precomputed_block: &PrecomputedBlock) -> Result<(), IndexerError> {
         let new_block = Block::from_precomputed(precomputed_block, 0);
-        let new_root_id = self
-            .branches
-            .insert(Node::new(new_block), AsRoot)
-            .expect("insert as root always succeeds");
+        let new_root_id = self.branches.insert(Node::new(new_block), AsRoot)?;
 
         self.root = new_root_id.clone();
 
         let child_ids: Vec<NodeId> = self
             .branches
-            .traverse_level_order_ids(&new_root_id)
-            .expect("new_root_id received from .insert() call, is valid")
+            .traverse_level_order_ids(&new_root_id)?
             .collect();
 
         for node_id in child_ids {
-            let node = self
+            if node_id != new_root_id {
+                self.branches.get_mut(&node_id)?.data_mut().height += 1;
+            }
+        }
+        self.backfill_lengths()
+    }
+
+    /// Fills in the lengths of blocks lacking one, one more than their parent's, and the
+    /// root's one less than its children's
+    fn backfill_lengths(&mut self) -> Result<(), IndexerError> {
+        let root_length = match self.root_block()?.blockchain_length {
+            Some(length) => Some(length),
+            None => self
                 .branches
-                .get_mut(&node_id)
-                .expect("node_id from iterator, cannot be invalid");
+                .children(&self.root)?
+                .find_map(|child| child.data().blockchain_length)
+                .and_then(|length| length.checked_sub(1)),
+        };
+        self.branches
+            .get_mut(&self.root)?
+            .data_mut()
+            .blockchain_length = root_length;
 
-            if node_id != new_root_id {
-                let mut block = node.data().clone();
-                block.height += 1;
-                node.replace_data(block);
+        let node_ids: Vec<NodeId> = self
+            .branches
+            .traverse_level_order_ids(&self.root)?
+            .collect();
+        for node_id in node_ids {
+            let parent_length = match self.branches.get(&node_id)?.data().blockchain_length {
+                Some(length) => length,
+                None => continue,
+            };
+            let child_ids: Vec<NodeId> = self.branches.children_ids(&node_id)?.cloned().collect();
+            for child_id in child_ids {
+                let child = self.branches.get_mut(&child_id)?.data_mut();
+                if child.blockchain_length.is_none() {
+                    child.blockchain_length = Some(parent_length + 1);
+                }
             }
         }
+        Ok(())
     }
 
-    pub fn root_block(&self) -> &Block {
-        self.branches.get(&self.root).unwrap().data()
+    pub fn root_block(&self) -> Result<&Block, IndexerError> {
+        Ok(self.branches.get(&self.root)?.data())
+    }
+
+    /// The branch's nodes in post order, none if the root is missing
+    fn nodes(&self) -> impl Iterator<Item = &Node<Block>> {
+        self.branches
+            .traverse_post_order(&self.root)
+            .into_iter()
+            .flatten()
     }
 
     pub fn top_leaves_with_id(&self) -> Vec<(NodeId, Block)> {
@@ -321,12 +323,14 @@ impl Branch {
 
         for node_id in self
             .branches
-            .traverse_post_order_ids(self.branches.root_node_id().unwrap())
-            .unwrap()
+            .traverse_post_order_ids(&self.root)
+            .into_iter()
+            .flatten()
         {
-            let node = self.branches.get(&node_id).unwrap();
-            if node.data().height + 1 == self.height() {
-                top_leaves.push((node_id.clone(), node.data().clone()));
+            if let Ok(node) = self.branches.get(&node_id) {
+                if node.data().height + 1 == self.height() {
+                    top_leaves.push((node_id.clone(), node.data().clone()));
+                }
             }
         }
 
@@ -346,8 +350,9 @@ impl Branch {
 
         for node in self
             .branches
-            .traverse_level_order(self.branches.root_node_id().unwrap())
-            .unwrap()
+            .traverse_level_order(&self.root)
+            .into_iter()
+            .flatten()
         {
             if node.data().height + 1 == self.height() {
                 leaves.push(node.data().clone());
@@ -371,19 +376,12 @@ impl Branch {
 
     pub fn longest_chain(&self) -> Vec<BlockHash> {
         let mut longest_chain = Vec::new();
-        if let Some((node_id, _)) = self.best_tip_with_id() {
+        if let Some((node_id, best_tip)) = self.best_tip_with_id() {
             // push the leaf itself
-            longest_chain.push(
-                self.branches
-                    .get(&node_id)
-                    .unwrap()
-                    .data()
-                    .state_hash
-                    .clone(),
-            );
+            longest_chain.push(best_tip.state_hash);
 
             // push the leaf's ancestors
-            for node in self.branches.ancestors(&node_id).expect("node_id is valid") {
+            for node in self.branches.ancestors(&node_id).into_iter().flatten() {
                 longest_chain.push(node.data().state_hash.clone());
             }
         }
@@ -391,59 +389,84 @@ impl Branch {
         longest_chain
     }
 
-    pub fn len(&self) -> u32 {
-        let mut size = 0;
-        if let Some(root) = self.branches.root_node_id() {
-            for _ in self.branches.traverse_level_order_ids(root).unwrap() {
-                size += 1;
+    /// Renders the subtree of the best tip's `depth`-th ancestor, or of the root if the
+    /// branch is shallower
+    pub fn render_near_best_tip(&self, depth: u32) -> String {
+        let mut rendering = String::new();
+        if let Some((best_tip_id, _)) = self.best_tip_with_id() {
+            let subtree_root = self
+                .branches
+                .ancestor_ids(&best_tip_id)
+                .into_iter()
+                .flatten()
+                .take(depth as usize)
+                .last()
+                .cloned()
+                .unwrap_or(best_tip_id);
+            self.render_subtree(&subtree_root, "", &mut rendering);
+        }
+        rendering
+    }
+
+    fn render_subtree(&self, node_id: &NodeId, indent: &str, rendering: &mut String) {
+        if let Ok(node) = self.branches.get(node_id) {
+            rendering.push_str(&format!("{indent}{:?}\n", node.data()));
+
+            let child_indent = format!("{indent}  ");
+            for child_id in node.children() {
+                self.render_subtree(child_id, &child_indent, rendering);
             }
         }
-        size
+    }
+
+    pub fn len(&self) -> u32 {
+        self.nodes().count() as u32
     }
 
     pub fn height(&self) -> u32 {
         self.branches.height() as u32
     }
 
-    pub fn mem(&self, state_hash: &BlockHash) -> bool {
-        for node in self
-            .branches
-            .traverse_post_order(self.branches.root_node_id().unwrap())
-            .unwrap()
-        {
-            if &node.data().state_hash == state_hash {
-                return true;
-            }
-        }
-        false
+    /// The branch's blocks in level order, each block after its parent
+    pub fn blocks(&self) -> Vec<Block> {
+        self.branches
+            .traverse_level_order(&self.root)
+            .into_iter()
+            .flatten()
+            .map(|node| node.data().clone())
+            .collect()
     }
-}
 
-impl<T> Leaf<T> {
-    pub fn new(data: Block, ledger: T) -> Self {
-        Self {
-            block: data,
-            ledger,
+    /// Rebuilds a branch from its blocks in level order, see [Branch::blocks]
+    pub fn from_blocks(blocks: &[Block]) -> Result<Self, IndexerError> {
+        let (root_block, descendants) = blocks
+            .split_first()
+            .ok_or_else(|| IndexerError::WitnessTree("a branch has at least one block".into()))?;
+        let mut branches = Tree::new();
+        let root = branches.insert(Node::new(root_block.clone()), AsRoot)?;
+        let mut node_ids = HashMap::from([(root_block.state_hash.clone(), root.clone())]);
+        for block in descendants {
+            let parent_id = node_ids.get(&block.parent_hash).ok_or_else(|| {
+                IndexerError::WitnessTree(format!(
+                    "parent of block {} doesn't precede it in the branch",
+                    block.state_hash.0
+                ))
+            })?;
+            let node_id = branches.insert(Node::new(block.clone()), UnderNode(parent_id))?;
+            node_ids.insert(block.state_hash.clone(), node_id);
         }
+        Ok(Self { root, branches })
     }
 
-    pub fn get_ledger(&self) -> &T {
-        &self.ledger
+    /// The branch's block with the state hash, if any
+    pub fn block(&self, state_hash: &BlockHash) -> Option<&Block> {
+        self.nodes()
+            .map(|node| node.data())
+            .find(|block| &block.state_hash == state_hash)
     }
-}
 
-impl<T> Serialize for Leaf<T>
-where
-    T: Serialize,
-{
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: serde::Serializer,
-    {
-        let mut state = serializer.serialize_struct("Leaf", 2)?;
-        state.serialize_field("block", &self.block)?;
-        state.serialize_field("ledger", &self.ledger)?;
-        state.end()
+    pub fn mem(&self, state_hash: &BlockHash) -> bool {
+        self.block(state_hash).is_some()
     }
 }
 
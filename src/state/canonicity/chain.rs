@@ -0,0 +1,69 @@
+use super::store::CanonicityStore;
+use crate::{block::BlockHash, store::IndexerStore};
+
+/// Canonical blocks by length, from the canonicity store and then the root branch up to
+/// the canonical tip, so callers don't need to know which layer a block is in
+///
+/// Yields `(blockchain_length, state_hash)` pairs in increasing length, stopping at the
+/// first length neither layer has a canonical block at
+pub struct CanonicalChain<'a> {
+    indexer_store: Option<&'a IndexerStore>,
+    /// Root branch blocks from its root to the canonical tip, lowest first
+    witness_chain: Vec<(u32, BlockHash)>,
+    next_length: u32,
+    done: bool,
+}
+
+impl<'a> CanonicalChain<'a> {
+    pub fn new(
+        indexer_store: Option<&'a IndexerStore>,
+        witness_chain: Vec<(u32, BlockHash)>,
+        from_length: u32,
+    ) -> Self {
+        Self {
+            indexer_store,
+            witness_chain,
+            next_length: from_length,
+            done: false,
+        }
+    }
+
+    fn witness_hash(&self, length: u32) -> Option<BlockHash> {
+        self.witness_chain
+            .binary_search_by_key(&length, |(length, _)| *length)
+            .ok()
+            .map(|idx| self.witness_chain[idx].1.clone())
+    }
+}
+
+impl<'a> Iterator for CanonicalChain<'a> {
+    type Item = anyhow::Result<(u32, BlockHash)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let length = self.next_length;
+        let stored = match self.indexer_store {
+            Some(indexer_store) => match indexer_store.get_canonical_hash_at_height(length) {
+                Ok(stored) => stored,
+                Err(e) => {
+                    self.done = true;
+                    return Some(Err(e));
+                }
+            },
+            None => None,
+        };
+        match stored.or_else(|| self.witness_hash(length)) {
+            Some(state_hash) => {
+                self.next_length += 1;
+                Some(Ok((length, state_hash)))
+            }
+            None => {
+                self.done = true;
+                None
+            }
+        }
+    }
+}
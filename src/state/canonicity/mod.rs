@@ -0,0 +1,11 @@
+pub mod chain;
+pub mod store;
+
+use serde_derive::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub enum Canonicity {
+    Canonical,
+    Orphaned,
+    Pending,
+}
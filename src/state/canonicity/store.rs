@@ -0,0 +1,30 @@
+use crate::{block::BlockHash, state::ledger::Ledger};
+
+use super::Canonicity;
+
+/// Store of block canonicity and the canonical chain by blockchain length
+pub trait CanonicityStore {
+    /// Set the canonicity of a block, canonical blocks are also indexed by length
+    fn set_canonicity(&self, state_hash: &BlockHash, canonicity: Canonicity) -> anyhow::Result<()>;
+
+    /// Get the canonicity of a block
+    fn get_canonicity(&self, state_hash: &BlockHash) -> anyhow::Result<Option<Canonicity>>;
+
+    /// Get the state hash of the canonical block of the given length
+    fn get_canonical_hash_at_height(&self, height: u32) -> anyhow::Result<Option<BlockHash>>;
+
+    /// Replace the canonical chain from the given length on, in a single write: the
+    /// canonical blocks of at least that length are recorded as orphaned, no longer indexed
+    /// by length or counted in the account aggregates, and their ledger snapshots are
+    /// dropped. Then the given canonicity updates are applied and the ledger snapshotted.
+    /// Returns the state hashes of the orphaned canonical blocks
+    ///
+    /// The canonical SNARK work and balance lookups follow the blocks' canonicity, so
+    /// they're reverted by the same write
+    fn rollback_canonical(
+        &self,
+        from_length: u32,
+        updates: &[(BlockHash, Canonicity)],
+        ledger: (&BlockHash, &Ledger),
+    ) -> anyhow::Result<Vec<BlockHash>>;
+}
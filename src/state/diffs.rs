@@ -0,0 +1,126 @@
+use crate::{
+    block::BlockHash,
+    state::ledger::{diff::LedgerDiff, store::LedgerStore},
+    store::IndexerStore,
+    DIFFS_MAP_CAPACITY_DEFAULT,
+};
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    sync::Arc,
+};
+use tracing::trace;
+
+/// Ledger diffs of the pending blocks following the canonical tip
+///
+/// At most `capacity` diffs are kept in memory, the oldest ones are spilled to the
+/// store's `diffs` column family and loaded back on demand. Without a store, nothing
+/// is spilled
+pub struct DiffsMap {
+    diffs: HashMap<BlockHash, LedgerDiff>,
+    /// Hashes of the in-memory diffs, oldest first
+    order: VecDeque<BlockHash>,
+    spilled: HashSet<BlockHash>,
+    capacity: usize,
+    indexer_store: Option<Arc<IndexerStore>>,
+}
+
+impl DiffsMap {
+    pub fn new(indexer_store: Option<Arc<IndexerStore>>) -> Self {
+        Self {
+            diffs: HashMap::new(),
+            order: VecDeque::new(),
+            spilled: HashSet::new(),
+            capacity: DIFFS_MAP_CAPACITY_DEFAULT,
+            indexer_store,
+        }
+    }
+
+    /// Sets the max number of diffs kept in memory, spilling any above it
+    pub fn set_capacity(&mut self, capacity: usize) -> anyhow::Result<()> {
+        self.capacity = capacity;
+        self.spill()
+    }
+
+    pub fn insert(&mut self, state_hash: BlockHash, diff: LedgerDiff) -> anyhow::Result<()> {
+        self.remove(&state_hash)?;
+        self.order.push_back(state_hash.clone());
+        self.diffs.insert(state_hash, diff);
+        self.spill()
+    }
+
+    /// Records the diffs spilled by a previous run, which are still in the store
+    pub fn insert_spilled(&mut self, state_hashes: impl IntoIterator<Item = BlockHash>) {
+        self.spilled.extend(state_hashes);
+    }
+
+    /// The block's diff, loaded from the store if it's been spilled
+    pub fn get(&self, state_hash: &BlockHash) -> anyhow::Result<Option<LedgerDiff>> {
+        if let Some(diff) = self.diffs.get(state_hash) {
+            return Ok(Some(diff.clone()));
+        }
+        match &self.indexer_store {
+            Some(indexer_store) if self.spilled.contains(state_hash) => {
+                indexer_store.get_spilled_diff(state_hash)
+            }
+            _ => Ok(None),
+        }
+    }
+
+    pub fn contains(&self, state_hash: &BlockHash) -> bool {
+        self.diffs.contains_key(state_hash) || self.spilled.contains(state_hash)
+    }
+
+    pub fn remove(&mut self, state_hash: &BlockHash) -> anyhow::Result<()> {
+        if self.diffs.remove(state_hash).is_some() {
+            self.order.retain(|hash| hash != state_hash);
+        } else if self.spilled.remove(state_hash) {
+            if let Some(indexer_store) = &self.indexer_store {
+                indexer_store.remove_spilled_diff(state_hash)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Hashes of all pending blocks, in memory or spilled
+    pub fn keys(&self) -> impl Iterator<Item = &BlockHash> {
+        self.diffs.keys().chain(self.spilled.iter())
+    }
+
+    /// The in-memory diffs
+    pub fn in_memory(&self) -> impl Iterator<Item = (&BlockHash, &LedgerDiff)> {
+        self.diffs.iter()
+    }
+
+    /// Hashes of the spilled diffs
+    pub fn spilled(&self) -> impl Iterator<Item = &BlockHash> {
+        self.spilled.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.diffs.len() + self.spilled.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn num_in_memory(&self) -> usize {
+        self.diffs.len()
+    }
+
+    fn spill(&mut self) -> anyhow::Result<()> {
+        if let Some(indexer_store) = &self.indexer_store {
+            while self.diffs.len() > self.capacity {
+                let state_hash = self.order.pop_front().expect("every diff is ordered");
+                let diff = self.diffs.remove(&state_hash).expect("ordered diffs exist");
+                trace!(
+                    "Spilling the ledger diff of {:?} to the store",
+                    state_hash.0
+                );
+                indexer_store.add_spilled_diff(&state_hash, &diff)?;
+                self.spilled.insert(state_hash);
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,23 @@
+pub mod store;
+
+use crate::{block::BlockHash, state::Canonicity};
+use serde_derive::{Deserialize, Serialize};
+
+/// State transition appended to the store's event log
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub enum IndexerEvent {
+    BlockAdded {
+        state_hash: BlockHash,
+        blockchain_length: Option<u32>,
+    },
+    BestTipChanged {
+        state_hash: BlockHash,
+    },
+    CanonicityChanged {
+        state_hash: BlockHash,
+        canonicity: Canonicity,
+    },
+    LedgerSnapshotTaken {
+        state_hash: BlockHash,
+    },
+}
@@ -0,0 +1,19 @@
+use super::IndexerEvent;
+
+/// Ordered log of the indexer's state transitions
+///
+/// Events are appended in the same write as the transition they record
+pub trait EventStore {
+    /// Get the event with the given sequence number
+    fn get_event(&self, seq_num: u64) -> anyhow::Result<Option<IndexerEvent>>;
+
+    /// Get at most `limit` events, in order, starting at sequence number `seq_num`
+    fn get_events_from(
+        &self,
+        seq_num: u64,
+        limit: usize,
+    ) -> anyhow::Result<Vec<(u64, IndexerEvent)>>;
+
+    /// Get the sequence number of the next event, i.e. the number of events logged
+    fn get_next_event_seq_num(&self) -> anyhow::Result<u64>;
+}
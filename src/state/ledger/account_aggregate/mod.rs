@@ -0,0 +1,121 @@
+pub mod store;
+
+use crate::{
+    block::precomputed::PrecomputedBlock,
+    state::ledger::{
+        command::{CommandStatusData, SignedCommand, UserCommandWithStatus},
+        public_key::PublicKey,
+    },
+};
+use mina_serialization_types::staged_ledger_diff::{SignedCommandPayloadBody, UserCommand};
+use serde::{Deserialize, Serialize};
+
+/// Running totals of an account's canonical user commands
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub struct AccountAggregate {
+    /// Total amount received in applied payments
+    pub total_received: u64,
+    /// Total fees paid as fee payer, including failed commands
+    pub total_fees_paid: u64,
+    /// Number of commands paid for as fee payer
+    pub num_commands: u64,
+}
+
+impl AccountAggregate {
+    /// Increments of the aggregates of the accounts involved in the block's user commands
+    pub fn from_precomputed_block(precomputed_block: &PrecomputedBlock) -> Vec<(PublicKey, Self)> {
+        let mut increments: Vec<(PublicKey, Self)> = vec![];
+        for command in precomputed_block.commands() {
+            let command = UserCommandWithStatus(command);
+            let applied = matches!(command.status_data(), CommandStatusData::Applied { .. });
+            let UserCommand::SignedCommand(signed_command) = command.data();
+            let signed_command = SignedCommand(signed_command);
+
+            let fee = signed_command.payload_common().fee.inner().inner();
+            add_increment(
+                &mut increments,
+                signed_command.fee_payer_pk(),
+                Self {
+                    total_fees_paid: fee,
+                    num_commands: 1,
+                    ..Default::default()
+                },
+            );
+
+            if let (true, SignedCommandPayloadBody::PaymentPayload(payment_payload)) =
+                (applied, signed_command.payload_body())
+            {
+                let payment = payment_payload.inner().inner();
+                add_increment(
+                    &mut increments,
+                    payment.receiver_pk.into(),
+                    Self {
+                        total_received: payment.amount.inner().inner(),
+                        ..Default::default()
+                    },
+                );
+            }
+        }
+        increments
+    }
+
+    /// The aggregates less the block's increments, as when the block is no longer canonical
+    pub fn saturating_sub(&self, decrement: &Self) -> Self {
+        Self {
+            total_received: self.total_received.saturating_sub(decrement.total_received),
+            total_fees_paid: self
+                .total_fees_paid
+                .saturating_sub(decrement.total_fees_paid),
+            num_commands: self.num_commands.saturating_sub(decrement.num_commands),
+        }
+    }
+
+    /// The aggregates plus the block's increments, as when the block becomes canonical
+    pub fn saturating_add(&self, increment: &Self) -> Self {
+        Self {
+            total_received: self.total_received.saturating_add(increment.total_received),
+            total_fees_paid: self
+                .total_fees_paid
+                .saturating_add(increment.total_fees_paid),
+            num_commands: self.num_commands.saturating_add(increment.num_commands),
+        }
+    }
+
+    /// Counter encoding merged by the store, see [crate::store::kv::merge_counters]
+    pub fn to_counters(&self) -> Vec<u8> {
+        [self.total_received, self.total_fees_paid, self.num_commands]
+            .iter()
+            .flat_map(|counter| counter.to_be_bytes())
+            .collect()
+    }
+
+    pub fn from_counters(bytes: &[u8]) -> anyhow::Result<Self> {
+        if bytes.len() != 24 {
+            return Err(anyhow::Error::msg(format!(
+                "Account aggregate has {} bytes, expected 24",
+                bytes.len()
+            )));
+        }
+        let counter = |n: usize| u64::from_be_bytes(bytes[8 * n..8 * (n + 1)].try_into().unwrap());
+        Ok(Self {
+            total_received: counter(0),
+            total_fees_paid: counter(1),
+            num_commands: counter(2),
+        })
+    }
+}
+
+fn add_increment(
+    increments: &mut Vec<(PublicKey, AccountAggregate)>,
+    pk: PublicKey,
+    increment: AccountAggregate,
+) {
+    match increments.iter_mut().find(|(key, _)| key == &pk) {
+        Some((_, aggregate)) => {
+            aggregate.total_received += increment.total_received;
+            aggregate.total_fees_paid += increment.total_fees_paid;
+            aggregate.num_commands += increment.num_commands;
+        }
+        None => increments.push((pk, increment)),
+    }
+}
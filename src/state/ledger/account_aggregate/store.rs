@@ -0,0 +1,12 @@
+use crate::state::ledger::public_key::PublicKey;
+
+use super::AccountAggregate;
+
+/// Store of per-account aggregates over the canonical chain
+///
+/// Aggregates are incremented by merges when a block becomes canonical, without
+/// reading the current totals
+pub trait AccountAggregateStore {
+    /// Get the aggregates of `pk`, all zero if it has no canonical commands
+    fn get_account_aggregate(&self, pk: &PublicKey) -> anyhow::Result<AccountAggregate>;
+}
@@ -0,0 +1,44 @@
+pub mod store;
+
+use crate::{
+    block::{precomputed::PrecomputedBlock, BlockHash},
+    state::ledger::{post_balances::PostBalanceUpdate, public_key::PublicKey, Amount},
+};
+use serde::{Deserialize, Serialize};
+
+/// Balance of an account after a block changed it
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct BalanceHistoryEntry {
+    pub balance: Amount,
+    pub state_hash: BlockHash,
+    pub blockchain_length: u32,
+}
+
+impl BalanceHistoryEntry {
+    /// Final balances of the accounts changed by the block's user commands
+    pub fn from_precomputed_block(precomputed_block: &PrecomputedBlock) -> Vec<(PublicKey, Self)> {
+        let state_hash = BlockHash(precomputed_block.state_hash.clone());
+        let blockchain_length = precomputed_block.blockchain_length.unwrap_or_default();
+        let mut entries: Vec<(PublicKey, Self)> = vec![];
+
+        for update in PostBalanceUpdate::from_precomputed(precomputed_block) {
+            for post_balance in [update.fee_payer, update.source, update.receiver] {
+                let entry = Self {
+                    balance: post_balance.balance.into(),
+                    state_hash: state_hash.clone(),
+                    blockchain_length,
+                };
+
+                // later commands in the block supersede earlier ones
+                match entries
+                    .iter_mut()
+                    .find(|(pk, _)| pk == &post_balance.public_key)
+                {
+                    Some((_, existing)) => *existing = entry,
+                    None => entries.push((post_balance.public_key, entry)),
+                }
+            }
+        }
+        entries
+    }
+}
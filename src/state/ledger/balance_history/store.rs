@@ -0,0 +1,20 @@
+use crate::{block::precomputed::PrecomputedBlock, state::ledger::public_key::PublicKey};
+
+use super::BalanceHistoryEntry;
+
+/// Store of account balances by public key and blockchain length
+pub trait BalanceStore {
+    /// Add the balances of the accounts changed by a block
+    fn add_balances(&self, block: &PrecomputedBlock) -> anyhow::Result<()>;
+
+    /// Get every recorded balance of `pk`, ordered by blockchain length
+    fn get_balance_history(&self, pk: &PublicKey) -> anyhow::Result<Vec<BalanceHistoryEntry>>;
+
+    /// Get the balance of `pk` as of the canonical block of the given length, blocks which
+    /// aren't canonical, e.g. of forks, are skipped
+    fn get_balance_at_height(
+        &self,
+        pk: &PublicKey,
+        blockchain_length: u32,
+    ) -> anyhow::Result<Option<BalanceHistoryEntry>>;
+}
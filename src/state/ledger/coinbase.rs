@@ -1,11 +1,11 @@
 use crate::block::precomputed::PrecomputedBlock;
 
-use super::{diff::account::AccountDiff, PublicKey};
+use super::{diff::account::AccountDiff, Amount, PublicKey};
 
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct Coinbase {
     pub receiver: PublicKey,
-    supercharge: bool,
+    amount: Amount,
 }
 
 impl Coinbase {
@@ -20,15 +20,15 @@ impl Coinbase {
             .inner()
             .inner();
         let receiver = consensus_state.coinbase_receiver.into();
-        let supercharge = consensus_state.supercharge_coinbase;
+        let amount = precomputed_block
+            .network
+            .coinbase_amount(consensus_state.supercharge_coinbase)
+            .into();
 
-        Self {
-            receiver,
-            supercharge,
-        }
+        Self { receiver, amount }
     }
 
     pub fn as_account_diff(self) -> AccountDiff {
-        AccountDiff::from_coinbase(self.receiver, self.supercharge)
+        AccountDiff::from_coinbase(self.receiver, self.amount)
     }
 }
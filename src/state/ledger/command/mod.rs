@@ -1,7 +1,10 @@
+pub mod store;
+
 use crate::{
-    block::precomputed::PrecomputedBlock,
+    block::{precomputed::PrecomputedBlock, BlockHash},
     state::ledger::{public_key::PublicKey, Amount},
 };
+use blake2::{digest::consts::U32, Blake2b, Digest};
 use mina_serialization_types::{
     staged_ledger_diff::{
         SignedCommandPayloadBody, SignedCommandPayloadCommon, StakeDelegation,
@@ -11,6 +14,12 @@ use mina_serialization_types::{
 };
 use serde::{Deserialize, Serialize};
 
+/// Base58check version byte of binprot serialized signed commands
+const SIGNED_COMMAND_VERSION_BYTE: u8 = 0x13;
+
+/// Base58check version byte of transaction hashes
+const TRANSACTION_HASH_VERSION_BYTE: u8 = 0x1d;
+
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
 pub enum TransactionType {
     Payment,
@@ -69,6 +78,14 @@ impl UserCommandWithStatus {
 #[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub struct PaymentPayload(pub PaymentPayloadV1);
 
+/// User command along with the block it was included in
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct CommandWithStateHash {
+    pub command: UserCommandWithStatus,
+    pub state_hash: BlockHash,
+    pub blockchain_length: Option<u32>,
+}
+
 impl Command {
     pub fn from_precomputed_block(precomputed_block: &PrecomputedBlock) -> Vec<Self> {
         precomputed_block
@@ -142,6 +159,25 @@ impl SignedCommand {
     pub fn signer(&self) -> PublicKey {
         self.0.clone().inner().inner().signer.0.inner().into()
     }
+
+    /// Mina's transaction hash of the signed command, e.g. `CkpZ...`
+    ///
+    /// The blake2b-256 digest of the base58check encoded binprot serialization, itself
+    /// base58check encoded as a versioned, length prefixed string
+    pub fn hash_signed_command(&self) -> anyhow::Result<String> {
+        let mut binprot = vec![];
+        bin_prot::to_writer(&mut binprot, &self.0)?;
+        let encoded = bs58::encode(binprot)
+            .with_check_version(SIGNED_COMMAND_VERSION_BYTE)
+            .into_string();
+
+        let digest = Blake2b::<U32>::digest(encoded.as_bytes());
+        let mut hash = vec![1, digest.len() as u8];
+        hash.extend_from_slice(&digest);
+        Ok(bs58::encode(hash)
+            .with_check_version(TRANSACTION_HASH_VERSION_BYTE)
+            .into_string())
+    }
 }
 
 impl UserCommandWithStatus {
@@ -0,0 +1,42 @@
+use crate::{
+    block::{precomputed::PrecomputedBlock, BlockHash},
+    state::ledger::public_key::PublicKey,
+};
+
+use super::CommandWithStateHash;
+
+/// Store of user commands keyed by their hash and the block including them
+pub trait CommandStore {
+    /// Add the user commands of a block
+    fn add_commands(&self, block: &PrecomputedBlock) -> anyhow::Result<()>;
+
+    /// Get a user command, its containing block, and status by hash
+    fn get_command_by_hash(
+        &self,
+        command_hash: &str,
+    ) -> anyhow::Result<Option<CommandWithStateHash>>;
+
+    /// Get the hashes of the user commands in a block, in block order
+    fn get_command_hashes_in_block(&self, state_hash: &BlockHash) -> anyhow::Result<Vec<String>>;
+
+    /// Get the user commands in a block, in block order
+    fn get_commands_in_block(
+        &self,
+        state_hash: &BlockHash,
+    ) -> anyhow::Result<Vec<CommandWithStateHash>>;
+
+    /// Get the user commands sent or received by `pk`, ordered by blockchain length
+    fn get_commands_for_public_key(
+        &self,
+        pk: &PublicKey,
+    ) -> anyhow::Result<Vec<CommandWithStateHash>>;
+
+    /// Get at most `limit` of the user commands sent or received by `pk`, ordered by
+    /// blockchain length, after skipping the first `offset`
+    fn get_commands_for_public_key_page(
+        &self,
+        pk: &PublicKey,
+        offset: usize,
+        limit: usize,
+    ) -> anyhow::Result<Vec<CommandWithStateHash>>;
+}
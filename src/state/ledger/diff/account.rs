@@ -55,14 +55,10 @@ impl AccountDiff {
         }
     }
 
-    pub fn from_coinbase(coinbase_receiver: PublicKey, supercharge_coinbase: bool) -> Self {
-        let amount = match supercharge_coinbase {
-            true => 1440,
-            false => 720,
-        } * (1e9 as u64);
+    pub fn from_coinbase(coinbase_receiver: PublicKey, amount: Amount) -> Self {
         AccountDiff::Payment(PaymentDiff {
             public_key: coinbase_receiver,
-            amount: amount.into(),
+            amount,
             update_type: UpdateType::Deposit,
         })
     }
@@ -148,6 +144,7 @@ impl std::fmt::Debug for UpdateType {
 #[cfg(test)]
 mod tests {
     use super::{AccountDiff, DelegationDiff, PaymentDiff, UpdateType};
+    use crate::network::Network;
     use crate::state::ledger::account::Amount;
     use crate::state::ledger::command::{Command, Delegation, Payment};
     use crate::state::ledger::PublicKey;
@@ -217,10 +214,10 @@ mod tests {
         let coinbase_receiver_result = PublicKey::from_address(coinbase_receiver_str).unwrap();
         let coinbase_receiver = coinbase_receiver_result.clone();
 
-        let supercharge_coinbase = true;
+        let amount = Network::Mainnet.coinbase_amount(true);
 
         let account_diff =
-            AccountDiff::from_coinbase(coinbase_receiver_result.into(), supercharge_coinbase);
+            AccountDiff::from_coinbase(coinbase_receiver_result.into(), amount.into());
 
         let expected_payment_diff = PaymentDiff {
             public_key: coinbase_receiver.into(),
@@ -0,0 +1,148 @@
+pub mod store;
+
+use crate::{
+    block::{precomputed::PrecomputedBlock, BlockHash},
+    state::ledger::{
+        command::SignedCommand, public_key::PublicKey, snark_work::SnarkWorkSummary, Amount,
+    },
+};
+use mina_serialization_types::staged_ledger_diff::{CoinBase, UserCommand};
+use serde::{Deserialize, Serialize};
+
+/// Balance changes of a block which are not caused by user commands
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub enum InternalCommand {
+    /// Block reward, net of any part paid to SNARK workers
+    Coinbase { receiver: PublicKey, amount: Amount },
+    /// Transaction fees paid to the block producer or SNARK fees paid by the block producer
+    FeeTransfer {
+        sender: PublicKey,
+        receiver: PublicKey,
+        amount: Amount,
+    },
+    /// Part of the coinbase paid to a SNARK worker
+    FeeTransferViaCoinbase {
+        sender: PublicKey,
+        receiver: PublicKey,
+        amount: Amount,
+    },
+}
+
+/// Internal command along with the block it was included in
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct InternalCommandWithStateHash {
+    pub command: InternalCommand,
+    pub state_hash: BlockHash,
+    pub blockchain_length: Option<u32>,
+}
+
+impl InternalCommand {
+    pub fn from_precomputed_block(precomputed_block: &PrecomputedBlock) -> Vec<Self> {
+        let coinbase_receiver = precomputed_block.coinbase_receiver();
+        let mut internal_commands = vec![];
+
+        // coinbase and its fee transfers
+        let coinbase_fee_transfers = match precomputed_block.coinbase() {
+            CoinBase::Zero => None,
+            CoinBase::One(fee_transfer) => Some(fee_transfer.into_iter().collect::<Vec<_>>()),
+            CoinBase::Two(fee_transfer0, fee_transfer1) => {
+                Some(fee_transfer0.into_iter().chain(fee_transfer1).collect())
+            }
+        };
+        if let Some(coinbase_fee_transfers) = coinbase_fee_transfers {
+            let mut coinbase_amount = precomputed_block.coinbase_amount();
+
+            for fee_transfer in coinbase_fee_transfers {
+                let fee_transfer = fee_transfer.inner();
+                let amount: u64 = fee_transfer.fee.inner().inner();
+                coinbase_amount = coinbase_amount.saturating_sub(amount);
+                internal_commands.push(Self::FeeTransferViaCoinbase {
+                    sender: coinbase_receiver.clone(),
+                    receiver: fee_transfer.receiver_pk.into(),
+                    amount: amount.into(),
+                });
+            }
+            internal_commands.push(Self::Coinbase {
+                receiver: coinbase_receiver.clone(),
+                amount: coinbase_amount.into(),
+            });
+        }
+
+        // SNARK fees are paid out of the transaction fees
+        let mut snark_fees = 0;
+        for work in SnarkWorkSummary::from_precomputed_block(precomputed_block) {
+            if work.fee.0 > 0 {
+                snark_fees += work.fee.0;
+                internal_commands.push(Self::FeeTransfer {
+                    sender: coinbase_receiver.clone(),
+                    receiver: work.prover,
+                    amount: work.fee,
+                });
+            }
+        }
+
+        let transaction_fees: u64 = precomputed_block
+            .commands()
+            .into_iter()
+            .map(|command| match command.inner().data.inner().inner() {
+                UserCommand::SignedCommand(signed_command) => {
+                    let fee: u64 = SignedCommand(signed_command)
+                        .payload_common()
+                        .fee
+                        .inner()
+                        .inner();
+                    fee
+                }
+            })
+            .sum();
+        if transaction_fees > snark_fees {
+            internal_commands.push(Self::FeeTransfer {
+                sender: coinbase_receiver.clone(),
+                receiver: coinbase_receiver,
+                amount: (transaction_fees - snark_fees).into(),
+            });
+        }
+
+        internal_commands
+    }
+
+    pub fn receiver(&self) -> &PublicKey {
+        match self {
+            Self::Coinbase { receiver, .. }
+            | Self::FeeTransfer { receiver, .. }
+            | Self::FeeTransferViaCoinbase { receiver, .. } => receiver,
+        }
+    }
+}
+
+impl std::fmt::Display for InternalCommand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Coinbase { receiver, amount } => {
+                write!(f, "Coinbase of {} to {}", amount.0, receiver.to_address())
+            }
+            Self::FeeTransfer {
+                sender,
+                receiver,
+                amount,
+            } => write!(
+                f,
+                "Fee transfer of {} from {} to {}",
+                amount.0,
+                sender.to_address(),
+                receiver.to_address()
+            ),
+            Self::FeeTransferViaCoinbase {
+                sender,
+                receiver,
+                amount,
+            } => write!(
+                f,
+                "Fee transfer via coinbase of {} from {} to {}",
+                amount.0,
+                sender.to_address(),
+                receiver.to_address()
+            ),
+        }
+    }
+}
@@ -0,0 +1,24 @@
+use crate::{
+    block::{precomputed::PrecomputedBlock, BlockHash},
+    state::ledger::public_key::PublicKey,
+};
+
+use super::InternalCommandWithStateHash;
+
+/// Store of coinbases and fee transfers
+pub trait InternalCommandStore {
+    /// Add the internal commands of a block
+    fn add_internal_commands(&self, block: &PrecomputedBlock) -> anyhow::Result<()>;
+
+    /// Get the internal commands of a block
+    fn get_internal_commands(
+        &self,
+        state_hash: &BlockHash,
+    ) -> anyhow::Result<Vec<InternalCommandWithStateHash>>;
+
+    /// Get the internal commands paid to `pk`, ordered by blockchain length
+    fn get_internal_commands_for_public_key(
+        &self,
+        pk: &PublicKey,
+    ) -> anyhow::Result<Vec<InternalCommandWithStateHash>>;
+}
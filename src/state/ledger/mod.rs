@@ -1,10 +1,15 @@
 pub mod account;
+pub mod account_aggregate;
+pub mod balance_history;
 pub mod coinbase;
 pub mod command;
 pub mod diff;
 pub mod genesis;
+pub mod internal_command;
 pub mod post_balances;
 pub mod public_key;
+pub mod snark_work;
+pub mod staking;
 pub mod store;
 
 use crate::{block::precomputed::PrecomputedBlock, state::ledger::post_balances::UserCommandType};
@@ -190,6 +195,24 @@ impl Ledger {
         }
         Ok(())
     }
+
+    /// The `num` accounts with the largest balances, largest first
+    ///
+    /// Balances are total balances, locked (vesting) balances aren't modeled yet
+    pub fn top_accounts(&self, num: usize) -> Vec<Account> {
+        let mut accounts: Vec<&Account> = self.accounts.values().collect();
+        if num < accounts.len() {
+            accounts.select_nth_unstable_by(num, |a, b| b.balance.cmp(&a.balance));
+            accounts.truncate(num);
+        }
+        accounts.sort_by_cached_key(|account| {
+            (
+                std::cmp::Reverse(account.balance),
+                account.public_key.to_address(),
+            )
+        });
+        accounts.into_iter().cloned().collect()
+    }
 }
 
 impl PartialEq for Ledger {
@@ -0,0 +1,73 @@
+pub mod store;
+
+use crate::{
+    block::{precomputed::PrecomputedBlock, BlockHash},
+    state::ledger::{public_key::PublicKey, Amount},
+};
+use serde::{Deserialize, Serialize};
+
+/// Completed SNARK work included in a block
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct SnarkWorkSummary {
+    pub fee: Amount,
+    pub prover: PublicKey,
+}
+
+/// Completed SNARK work along with the block it was included in
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct SnarkWorkSummaryWithStateHash {
+    pub fee: Amount,
+    pub prover: PublicKey,
+    pub state_hash: BlockHash,
+    pub blockchain_length: Option<u32>,
+}
+
+/// Completed SNARK work of a prover included in canonical blocks
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct ProverSnarkWork {
+    pub prover: PublicKey,
+    /// Ordered by blockchain length
+    pub snark_work: Vec<SnarkWorkSummaryWithStateHash>,
+    /// Ordered by epoch
+    pub epoch_fees: Vec<EpochSnarkFees>,
+}
+
+/// Number and fees of a prover's canonical SNARK works in an epoch
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct EpochSnarkFees {
+    pub epoch: u32,
+    pub num_works: u32,
+    pub total_fees: u64,
+}
+
+impl SnarkWorkSummary {
+    pub fn from_precomputed_block(precomputed_block: &PrecomputedBlock) -> Vec<Self> {
+        precomputed_block
+            .completed_works()
+            .into_iter()
+            .map(|work| {
+                let work = work.inner();
+                let fee: u64 = work.fee.inner().inner();
+                Self {
+                    fee: fee.into(),
+                    prover: work.prover.into(),
+                }
+            })
+            .collect()
+    }
+}
+
+impl SnarkWorkSummaryWithStateHash {
+    pub fn from_summary(
+        summary: SnarkWorkSummary,
+        state_hash: &BlockHash,
+        blockchain_length: Option<u32>,
+    ) -> Self {
+        Self {
+            fee: summary.fee,
+            prover: summary.prover,
+            state_hash: state_hash.clone(),
+            blockchain_length,
+        }
+    }
+}
@@ -0,0 +1,24 @@
+use crate::{
+    block::{precomputed::PrecomputedBlock, BlockHash},
+    state::ledger::public_key::PublicKey,
+};
+
+use super::{SnarkWorkSummary, SnarkWorkSummaryWithStateHash};
+
+/// Store of completed SNARK work by block and by prover
+pub trait SnarkStore {
+    /// Add the completed SNARK work of a block
+    fn add_snark_work(&self, block: &PrecomputedBlock) -> anyhow::Result<()>;
+
+    /// Get the completed SNARK work included in a block
+    fn get_snark_work_in_block(
+        &self,
+        state_hash: &BlockHash,
+    ) -> anyhow::Result<Vec<SnarkWorkSummary>>;
+
+    /// Get the completed SNARK work of `prover`, ordered by blockchain length
+    fn get_snark_work_by_prover(
+        &self,
+        prover: &PublicKey,
+    ) -> anyhow::Result<Vec<SnarkWorkSummaryWithStateHash>>;
+}
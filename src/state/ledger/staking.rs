@@ -0,0 +1,54 @@
+use super::{public_key::PublicKey, Ledger};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Stake delegated to a block producer
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DelegateStake {
+    pub delegate: String,
+    pub total_delegated: u64,
+    pub num_delegators: u32,
+}
+
+/// Stake of a ledger per delegate, largest first
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StakeDistribution {
+    /// Epoch of the staking ledger, `None` for the best ledger
+    pub epoch: Option<u32>,
+    pub total_stake: u64,
+    pub delegates: Vec<DelegateStake>,
+}
+
+impl StakeDistribution {
+    /// Accounts without a delegate stake for themselves
+    pub fn new(ledger: &Ledger, epoch: Option<u32>) -> Self {
+        let mut stakes: HashMap<&PublicKey, (u64, u32)> = HashMap::new();
+        for account in ledger.accounts.values() {
+            let delegate = account.delegate.as_ref().unwrap_or(&account.public_key);
+            let (total_delegated, num_delegators) = stakes.entry(delegate).or_default();
+            *total_delegated += account.balance.0;
+            *num_delegators += 1;
+        }
+
+        let mut delegates: Vec<DelegateStake> = stakes
+            .into_iter()
+            .map(
+                |(delegate, (total_delegated, num_delegators))| DelegateStake {
+                    delegate: delegate.to_address(),
+                    total_delegated,
+                    num_delegators,
+                },
+            )
+            .collect();
+        delegates.sort_by(|a, b| {
+            b.total_delegated
+                .cmp(&a.total_delegated)
+                .then_with(|| a.delegate.cmp(&b.delegate))
+        });
+        Self {
+            epoch,
+            total_stake: delegates.iter().map(|stake| stake.total_delegated).sum(),
+            delegates,
+        }
+    }
+}
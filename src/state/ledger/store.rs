@@ -1,12 +1,44 @@
-use crate::block::BlockHash;
+use crate::{block::BlockHash, state::diffs::DiffsMap};
 
-use super::Ledger;
+use super::{diff::LedgerDiff, Ledger};
 
-/// Store of canonical and epoch boundary ledgers
+/// Store of sparse ledger snapshots, per-block ledger diffs, and staking ledgers
 pub trait LedgerStore {
-    /// Add ledger associated with a canonical block
+    /// Add a full ledger snapshot associated with a canonical block
     fn add_ledger(&self, state_hash: &BlockHash, ledger: Ledger) -> anyhow::Result<()>;
 
     /// Get a ledger associated with an arbitrary block
+    ///
+    /// The ledger is reconstructed by applying diffs to the last reconstructed ledger, if it's
+    /// an ancestor, or else the nearest ancestor snapshot
     fn get_ledger(&self, state_hash: &BlockHash) -> anyhow::Result<Option<Ledger>>;
+
+    /// Add the ledger diff of a block
+    fn add_ledger_diff(
+        &self,
+        state_hash: &BlockHash,
+        parent_hash: &BlockHash,
+        diff: &LedgerDiff,
+    ) -> anyhow::Result<()>;
+
+    /// Get the ledger diff of a block along with the block's parent hash
+    fn get_ledger_diff(
+        &self,
+        state_hash: &BlockHash,
+    ) -> anyhow::Result<Option<(BlockHash, LedgerDiff)>>;
+
+    /// Add the ledger diff of a pending block spilled out of memory, see [DiffsMap]
+    fn add_spilled_diff(&self, state_hash: &BlockHash, diff: &LedgerDiff) -> anyhow::Result<()>;
+
+    /// Get the spilled ledger diff of a pending block
+    fn get_spilled_diff(&self, state_hash: &BlockHash) -> anyhow::Result<Option<LedgerDiff>>;
+
+    /// Remove the spilled ledger diff of a block which is no longer pending
+    fn remove_spilled_diff(&self, state_hash: &BlockHash) -> anyhow::Result<()>;
+
+    /// Add the staking ledger of an epoch
+    fn add_staking_ledger(&self, epoch: u32, ledger: Ledger) -> anyhow::Result<()>;
+
+    /// Get the staking ledger of an epoch
+    fn get_staking_ledger(&self, epoch: u32) -> anyhow::Result<Option<Ledger>>;
 }
@@ -0,0 +1,33 @@
+pub mod store;
+
+use crate::{
+    block::{Block, BlockHash},
+    state::ledger::diff::LedgerDiff,
+};
+use serde_derive::{Deserialize, Serialize};
+
+/// Progress of the indexer, recorded on every state update so a restarted
+/// server knows where it left off
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub struct StoreMetadata {
+    pub best_tip: BlockHash,
+    pub canonical_tip: BlockHash,
+    pub max_canonical_height: u32,
+    pub blocks_processed: u32,
+}
+
+/// Compact serialization of the witness tree, persisted periodically so a restarted
+/// server only re-ingests the blocks added since
+#[derive(Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub struct PersistedWitnessTree {
+    pub best_tip: BlockHash,
+    pub canonical_tip: BlockHash,
+    /// Blocks of the root branch in level order
+    pub root_branch: Vec<Block>,
+    /// Blocks of each dangling branch in level order
+    pub dangling_branches: Vec<Vec<Block>>,
+    /// In-memory ledger diffs of the blocks following the canonical tip
+    pub diffs: Vec<(BlockHash, LedgerDiff)>,
+    /// Blocks following the canonical tip whose ledger diffs are spilled to the store
+    pub spilled_diffs: Vec<BlockHash>,
+}
@@ -0,0 +1,16 @@
+use super::{PersistedWitnessTree, StoreMetadata};
+
+/// Store of the indexer's progress
+pub trait MetadataStore {
+    /// Record the best tip, canonical tip, max canonical height, and number of blocks processed
+    fn set_metadata(&self, metadata: &StoreMetadata) -> anyhow::Result<()>;
+
+    /// Get the last recorded metadata, if any
+    fn get_metadata(&self) -> anyhow::Result<Option<StoreMetadata>>;
+
+    /// Record the serialized witness tree, replacing the previous one
+    fn set_witness_tree(&self, witness_tree: &PersistedWitnessTree) -> anyhow::Result<()>;
+
+    /// Get the last recorded witness tree, if any
+    fn get_witness_tree(&self) -> anyhow::Result<Option<PersistedWitnessTree>>;
+}
@@ -1,51 +1,71 @@
 use self::summary::{
-    DbStats, SummaryShort, SummaryVerbose, WitnessTreeSummaryShort, WitnessTreeSummaryVerbose,
+    ConsensusTimeSummary, DanglingBranchSummary, DbStats, SummaryShort, SummaryVerbose,
+    WitnessTreeSummaryShort, WitnessTreeSummaryVerbose,
 };
 use crate::{
     block::{
-        parser::BlockParser, precomputed::PrecomputedBlock, store::BlockStore, Block, BlockHash,
-        BlockWithoutHeight,
+        find_block_file, parse_file,
+        parser::BlockParser,
+        precomputed::{PrecomputedBlock, ProtocolVersion},
+        store::BlockStore,
+        Block, BlockHash, BlockWithoutHeight,
     },
+    error::IndexerError,
+    network::Network,
     state::{
         branch::Branch,
+        canonicity::{chain::CanonicalChain, store::CanonicityStore},
+        diffs::DiffsMap,
         ledger::{
             command::Command, diff::LedgerDiff, genesis::GenesisLedger, store::LedgerStore, Ledger,
         },
+        metadata::{store::MetadataStore, PersistedWitnessTree, StoreMetadata},
+        observer::StateObserver,
     },
     store::IndexerStore,
     BLOCK_REPORTING_FREQ_NUM, BLOCK_REPORTING_FREQ_SEC, CANONICAL_UPDATE_THRESHOLD,
-    MAINNET_CANONICAL_THRESHOLD, MAINNET_TRANSITION_FRONTIER_K, PRUNE_INTERVAL_DEFAULT,
+    DANGLING_MAX_AGE_SLOTS_DEFAULT, DANGLING_MAX_DEPTH_DEFAULT, LEDGER_SNAPSHOT_INTERVAL_DEFAULT,
+    PRUNE_INTERVAL_DEFAULT, SUMMARY_WITNESS_TREE_DEPTH, WITNESS_TREE_INTERVAL_DEFAULT,
 };
 use id_tree::NodeId;
-use serde_derive::{Deserialize, Serialize};
 use std::{
-    collections::HashMap,
-    str::FromStr,
+    path::Path,
     sync::Arc,
     time::{Duration, Instant},
 };
 use time::{OffsetDateTime, PrimitiveDateTime};
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
 
 pub mod branch;
+pub mod canonicity;
+pub mod diffs;
+pub mod event;
 pub mod ledger;
+pub mod metadata;
+pub mod observer;
 pub mod summary;
 
+pub use canonicity::Canonicity;
+
 /// Rooted forest of precomputed block summaries aka the witness tree
 /// `root_branch` - represents the tree of blocks connecting back to a known ledger state, e.g. genesis
 /// `dangling_branches` - trees of blocks stemming from an unknown ledger state
 pub struct IndexerState {
     /// Indexer mode
     pub mode: IndexerMode,
+    /// Network the indexed blocks belong to
+    pub network: Network,
+    /// Protocol version of the indexed blocks
+    pub protocol_version: ProtocolVersion,
     /// Indexer phase
     pub phase: IndexerPhase,
     /// Block representing the best tip of the root branch
     pub best_tip: Tip,
     /// Highest known canonical block
     pub canonical_tip: Tip,
-    /// Map of ledger diffs following the canonical tip
-    pub diffs_map: HashMap<BlockHash, LedgerDiff>,
-    /// Append-only tree of blocks built from genesis, each containing a ledger
+    /// Map of ledger diffs following the canonical tip, the oldest are spilled to the store
+    pub diffs_map: DiffsMap,
+    /// Tree of blocks descending from the root block, their ledgers and data are in the store
     pub root_branch: Branch,
     /// Dynamic, dangling branches eventually merged into the `root_branch`
     /// needed for the possibility of missing blocks
@@ -58,14 +78,36 @@ pub struct IndexerState {
     pub prune_interval: u32,
     /// Threshold for updating the canonical tip and db ledger
     pub canonical_update_threshold: u32,
+    /// Number of confirmations, i.e. blocks on top of the best tip's ancestor, before
+    /// it becomes the canonical tip
+    pub canonical_threshold: u32,
+    /// Prune orphaned blocks more than `transition_frontier_length` blocks below the canonical tip
+    pub prune_orphaned_blocks: bool,
     /// Number of blocks added to the state
     pub blocks_processed: u32,
+    /// Number of blocks added between persisting the witness tree, 0 if only persisted on demand
+    pub witness_tree_interval: u32,
+    /// Height difference between the canonical ledger snapshots
+    pub ledger_snapshot_interval: u32,
+    /// Dangling branches whose blocks are all more than this many blocks below the canonical
+    /// tip are given up on
+    pub dangling_max_depth: u32,
+    /// Dangling branches whose blocks are all more than this many slots older than the best
+    /// tip are given up on
+    pub dangling_max_age_slots: u32,
     /// Time the indexer started running
     pub time: Instant,
     /// Datetime the indexer started running
     pub date_time: OffsetDateTime,
+    /// Called with the state after each block added during initialization
+    progress_hook: Option<ProgressHook>,
+    /// Notified of the state's changes, in the order they were added
+    observers: Vec<Arc<dyn StateObserver>>,
 }
 
+/// Observes the partially built state during initialization, e.g. to answer queries
+pub type ProgressHook = Box<dyn Fn(&IndexerState) + Send + Sync>;
+
 #[derive(Debug, Clone)]
 pub struct Tip {
     pub state_hash: BlockHash,
@@ -103,40 +145,39 @@ pub enum ExtensionDirection {
     Reverse,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
-pub enum Canonicity {
-    Canonical,
-    Orphaned,
-    Pending,
-}
-
 impl IndexerState {
     /// Creates a new indexer state from the genesis ledger
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         mode: IndexerMode,
+        network: Network,
+        protocol_version: ProtocolVersion,
         root_hash: BlockHash,
         genesis_ledger: GenesisLedger,
         indexer_store: Arc<IndexerStore>,
         transition_frontier_length: u32,
         prune_interval: u32,
         canonical_update_threshold: u32,
+        canonical_threshold: u32,
+        prune_orphaned_blocks: bool,
     ) -> anyhow::Result<Self> {
-        let root_branch = Branch::new_genesis(root_hash.clone());
+        let root_branch = Branch::new_genesis(root_hash.clone())?;
 
-        indexer_store
-            .add_ledger(&root_hash, genesis_ledger.into())
-            .expect("ledger add succeeds");
+        indexer_store.add_ledger(&root_hash, genesis_ledger.into())?;
+        indexer_store.add_genesis_block(root_branch.root_block()?)?;
 
         let tip = Tip {
-            state_hash: root_branch.root_block().state_hash.clone(),
+            state_hash: root_branch.root_block()?.state_hash.clone(),
             node_id: root_branch.root.clone(),
         };
 
         Ok(Self {
             mode,
+            network,
+            protocol_version,
             phase: IndexerPhase::InitializingFromBlockDir,
             canonical_tip: tip.clone(),
-            diffs_map: HashMap::new(),
+            diffs_map: DiffsMap::new(Some(indexer_store.clone())),
             best_tip: tip,
             root_branch,
             dangling_branches: Vec::new(),
@@ -144,9 +185,17 @@ impl IndexerState {
             transition_frontier_length,
             prune_interval,
             canonical_update_threshold,
+            canonical_threshold,
+            prune_orphaned_blocks,
             blocks_processed: 0,
             time: Instant::now(),
             date_time: OffsetDateTime::now_utc(),
+            witness_tree_interval: WITNESS_TREE_INTERVAL_DEFAULT,
+            ledger_snapshot_interval: LEDGER_SNAPSHOT_INTERVAL_DEFAULT,
+            dangling_max_depth: DANGLING_MAX_DEPTH_DEFAULT,
+            dangling_max_age_slots: DANGLING_MAX_AGE_SLOTS_DEFAULT,
+            progress_hook: None,
+            observers: vec![],
         })
     }
 
@@ -154,6 +203,8 @@ impl IndexerState {
     #[allow(clippy::too_many_arguments)]
     pub fn new_non_genesis(
         mode: IndexerMode,
+        network: Network,
+        protocol_version: ProtocolVersion,
         root_hash: BlockHash,
         ledger: Ledger,
         blockchain_length: Option<u32>,
@@ -162,27 +213,29 @@ impl IndexerState {
         transition_frontier_length: u32,
         prune_interval: u32,
         canonical_update_threshold: u32,
+        canonical_threshold: u32,
+        prune_orphaned_blocks: bool,
     ) -> anyhow::Result<Self> {
         let root_branch = Branch::new_non_genesis(
             root_hash.clone(),
             blockchain_length,
             global_slot_since_genesis,
-        );
+        )?;
 
-        indexer_store
-            .add_ledger(&root_hash, ledger)
-            .expect("ledger add succeeds");
+        indexer_store.add_ledger(&root_hash, ledger)?;
 
         let tip = Tip {
-            state_hash: root_branch.root_block().state_hash.clone(),
+            state_hash: root_branch.root_block()?.state_hash.clone(),
             node_id: root_branch.root.clone(),
         };
 
         Ok(Self {
             mode,
+            network,
+            protocol_version,
             phase: IndexerPhase::InitializingFromDB,
             canonical_tip: tip.clone(),
-            diffs_map: HashMap::new(),
+            diffs_map: DiffsMap::new(Some(indexer_store.clone())),
             best_tip: tip,
             root_branch,
             dangling_branches: Vec::new(),
@@ -190,51 +243,266 @@ impl IndexerState {
             transition_frontier_length,
             prune_interval,
             canonical_update_threshold,
+            canonical_threshold,
+            prune_orphaned_blocks,
             blocks_processed: 0,
             time: Instant::now(),
             date_time: OffsetDateTime::now_utc(),
+            witness_tree_interval: WITNESS_TREE_INTERVAL_DEFAULT,
+            ledger_snapshot_interval: LEDGER_SNAPSHOT_INTERVAL_DEFAULT,
+            dangling_max_depth: DANGLING_MAX_DEPTH_DEFAULT,
+            dangling_max_age_slots: DANGLING_MAX_AGE_SLOTS_DEFAULT,
+            progress_hook: None,
+            observers: vec![],
         })
     }
 
+    /// Restores the indexer state of a previous run from its database
+    ///
+    /// The witness tree is restored from its last persisted serialization, see
+    /// [IndexerState::persist_witness_tree], or else the root branch is rebuilt from the
+    /// recorded canonical tip. Either way, it's extended by the stored blocks descending
+    /// from it, up to the recorded best tip. Dangling blocks added since the tree was
+    /// persisted aren't restored, they stay in the store
+    #[allow(clippy::too_many_arguments)]
+    pub fn new_from_db(
+        mode: IndexerMode,
+        network: Network,
+        protocol_version: ProtocolVersion,
+        indexer_store: Arc<IndexerStore>,
+        transition_frontier_length: u32,
+        prune_interval: u32,
+        canonical_update_threshold: u32,
+        canonical_threshold: u32,
+        prune_orphaned_blocks: bool,
+    ) -> anyhow::Result<Self> {
+        if let Some(stored_network) = indexer_store.network()? {
+            if stored_network != network {
+                return Err(anyhow::Error::msg(format!(
+                    "Database holds {stored_network} blocks, not {network} blocks"
+                )));
+            }
+        }
+        let metadata = indexer_store
+            .get_metadata()?
+            .ok_or_else(|| anyhow::Error::msg("Database has no recorded state to restore from"))?;
+
+        // start from the persisted witness tree if it's recent enough to hold the recorded
+        // canonical tip, otherwise rebuild the root branch from the canonical tip
+        let witness_tree = indexer_store
+            .get_witness_tree()?
+            .map(|witness_tree| -> anyhow::Result<_> {
+                let root_branch = Branch::from_blocks(&witness_tree.root_branch)?;
+                let dangling_branches = witness_tree
+                    .dangling_branches
+                    .iter()
+                    .map(|blocks| Branch::from_blocks(blocks))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let mut diffs_map = DiffsMap::new(Some(indexer_store.clone()));
+                for (state_hash, diff) in witness_tree.diffs {
+                    diffs_map.insert(state_hash, diff)?;
+                }
+                diffs_map.insert_spilled(witness_tree.spilled_diffs);
+                Ok((
+                    root_branch,
+                    dangling_branches,
+                    diffs_map,
+                    witness_tree.canonical_tip,
+                ))
+            })
+            .transpose()?
+            .filter(|(root_branch, ..)| {
+                let holds_canonical_tip = root_branch.mem(&metadata.canonical_tip);
+                if !holds_canonical_tip {
+                    debug!("Persisted witness tree is older than the recorded canonical tip");
+                }
+                holds_canonical_tip
+            });
+        let (mut root_branch, dangling_branches, mut diffs_map, canonical_tip_hash) =
+            match witness_tree {
+                Some(witness_tree) => witness_tree,
+                None => {
+                    let canonical_tip_block = indexer_store
+                        .get_block(&metadata.canonical_tip)?
+                        .ok_or_else(|| {
+                            anyhow::Error::msg(format!(
+                                "Recorded canonical tip {} is missing from the database",
+                                metadata.canonical_tip.0
+                            ))
+                        })?;
+                    (
+                        Branch::new(&canonical_tip_block)?,
+                        vec![],
+                        DiffsMap::new(Some(indexer_store.clone())),
+                        metadata.canonical_tip.clone(),
+                    )
+                }
+            };
+        if indexer_store.get_ledger(&canonical_tip_hash)?.is_none() {
+            return Err(anyhow::Error::msg(format!(
+                "Ledger of the canonical tip {} is missing from the database",
+                canonical_tip_hash.0
+            )));
+        }
+
+        // extend the root branch with the stored blocks above its best tip
+        let best_tip_length = indexer_store
+            .get_block_header(&metadata.best_tip)?
+            .and_then(|header| header.blockchain_length)
+            .unwrap_or(0);
+        Self::extend_from_store(
+            &mut root_branch,
+            &dangling_branches,
+            &mut diffs_map,
+            &indexer_store,
+            best_tip_length,
+        )?;
+
+        let canonical_tip_id = root_branch
+            .branches
+            .traverse_level_order_ids(&root_branch.root)
+            .map_err(IndexerError::from)?
+            .find(|node_id| {
+                root_branch
+                    .branches
+                    .get(node_id)
+                    .map_or(false, |node| node.data().state_hash == canonical_tip_hash)
+            })
+            .ok_or_else(|| {
+                anyhow::Error::msg(format!(
+                    "Canonical tip {} is missing from the persisted witness tree",
+                    canonical_tip_hash.0
+                ))
+            })?;
+        let canonical_tip = Tip {
+            state_hash: canonical_tip_hash,
+            node_id: canonical_tip_id,
+        };
+        let (best_tip_id, best_tip_block) = root_branch.best_tip_with_id().ok_or_else(|| {
+            IndexerError::WitnessTree("the restored root branch has no best tip".to_string())
+        })?;
+        let best_tip = Tip {
+            state_hash: best_tip_block.state_hash,
+            node_id: best_tip_id,
+        };
+        info!(
+            "Restored {} blocks from canonical tip {:?} to best tip {:?}",
+            root_branch.len(),
+            canonical_tip.state_hash.0,
+            best_tip.state_hash.0
+        );
+
+        Ok(Self {
+            mode,
+            network,
+            protocol_version,
+            phase: IndexerPhase::InitializingFromDB,
+            canonical_tip,
+            diffs_map,
+            best_tip,
+            root_branch,
+            dangling_branches,
+            indexer_store: Some(indexer_store),
+            transition_frontier_length,
+            prune_interval,
+            canonical_update_threshold,
+            canonical_threshold,
+            prune_orphaned_blocks,
+            blocks_processed: metadata.blocks_processed,
+            time: Instant::now(),
+            date_time: OffsetDateTime::now_utc(),
+            witness_tree_interval: WITNESS_TREE_INTERVAL_DEFAULT,
+            ledger_snapshot_interval: LEDGER_SNAPSHOT_INTERVAL_DEFAULT,
+            dangling_max_depth: DANGLING_MAX_DEPTH_DEFAULT,
+            dangling_max_age_slots: DANGLING_MAX_AGE_SLOTS_DEFAULT,
+            progress_hook: None,
+            observers: vec![],
+        })
+    }
+
+    /// Extends the root branch with the stored blocks above its best tip up to the given
+    /// length, except the ones in dangling branches
+    fn extend_from_store(
+        root_branch: &mut Branch,
+        dangling_branches: &[Branch],
+        diffs_map: &mut DiffsMap,
+        indexer_store: &IndexerStore,
+        best_tip_length: u32,
+    ) -> anyhow::Result<()> {
+        let root_length = root_branch
+            .best_tip()
+            .and_then(|block| block.blockchain_length)
+            .unwrap_or(0);
+        for length in root_length + 1..=best_tip_length {
+            for state_hash in indexer_store.get_blocks_at_height(length)? {
+                if dangling_branches
+                    .iter()
+                    .any(|branch| branch.mem(&state_hash))
+                {
+                    continue;
+                }
+                let block = match indexer_store.get_block(&state_hash)? {
+                    Some(block) => block,
+                    // body dropped with its epoch
+                    None => continue,
+                };
+                if root_branch.simple_extension(&block)?.is_some() {
+                    diffs_map.insert(state_hash, LedgerDiff::from_precomputed_block(&block))?;
+                } else {
+                    debug!(
+                        "Stored block {:?} doesn't descend from the canonical tip",
+                        state_hash.0
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+
     /// Creates a new indexer state for testing
     pub fn new_testing(
         root_block: &PrecomputedBlock,
         root_ledger: Option<Ledger>,
-        rocksdb_path: Option<&std::path::Path>,
+        indexer_store: Option<IndexerStore>,
         transition_frontier_length: Option<u32>,
     ) -> anyhow::Result<Self> {
-        let root_branch = Branch::new_testing(root_block);
-        let indexer_store = rocksdb_path.map(|path| {
-            let store = IndexerStore::new(path).unwrap();
-            if let Some(ledger) = root_ledger {
-                store
-                    .add_ledger(&BlockHash(root_block.state_hash.clone()), ledger)
-                    .expect("ledger add succeeds");
-            }
-            store
-        });
+        let root_branch = Branch::new_testing(root_block)?;
+        let indexer_store = indexer_store.map(Arc::new);
+        if let (Some(store), Some(ledger)) = (indexer_store.as_ref(), root_ledger) {
+            store.add_ledger(&BlockHash(root_block.state_hash.clone()), ledger)?;
+        }
 
         let tip = Tip {
-            state_hash: root_branch.root_block().state_hash.clone(),
+            state_hash: root_branch.root_block()?.state_hash.clone(),
             node_id: root_branch.root.clone(),
         };
 
         Ok(Self {
             mode: IndexerMode::Test,
+            network: root_block.network.clone(),
+            protocol_version: root_block.protocol_version,
             phase: IndexerPhase::Testing,
             canonical_tip: tip.clone(),
-            diffs_map: HashMap::new(),
+            diffs_map: DiffsMap::new(indexer_store.clone()),
             best_tip: tip,
             root_branch,
             dangling_branches: Vec::new(),
-            indexer_store: indexer_store.map(Arc::new),
+            indexer_store,
             transition_frontier_length: transition_frontier_length
-                .unwrap_or(MAINNET_TRANSITION_FRONTIER_K),
+                .unwrap_or(root_block.network.transition_frontier_k()),
             prune_interval: PRUNE_INTERVAL_DEFAULT,
             canonical_update_threshold: CANONICAL_UPDATE_THRESHOLD,
+            canonical_threshold: root_block.network.canonical_threshold(),
+            prune_orphaned_blocks: false,
             blocks_processed: 0,
             time: Instant::now(),
             date_time: OffsetDateTime::now_utc(),
+            witness_tree_interval: WITNESS_TREE_INTERVAL_DEFAULT,
+            ledger_snapshot_interval: LEDGER_SNAPSHOT_INTERVAL_DEFAULT,
+            dangling_max_depth: DANGLING_MAX_DEPTH_DEFAULT,
+            dangling_max_age_slots: DANGLING_MAX_AGE_SLOTS_DEFAULT,
+            progress_hook: None,
+            observers: vec![],
         })
     }
 
@@ -242,6 +510,7 @@ impl IndexerState {
     fn prune_root_branch(&mut self) -> anyhow::Result<()> {
         let k = self.transition_frontier_length;
         self.update_canonical()?;
+        self.collect_dangling_branches()?;
 
         if self.root_branch.height() > self.prune_interval * k {
             let best_tip_block = self.best_tip_block().clone();
@@ -253,25 +522,120 @@ impl IndexerState {
             );
 
             self.root_branch
-                .prune_transition_frontier(k, &best_tip_block);
+                .prune_transition_frontier(k, &best_tip_block)?;
         }
 
         Ok(())
     }
 
+    /// Drops the dangling branches which can't reconnect to the root branch anymore,
+    /// i.e. too far below the canonical tip or too old, and records their blocks as
+    /// orphaned in the store. Returns the number of branches dropped
+    pub fn collect_dangling_branches(&mut self) -> anyhow::Result<usize> {
+        let canonical_length = self.canonical_tip_block().blockchain_length.unwrap_or(0);
+        let best_tip_slot = self.best_tip_block().global_slot_since_genesis;
+        let (hopeless, dangling_branches): (Vec<_>, Vec<_>) =
+            std::mem::take(&mut self.dangling_branches)
+                .into_iter()
+                .partition(|branch| {
+                    let blocks = branch.blocks();
+                    let max_length = blocks
+                        .iter()
+                        .filter_map(|block| block.blockchain_length)
+                        .max()
+                        .unwrap_or(0);
+                    let max_slot = blocks
+                        .iter()
+                        .map(|block| block.global_slot_since_genesis)
+                        .max()
+                        .unwrap_or(0);
+                    max_length.saturating_add(self.dangling_max_depth) < canonical_length
+                        || max_slot.saturating_add(self.dangling_max_age_slots) < best_tip_slot
+                });
+        self.dangling_branches = dangling_branches;
+
+        for branch in &hopeless {
+            debug!(
+                "Dropping dangling branch rooted at {:?} with {} blocks",
+                branch.root_block()?.state_hash.0,
+                branch.len()
+            );
+            // the blocks stay in the store
+            for block in branch.blocks() {
+                self.diffs_map.remove(&block.state_hash)?;
+                if let Some(indexer_store) = &self.indexer_store {
+                    indexer_store.set_canonicity(&block.state_hash, Canonicity::Orphaned)?;
+                    self.notify(|observer| {
+                        observer.canonicity_updated(&block.state_hash, &Canonicity::Orphaned)
+                    });
+                }
+            }
+        }
+        Ok(hopeless.len())
+    }
+
     /// The highest known canonical block
     pub fn canonical_tip_block(&self) -> &Block {
         self.get_block_from_id(&self.canonical_tip.node_id)
+            .expect("the canonical tip is in the root branch")
+    }
+
+    /// Root branch blocks from its root to the canonical tip, lowest first, as
+    /// `(blockchain_length, state_hash)` pairs
+    pub fn witness_canonical_chain(&self) -> Vec<(u32, BlockHash)> {
+        let mut chain: Vec<(u32, BlockHash)> = std::iter::once(self.canonical_tip_block())
+            .chain(
+                self.root_branch
+                    .branches
+                    .ancestors(&self.canonical_tip.node_id)
+                    .into_iter()
+                    .flatten()
+                    .map(|node| node.data()),
+            )
+            .filter_map(|block| {
+                block
+                    .blockchain_length
+                    .map(|length| (length, block.state_hash.clone()))
+            })
+            .collect();
+        chain.reverse();
+        chain
+    }
+
+    /// State hashes of the best chain, from the best tip, without looking for the best leaf
+    /// like [Branch::longest_chain]
+    pub fn best_chain(&self) -> Vec<BlockHash> {
+        std::iter::once(self.best_tip_block())
+            .chain(
+                self.root_branch
+                    .branches
+                    .ancestors(&self.best_tip.node_id)
+                    .into_iter()
+                    .flatten()
+                    .map(|node| node.data()),
+            )
+            .map(|block| block.state_hash.clone())
+            .collect()
+    }
+
+    /// Canonical blocks from the given length on, from the store and then the root branch
+    pub fn canonical_chain(&self, from_length: u32) -> CanonicalChain<'_> {
+        CanonicalChain::new(
+            self.indexer_store.as_deref(),
+            self.witness_canonical_chain(),
+            from_length,
+        )
     }
 
     /// The highest block known to be a descendant of the original root block
     pub fn best_tip_block(&self) -> &Block {
         self.get_block_from_id(&self.best_tip.node_id)
+            .expect("the best tip is in the root branch")
     }
 
     /// Only works with blocks in the root branch
-    fn get_block_from_id(&self, node_id: &NodeId) -> &Block {
-        self.root_branch.branches.get(node_id).unwrap().data()
+    fn get_block_from_id(&self, node_id: &NodeId) -> Result<&Block, IndexerError> {
+        Ok(self.root_branch.branches.get(node_id)?.data())
     }
 
     /// Updates the canonical tip if the precondition is met
@@ -279,27 +643,29 @@ impl IndexerState {
         if self.best_tip_block().height - self.canonical_tip_block().height
             > self.canonical_update_threshold
         {
+            let canonical_threshold = self.canonical_threshold as usize;
             let mut canonical_hashes = vec![];
-            let old_canonical_tip_id = self.canonical_tip.node_id.clone();
-            let old_canonical_tip_hash = self.canonical_tip_block().state_hash.clone();
+            let old_canonical_tip = self.canonical_tip.clone();
+            let old_canonical_tip_id = old_canonical_tip.node_id.clone();
+            let old_canonical_tip_height = self.canonical_tip_block().height;
+            let mut descends_from_canonical_tip = false;
 
             // update canonical_tip
             for (n, ancestor_id) in self
                 .root_branch
                 .branches
                 .ancestor_ids(&self.best_tip.node_id)
-                .unwrap()
+                .map_err(IndexerError::from)?
                 .enumerate()
             {
+                descends_from_canonical_tip |= ancestor_id == &old_canonical_tip_id;
                 // only add blocks between the old_canonical_tip and the new one
-                if n + 1 == MAINNET_CANONICAL_THRESHOLD as usize {
+                if n + 1 == canonical_threshold {
                     self.canonical_tip.node_id = ancestor_id.clone();
                     self.canonical_tip.state_hash =
-                        self.get_block_from_id(ancestor_id).state_hash.clone();
-                } else if n > MAINNET_CANONICAL_THRESHOLD as usize
-                    && ancestor_id != &old_canonical_tip_id
-                {
-                    let ancestor_block = self.get_block_from_id(ancestor_id);
+                        self.get_block_from_id(ancestor_id)?.state_hash.clone();
+                } else if n > canonical_threshold && ancestor_id != &old_canonical_tip_id {
+                    let ancestor_block = self.get_block_from_id(ancestor_id)?;
                     canonical_hashes.push(ancestor_block.state_hash.clone());
                 } else if ancestor_id == &old_canonical_tip_id {
                     break;
@@ -308,38 +674,50 @@ impl IndexerState {
 
             canonical_hashes.reverse();
 
-            // update canonical ledger
-            if let Some(indexer_store) = &self.indexer_store {
-                let mut ledger = indexer_store
-                    .get_ledger(&old_canonical_tip_hash)
-                    .unwrap()
-                    .unwrap();
-
-                // apply the new canonical diffs to the old canonical ledger
-                for canonical_hash in &canonical_hashes {
-                    if let Some(precomputed_block) = indexer_store.get_block(canonical_hash)? {
-                        ledger.apply_post_balances(&precomputed_block);
-                    }
-                }
+            // a fork deeper than the canonical threshold won, its blocks replace the
+            // canonical blocks above where it forks off
+            if !descends_from_canonical_tip && self.indexer_store.is_some() {
+                let new_canonical_tip = self.canonical_tip.state_hash.clone();
+                self.canonical_tip = old_canonical_tip;
+                let num_orphaned = self.rollback_canonical(&new_canonical_tip)?;
+                warn!(
+                    "Deep fork won, orphaned {num_orphaned} canonical blocks, new canonical tip {:?}",
+                    new_canonical_tip.0
+                );
+                return Ok(());
+            }
 
-                // update the ledger
-                indexer_store
-                    .add_ledger(&self.canonical_tip_block().state_hash, ledger)
-                    .unwrap();
+            // snapshot the canonical ledger at sparse intervals,
+            // in between, ledgers are reconstructed from the stored diffs
+            if self.canonical_tip_block().height / self.ledger_snapshot_interval
+                > old_canonical_tip_height / self.ledger_snapshot_interval
+            {
+                self.snapshot_canonical_ledger()?;
             }
 
             // update canonicity store
             for block_hash in self.diffs_map.keys() {
                 if let Some(indexer_store) = &self.indexer_store {
-                    if canonical_hashes.contains(block_hash) {
-                        indexer_store
-                            .set_canonicity(block_hash, Canonicity::Canonical)
-                            .unwrap();
+                    let canonicity = if canonical_hashes.contains(block_hash) {
+                        Canonicity::Canonical
                     } else {
-                        indexer_store
-                            .set_canonicity(block_hash, Canonicity::Orphaned)
-                            .unwrap();
-                    }
+                        Canonicity::Orphaned
+                    };
+                    indexer_store.set_canonicity(block_hash, canonicity.clone())?;
+                    self.notify(|observer| observer.canonicity_updated(block_hash, &canonicity));
+                }
+            }
+
+            // prune orphaned blocks more than k blocks below the new canonical tip
+            if self.prune_orphaned_blocks {
+                if let (Some(indexer_store), Some(height)) = (
+                    &self.indexer_store,
+                    self.canonical_tip_block()
+                        .blockchain_length
+                        .and_then(|length| length.checked_sub(self.transition_frontier_length + 1)),
+                ) {
+                    let num_pruned = indexer_store.prune_orphaned_blocks(height)?;
+                    debug!("Pruned {num_pruned} orphaned blocks of length at most {height}");
                 }
             }
 
@@ -348,18 +726,254 @@ impl IndexerState {
                 .root_branch
                 .branches
                 .traverse_level_order_ids(&old_canonical_tip_id)
-                .unwrap()
+                .map_err(IndexerError::from)?
             {
-                if self.get_block_from_id(&node_id).height <= self.canonical_tip_block().height {
-                    self.diffs_map
-                        .remove(&self.get_block_from_id(&node_id).state_hash.clone());
+                let block = self.get_block_from_id(&node_id)?;
+                if block.height <= self.canonical_tip_block().height {
+                    let state_hash = block.state_hash.clone();
+                    self.diffs_map.remove(&state_hash)?;
                 }
             }
+
+            self.record_metadata()?;
+        }
+
+        Ok(())
+    }
+
+    /// Stores the canonical tip's ledger whole, returns the canonical tip's hash if
+    /// its ledger could be reconstructed
+    pub fn snapshot_canonical_ledger(&self) -> anyhow::Result<Option<BlockHash>> {
+        if let Some(indexer_store) = &self.indexer_store {
+            let state_hash = self.canonical_tip.state_hash.clone();
+            if let Some(ledger) = indexer_store.get_ledger(&state_hash)? {
+                indexer_store.add_ledger(&state_hash, ledger)?;
+                self.notify(|observer| observer.ledger_snapshot_taken(&state_hash));
+                return Ok(Some(state_hash));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Makes the chain of the stored block canonical in place of the canonical blocks above
+    /// where it forks off, for forks deeper than the canonical threshold or canonicity
+    /// decided on misordered blocks
+    ///
+    /// The replaced blocks are orphaned, the block's ledger is reconstructed from the
+    /// snapshot before the fork and the new chain's diffs and snapshotted, and the root
+    /// branch is rebuilt from the block with the stored blocks above it. The store is
+    /// updated in a single write. Returns the number of orphaned canonical blocks
+    pub fn rollback_canonical(&mut self, new_canonical_tip: &BlockHash) -> anyhow::Result<usize> {
+        let indexer_store = self
+            .indexer_store
+            .clone()
+            .ok_or_else(|| anyhow::Error::msg("Rolling back canonicity requires a store"))?;
+
+        // walk back from the new canonical tip to the canonical chain
+        let mut new_chain = vec![];
+        let mut curr_hash = new_canonical_tip.clone();
+        let fork_length = loop {
+            let ancestry = indexer_store
+                .get_block_ancestry(&curr_hash)?
+                .ok_or_else(|| {
+                    anyhow::Error::msg(format!("Block {} is missing from the store", curr_hash.0))
+                })?;
+            let length = ancestry.blockchain_length.ok_or_else(|| {
+                anyhow::Error::msg(format!("Block {} has no length", curr_hash.0))
+            })?;
+            let canonical_hash = self
+                .canonical_chain(length)
+                .next()
+                .transpose()?
+                .map(|(_, state_hash)| state_hash);
+            if canonical_hash.as_ref() == Some(&curr_hash) {
+                break length;
+            }
+            if ancestry.parent_hash == curr_hash {
+                return Err(anyhow::Error::msg(format!(
+                    "Block {} doesn't descend from the canonical chain",
+                    new_canonical_tip.0
+                )));
+            }
+            new_chain.push(curr_hash);
+            curr_hash = ancestry.parent_hash;
+        };
+        if new_chain.is_empty() {
+            return Ok(0);
+        }
+        new_chain.reverse();
+        info!(
+            "Rolling back the canonical chain above length {fork_length} to {:?}",
+            new_canonical_tip.0
+        );
+
+        // the new canonical tip's ledger is reconstructed from the snapshot before the fork
+        // and the new chain's diffs
+        let ledger = indexer_store
+            .get_ledger(new_canonical_tip)?
+            .ok_or_else(|| {
+                anyhow::Error::msg(format!(
+                    "Ledger of block {} can't be reconstructed",
+                    new_canonical_tip.0
+                ))
+            })?;
+
+        // the root branch is rebuilt from the new canonical tip, the pending blocks left
+        // out of it are orphaned
+        let tip_block = indexer_store.get_block(new_canonical_tip)?.ok_or_else(|| {
+            anyhow::Error::msg(format!(
+                "Block {} is missing from the store",
+                new_canonical_tip.0
+            ))
+        })?;
+        let best_tip_length = self.best_tip_block().blockchain_length.unwrap_or(0);
+        let mut root_branch = Branch::new(&tip_block)?;
+        let mut root_diffs = DiffsMap::new(None);
+        Self::extend_from_store(
+            &mut root_branch,
+            &self.dangling_branches,
+            &mut root_diffs,
+            &indexer_store,
+            best_tip_length,
+        )?;
+
+        // the root branch's canonical blocks aren't all in the store's canonical chain,
+        // those below the fork are recorded as they're left out of the rebuilt root branch
+        let mut updates = vec![];
+        for (length, state_hash) in self.witness_canonical_chain() {
+            if length <= fork_length {
+                if indexer_store.get_canonicity(&state_hash)? != Some(Canonicity::Canonical) {
+                    updates.push((state_hash, Canonicity::Canonical));
+                }
+            } else {
+                updates.push((state_hash, Canonicity::Orphaned));
+            }
         }
+        updates.extend(
+            new_chain
+                .iter()
+                .map(|state_hash| (state_hash.clone(), Canonicity::Canonical)),
+        );
+        let old_root_branch = self.root_branch.blocks();
+        let mut pending_orphans = vec![];
+        for block in &old_root_branch {
+            if !root_branch.mem(&block.state_hash)
+                && !new_chain.contains(&block.state_hash)
+                && indexer_store.get_canonicity(&block.state_hash)?.is_none()
+            {
+                pending_orphans.push(block.state_hash.clone());
+            }
+        }
+        let all_updates: Vec<(BlockHash, Canonicity)> = updates
+            .iter()
+            .cloned()
+            .chain(
+                pending_orphans
+                    .iter()
+                    .map(|state_hash| (state_hash.clone(), Canonicity::Orphaned)),
+            )
+            .collect();
+
+        // all canonicity, aggregate and ledger changes are written at once
+        let mut orphaned = indexer_store.rollback_canonical(
+            fork_length + 1,
+            &all_updates,
+            (new_canonical_tip, &ledger),
+        )?;
+        for (state_hash, canonicity) in &updates {
+            if *canonicity == Canonicity::Orphaned && !orphaned.contains(state_hash) {
+                orphaned.push(state_hash.clone());
+            }
+        }
+        for state_hash in orphaned.iter().chain(&pending_orphans) {
+            self.notify(|observer| observer.canonicity_updated(state_hash, &Canonicity::Orphaned));
+        }
+        for (state_hash, canonicity) in &updates {
+            if *canonicity == Canonicity::Canonical {
+                self.notify(|observer| observer.canonicity_updated(state_hash, canonicity));
+            }
+        }
+        self.notify(|observer| observer.ledger_snapshot_taken(new_canonical_tip));
 
+        for block in &old_root_branch {
+            self.diffs_map.remove(&block.state_hash)?;
+        }
+        for (state_hash, diff) in root_diffs.in_memory() {
+            self.diffs_map.insert(state_hash.clone(), diff.clone())?;
+        }
+        self.root_branch = root_branch;
+
+        self.canonical_tip = Tip {
+            state_hash: new_canonical_tip.clone(),
+            node_id: self.root_branch.root.clone(),
+        };
+        let (best_tip_id, best_tip) = self.root_branch.best_tip_with_id().ok_or_else(|| {
+            IndexerError::WitnessTree("the root branch has no best tip".to_string())
+        })?;
+        self.best_tip = Tip {
+            state_hash: best_tip.state_hash.clone(),
+            node_id: best_tip_id,
+        };
+        self.notify(|observer| observer.best_tip_changed(&best_tip));
+        self.record_metadata()?;
+        Ok(orphaned.len())
+    }
+
+    /// Records the best and canonical tips and the number of blocks processed in the store
+    pub fn record_metadata(&self) -> anyhow::Result<()> {
+        if let Some(indexer_store) = &self.indexer_store {
+            indexer_store.set_metadata(&StoreMetadata {
+                best_tip: self.best_tip.state_hash.clone(),
+                canonical_tip: self.canonical_tip.state_hash.clone(),
+                max_canonical_height: self.canonical_tip_block().blockchain_length.unwrap_or(0),
+                blocks_processed: self.blocks_processed,
+            })?;
+        }
         Ok(())
     }
 
+    /// Records the witness tree in the store so a restarted server only re-ingests
+    /// the blocks added since
+    pub fn persist_witness_tree(&self) -> anyhow::Result<()> {
+        if let Some(indexer_store) = &self.indexer_store {
+            indexer_store.set_witness_tree(&PersistedWitnessTree {
+                best_tip: self.best_tip.state_hash.clone(),
+                canonical_tip: self.canonical_tip.state_hash.clone(),
+                root_branch: self.root_branch.blocks(),
+                dangling_branches: self.dangling_branches.iter().map(Branch::blocks).collect(),
+                diffs: self
+                    .diffs_map
+                    .in_memory()
+                    .map(|(state_hash, diff)| (state_hash.clone(), diff.clone()))
+                    .collect(),
+                spilled_diffs: self.diffs_map.spilled().cloned().collect(),
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Adds an observer notified of the state's changes from now on
+    pub fn add_observer(&mut self, observer: Arc<dyn StateObserver>) {
+        self.observers.push(observer);
+    }
+
+    fn notify(&self, hook: impl Fn(&dyn StateObserver)) {
+        for observer in &self.observers {
+            hook(observer.as_ref());
+        }
+    }
+
+    /// Sets the hook observing the state during initialization, `None` removes it
+    pub fn set_progress_hook(&mut self, progress_hook: Option<ProgressHook>) {
+        self.progress_hook = progress_hook;
+    }
+
+    fn report_progress(&self) {
+        if let Some(progress_hook) = &self.progress_hook {
+            progress_hook(self);
+        }
+    }
+
     /// Initialize indexer state from a collection of contiguous canonical blocks
     pub async fn initialize_with_contiguous_canonical(
         &mut self,
@@ -369,7 +983,9 @@ impl IndexerState {
         if let Some(indexer_store) = self.indexer_store.as_ref() {
             let mut ledger = indexer_store
                 .get_ledger(&self.canonical_tip.state_hash)?
-                .unwrap();
+                .ok_or_else(|| {
+                    IndexerError::MissingLedger(self.canonical_tip.state_hash.0.clone())
+                })?;
             let total_time = Instant::now();
 
             info!("Reporting every {BLOCK_REPORTING_FREQ_NUM} blocks");
@@ -390,34 +1006,35 @@ impl IndexerState {
                     debug!("Rate: {rate} blocks/s");
                 }
 
-                let precomputed_block = block_parser.next().await?.unwrap();
+                let precomputed_block = block_parser.next().await?.ok_or_else(|| {
+                    IndexerError::Other(format!(
+                        "Ran out of canonical blocks after {block_count} of {}",
+                        block_parser.num_canonical
+                    ))
+                })?;
+                if self.reject_if_incompatible(&precomputed_block)? {
+                    continue;
+                }
 
                 // apply and add to db
-                ledger.apply_post_balances(&precomputed_block);
+                let diff = LedgerDiff::from_precomputed_block(&precomputed_block);
+                ledger.apply_diff(&diff)?;
                 indexer_store.add_block(&precomputed_block)?;
-
-                if let Some(height) = precomputed_block.blockchain_length {
-                    let tmstmp = precomputed_block.timestamp();
-
-                    for cmd in precomputed_block.commands() {
-                        indexer_store.put_tx(height, tmstmp, cmd)?;
-                    }
-                }
+                self.notify(|observer| observer.block_added(&precomputed_block));
 
                 // TODO: store ledger at specified cadence, e.g. at epoch boundaries
-                // for now, just store every 1000 blocks
-                if block_count % 1000 == 0 {
-                    indexer_store.add_ledger(
-                        &BlockHash(precomputed_block.state_hash.clone()),
-                        ledger.clone(),
-                    )?;
+                // for now, just store every ledger_snapshot_interval blocks
+                if block_count % self.ledger_snapshot_interval == 0 {
+                    let state_hash = BlockHash(precomputed_block.state_hash.clone());
+                    indexer_store.add_ledger(&state_hash, ledger.clone())?;
+                    self.notify(|observer| observer.ledger_snapshot_taken(&state_hash));
                 }
 
                 if block_count == block_parser.num_canonical {
                     // update root branch
                     self.root_branch = Branch::new(&precomputed_block)?;
                     self.best_tip = Tip {
-                        state_hash: self.root_branch.root_block().state_hash.clone(),
+                        state_hash: self.root_branch.root_block()?.state_hash.clone(),
                         node_id: self.root_branch.root.clone(),
                     };
                     self.canonical_tip = self.best_tip.clone();
@@ -425,7 +1042,12 @@ impl IndexerState {
             }
 
             // store the most recent canonical ledger
-            indexer_store.add_ledger(&self.root_branch.root_block().state_hash, ledger.clone())?;
+            let root_hash = &self.root_branch.root_block()?.state_hash;
+            indexer_store.add_ledger(root_hash, ledger.clone())?;
+            self.notify(|observer| observer.ledger_snapshot_taken(root_hash));
+            self.notify(|observer| observer.best_tip_changed(self.best_tip_block()));
+            self.record_metadata()?;
+            self.report_progress();
         }
 
         // now add the successive non-canoical blocks
@@ -439,6 +1061,59 @@ impl IndexerState {
         self.add_blocks(block_parser, 0).await
     }
 
+    /// Dangling branches taller than the transition frontier, they won't connect to the root
+    /// branch unless their missing ancestors are backfilled
+    pub fn deep_dangling_branches(&self) -> impl Iterator<Item = &Branch> {
+        self.dangling_branches
+            .iter()
+            .filter(|branch| branch.height() > self.transition_frontier_length)
+    }
+
+    /// Adds the missing ancestors of the deep dangling branches found in the blocks
+    /// directory, walking back from each branch's root until an ancestor's file is missing
+    ///
+    /// Returns the number of blocks added
+    pub async fn backfill_dangling_branches(&mut self, blocks_dir: &Path) -> anyhow::Result<u32> {
+        let mut num_added = 0;
+        let mut missing: Vec<BlockHash> = self
+            .deep_dangling_branches()
+            .map(|branch| -> Result<_, IndexerError> {
+                Ok(branch.root_block()?.parent_hash.clone())
+            })
+            .collect::<Result<_, _>>()?;
+        while let Some(state_hash) = missing.pop() {
+            let path = match find_block_file(blocks_dir, &state_hash)? {
+                Some(path) => path,
+                None => {
+                    debug!(
+                        "Missing dangling branch ancestor {:?} isn't in {}",
+                        state_hash.0,
+                        blocks_dir.display()
+                    );
+                    continue;
+                }
+            };
+            let precomputed_block = parse_file(&path, &self.network).await?;
+            if matches!(
+                self.add_block(&precomputed_block)?,
+                ExtensionType::BlockNotAdded
+            ) {
+                continue;
+            }
+            num_added += 1;
+
+            // keep walking back while the branch is still dangling
+            if self
+                .dangling_branches
+                .iter()
+                .any(|branch| is_root(branch, &state_hash))
+            {
+                missing.push(BlockHash::previous_state_hash(&precomputed_block));
+            }
+        }
+        Ok(num_added)
+    }
+
     /// Adds blocks to the state according to block_parser then changes phase to Watching
     ///
     /// Returns the number of blocks parsed
@@ -485,6 +1160,7 @@ impl IndexerState {
             }
 
             self.add_block(&block)?;
+            self.report_progress();
             block_count += 1;
         }
 
@@ -498,12 +1174,88 @@ impl IndexerState {
         Ok(())
     }
 
-    /// Adds the block to the witness tree and the precomputed block to the db
+    /// Adds the block to the witness tree and the precomputed block to the db,
+    /// then records the updated tips in the store metadata, persists the witness tree
+    /// every `witness_tree_interval` blocks, and notifies the observers
+    ///
+    /// A block lacking its length gets one more than its parent's, if the parent is known.
+    /// Blocks of another network or protocol version, or whose length or slot contradicts
+    /// a known parent or child, are recorded as rejected and not added
     ///
     /// Errors if the block is already present in the witness tree
     pub fn add_block(
         &mut self,
         precomputed_block: &PrecomputedBlock,
+    ) -> anyhow::Result<ExtensionType> {
+        let with_length;
+        let precomputed_block = match self.infer_blockchain_length(precomputed_block)? {
+            Some(blockchain_length) => {
+                debug!(
+                    "Inferred length {blockchain_length} of block {:?} from its parent",
+                    precomputed_block.state_hash
+                );
+                with_length = PrecomputedBlock {
+                    blockchain_length: Some(blockchain_length),
+                    ..precomputed_block.clone()
+                };
+                &with_length
+            }
+            None => precomputed_block,
+        };
+        let best_tip = self.best_tip.state_hash.clone();
+        let extension = self.add_block_to_witness_tree(precomputed_block)?;
+        if !matches!(extension, ExtensionType::BlockNotAdded) {
+            self.record_metadata()?;
+            if self.witness_tree_interval > 0
+                && self.blocks_processed % self.witness_tree_interval == 0
+            {
+                self.persist_witness_tree()?;
+            }
+            self.notify(|observer| observer.block_added(precomputed_block));
+            if self.best_tip.state_hash != best_tip {
+                self.notify(|observer| observer.best_tip_changed(self.best_tip_block()));
+            }
+        }
+        Ok(extension)
+    }
+
+    /// Length of a block lacking one, one more than its parent's in the witness tree or store
+    fn infer_blockchain_length(
+        &self,
+        precomputed_block: &PrecomputedBlock,
+    ) -> anyhow::Result<Option<u32>> {
+        if precomputed_block.blockchain_length.is_some() {
+            return Ok(None);
+        }
+        let parent_hash = BlockHash::previous_state_hash(precomputed_block);
+        let parent_length = self
+            .known_block(&parent_hash)?
+            .and_then(|(blockchain_length, _)| blockchain_length);
+        Ok(parent_length.map(|length| length + 1))
+    }
+
+    /// Length and global slot of a block in the witness tree or store
+    fn known_block(&self, state_hash: &BlockHash) -> anyhow::Result<Option<(Option<u32>, u32)>> {
+        if let Some(block) = std::iter::once(&self.root_branch)
+            .chain(&self.dangling_branches)
+            .find_map(|branch| branch.block(state_hash))
+        {
+            return Ok(Some((
+                block.blockchain_length,
+                block.global_slot_since_genesis,
+            )));
+        }
+        match &self.indexer_store {
+            Some(indexer_store) => Ok(indexer_store
+                .get_block_header(state_hash)?
+                .map(|header| (header.blockchain_length, header.global_slot_since_genesis()))),
+            None => Ok(None),
+        }
+    }
+
+    fn add_block_to_witness_tree(
+        &mut self,
+        precomputed_block: &PrecomputedBlock,
     ) -> anyhow::Result<ExtensionType> {
         self.prune_root_branch()?;
 
@@ -515,8 +1267,18 @@ impl IndexerState {
             return Ok(ExtensionType::BlockNotAdded);
         }
 
+        if self.reject_if_incompatible(precomputed_block)? {
+            return Ok(ExtensionType::BlockNotAdded);
+        }
+
         let incoming_length = precomputed_block.blockchain_length.unwrap_or(u32::MAX);
-        if self.root_branch.root_block().blockchain_length.unwrap_or(0) > incoming_length {
+        if self
+            .root_branch
+            .root_block()?
+            .blockchain_length
+            .unwrap_or(0)
+            > incoming_length
+        {
             debug!(
                 "Block with state hash {:?} has length {incoming_length} which is too low to add to the witness tree",
                 precomputed_block.state_hash,
@@ -524,24 +1286,15 @@ impl IndexerState {
             return Ok(ExtensionType::BlockNotAdded);
         }
 
-        // add block to the db
+        // add block, its ledger diff, and derived data to the db
+        let state_hash = BlockHash(precomputed_block.state_hash.clone());
+        let diff = LedgerDiff::from_precomputed_block(precomputed_block);
         if let Some(indexer_store) = self.indexer_store.as_ref() {
             indexer_store.add_block(precomputed_block)?;
-
-            if let Some(height) = precomputed_block.blockchain_length {
-                let tmstmp = precomputed_block.timestamp();
-
-                for cmd in precomputed_block.commands() {
-                    indexer_store.put_tx(height, tmstmp, cmd)?;
-                }
-            }
         }
 
         self.blocks_processed += 1;
-        self.diffs_map.insert(
-            BlockHash(precomputed_block.state_hash.clone()),
-            LedgerDiff::from_precomputed_block(precomputed_block),
-        );
+        self.diffs_map.insert(state_hash, diff)?;
 
         // forward extension on root branch
         if self.is_length_within_root_bounds(precomputed_block) {
@@ -570,9 +1323,10 @@ impl IndexerState {
         &mut self,
         precomputed_block: &PrecomputedBlock,
     ) -> anyhow::Result<Option<ExtensionType>> {
-        if let Some((new_node_id, new_block)) = self.root_branch.simple_extension(precomputed_block)
+        if let Some((new_node_id, new_block)) =
+            self.root_branch.simple_extension(precomputed_block)?
         {
-            self.update_best_tip(&new_block, &new_node_id);
+            self.update_best_tip(&new_block, &new_node_id)?;
 
             // check if new block connects to a dangling branch
             let mut merged_tip_id = None;
@@ -580,8 +1334,8 @@ impl IndexerState {
 
             for (index, dangling_branch) in self.dangling_branches.iter_mut().enumerate() {
                 // new block is the parent of the dangling branch root
-                if is_reverse_extension(dangling_branch, precomputed_block) {
-                    merged_tip_id = self.root_branch.merge_on(&new_node_id, dangling_branch);
+                if is_reverse_extension(dangling_branch, precomputed_block)? {
+                    merged_tip_id = self.root_branch.merge_on(&new_node_id, dangling_branch)?;
                     branches_to_remove.push(index);
                 }
 
@@ -589,16 +1343,10 @@ impl IndexerState {
             }
 
             if let Some(merged_tip_id) = merged_tip_id {
-                let merged_tip_block = self
-                    .root_branch
-                    .branches
-                    .get(&merged_tip_id)
-                    .unwrap()
-                    .data()
-                    .clone();
+                let merged_tip_block = self.get_block_from_id(&merged_tip_id)?.clone();
 
                 if merged_tip_block > self.best_tip_block().clone() {
-                    self.update_best_tip(&merged_tip_block, &merged_tip_id);
+                    self.update_best_tip(&merged_tip_block, &merged_tip_id)?;
                 }
             }
 
@@ -625,10 +1373,12 @@ impl IndexerState {
     ) -> anyhow::Result<Option<(usize, NodeId, ExtensionDirection)>> {
         let mut extension = None;
         for (index, dangling_branch) in self.dangling_branches.iter_mut().enumerate() {
-            let min_length = dangling_branch.root_block().blockchain_length.unwrap_or(0);
+            let min_length = dangling_branch.root_block()?.blockchain_length.unwrap_or(0);
             let max_length = dangling_branch
                 .best_tip()
-                .unwrap()
+                .ok_or_else(|| {
+                    IndexerError::WitnessTree(format!("dangling branch {index} has no best tip"))
+                })?
                 .blockchain_length
                 .unwrap_or(0);
 
@@ -636,15 +1386,11 @@ impl IndexerState {
             if let Some(length) = precomputed_block.blockchain_length {
                 if max_length + 1 >= length && length + 1 >= min_length {
                     // simple reverse
-                    if is_reverse_extension(dangling_branch, precomputed_block) {
-                        dangling_branch.new_root(precomputed_block);
+                    if is_reverse_extension(dangling_branch, precomputed_block)? {
+                        dangling_branch.new_root(precomputed_block)?;
                         extension = Some((
                             index,
-                            dangling_branch
-                                .branches
-                                .root_node_id()
-                                .expect("has root")
-                                .clone(),
+                            dangling_branch.root.clone(),
                             ExtensionDirection::Reverse,
                         ));
                         break;
@@ -652,7 +1398,7 @@ impl IndexerState {
 
                     // simple forward
                     if let Some((new_node_id, _)) =
-                        dangling_branch.simple_extension(precomputed_block)
+                        dangling_branch.simple_extension(precomputed_block)?
                     {
                         extension = Some((index, new_node_id, ExtensionDirection::Forward));
                         break;
@@ -664,22 +1410,19 @@ impl IndexerState {
                 // we don't know the blockchain_length for the incoming block, so we can't discriminate
 
                 // simple reverse
-                if is_reverse_extension(dangling_branch, precomputed_block) {
-                    dangling_branch.new_root(precomputed_block);
+                if is_reverse_extension(dangling_branch, precomputed_block)? {
+                    dangling_branch.new_root(precomputed_block)?;
                     extension = Some((
                         index,
-                        dangling_branch
-                            .branches
-                            .root_node_id()
-                            .expect("has root")
-                            .clone(),
+                        dangling_branch.root.clone(),
                         ExtensionDirection::Reverse,
                     ));
                     break;
                 }
 
                 // simple forward
-                if let Some((new_node_id, _)) = dangling_branch.simple_extension(precomputed_block)
+                if let Some((new_node_id, _)) =
+                    dangling_branch.simple_extension(precomputed_block)?
                 {
                     extension = Some((index, new_node_id, ExtensionDirection::Forward));
                     break;
@@ -702,7 +1445,7 @@ impl IndexerState {
     ) -> anyhow::Result<ExtensionType> {
         let mut branches_to_update = Vec::new();
         for (index, dangling_branch) in self.dangling_branches.iter().enumerate() {
-            if is_reverse_extension(dangling_branch, precomputed_block) {
+            if is_reverse_extension(dangling_branch, precomputed_block)? {
                 branches_to_update.push(index);
             }
         }
@@ -715,8 +1458,10 @@ impl IndexerState {
                 } else {
                     *dangling_branch_index
                 };
-                let branch_to_update = self.dangling_branches.get_mut(index).unwrap();
-                extended_branch.merge_on(&new_node_id, branch_to_update);
+                let branch_to_update = self.dangling_branches.get_mut(index).ok_or_else(|| {
+                    IndexerError::WitnessTree(format!("dangling branch {index} is missing"))
+                })?;
+                extended_branch.merge_on(&new_node_id, branch_to_update)?;
 
                 // remove one for each index we see
                 self.dangling_branches.remove(index);
@@ -737,12 +1482,124 @@ impl IndexerState {
         &mut self,
         precomputed_block: &PrecomputedBlock,
     ) -> anyhow::Result<ExtensionType> {
-        self.dangling_branches
-            .push(Branch::new(precomputed_block).expect("cannot fail"));
+        self.dangling_branches.push(Branch::new(precomputed_block)?);
 
         Ok(ExtensionType::DanglingNew)
     }
 
+    /// Returns the reason the block cannot be ingested, if any
+    fn incompatibility(&self, precomputed_block: &PrecomputedBlock) -> Option<String> {
+        if precomputed_block.network != self.network {
+            return Some(format!(
+                "block is from {}, the indexer is configured for {}",
+                precomputed_block.network, self.network
+            ));
+        }
+
+        if let Some(genesis_hash) = self.network.genesis_hash() {
+            let block_genesis_hash = precomputed_block.genesis_state_hash();
+            if block_genesis_hash.0 != genesis_hash {
+                return Some(format!(
+                    "block has genesis state hash {}, {} genesis state hash is {genesis_hash}",
+                    block_genesis_hash.0, self.network
+                ));
+            }
+        }
+
+        if !self
+            .protocol_version
+            .is_compatible(&precomputed_block.protocol_version)
+        {
+            return Some(format!(
+                "block has protocol version {}, the indexer is configured for {}",
+                precomputed_block.protocol_version, self.protocol_version
+            ));
+        }
+
+        None
+    }
+
+    /// Returns the reason the block cannot be attached to its parent or the dangling
+    /// branches rooted at its children, if any
+    ///
+    /// A child is one longer than its parent and in a later slot
+    fn linkage_error(
+        &self,
+        precomputed_block: &PrecomputedBlock,
+    ) -> anyhow::Result<Option<String>> {
+        let state_hash = BlockHash(precomputed_block.state_hash.clone());
+        let parent_hash = BlockHash::previous_state_hash(precomputed_block);
+        if parent_hash == state_hash {
+            return Ok(Some("block claims to be its own parent".to_string()));
+        }
+
+        let blockchain_length = precomputed_block.blockchain_length;
+        let global_slot = precomputed_block.global_slot_since_genesis();
+        if let Some((parent_length, parent_slot)) = self.known_block(&parent_hash)? {
+            if let (Some(length), Some(parent_length)) = (blockchain_length, parent_length) {
+                if length != parent_length + 1 {
+                    return Ok(Some(format!(
+                        "block has length {length}, its parent {parent_hash:?} has length {parent_length}"
+                    )));
+                }
+            }
+            if global_slot <= parent_slot {
+                return Ok(Some(format!(
+                    "block is in slot {global_slot}, not after its parent {parent_hash:?} in slot {parent_slot}"
+                )));
+            }
+        }
+
+        for child in self
+            .dangling_branches
+            .iter()
+            .map(Branch::root_block)
+            .filter(|root| root.parent_hash == state_hash)
+        {
+            if let (Some(length), Some(child_length)) = (blockchain_length, child.blockchain_length)
+            {
+                if child_length != length + 1 {
+                    return Ok(Some(format!(
+                        "block has length {length}, its child {:?} has length {child_length}",
+                        child.state_hash
+                    )));
+                }
+            }
+            if child.global_slot_since_genesis <= global_slot {
+                return Ok(Some(format!(
+                    "block is in slot {global_slot}, not before its child {:?} in slot {}",
+                    child.state_hash, child.global_slot_since_genesis
+                )));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Records incompatible blocks in the store instead of ingesting them
+    ///
+    /// Returns whether the block was rejected
+    fn reject_if_incompatible(&self, precomputed_block: &PrecomputedBlock) -> anyhow::Result<bool> {
+        let reason = match self.incompatibility(precomputed_block) {
+            Some(reason) => Some(reason),
+            None => self.linkage_error(precomputed_block)?,
+        };
+        if let Some(reason) = reason {
+            warn!(
+                "Rejecting block with state hash {:?}: {reason}",
+                precomputed_block.state_hash
+            );
+
+            if let Some(indexer_store) = self.indexer_store.as_ref() {
+                indexer_store.add_rejected_block(
+                    &BlockHash(precomputed_block.state_hash.clone()),
+                    &reason,
+                )?;
+            }
+            return Ok(true);
+        }
+        Ok(false)
+    }
+
     /// Checks if it's even possible to add block to the root branch
     fn is_length_within_root_bounds(&self, precomputed_block: &PrecomputedBlock) -> bool {
         (precomputed_block.blockchain_length.is_some()
@@ -753,17 +1610,14 @@ impl IndexerState {
 
     fn is_block_already_in_db(&self, precomputed_block: &PrecomputedBlock) -> anyhow::Result<bool> {
         if let Some(indexer_store) = self.indexer_store.as_ref() {
-            match indexer_store.get_block(&BlockHash(precomputed_block.state_hash.to_string()))? {
-                None => Ok(false),
-                Some(_block) => Ok(true),
-            }
+            indexer_store.exists(&BlockHash(precomputed_block.state_hash.to_string()))
         } else {
             Ok(false)
         }
     }
 
     /// Update the best tip of the root branch
-    fn update_best_tip(&mut self, incoming_block: &Block, node_id: &NodeId) {
+    fn update_best_tip(&mut self, incoming_block: &Block, node_id: &NodeId) -> anyhow::Result<()> {
         if let Some(incoming_length) = incoming_block.blockchain_length {
             let best_tip_length = self.best_tip_block().blockchain_length.unwrap_or(0);
 
@@ -775,9 +1629,12 @@ impl IndexerState {
             }
         }
 
-        let (id, block) = self.root_branch.best_tip_with_id().unwrap();
+        let (id, block) = self.root_branch.best_tip_with_id().ok_or_else(|| {
+            IndexerError::WitnessTree("the root branch has no best tip".to_string())
+        })?;
         self.best_tip.node_id = id;
         self.best_tip.state_hash = block.state_hash;
+        Ok(())
     }
 
     pub fn chain_commands(&self) -> Vec<Command> {
@@ -793,57 +1650,24 @@ impl IndexerState {
         }
         vec![]
     }
-    pub fn get_block_status(&self, state_hash: &BlockHash) -> Option<Canonicity> {
+    pub fn get_block_status(&self, state_hash: &BlockHash) -> anyhow::Result<Option<Canonicity>> {
         // first check the db, then diffs map
         if let Some(indexer_store) = &self.indexer_store {
-            return indexer_store.get_canonicity(state_hash).unwrap();
-        } else if self.diffs_map.get(state_hash).is_some() {
-            return Some(Canonicity::Pending);
+            return indexer_store.get_canonicity(state_hash);
+        } else if self.diffs_map.contains(state_hash) {
+            return Ok(Some(Canonicity::Pending));
         }
 
-        None
+        Ok(None)
     }
 
     // TODO: maybe we should add another function for getting a ledger at a specific slot/"height"?
     pub fn best_ledger(&mut self) -> anyhow::Result<Option<Ledger>> {
         self.update_canonical()?;
 
-        // get the most recent canonical ledger
+        // reconstructed from the nearest snapshot and the stored diffs
         if let Some(indexer_store) = &self.indexer_store {
-            if let Some(mut ledger) =
-                indexer_store.get_ledger(&self.canonical_tip_block().state_hash)?
-            {
-                // collect diffs from canonical tip to best tip
-                let mut hashes_since_canonical_tip =
-                    if self.best_tip.state_hash != self.canonical_tip.state_hash {
-                        vec![self.best_tip.state_hash.clone()]
-                    } else {
-                        vec![]
-                    };
-
-                for ancestor in self
-                    .root_branch
-                    .branches
-                    .ancestors(&self.best_tip.node_id)
-                    .unwrap()
-                {
-                    if ancestor.data().state_hash != self.canonical_tip.state_hash {
-                        hashes_since_canonical_tip.push(ancestor.data().state_hash.clone());
-                    } else {
-                        break;
-                    }
-                }
-
-                // apply diffs from canonical tip to best tip
-                hashes_since_canonical_tip.reverse();
-                for hash in hashes_since_canonical_tip {
-                    if let Some(precomputed_block) = indexer_store.get_block(&hash)? {
-                        ledger.apply_post_balances(&precomputed_block);
-                    }
-                }
-
-                return Ok(Some(ledger));
-            }
+            return indexer_store.get_ledger(&self.best_tip.state_hash);
         }
 
         Ok(None)
@@ -871,36 +1695,84 @@ impl IndexerState {
             }
         }
 
-        let db_stats_str = self.indexer_store.as_ref().map(|db| db.db_stats());
-        let mem = self
-            .indexer_store
-            .as_ref()
-            .map(|db| db.memtables_size())
-            .unwrap_or_default();
         let witness_tree = WitnessTreeSummaryShort {
             best_tip_hash: self.best_tip_block().state_hash.0.clone(),
             best_tip_length: self.best_tip_block().blockchain_length.unwrap_or(0),
             canonical_tip_hash: self.canonical_tip_block().state_hash.0.clone(),
             canonical_tip_length: self.canonical_tip_block().blockchain_length.unwrap_or(0),
-            root_hash: self.root_branch.root_block().state_hash.0.clone(),
+            root_hash: self
+                .root_branch
+                .root_block()
+                .map(|root| root.state_hash.0.clone())
+                .unwrap_or_default(),
             root_height: self.root_branch.height(),
             root_length: self.root_branch.len(),
             num_leaves: self.root_branch.leaves().len() as u32,
             num_dangling: self.dangling_branches.len() as u32,
+            num_deep_dangling: self.deep_dangling_branches().count() as u32,
             max_dangling_height,
             max_dangling_length,
+            dangling_branches: self.dangling_branch_summaries(),
         };
 
         SummaryShort {
+            network: self.network.to_string(),
             uptime: self.time.clone().elapsed(),
             date_time: PrimitiveDateTime::new(self.date_time.date(), self.date_time.time()),
             blocks_processed: self.blocks_processed,
+            consensus_time: self.consensus_time(),
             witness_tree,
-            db_stats: db_stats_str.map(|s| DbStats::from_str(&format!("{mem}\n{s}")).unwrap()),
+            db_stats: self.db_stats(),
         }
     }
 
+    /// Consensus time of the best tip
+    pub fn consensus_time(&self) -> ConsensusTimeSummary {
+        ConsensusTimeSummary::new(
+            &self.network,
+            self.best_tip_block().global_slot_since_genesis,
+        )
+    }
+
+    /// Root, missing parent, and best tip of each dangling branch
+    fn dangling_branch_summaries(&self) -> Vec<DanglingBranchSummary> {
+        self.dangling_branches
+            .iter()
+            .filter_map(|dangling| {
+                let root = dangling.root_block().ok()?;
+                let tip = dangling.best_tip().unwrap_or_else(|| root.clone());
+                Some(DanglingBranchSummary {
+                    root_hash: root.state_hash.0.clone(),
+                    root_length: root.blockchain_length.unwrap_or(0),
+                    missing_parent_hash: root.parent_hash.0.clone(),
+                    tip_hash: tip.state_hash.0.clone(),
+                    tip_length: tip.blockchain_length.unwrap_or(0),
+                    height: dangling.height(),
+                    len: dangling.len(),
+                    deep: dangling.height() > self.transition_frontier_length,
+                })
+            })
+            .collect()
+    }
+
     pub fn summary_verbose(&self) -> SummaryVerbose {
+        SummaryVerbose {
+            network: self.network.to_string(),
+            uptime: self.time.clone().elapsed(),
+            date_time: PrimitiveDateTime::new(self.date_time.date(), self.date_time.time()),
+            blocks_processed: self.blocks_processed,
+            consensus_time: self.consensus_time(),
+            witness_tree: self.witness_tree_summary(),
+            db_stats: self.db_stats(),
+            store_operations: self
+                .indexer_store
+                .as_ref()
+                .map(|store| store.operation_stats()),
+        }
+    }
+
+    /// Tips, root, and dangling branches of the witness tree, with a rendering near the best tip
+    pub fn witness_tree_summary(&self) -> WitnessTreeSummaryVerbose {
         let mut max_dangling_height = 0;
         let mut max_dangling_length = 0;
 
@@ -913,37 +1785,39 @@ impl IndexerState {
             }
         }
 
-        let db_stats_str = self.indexer_store.as_ref().map(|db| db.db_stats());
-        let mem = self
-            .indexer_store
-            .as_ref()
-            .map(|db| db.memtables_size())
-            .unwrap_or_default();
-        let witness_tree = WitnessTreeSummaryVerbose {
+        WitnessTreeSummaryVerbose {
             best_tip_hash: self.best_tip_block().state_hash.0.clone(),
             best_tip_length: self.best_tip_block().blockchain_length.unwrap_or(0),
             canonical_tip_hash: self.canonical_tip_block().state_hash.0.clone(),
             canonical_tip_length: self.canonical_tip_block().blockchain_length.unwrap_or(0),
-            root_hash: self.root_branch.root_block().state_hash.0.clone(),
+            root_hash: self
+                .root_branch
+                .root_block()
+                .map(|root| root.state_hash.0.clone())
+                .unwrap_or_default(),
             root_height: self.root_branch.height(),
             root_length: self.root_branch.len(),
             num_leaves: self.root_branch.leaves().len() as u32,
             num_dangling: self.dangling_branches.len() as u32,
+            num_deep_dangling: self.deep_dangling_branches().count() as u32,
             max_dangling_height,
             max_dangling_length,
-            witness_tree: format!("{self:?}"),
-        };
-
-        SummaryVerbose {
-            uptime: self.time.clone().elapsed(),
-            date_time: PrimitiveDateTime::new(self.date_time.date(), self.date_time.time()),
-            blocks_processed: self.blocks_processed,
-            witness_tree,
-            db_stats: db_stats_str.map(|s| DbStats::from_str(&format!("{mem}\n{s}")).unwrap()),
+            dangling_branches: self.dangling_branch_summaries(),
+            witness_tree: Some(
+                self.root_branch
+                    .render_near_best_tip(SUMMARY_WITNESS_TREE_DEPTH),
+            ),
         }
     }
 
-    fn is_initializing(&self) -> bool {
+    /// Statistics of the indexer store, if its backend provides them
+    fn db_stats(&self) -> Option<DbStats> {
+        let indexer_store = self.indexer_store.as_ref()?;
+        indexer_store.stats().ok().flatten()
+    }
+
+    /// Blocks are still ingested from the startup directory or the database
+    pub fn is_initializing(&self) -> bool {
         self.phase == IndexerPhase::InitializingFromBlockDir
             || self.phase == IndexerPhase::InitializingFromDB
     }
@@ -954,8 +1828,18 @@ impl IndexerState {
 }
 
 /// Checks if the block is the parent of the branch's root
-fn is_reverse_extension(branch: &Branch, precomputed_block: &PrecomputedBlock) -> bool {
-    precomputed_block.state_hash == branch.root_block().parent_hash.0
+fn is_reverse_extension(
+    branch: &Branch,
+    precomputed_block: &PrecomputedBlock,
+) -> Result<bool, IndexerError> {
+    Ok(precomputed_block.state_hash == branch.root_block()?.parent_hash.0)
+}
+
+/// Checks if the block is the branch's root
+fn is_root(branch: &Branch, state_hash: &BlockHash) -> bool {
+    branch
+        .root_block()
+        .map_or(false, |root| &root.state_hash == state_hash)
 }
 
 /// Errors if the blocks is added a second time
@@ -963,7 +1847,7 @@ fn same_block_added_twice(
     branch: &Branch,
     precomputed_block: &PrecomputedBlock,
 ) -> anyhow::Result<()> {
-    if precomputed_block.state_hash == branch.root_block().state_hash.0 {
+    if precomputed_block.state_hash == branch.root_block()?.state_hash.0 {
         return Err(anyhow::Error::msg(format!(
             "Block with hash {:?} added twice to the indexer state",
             precomputed_block.state_hash,
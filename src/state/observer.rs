@@ -0,0 +1,23 @@
+use crate::{
+    block::{precomputed::PrecomputedBlock, Block, BlockHash},
+    state::Canonicity,
+};
+
+/// Reacts to changes of the indexer state, e.g. to call webhooks or export data,
+/// without changes to the server's loop
+///
+/// Hooks are called on the thread changing the state, once the change is in the
+/// store, so heavy work should be handed off elsewhere
+pub trait StateObserver: Send + Sync {
+    /// The block was added to the witness tree or, during initialization, to the
+    /// contiguous canonical chain
+    fn block_added(&self, _block: &PrecomputedBlock) {}
+
+    fn best_tip_changed(&self, _best_tip: &Block) {}
+
+    /// The block became canonical or orphaned
+    fn canonicity_updated(&self, _state_hash: &BlockHash, _canonicity: &Canonicity) {}
+
+    /// The ledger as of the block was stored whole, instead of as a diff
+    fn ledger_snapshot_taken(&self, _state_hash: &BlockHash) {}
+}
@@ -1,12 +1,15 @@
+use crate::{network::Network, store::metrics::StoreOperationStats};
 use bytesize::ByteSize;
 use serde_derive::{Deserialize, Serialize};
-use std::{str::Lines, time::Duration};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use time::PrimitiveDateTime;
 
 pub trait Summary {
+    fn network(&self) -> String;
     fn uptime(&self) -> Duration;
     fn date_time(&self) -> PrimitiveDateTime;
     fn blocks_processed(&self) -> u32;
+    fn consensus_time(&self) -> ConsensusTimeSummary;
     fn best_tip_length(&self) -> u32;
     fn best_tip_hash(&self) -> String;
     fn canonical_tip_length(&self) -> u32;
@@ -16,27 +19,34 @@ pub trait Summary {
     fn root_length(&self) -> u32;
     fn num_leaves(&self) -> u32;
     fn num_dangling(&self) -> u32;
+    fn num_deep_dangling(&self) -> u32;
     fn max_dangling_height(&self) -> u32;
     fn max_dangling_length(&self) -> u32;
-    fn db_stats(&self) -> DbStats;
+    fn dangling_branches(&self) -> Vec<DanglingBranchSummary>;
+    fn db_stats(&self) -> Option<DbStats>;
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SummaryShort {
+    pub network: String,
     pub uptime: Duration,
     pub date_time: PrimitiveDateTime,
     pub blocks_processed: u32,
+    pub consensus_time: ConsensusTimeSummary,
     pub witness_tree: WitnessTreeSummaryShort,
     pub db_stats: Option<DbStats>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SummaryVerbose {
+    pub network: String,
     pub uptime: Duration,
     pub date_time: PrimitiveDateTime,
     pub blocks_processed: u32,
+    pub consensus_time: ConsensusTimeSummary,
     pub witness_tree: WitnessTreeSummaryVerbose,
     pub db_stats: Option<DbStats>,
+    pub store_operations: Option<StoreOperationStats>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -50,8 +60,11 @@ pub struct WitnessTreeSummaryShort {
     pub root_length: u32,
     pub num_leaves: u32,
     pub num_dangling: u32,
+    /// Number of dangling branches taller than the transition frontier
+    pub num_deep_dangling: u32,
     pub max_dangling_height: u32,
     pub max_dangling_length: u32,
+    pub dangling_branches: Vec<DanglingBranchSummary>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -65,21 +78,84 @@ pub struct WitnessTreeSummaryVerbose {
     pub root_length: u32,
     pub num_leaves: u32,
     pub num_dangling: u32,
+    /// Number of dangling branches taller than the transition frontier
+    pub num_deep_dangling: u32,
     pub max_dangling_height: u32,
     pub max_dangling_length: u32,
-    pub witness_tree: String,
+    pub dangling_branches: Vec<DanglingBranchSummary>,
+    /// Rendering of the root branch near its best tip, only at verbosity 2
+    pub witness_tree: Option<String>,
 }
 
+/// Epoch and slot of the best tip, along with the slot of the wall-clock time to tell how
+/// far the indexer lags behind the network
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConsensusTimeSummary {
+    pub epoch: u32,
+    pub global_slot: u32,
+    pub slot_in_epoch: u32,
+    /// Global slot of the wall-clock time, `None` if the network's genesis timestamp is unknown
+    pub wall_clock_slot: Option<u32>,
+}
+
+impl ConsensusTimeSummary {
+    /// Consensus time of the best tip's global slot, as of now
+    pub fn new(network: &Network, global_slot: u32) -> Self {
+        let now_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|now| now.as_millis() as u64)
+            .unwrap_or(0);
+        Self {
+            epoch: global_slot / network.slots_per_epoch(),
+            global_slot,
+            slot_in_epoch: global_slot % network.slots_per_epoch(),
+            wall_clock_slot: network.global_slot_at(now_ms),
+        }
+    }
+
+    /// Number of slots the best tip is behind the wall-clock time
+    pub fn slot_lag(&self) -> Option<u32> {
+        self.wall_clock_slot
+            .map(|wall_clock_slot| wall_clock_slot.saturating_sub(self.global_slot))
+    }
+}
+
+/// Root and best tip of a dangling branch, along with the missing parent of its root,
+/// telling recent forks apart from branches missing historical blocks
 #[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DanglingBranchSummary {
+    pub root_hash: String,
+    pub root_length: u32,
+    pub missing_parent_hash: String,
+    pub tip_hash: String,
+    pub tip_length: u32,
+    pub height: u32,
+    pub len: u32,
+    /// Taller than the transition frontier, its missing ancestors need backfilling
+    pub deep: bool,
+}
+
+/// Statistics of the indexer store, summed over its column families
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct DbStats {
-    memory: u64,
-    uptime: String,
-    cum_writes: String,
-    cum_wal: String,
-    cum_stall: String,
-    int_writes: String,
-    int_wal: String,
-    int_stall: String,
+    pub memtables_size: u64,
+    pub live_data_size: u64,
+    pub sst_files_size: u64,
+    pub num_keys: u64,
+    pub pending_compaction_bytes: u64,
+    pub num_running_compactions: u64,
+    pub column_families: Vec<ColumnFamilyStats>,
+}
+
+/// Statistics of a single column family
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ColumnFamilyStats {
+    pub name: String,
+    pub memtables_size: u64,
+    pub live_data_size: u64,
+    pub sst_files_size: u64,
+    pub num_keys: u64,
+    pub pending_compaction_bytes: u64,
 }
 
 impl std::fmt::Display for SummaryShort {
@@ -91,18 +167,63 @@ impl std::fmt::Display for SummaryShort {
 impl std::fmt::Display for SummaryVerbose {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         summary_short(self, f)?;
-        writeln!(f, "\n===== Witness tree =====")?;
-        write!(f, "{}", self.witness_tree.witness_tree)?;
+        if let Some(db_stats) = &self.db_stats {
+            writeln!(f, "\n=== Column families ===")?;
+            for cf_stats in &db_stats.column_families {
+                writeln!(f, "{cf_stats}")?;
+            }
+        }
+        if let Some(store_operations) = &self.store_operations {
+            writeln!(f, "\n=== Store operations ===")?;
+            write!(f, "{store_operations}")?;
+        }
+        if let Some(witness_tree) = &self.witness_tree.witness_tree {
+            writeln!(f, "\n===== Witness tree =====")?;
+            write!(f, "{witness_tree}")?;
+        }
         Ok(())
     }
 }
 
+impl SummaryVerbose {
+    /// Summary at the verbosity level, 1 leaves out the witness tree rendering
+    pub fn with_verbosity(mut self, verbosity: u8) -> Self {
+        if verbosity < 2 {
+            self.witness_tree.witness_tree = None;
+        }
+        self
+    }
+}
+
+impl std::fmt::Display for DanglingBranchSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "root {} (length {}), missing parent {}, tip {} (length {}), {} blocks, height {}{}",
+            self.root_hash,
+            self.root_length,
+            self.missing_parent_hash,
+            self.tip_hash,
+            self.tip_length,
+            self.len,
+            self.height,
+            if self.deep {
+                ", deeper than the transition frontier"
+            } else {
+                ""
+            }
+        )
+    }
+}
+
 impl From<SummaryVerbose> for SummaryShort {
     fn from(value: SummaryVerbose) -> Self {
         Self {
+            network: value.network,
             uptime: value.uptime,
             date_time: value.date_time,
             blocks_processed: value.blocks_processed,
+            consensus_time: value.consensus_time,
             witness_tree: value.witness_tree.into(),
             db_stats: value.db_stats,
         }
@@ -121,17 +242,41 @@ impl From<WitnessTreeSummaryVerbose> for WitnessTreeSummaryShort {
             root_length: value.root_length,
             num_leaves: value.num_leaves,
             num_dangling: value.num_dangling,
+            num_deep_dangling: value.num_deep_dangling,
             max_dangling_height: value.max_dangling_height,
             max_dangling_length: value.max_dangling_length,
+            dangling_branches: value.dangling_branches,
         }
     }
 }
 
 fn summary_short(state: &impl Summary, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
     writeln!(f, "===== Mina-indexer summary =====")?;
+    writeln!(f, "  Network:      {}", state.network())?;
     writeln!(f, "  Uptime:       {:?}", state.uptime())?;
     writeln!(f, "  Started:      {}", state.date_time())?;
     writeln!(f, "  Blocks added: {}", state.blocks_processed())?;
+    if state.num_deep_dangling() > 0 {
+        writeln!(
+            f,
+            "\n!!! {} dangling branch(es) deeper than the transition frontier, their missing ancestors need backfilling",
+            state.num_deep_dangling()
+        )?;
+    }
+
+    let consensus_time = state.consensus_time();
+    writeln!(f, "\n=== Best tip consensus time ===")?;
+    writeln!(f, "  Epoch:           {}", consensus_time.epoch)?;
+    writeln!(f, "  Global slot:     {}", consensus_time.global_slot)?;
+    writeln!(f, "  Slot in epoch:   {}", consensus_time.slot_in_epoch)?;
+    if let (Some(wall_clock_slot), Some(slot_lag)) =
+        (consensus_time.wall_clock_slot, consensus_time.slot_lag())
+    {
+        writeln!(
+            f,
+            "  Wall-clock slot: {wall_clock_slot} ({slot_lag} slots behind)"
+        )?;
+    }
 
     writeln!(f, "\n=== Root branch ===")?;
     writeln!(f, "  Height:               {}", state.root_height())?;
@@ -152,27 +297,24 @@ fn summary_short(state: &impl Summary, f: &mut std::fmt::Formatter<'_>) -> std::
         writeln!(f, "  Num:        {}", state.num_dangling())?;
         writeln!(f, "  Max height: {}", state.max_dangling_length())?;
         writeln!(f, "  Max length: {}", state.max_dangling_height())?;
+        for (n, branch) in state.dangling_branches().iter().enumerate() {
+            writeln!(f, "  {n}: {branch}")?;
+        }
     }
 
-    // let db_stats = state.db_stats.as_ref().unwrap();
-    writeln!(f, "\n=== DB stats ===")?;
-    writeln!(
-        f,
-        "  All memtable size: {}",
-        ByteSize::b(state.db_stats().memory)
-    )?;
-    writeln!(f, "  Uptime:            {}", state.db_stats().uptime)?;
-    writeln!(f, "  Cumulative writes: {}", state.db_stats().cum_writes)?;
-    writeln!(f, "  Cumulative WAL:    {}", state.db_stats().cum_wal)?;
-    writeln!(f, "  Cumulative stall:  {}", state.db_stats().cum_stall)?;
-    writeln!(f, "  Interval writes:   {}", state.db_stats().int_writes)?;
-    writeln!(f, "  Interval WAL:      {}", state.db_stats().int_wal)?;
-    writeln!(f, "  Interval stall:    {}", state.db_stats().int_stall)?;
+    if let Some(db_stats) = state.db_stats() {
+        writeln!(f, "\n=== DB stats ===")?;
+        write!(f, "{db_stats}")?;
+    }
 
     Ok(())
 }
 
 impl Summary for SummaryShort {
+    fn network(&self) -> String {
+        self.network.clone()
+    }
+
     fn best_tip_hash(&self) -> String {
         self.witness_tree.best_tip_hash.clone()
     }
@@ -185,6 +327,10 @@ impl Summary for SummaryShort {
         self.blocks_processed
     }
 
+    fn consensus_time(&self) -> ConsensusTimeSummary {
+        self.consensus_time.clone()
+    }
+
     fn canonical_tip_hash(&self) -> String {
         self.witness_tree.canonical_tip_hash.clone()
     }
@@ -197,8 +343,8 @@ impl Summary for SummaryShort {
         self.date_time
     }
 
-    fn db_stats(&self) -> DbStats {
-        self.db_stats.as_ref().unwrap().clone()
+    fn db_stats(&self) -> Option<DbStats> {
+        self.db_stats.clone()
     }
 
     fn max_dangling_height(&self) -> u32 {
@@ -213,6 +359,14 @@ impl Summary for SummaryShort {
         self.witness_tree.num_dangling
     }
 
+    fn num_deep_dangling(&self) -> u32 {
+        self.witness_tree.num_deep_dangling
+    }
+
+    fn dangling_branches(&self) -> Vec<DanglingBranchSummary> {
+        self.witness_tree.dangling_branches.clone()
+    }
+
     fn num_leaves(&self) -> u32 {
         self.witness_tree.num_leaves
     }
@@ -235,6 +389,10 @@ impl Summary for SummaryShort {
 }
 
 impl Summary for SummaryVerbose {
+    fn network(&self) -> String {
+        self.network.clone()
+    }
+
     fn best_tip_hash(&self) -> String {
         self.witness_tree.best_tip_hash.clone()
     }
@@ -247,6 +405,10 @@ impl Summary for SummaryVerbose {
         self.blocks_processed
     }
 
+    fn consensus_time(&self) -> ConsensusTimeSummary {
+        self.consensus_time.clone()
+    }
+
     fn canonical_tip_hash(&self) -> String {
         self.witness_tree.canonical_tip_hash.clone()
     }
@@ -259,8 +421,8 @@ impl Summary for SummaryVerbose {
         self.date_time
     }
 
-    fn db_stats(&self) -> DbStats {
-        self.db_stats.as_ref().unwrap().clone()
+    fn db_stats(&self) -> Option<DbStats> {
+        self.db_stats.clone()
     }
 
     fn max_dangling_height(&self) -> u32 {
@@ -275,6 +437,14 @@ impl Summary for SummaryVerbose {
         self.witness_tree.num_dangling
     }
 
+    fn num_deep_dangling(&self) -> u32 {
+        self.witness_tree.num_deep_dangling
+    }
+
+    fn dangling_branches(&self) -> Vec<DanglingBranchSummary> {
+        self.witness_tree.dangling_branches.clone()
+    }
+
     fn num_leaves(&self) -> u32 {
         self.witness_tree.num_leaves
     }
@@ -296,30 +466,42 @@ impl Summary for SummaryVerbose {
     }
 }
 
-impl std::str::FromStr for DbStats {
-    type Err = anyhow::Error;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut lines = s.lines();
-        let memory = lines.next().unwrap().parse::<u64>()?;
-        lines.nth(1).unwrap(); // skip header
-        Ok(DbStats {
-            memory,
-            uptime: value(&mut lines),
-            cum_writes: value(&mut lines),
-            cum_wal: value(&mut lines),
-            cum_stall: value(&mut lines),
-            int_writes: value(&mut lines),
-            int_wal: value(&mut lines),
-            int_stall: value(&mut lines),
-        })
+impl std::fmt::Display for DbStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "  Memtables size:      {}",
+            ByteSize::b(self.memtables_size)
+        )?;
+        writeln!(
+            f,
+            "  Live data size:      {}",
+            ByteSize::b(self.live_data_size)
+        )?;
+        writeln!(
+            f,
+            "  SST files size:      {}",
+            ByteSize::b(self.sst_files_size)
+        )?;
+        writeln!(f, "  Num keys:            {}", self.num_keys)?;
+        writeln!(
+            f,
+            "  Pending compactions: {}",
+            ByteSize::b(self.pending_compaction_bytes)
+        )?;
+        writeln!(f, "  Running compactions: {}", self.num_running_compactions)
     }
 }
 
-fn value(lines: &mut Lines) -> String {
-    let mut res = String::new();
-    let line = lines.next().unwrap();
-    let idx = line.find(':').unwrap();
-    res.push_str(line[(idx + 1)..].trim_start());
-    res
+impl std::fmt::Display for ColumnFamilyStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "  {:<28} keys: {:<10} live data: {:<10} memtables: {}",
+            self.name,
+            self.num_keys,
+            ByteSize::b(self.live_data_size).to_string(),
+            ByteSize::b(self.memtables_size)
+        )
+    }
 }
@@ -0,0 +1,91 @@
+use serde::{de::DeserializeOwned, Serialize};
+use serde_derive::{Deserialize, Serialize};
+
+/// First byte of every enveloped value
+const ENVELOPE_MAGIC: u8 = 0xee;
+
+/// Version of the stored value format, bumped with breaking changes of the stored types
+pub const VALUE_FORMAT_VERSION: u8 = 1;
+
+/// Serialization format of stored values
+///
+/// Values are wrapped in an envelope `{magic}{format version}{codec}{payload}`,
+/// so values written with any codec can be read back regardless of the selected one
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum ValueCodec {
+    #[default]
+    Bcs,
+    Bincode,
+    Cbor,
+}
+
+impl ValueCodec {
+    fn tag(self) -> u8 {
+        match self {
+            Self::Bcs => 0,
+            Self::Bincode => 1,
+            Self::Cbor => 2,
+        }
+    }
+
+    fn from_tag(tag: u8) -> Option<Self> {
+        match tag {
+            0 => Some(Self::Bcs),
+            1 => Some(Self::Bincode),
+            2 => Some(Self::Cbor),
+            _ => None,
+        }
+    }
+
+    /// Serializes the value with this codec and wraps it in an envelope
+    pub fn encode<T: Serialize + ?Sized>(self, value: &T) -> anyhow::Result<Vec<u8>> {
+        let mut bytes = vec![ENVELOPE_MAGIC, VALUE_FORMAT_VERSION, self.tag()];
+        match self {
+            Self::Bcs => bytes.extend(bcs::to_bytes(value)?),
+            Self::Bincode => bytes.extend(bincode::serialize(value)?),
+            Self::Cbor => ciborium::ser::into_writer(value, &mut bytes)
+                .map_err(|e| anyhow::Error::msg(format!("CBOR serialization failed: {e}")))?,
+        }
+        Ok(bytes)
+    }
+
+    fn deserialize<T: DeserializeOwned>(self, payload: &[u8]) -> anyhow::Result<T> {
+        Ok(match self {
+            Self::Bcs => bcs::from_bytes(payload)?,
+            Self::Bincode => bincode::deserialize(payload)?,
+            Self::Cbor => ciborium::de::from_reader(payload)
+                .map_err(|e| anyhow::Error::msg(format!("CBOR deserialization failed: {e}")))?,
+        })
+    }
+}
+
+impl std::fmt::Display for ValueCodec {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Bcs => write!(f, "bcs"),
+            Self::Bincode => write!(f, "bincode"),
+            Self::Cbor => write!(f, "cbor"),
+        }
+    }
+}
+
+/// Deserializes a stored value with the codec it was written with
+///
+/// Values written before envelopes were introduced are plain bcs
+pub fn decode<T: DeserializeOwned>(bytes: &[u8]) -> anyhow::Result<T> {
+    if let [ENVELOPE_MAGIC, version, tag, payload @ ..] = bytes {
+        let result = match (*version, ValueCodec::from_tag(*tag)) {
+            (VALUE_FORMAT_VERSION, Some(codec)) => codec.deserialize(payload),
+            (VALUE_FORMAT_VERSION, None) => {
+                Err(anyhow::Error::msg(format!("Unknown value codec {tag}")))
+            }
+            (version, _) => Err(anyhow::Error::msg(format!(
+                "Unsupported value format version {version}"
+            ))),
+        };
+        // plain bcs values may happen to start like an envelope
+        return result.or_else(|e| bcs::from_bytes(bytes).map_err(|_| e));
+    }
+    Ok(bcs::from_bytes(bytes)?)
+}
@@ -0,0 +1,79 @@
+use crate::{block::BlockHash, state::ledger::public_key::PublicKey};
+
+/// Length of a public key address, the fixed length prefix of account keyed column families
+pub const ADDRESS_LEN: usize = 55;
+
+/// Length of a state hash, the fixed length suffix of command and creator keys
+pub const STATE_HASH_LEN: usize = 52;
+
+/// Column families whose keys start with an account address
+pub const ACCOUNT_KEYED_COLUMN_FAMILIES: [&str; 5] = [
+    "creators",
+    "account-commands",
+    "recipient-internal-commands",
+    "prover-snarks",
+    "balances",
+];
+
+/// Prefix of the canonicity of each block, `b:{state hash}`
+pub const BLOCK_CANONICITY_PREFIX: &[u8] = b"b:";
+
+/// Prefix of the canonical chain by length, `c:{big endian length}`
+pub const CANONICAL_HEIGHT_PREFIX: &[u8] = b"c:";
+
+/// `b:{state hash}`
+pub fn block_canonicity_key(state_hash: &BlockHash) -> Vec<u8> {
+    [BLOCK_CANONICITY_PREFIX, state_hash.0.as_bytes()].concat()
+}
+
+/// `c:{big endian length}`
+pub fn canonical_height_key(blockchain_length: u32) -> Vec<u8> {
+    [CANONICAL_HEIGHT_PREFIX, &blockchain_length.to_be_bytes()].concat()
+}
+
+/// `{big endian epoch}`, the prefix of the block bodies of the epoch
+pub fn epoch_prefix(epoch: u32) -> Vec<u8> {
+    epoch.to_be_bytes().to_vec()
+}
+
+/// `{big endian epoch}{state hash}`
+pub fn block_body_key(epoch: u32, state_hash: &BlockHash) -> Vec<u8> {
+    [&epoch.to_be_bytes(), state_hash.0.as_bytes()].concat()
+}
+
+/// `{command hash}{state hash}`, a command is stored once per block including it
+pub fn command_key(command_hash: &str, state_hash: &BlockHash) -> Vec<u8> {
+    [command_hash.as_bytes(), state_hash.0.as_bytes()].concat()
+}
+
+/// `{address}`, the prefix of every entry of the account
+pub fn account_prefix(pk: &PublicKey) -> Vec<u8> {
+    pk.to_address().into_bytes()
+}
+
+/// `{address}{big endian length}`, the prefix of the account's entries of the given length
+pub fn account_height_prefix(pk: &PublicKey, blockchain_length: u32) -> Vec<u8> {
+    let mut key = account_prefix(pk);
+    key.extend_from_slice(&blockchain_length.to_be_bytes());
+    key
+}
+
+/// `{address}{big endian length}{state hash}{suffix}`
+pub fn account_key(
+    pk: &PublicKey,
+    blockchain_length: u32,
+    state_hash: &BlockHash,
+    suffix: &[u8],
+) -> Vec<u8> {
+    let mut key = account_height_prefix(pk, blockchain_length);
+    key.extend_from_slice(state_hash.0.as_bytes());
+    key.extend_from_slice(suffix);
+    key
+}
+
+/// `{address}{state hash}`
+pub fn creator_key(creator: &PublicKey, state_hash: &BlockHash) -> Vec<u8> {
+    let mut key = account_prefix(creator);
+    key.extend_from_slice(state_hash.0.as_bytes());
+    key
+}
@@ -0,0 +1,271 @@
+use std::path::{Path, PathBuf};
+
+/// Namespace of entries which don't belong to any other namespace
+pub const DEFAULT_NAMESPACE: &str = "default";
+
+/// Iterator over the `(key, value)` entries of a namespace, in key order
+pub type KvIterator<'a> = Box<dyn Iterator<Item = anyhow::Result<(Box<[u8]>, Box<[u8]>)>> + 'a>;
+
+/// Key-value storage backend of the indexer store
+///
+/// Entries are grouped in namespaces, e.g. RocksDB column families
+pub trait KvStore: std::fmt::Debug + Send + Sync {
+    /// Get the value of `key` in the namespace `cf`
+    fn get_cf(&self, cf: &str, key: &[u8]) -> anyhow::Result<Option<Vec<u8>>>;
+
+    /// Iterate over the entries of the namespace `cf` in key order, starting at `from`
+    fn iterator_from_cf<'a>(&'a self, cf: &str, from: &[u8]) -> KvIterator<'a>;
+
+    /// Iterate over the entries of the namespace `cf` whose keys start with `prefix`, in key order
+    fn prefix_iterator_cf<'a>(&'a self, cf: &str, prefix: &[u8]) -> KvIterator<'a> {
+        let prefix = prefix.to_vec();
+        Box::new(
+            self.iterator_from_cf(cf, &prefix)
+                .take_while(move |entry| match entry {
+                    Ok((key, _)) => key.starts_with(&prefix),
+                    Err(_) => true,
+                }),
+        )
+    }
+
+    /// Atomically apply all writes of the batch
+    fn write(&self, batch: KvBatch) -> anyhow::Result<()>;
+
+    /// Whether the namespace `cf` contains `key`
+    fn contains_cf(&self, cf: &str, key: &[u8]) -> anyhow::Result<bool> {
+        Ok(self.get_cf(cf, key)?.is_some())
+    }
+
+    /// Get the value of `key` in the default namespace
+    fn get(&self, key: &[u8]) -> anyhow::Result<Option<Vec<u8>>> {
+        self.get_cf(DEFAULT_NAMESPACE, key)
+    }
+
+    /// Put `value` at `key` in the namespace `cf`
+    fn put_cf(&self, cf: &str, key: &[u8], value: &[u8]) -> anyhow::Result<()> {
+        let mut batch = KvBatch::default();
+        batch.put_cf(cf, key, value);
+        self.write(batch)
+    }
+
+    /// Put `value` at `key` in the default namespace
+    fn put(&self, key: &[u8], value: &[u8]) -> anyhow::Result<()> {
+        self.put_cf(DEFAULT_NAMESPACE, key, value)
+    }
+
+    /// Delete `key` from the default namespace
+    fn delete(&self, key: &[u8]) -> anyhow::Result<()> {
+        let mut batch = KvBatch::default();
+        batch.delete(key);
+        self.write(batch)
+    }
+
+    /// Catch up with the primary instance, if this is a secondary one
+    fn try_catch_up_with_primary(&self) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    /// Sync the write-ahead log to disk, if there is one
+    fn flush_wal(&self) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    /// Persist the buffered writes of the namespace `cf`, e.g. memtables
+    fn flush_cf(&self, _cf: &str) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    /// Compact the full key range of the namespace `cf`
+    fn compact_cf(&self, _cf: &str) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    /// Compact the keys of the namespace `cf` from `from` inclusive to `to` exclusive
+    fn compact_range_cf(&self, _cf: &str, _from: &[u8], _to: &[u8]) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    /// Create a new backup in `backup_dir`, keeping only the `num_backups_to_keep` most recent backups
+    fn backup(&self, _backup_dir: &Path, _num_backups_to_keep: usize) -> anyhow::Result<()> {
+        Err(anyhow::Error::msg(
+            "Backups are not supported by this backend",
+        ))
+    }
+
+    /// Create a consistent copy of the live database in `dst`, which must not exist
+    fn checkpoint(&self, _dst: &Path) -> anyhow::Result<()> {
+        Err(anyhow::Error::msg(
+            "Checkpoints are not supported by this backend",
+        ))
+    }
+
+    /// Write the entries of the namespace `cf` in key order to the SST file `dst`, returning
+    /// the number of entries, no file is written if the namespace is empty
+    fn export_cf(&self, _cf: &str, _dst: &Path) -> anyhow::Result<u64> {
+        Err(anyhow::Error::msg(
+            "SST export is not supported by this backend",
+        ))
+    }
+
+    /// Bulk load the SST `files` into the namespace `cf`, their entries replace any existing
+    /// entries with the same keys
+    fn ingest_cf(&self, _cf: &str, _files: &[PathBuf]) -> anyhow::Result<()> {
+        Err(anyhow::Error::msg(
+            "SST ingestion is not supported by this backend",
+        ))
+    }
+
+    /// Backend specific string property, e.g. `rocksdb.dbstats`
+    fn property_value(&self, _name: &str) -> anyhow::Result<Option<String>> {
+        Ok(None)
+    }
+
+    /// Backend specific integer property, e.g. `rocksdb.estimate-num-keys`
+    fn property_int_value(&self, _name: &str) -> anyhow::Result<Option<u64>> {
+        Ok(None)
+    }
+
+    /// Backend specific integer property of the namespace `cf`
+    fn property_int_value_cf(&self, _cf: &str, _name: &str) -> anyhow::Result<Option<u64>> {
+        Ok(None)
+    }
+}
+
+/// Write of a [KvBatch]
+#[derive(Debug, Clone)]
+pub enum KvWrite {
+    Put {
+        cf: String,
+        key: Vec<u8>,
+        value: Vec<u8>,
+    },
+    Delete {
+        cf: String,
+        key: Vec<u8>,
+    },
+    /// Deletes the keys from `from` inclusive to `to` exclusive
+    DeleteRange {
+        cf: String,
+        from: Vec<u8>,
+        to: Vec<u8>,
+    },
+    /// Merged into the current value with [merge_counters]
+    Merge {
+        cf: String,
+        key: Vec<u8>,
+        value: Vec<u8>,
+    },
+}
+
+/// Merges counter increments into the current counters, without a read-modify-write
+///
+/// Both are sequences of big-endian `u64` counters, added element-wise
+pub fn merge_counters(existing: Option<&[u8]>, increments: &[u8]) -> Vec<u8> {
+    let existing = existing.unwrap_or_default();
+    let counter = |bytes: &[u8], n: usize| {
+        bytes
+            .get(8 * n..8 * (n + 1))
+            .map_or(0, |counter| u64::from_be_bytes(counter.try_into().unwrap()))
+    };
+    (0..existing.len().max(increments.len()) / 8)
+        .flat_map(|n| {
+            counter(existing, n)
+                .saturating_add(counter(increments, n))
+                .to_be_bytes()
+        })
+        .collect()
+}
+
+/// Writes applied atomically by [KvStore::write], in order
+#[derive(Debug, Default, Clone)]
+pub struct KvBatch {
+    writes: Vec<KvWrite>,
+}
+
+impl KvBatch {
+    /// Put `value` at `key` in the namespace `cf`
+    pub fn put_cf<K, V>(&mut self, cf: &str, key: K, value: V)
+    where
+        K: AsRef<[u8]>,
+        V: AsRef<[u8]>,
+    {
+        self.writes.push(KvWrite::Put {
+            cf: cf.to_string(),
+            key: key.as_ref().to_vec(),
+            value: value.as_ref().to_vec(),
+        });
+    }
+
+    /// Put `value` at `key` in the default namespace
+    pub fn put<K, V>(&mut self, key: K, value: V)
+    where
+        K: AsRef<[u8]>,
+        V: AsRef<[u8]>,
+    {
+        self.put_cf(DEFAULT_NAMESPACE, key, value);
+    }
+
+    /// Delete `key` from the namespace `cf`
+    pub fn delete_cf<K>(&mut self, cf: &str, key: K)
+    where
+        K: AsRef<[u8]>,
+    {
+        self.writes.push(KvWrite::Delete {
+            cf: cf.to_string(),
+            key: key.as_ref().to_vec(),
+        });
+    }
+
+    /// Delete the keys of the namespace `cf` from `from` inclusive to `to` exclusive,
+    /// with a single write regardless of the number of keys
+    pub fn delete_range_cf<K>(&mut self, cf: &str, from: K, to: K)
+    where
+        K: AsRef<[u8]>,
+    {
+        self.writes.push(KvWrite::DeleteRange {
+            cf: cf.to_string(),
+            from: from.as_ref().to_vec(),
+            to: to.as_ref().to_vec(),
+        });
+    }
+
+    /// Merge the counter `increments` into the value at `key` in the namespace `cf`
+    pub fn merge_cf<K, V>(&mut self, cf: &str, key: K, increments: V)
+    where
+        K: AsRef<[u8]>,
+        V: AsRef<[u8]>,
+    {
+        self.writes.push(KvWrite::Merge {
+            cf: cf.to_string(),
+            key: key.as_ref().to_vec(),
+            value: increments.as_ref().to_vec(),
+        });
+    }
+
+    /// Delete `key` from the default namespace
+    pub fn delete<K>(&mut self, key: K)
+    where
+        K: AsRef<[u8]>,
+    {
+        self.delete_cf(DEFAULT_NAMESPACE, key);
+    }
+
+    /// Number of writes in the batch
+    pub fn len(&self) -> usize {
+        self.writes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.writes.is_empty()
+    }
+
+    /// Writes of the batch, in order
+    pub fn writes(&self) -> &[KvWrite] {
+        &self.writes
+    }
+
+    /// Writes of the batch, in order
+    pub fn into_writes(self) -> Vec<KvWrite> {
+        self.writes
+    }
+}
@@ -0,0 +1,90 @@
+use super::kv::{merge_counters, KvBatch, KvIterator, KvStore, KvWrite};
+use std::{
+    collections::{BTreeMap, HashMap},
+    ops::Bound,
+    sync::RwLock,
+};
+
+type Namespace = BTreeMap<Vec<u8>, Vec<u8>>;
+
+/// In-memory storage backend, nothing is persisted
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+    namespaces: RwLock<HashMap<String, Namespace>>,
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// First entry of the namespace `cf` after `lower`
+    fn next_entry(&self, cf: &str, lower: Bound<&[u8]>) -> Option<(Box<[u8]>, Box<[u8]>)> {
+        let namespaces = self.namespaces.read().expect("memory store lock");
+        namespaces.get(cf).and_then(|namespace| {
+            namespace
+                .range::<[u8], _>((lower, Bound::Unbounded))
+                .next()
+                .map(|(key, value)| (key.clone().into(), value.clone().into()))
+        })
+    }
+}
+
+impl KvStore for MemoryStore {
+    fn get_cf(&self, cf: &str, key: &[u8]) -> anyhow::Result<Option<Vec<u8>>> {
+        let namespaces = self.namespaces.read().expect("memory store lock");
+        Ok(namespaces
+            .get(cf)
+            .and_then(|namespace| namespace.get(key))
+            .cloned())
+    }
+
+    fn contains_cf(&self, cf: &str, key: &[u8]) -> anyhow::Result<bool> {
+        let namespaces = self.namespaces.read().expect("memory store lock");
+        Ok(namespaces
+            .get(cf)
+            .map_or(false, |namespace| namespace.contains_key(key)))
+    }
+
+    fn iterator_from_cf<'a>(&'a self, cf: &str, from: &[u8]) -> KvIterator<'a> {
+        // entries are looked up one at a time so writes aren't blocked while iterating
+        let cf = cf.to_string();
+        let mut lower: Option<Box<[u8]>> = None;
+        let from = from.to_vec();
+        Box::new(std::iter::from_fn(move || {
+            let entry = match &lower {
+                None => self.next_entry(&cf, Bound::Included(from.as_slice())),
+                Some(key) => self.next_entry(&cf, Bound::Excluded(key.as_ref())),
+            }?;
+            lower = Some(entry.0.clone());
+            Some(Ok(entry))
+        }))
+    }
+
+    fn write(&self, batch: KvBatch) -> anyhow::Result<()> {
+        let mut namespaces = self.namespaces.write().expect("memory store lock");
+        for write in batch.into_writes() {
+            match write {
+                KvWrite::Put { cf, key, value } => {
+                    namespaces.entry(cf).or_default().insert(key, value);
+                }
+                KvWrite::Delete { cf, key } => {
+                    if let Some(namespace) = namespaces.get_mut(&cf) {
+                        namespace.remove(&key);
+                    }
+                }
+                KvWrite::DeleteRange { cf, from, to } => {
+                    if let Some(namespace) = namespaces.get_mut(&cf) {
+                        namespace.retain(|key, _| *key < from || *key >= to);
+                    }
+                }
+                KvWrite::Merge { cf, key, value } => {
+                    let namespace = namespaces.entry(cf).or_default();
+                    let merged = merge_counters(namespace.get(&key).map(Vec::as_slice), &value);
+                    namespace.insert(key, merged);
+                }
+            }
+        }
+        Ok(())
+    }
+}
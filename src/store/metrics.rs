@@ -0,0 +1,382 @@
+use super::kv::{KvBatch, KvIterator, KvStore, KvWrite};
+use serde_derive::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, RwLock,
+    },
+    time::{Duration, Instant},
+};
+
+/// Upper bounds in microseconds of the latency histogram buckets, the last
+/// bucket holds every latency of at least one second
+pub const LATENCY_BUCKET_BOUNDS_MICROS: [u64; 6] = [10, 100, 1_000, 10_000, 100_000, 1_000_000];
+
+const NUM_LATENCY_BUCKETS: usize = LATENCY_BUCKET_BOUNDS_MICROS.len() + 1;
+
+#[derive(Debug, Default)]
+struct LatencyHistogram {
+    count: AtomicU64,
+    total_micros: AtomicU64,
+    buckets: [AtomicU64; NUM_LATENCY_BUCKETS],
+}
+
+impl LatencyHistogram {
+    fn record(&self, latency: Duration) {
+        let micros = latency.as_micros() as u64;
+        let bucket = LATENCY_BUCKET_BOUNDS_MICROS
+            .iter()
+            .position(|bound| micros < *bound)
+            .unwrap_or(NUM_LATENCY_BUCKETS - 1);
+        self.count.fetch_add(1, Ordering::Relaxed);
+        self.total_micros.fetch_add(micros, Ordering::Relaxed);
+        self.buckets[bucket].fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn stats(&self) -> LatencyStats {
+        LatencyStats {
+            count: self.count.load(Ordering::Relaxed),
+            total_micros: self.total_micros.load(Ordering::Relaxed),
+            buckets: self
+                .buckets
+                .iter()
+                .map(|bucket| bucket.load(Ordering::Relaxed))
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct ColumnFamilyMetrics {
+    gets: LatencyHistogram,
+    iterations: LatencyHistogram,
+    entries_iterated: AtomicU64,
+    bytes_read: AtomicU64,
+    puts: AtomicU64,
+    deletes: AtomicU64,
+    merges: AtomicU64,
+    bytes_written: AtomicU64,
+}
+
+/// Counters and latency histograms of the reads and writes of a store, per
+/// column family
+///
+/// Shared by the [InstrumentedKvStore] which records them and the owner of
+/// the store, which reports them
+#[derive(Debug, Default)]
+pub struct StoreMetrics {
+    writes: LatencyHistogram,
+    column_families: RwLock<BTreeMap<String, Arc<ColumnFamilyMetrics>>>,
+}
+
+impl StoreMetrics {
+    fn cf(&self, cf: &str) -> Arc<ColumnFamilyMetrics> {
+        if let Some(metrics) = self
+            .column_families
+            .read()
+            .expect("store metrics lock")
+            .get(cf)
+        {
+            return metrics.clone();
+        }
+        self.column_families
+            .write()
+            .expect("store metrics lock")
+            .entry(cf.to_string())
+            .or_default()
+            .clone()
+    }
+
+    /// Snapshot of the metrics recorded so far
+    pub fn stats(&self) -> StoreOperationStats {
+        let column_families = self.column_families.read().expect("store metrics lock");
+        StoreOperationStats {
+            writes: self.writes.stats(),
+            column_families: column_families
+                .iter()
+                .map(|(name, metrics)| ColumnFamilyOperationStats {
+                    name: name.clone(),
+                    gets: metrics.gets.stats(),
+                    iterations: metrics.iterations.stats(),
+                    entries_iterated: metrics.entries_iterated.load(Ordering::Relaxed),
+                    bytes_read: metrics.bytes_read.load(Ordering::Relaxed),
+                    puts: metrics.puts.load(Ordering::Relaxed),
+                    deletes: metrics.deletes.load(Ordering::Relaxed),
+                    merges: metrics.merges.load(Ordering::Relaxed),
+                    bytes_written: metrics.bytes_written.load(Ordering::Relaxed),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Number and latencies of operations
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LatencyStats {
+    pub count: u64,
+    pub total_micros: u64,
+    /// Number of operations per bucket of [LATENCY_BUCKET_BOUNDS_MICROS]
+    pub buckets: Vec<u64>,
+}
+
+impl LatencyStats {
+    pub fn mean_micros(&self) -> u64 {
+        self.total_micros
+            .checked_div(self.count)
+            .unwrap_or_default()
+    }
+
+    /// Upper bound in microseconds of the bucket containing the `percentile`
+    /// latency, `None` if it's in the unbounded bucket or nothing was recorded
+    pub fn percentile_bound_micros(&self, percentile: f64) -> Option<u64> {
+        if self.count == 0 {
+            return None;
+        }
+
+        let target = (self.count as f64 * percentile / 100.0).ceil() as u64;
+        let mut seen = 0;
+        for (bucket, count) in self.buckets.iter().enumerate() {
+            seen += count;
+            if seen >= target.max(1) {
+                return LATENCY_BUCKET_BOUNDS_MICROS.get(bucket).copied();
+            }
+        }
+        None
+    }
+}
+
+impl std::fmt::Display for LatencyStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let bound = |percentile| {
+            self.percentile_bound_micros(percentile)
+                .map_or(">=1s".to_string(), |bound| format!("<{bound}us"))
+        };
+        if self.count == 0 {
+            write!(f, "0")
+        } else {
+            write!(
+                f,
+                "{} (mean {}us, p50 {}, p99 {})",
+                self.count,
+                self.mean_micros(),
+                bound(50.0),
+                bound(99.0)
+            )
+        }
+    }
+}
+
+/// Reads and writes of the store since it was opened
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StoreOperationStats {
+    /// Batch writes, which may span column families
+    pub writes: LatencyStats,
+    pub column_families: Vec<ColumnFamilyOperationStats>,
+}
+
+impl StoreOperationStats {
+    pub fn column_family(&self, name: &str) -> Option<&ColumnFamilyOperationStats> {
+        self.column_families.iter().find(|cf| cf.name == name)
+    }
+}
+
+/// Reads and writes of a single column family
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ColumnFamilyOperationStats {
+    pub name: String,
+    pub gets: LatencyStats,
+    /// Iterators are timed over all their `next` calls
+    pub iterations: LatencyStats,
+    pub entries_iterated: u64,
+    pub bytes_read: u64,
+    pub puts: u64,
+    pub deletes: u64,
+    pub merges: u64,
+    pub bytes_written: u64,
+}
+
+impl std::fmt::Display for StoreOperationStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "  Batch writes: {}", self.writes)?;
+        for cf in &self.column_families {
+            write!(f, "{cf}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for ColumnFamilyOperationStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "  {}", self.name)?;
+        writeln!(f, "    Gets:          {}", self.gets)?;
+        writeln!(f, "    Iterations:    {}", self.iterations)?;
+        writeln!(f, "    Entries read:  {}", self.entries_iterated)?;
+        writeln!(f, "    Bytes read:    {}", self.bytes_read)?;
+        writeln!(f, "    Puts:          {}", self.puts)?;
+        writeln!(f, "    Deletes:       {}", self.deletes)?;
+        writeln!(f, "    Merges:        {}", self.merges)?;
+        writeln!(f, "    Bytes written: {}", self.bytes_written)
+    }
+}
+
+/// Storage backend which records the [StoreMetrics] of the backend it wraps
+#[derive(Debug)]
+pub struct InstrumentedKvStore {
+    inner: Box<dyn KvStore>,
+    metrics: Arc<StoreMetrics>,
+}
+
+impl InstrumentedKvStore {
+    pub fn new(inner: Box<dyn KvStore>, metrics: Arc<StoreMetrics>) -> Self {
+        Self { inner, metrics }
+    }
+
+    fn instrument_iterator<'a>(&self, cf: &str, iter: KvIterator<'a>) -> KvIterator<'a> {
+        Box::new(InstrumentedIterator {
+            inner: iter,
+            metrics: self.metrics.cf(cf),
+            elapsed: Duration::ZERO,
+        })
+    }
+}
+
+/// Accumulates the time spent in `next`, which is recorded once the iterator is dropped
+struct InstrumentedIterator<'a> {
+    inner: KvIterator<'a>,
+    metrics: Arc<ColumnFamilyMetrics>,
+    elapsed: Duration,
+}
+
+impl Iterator for InstrumentedIterator<'_> {
+    type Item = anyhow::Result<(Box<[u8]>, Box<[u8]>)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let start = Instant::now();
+        let entry = self.inner.next();
+        self.elapsed += start.elapsed();
+        if let Some(Ok((key, value))) = &entry {
+            self.metrics
+                .entries_iterated
+                .fetch_add(1, Ordering::Relaxed);
+            self.metrics
+                .bytes_read
+                .fetch_add((key.len() + value.len()) as u64, Ordering::Relaxed);
+        }
+        entry
+    }
+}
+
+impl Drop for InstrumentedIterator<'_> {
+    fn drop(&mut self) {
+        self.metrics.iterations.record(self.elapsed);
+    }
+}
+
+impl KvStore for InstrumentedKvStore {
+    fn get_cf(&self, cf: &str, key: &[u8]) -> anyhow::Result<Option<Vec<u8>>> {
+        let start = Instant::now();
+        let value = self.inner.get_cf(cf, key);
+        let metrics = self.metrics.cf(cf);
+        metrics.gets.record(start.elapsed());
+        if let Ok(Some(value)) = &value {
+            metrics
+                .bytes_read
+                .fetch_add(value.len() as u64, Ordering::Relaxed);
+        }
+        value
+    }
+
+    fn contains_cf(&self, cf: &str, key: &[u8]) -> anyhow::Result<bool> {
+        let start = Instant::now();
+        let contains = self.inner.contains_cf(cf, key);
+        self.metrics.cf(cf).gets.record(start.elapsed());
+        contains
+    }
+
+    fn iterator_from_cf<'a>(&'a self, cf: &str, from: &[u8]) -> KvIterator<'a> {
+        self.instrument_iterator(cf, self.inner.iterator_from_cf(cf, from))
+    }
+
+    fn prefix_iterator_cf<'a>(&'a self, cf: &str, prefix: &[u8]) -> KvIterator<'a> {
+        self.instrument_iterator(cf, self.inner.prefix_iterator_cf(cf, prefix))
+    }
+
+    fn write(&self, batch: KvBatch) -> anyhow::Result<()> {
+        for write in batch.writes() {
+            match write {
+                KvWrite::Put { cf, key, value } => {
+                    let metrics = self.metrics.cf(cf);
+                    metrics.puts.fetch_add(1, Ordering::Relaxed);
+                    metrics
+                        .bytes_written
+                        .fetch_add((key.len() + value.len()) as u64, Ordering::Relaxed);
+                }
+                KvWrite::Delete { cf, .. } | KvWrite::DeleteRange { cf, .. } => {
+                    self.metrics.cf(cf).deletes.fetch_add(1, Ordering::Relaxed);
+                }
+                KvWrite::Merge { cf, key, value } => {
+                    let metrics = self.metrics.cf(cf);
+                    metrics.merges.fetch_add(1, Ordering::Relaxed);
+                    metrics
+                        .bytes_written
+                        .fetch_add((key.len() + value.len()) as u64, Ordering::Relaxed);
+                }
+            }
+        }
+
+        let start = Instant::now();
+        let result = self.inner.write(batch);
+        self.metrics.writes.record(start.elapsed());
+        result
+    }
+
+    fn try_catch_up_with_primary(&self) -> anyhow::Result<()> {
+        self.inner.try_catch_up_with_primary()
+    }
+
+    fn flush_wal(&self) -> anyhow::Result<()> {
+        self.inner.flush_wal()
+    }
+
+    fn flush_cf(&self, cf: &str) -> anyhow::Result<()> {
+        self.inner.flush_cf(cf)
+    }
+
+    fn compact_cf(&self, cf: &str) -> anyhow::Result<()> {
+        self.inner.compact_cf(cf)
+    }
+
+    fn compact_range_cf(&self, cf: &str, from: &[u8], to: &[u8]) -> anyhow::Result<()> {
+        self.inner.compact_range_cf(cf, from, to)
+    }
+
+    fn backup(&self, backup_dir: &Path, num_backups_to_keep: usize) -> anyhow::Result<()> {
+        self.inner.backup(backup_dir, num_backups_to_keep)
+    }
+
+    fn checkpoint(&self, dst: &Path) -> anyhow::Result<()> {
+        self.inner.checkpoint(dst)
+    }
+
+    fn export_cf(&self, cf: &str, dst: &Path) -> anyhow::Result<u64> {
+        self.inner.export_cf(cf, dst)
+    }
+
+    fn ingest_cf(&self, cf: &str, files: &[PathBuf]) -> anyhow::Result<()> {
+        self.inner.ingest_cf(cf, files)
+    }
+
+    fn property_value(&self, name: &str) -> anyhow::Result<Option<String>> {
+        self.inner.property_value(name)
+    }
+
+    fn property_int_value(&self, name: &str) -> anyhow::Result<Option<u64>> {
+        self.inner.property_int_value(name)
+    }
+
+    fn property_int_value_cf(&self, cf: &str, name: &str) -> anyhow::Result<Option<u64>> {
+        self.inner.property_int_value_cf(cf, name)
+    }
+}
@@ -0,0 +1,2670 @@
+use crate::{
+    block::{
+        metadata::BlockMetadata,
+        precomputed::{PrecomputedBlock, PrecomputedBlockHeader},
+        production::{BlockProduction, BlockRange, EpochSlots},
+        signed_command,
+        store::{BlockIterator, BlockStore},
+        Block, BlockHash, BlockWithoutHeight,
+    },
+    error::IndexerError,
+    network::Network,
+    state::{
+        canonicity::store::CanonicityStore,
+        event::{store::EventStore, IndexerEvent},
+        ledger::{
+            account_aggregate::{store::AccountAggregateStore, AccountAggregate},
+            balance_history::{store::BalanceStore, BalanceHistoryEntry},
+            command::{
+                store::CommandStore, CommandWithStateHash, SignedCommand, UserCommandWithStatus,
+            },
+            diff::LedgerDiff,
+            internal_command::{
+                store::InternalCommandStore, InternalCommand, InternalCommandWithStateHash,
+            },
+            public_key::PublicKey,
+            snark_work::{
+                store::SnarkStore, EpochSnarkFees, ProverSnarkWork, SnarkWorkSummary,
+                SnarkWorkSummaryWithStateHash,
+            },
+            store::LedgerStore,
+            Ledger,
+        },
+        metadata::{store::MetadataStore, PersistedWitnessTree, StoreMetadata},
+        summary::{ColumnFamilyStats, DbStats},
+        Canonicity,
+    },
+    LEDGER_SNAPSHOT_INTERVAL_DEFAULT,
+};
+use mina_serialization_types::{
+    signatures::SignatureJson, staged_ledger_diff::UserCommand, v1::UserCommandWithStatusV1,
+};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tracing::{debug, error, info, warn};
+use uuid::Uuid;
+
+pub mod codec;
+pub mod keys;
+pub mod kv;
+pub mod memory;
+pub mod metrics;
+pub mod rocks_db;
+
+use codec::ValueCodec;
+use kv::{KvBatch, KvIterator, KvStore, DEFAULT_NAMESPACE};
+use memory::MemoryStore;
+use metrics::{InstrumentedKvStore, StoreMetrics, StoreOperationStats};
+use rocks_db::{RocksDbStore, RocksDbTuningConfiguration};
+
+/// T-{Height}-{Timestamp}-{Signature} -> Transaction
+/// We use the signature as key until we have a better way to identify transactions (e.g. hash)
+/// The height is padded to 12 digits for sequential iteration
+#[derive(Debug, Clone)]
+pub struct TransactionKey(u32, u64, String);
+
+impl std::fmt::Display for TransactionKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "T-{:012}-{}-{}", self.0, self.1, self.2)
+    }
+}
+
+impl TransactionKey {
+    /// Creates a new key for a transaction
+    pub fn new<S>(h: u32, t: u64, s: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Self(h, t, s.into())
+    }
+
+    /// Returns the key as bytes
+    pub fn bytes(&self) -> Vec<u8> {
+        self.to_string().into_bytes()
+    }
+
+    /// Creates a new key from a slice
+    pub fn from_slice(bytes: &[u8]) -> anyhow::Result<Self> {
+        let key = std::str::from_utf8(bytes)?;
+        let parts: Vec<&str> = key.split('-').collect();
+
+        if parts.len() != 4 {
+            anyhow::bail!("Invalid transaction key: {}", key);
+        }
+
+        Ok(Self(
+            u32::from_str(parts[1])?,
+            u64::from_str(parts[2])?,
+            parts[3].to_string(),
+        ))
+    }
+
+    /// Returns the height of the transaction
+    pub fn height(&self) -> u32 {
+        self.0
+    }
+
+    /// Returns the timestamp of the transaction
+    pub fn timestamp(&self) -> u64 {
+        self.1
+    }
+
+    /// Returns the signature of the transaction
+    pub fn signature(&self) -> &str {
+        &self.2
+    }
+}
+
+/// Key in the default column family recording the network of the stored blocks
+const NETWORK_KEY: &[u8] = b"network";
+
+/// Key in the default column family recording the epochs whose block bodies have been dropped
+const DROPPED_EPOCHS_KEY: &[u8] = b"dropped-epochs";
+
+/// Key in the default column family recording the height up to which orphaned blocks are pruned
+const ORPHANS_PRUNED_HEIGHT_KEY: &[u8] = b"orphans-pruned-height";
+
+/// Key in the default column family recording the sequence number of the next logged event
+const NEXT_EVENT_SEQ_NUM_KEY: &[u8] = b"next-event-seq-num";
+
+/// Key in the default column family recording the genesis block, which has no precomputed block
+const GENESIS_BLOCK_KEY: &[u8] = b"genesis-block";
+
+/// Names of the store's column families
+pub const COLUMN_FAMILIES: [&str; 24] = [
+    "blocks",
+    "block-bodies",
+    "block-metadata",
+    "ancestry",
+    "ledgers",
+    "canonicity",
+    "tx",
+    "rejected",
+    "commands",
+    "block-commands",
+    "heights",
+    "creators",
+    "account-commands",
+    "internal-commands",
+    "recipient-internal-commands",
+    "snarks",
+    "prover-snarks",
+    "ledger-diffs",
+    "balances",
+    "staking-ledgers",
+    "metadata",
+    "events",
+    "account-aggregates",
+    "diffs",
+];
+
+/// Column families holding the stored blocks, see [IndexerStore::export_blocks]
+pub const BLOCK_COLUMN_FAMILIES: [&str; 2] = ["blocks", "block-bodies"];
+
+/// `{dir}/{cf}.sst`, the exported entries of the column family `cf`
+fn exported_blocks_file(dir: &Path, cf: &str) -> PathBuf {
+    dir.join(format!("{cf}.sst"))
+}
+
+#[derive(Debug)]
+pub struct IndexerStore {
+    db_path: PathBuf,
+    database: Box<dyn KvStore>,
+    value_codec: ValueCodec,
+    metrics: Arc<StoreMetrics>,
+    /// Last ledger reconstructed, the next one usually only needs the diffs of the blocks since
+    last_ledger: Mutex<Option<(BlockHash, Ledger)>>,
+}
+
+impl IndexerStore {
+    pub fn new_read_only(path: &Path, secondary: &Path) -> anyhow::Result<Self> {
+        let database = RocksDbStore::open_as_secondary(path, secondary, &COLUMN_FAMILIES)?;
+        Ok(Self::with_backend(path, Box::new(database)))
+    }
+
+    pub fn new(path: &Path) -> anyhow::Result<Self> {
+        Self::new_with_tuning(path, &RocksDbTuningConfiguration::default())
+    }
+
+    /// Opens the store at `path` with the given RocksDB tuning options
+    pub fn new_with_tuning(
+        path: &Path,
+        tuning: &RocksDbTuningConfiguration,
+    ) -> anyhow::Result<Self> {
+        let database = RocksDbStore::open(path, &COLUMN_FAMILIES, tuning)?;
+        Ok(Self::with_backend(path, Box::new(database)))
+    }
+
+    /// Creates a store which is kept in memory only, nothing is persisted
+    pub fn new_in_memory() -> Self {
+        Self::with_backend(Path::new(""), Box::new(MemoryStore::new()))
+    }
+
+    /// Creates a store on top of the given storage backend, whose reads and
+    /// writes are recorded in the store's [StoreMetrics]
+    pub fn with_backend(path: &Path, database: Box<dyn KvStore>) -> Self {
+        let metrics = Arc::new(StoreMetrics::default());
+        Self {
+            db_path: PathBuf::from(path),
+            database: Box::new(InstrumentedKvStore::new(database, metrics.clone())),
+            value_codec: ValueCodec::default(),
+            metrics,
+            last_ledger: Mutex::new(None),
+        }
+    }
+
+    /// Writes values with the given codec, values are read back with the codec
+    /// they were written with
+    pub fn with_value_codec(mut self, value_codec: ValueCodec) -> Self {
+        self.value_codec = value_codec;
+        self
+    }
+
+    pub fn db_path(&self) -> &Path {
+        &self.db_path
+    }
+
+    pub fn put_tx(
+        &self,
+        height: u32,
+        timestamp: u64,
+        tx: UserCommandWithStatusV1,
+    ) -> anyhow::Result<()> {
+        let mut batch = KvBatch::default();
+        self.batch_tx(&mut batch, height, timestamp, tx)?;
+        self.database.write(batch)?;
+        Ok(())
+    }
+
+    /// Records the genesis block, which has no precomputed block, as the canonical start of
+    /// the chain: its ancestry, blocks-by-height entry, and canonicity
+    pub fn add_genesis_block(&self, genesis: &Block) -> anyhow::Result<()> {
+        let ancestry = BlockWithoutHeight {
+            parent_hash: genesis.parent_hash.clone(),
+            state_hash: genesis.state_hash.clone(),
+            blockchain_length: genesis.blockchain_length,
+            global_slot_since_genesis: genesis.global_slot_since_genesis,
+        };
+        let mut batch = KvBatch::default();
+        let value = self.value_codec.encode(&ancestry)?;
+        batch.put(GENESIS_BLOCK_KEY, value.clone());
+        batch.put_cf("ancestry", genesis.state_hash.0.as_bytes(), value);
+        if let Some(blockchain_length) = genesis.blockchain_length {
+            let mut state_hashes = self.get_blocks_at_height(blockchain_length)?;
+            if !state_hashes.contains(&genesis.state_hash) {
+                state_hashes.push(genesis.state_hash.clone());
+                let key = blockchain_length.to_be_bytes();
+                batch.put_cf("heights", key, self.value_codec.encode(&state_hashes)?);
+            }
+        }
+        self.database.write(batch)?;
+        self.set_canonicity(&genesis.state_hash, Canonicity::Canonical)
+    }
+
+    /// The genesis block recorded by [Self::add_genesis_block], if any
+    pub fn get_genesis_block(&self) -> anyhow::Result<Option<BlockWithoutHeight>> {
+        self.database.try_catch_up_with_primary().ok();
+        match self.database.get(GENESIS_BLOCK_KEY)? {
+            Some(bytes) => Ok(Some(codec::decode(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the network of the stored blocks, if any block has been added
+    pub fn network(&self) -> anyhow::Result<Option<Network>> {
+        self.database.try_catch_up_with_primary().ok();
+        match self.database.get(NETWORK_KEY)? {
+            Some(bytes) => Ok(Some(codec::decode(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Records the network on first use, errors if `network` differs from the recorded one
+    fn check_network(&self, batch: &mut KvBatch, network: &Network) -> anyhow::Result<()> {
+        match self.network()? {
+            None => {
+                batch.put(NETWORK_KEY, self.value_codec.encode(network)?);
+                Ok(())
+            }
+            Some(stored_network) if &stored_network == network => Ok(()),
+            Some(stored_network) => Err(anyhow::Error::msg(format!(
+                "Cannot add a {network} block to a store of {stored_network} blocks"
+            ))),
+        }
+    }
+
+    /// Records a block which was refused by the indexer along with the reason
+    pub fn add_rejected_block(&self, state_hash: &BlockHash, reason: &str) -> anyhow::Result<()> {
+        let key = state_hash.0.as_bytes();
+        let value = self.value_codec.encode(reason)?;
+        self.database.put_cf("rejected", &key, &value)?;
+        Ok(())
+    }
+
+    /// Returns the reason the block was refused, if it was
+    pub fn get_rejected_block(&self, state_hash: &BlockHash) -> anyhow::Result<Option<String>> {
+        let mut reason = None;
+        let key = state_hash.0.as_bytes();
+        if let Some(bytes) = self.database.get_cf("rejected", &key)? {
+            reason = Some(codec::decode(&bytes)?);
+        }
+        Ok(reason)
+    }
+
+    /// Creates an iterator over the entries of a CF whose keys start with `prefix`
+    pub fn iter_prefix_cf<'a>(&'a self, cf: &str, prefix: &'a [u8]) -> KvIterator<'a> {
+        self.database.prefix_iterator_cf(cf, prefix)
+    }
+
+    /// Creates an iterator over the entries of an account keyed CF, see
+    /// [keys::ACCOUNT_KEYED_COLUMN_FAMILIES], of lengths `start_height` through `end_height`
+    pub fn iter_account_heights_cf<'a>(
+        &'a self,
+        cf: &str,
+        pk: &PublicKey,
+        start_height: u32,
+        end_height: u32,
+    ) -> KvIterator<'a> {
+        // seeks straight to the first entry of `start_height`
+        let end = keys::account_height_prefix(pk, end_height);
+        Box::new(
+            self.database
+                .iterator_from_cf(cf, &keys::account_height_prefix(pk, start_height))
+                .take_while(move |entry| match entry {
+                    Ok((key, _)) => key[..end.len().min(key.len())] <= end[..],
+                    Err(_) => true,
+                }),
+        )
+    }
+}
+
+impl BlockStore for IndexerStore {
+    fn add_block(&self, block: &PrecomputedBlock) -> anyhow::Result<()> {
+        // the block, its metadata, index entries, and derived data are written
+        // in a single batch so the store is never left partially updated
+        let mut batch = KvBatch::default();
+        let state_hash = BlockHash(block.state_hash.clone());
+        self.check_network(&mut batch, &block.network)?;
+
+        self.batch_block(&mut batch, block)?;
+
+        // blocks-by-height index
+        if let Some(blockchain_length) = block.blockchain_length {
+            let mut state_hashes = self.get_blocks_at_height(blockchain_length)?;
+            if !state_hashes.contains(&state_hash) {
+                state_hashes.push(state_hash.clone());
+
+                let key = blockchain_length.to_be_bytes();
+                let value = self.value_codec.encode(&state_hashes)?;
+                batch.put_cf("heights", key, value);
+            }
+        }
+
+        // blocks-by-creator index
+        for creator in [block.block_creator(), block.coinbase_receiver()] {
+            let key = keys::creator_key(&creator, &state_hash);
+            batch.put_cf("creators", key, b"");
+        }
+
+        self.batch_derived_data(&mut batch, block)?;
+        self.batch_events(
+            &mut batch,
+            &[IndexerEvent::BlockAdded {
+                state_hash,
+                blockchain_length: block.blockchain_length,
+            }],
+        )?;
+        self.database.write(batch)?;
+        Ok(())
+    }
+
+    fn get_block(&self, state_hash: &BlockHash) -> anyhow::Result<Option<PrecomputedBlock>> {
+        self.database.try_catch_up_with_primary().ok();
+        let key = state_hash.0.as_bytes();
+        let bytes = match self.database.get_cf("blocks", &key)? {
+            Some(bytes) => bytes,
+            None => return Ok(None),
+        };
+        let header: PrecomputedBlockHeader = match codec::decode(&bytes) {
+            Ok(header) => header,
+            // stored whole, before block bodies were split off
+            Err(_) => return Ok(Some(codec::decode(&bytes)?)),
+        };
+
+        let epoch = header.epoch();
+        let body = match self
+            .database
+            .get_cf("block-bodies", &keys::block_body_key(epoch, state_hash))?
+        {
+            Some(body) => body,
+            // stored before block bodies were partitioned by epoch
+            None => self.database.get_cf("block-bodies", &key)?.ok_or_else(|| {
+                IndexerError::DroppedBlockBody {
+                    state_hash: state_hash.0.clone(),
+                    epoch,
+                    dropped_epochs: self.get_dropped_epochs().unwrap_or_default(),
+                }
+            })?,
+        };
+        Ok(Some(PrecomputedBlock::from_parts(
+            header,
+            codec::decode(&body)?,
+        )))
+    }
+
+    /// The block without its proofs and staged ledger diff, only its header is deserialized
+    fn get_block_header(
+        &self,
+        state_hash: &BlockHash,
+    ) -> anyhow::Result<Option<PrecomputedBlockHeader>> {
+        let mut header = None;
+        self.database.try_catch_up_with_primary().ok();
+        let key = state_hash.0.as_bytes();
+        if let Some(bytes) = self.database.get_cf("blocks", &key)? {
+            header = Some(match codec::decode(&bytes) {
+                Ok(header) => header,
+                // stored whole, before block bodies were split off
+                Err(_) => codec::decode::<PrecomputedBlock>(&bytes)?.header(),
+            });
+        }
+        Ok(header)
+    }
+
+    /// Transaction count, fees, coinbase, and SNARK count of the block
+    fn get_block_metadata(&self, state_hash: &BlockHash) -> anyhow::Result<Option<BlockMetadata>> {
+        let mut metadata = None;
+        self.database.try_catch_up_with_primary().ok();
+        let key = state_hash.0.as_bytes();
+        if let Some(bytes) = self.database.get_cf("block-metadata", &key)? {
+            metadata = Some(codec::decode(&bytes)?);
+        }
+        Ok(metadata)
+    }
+
+    /// Whether the block is stored, without reading it
+    fn exists(&self, state_hash: &BlockHash) -> anyhow::Result<bool> {
+        self.database.try_catch_up_with_primary().ok();
+        self.database.contains_cf("blocks", state_hash.0.as_bytes())
+    }
+
+    fn get_blocks_at_height(&self, blockchain_length: u32) -> anyhow::Result<Vec<BlockHash>> {
+        let mut state_hashes = vec![];
+        self.database.try_catch_up_with_primary().ok();
+        let key = blockchain_length.to_be_bytes();
+        if let Some(bytes) = self.database.get_cf("heights", &key)? {
+            state_hashes = codec::decode(&bytes)?;
+        }
+        Ok(state_hashes)
+    }
+
+    /// Blocks created by, or paying their coinbase to, `creator`
+    fn get_blocks_by_creator(&self, creator: &PublicKey) -> anyhow::Result<Vec<BlockHash>> {
+        let mut state_hashes = vec![];
+        self.database.try_catch_up_with_primary().ok();
+        let prefix = keys::account_prefix(creator);
+        for entry in self.database.prefix_iterator_cf("creators", &prefix) {
+            let (key, _) = entry?;
+            let state_hash = std::str::from_utf8(&key[prefix.len()..])?;
+            state_hashes.push(BlockHash(state_hash.to_string()));
+        }
+        Ok(state_hashes)
+    }
+
+    /// Deletes orphaned blocks of length at most `height`, along with their block,
+    /// command, SNARK work, balance, and transaction index entries and ledger diffs,
+    /// returns the number of pruned blocks
+    ///
+    /// Canonicity records are kept so pruned blocks are still reported as orphaned.
+    /// Pruning stops below the first height without a known canonical block
+    fn prune_orphaned_blocks(&self, height: u32) -> anyhow::Result<u32> {
+        let start = match self.database.get(ORPHANS_PRUNED_HEIGHT_KEY)? {
+            Some(bytes) => codec::decode::<u32>(&bytes)? + 1,
+            None => 0,
+        };
+        if start > height {
+            return Ok(0);
+        }
+
+        let mut batch = KvBatch::default();
+        let mut num_pruned = 0;
+        let mut pruned_height = Some(height);
+        for entry in self
+            .database
+            .iterator_from_cf("heights", &start.to_be_bytes())
+        {
+            let (key, value) = entry?;
+            let blockchain_length = u32::from_be_bytes(key[..4].try_into()?);
+            if blockchain_length > height {
+                break;
+            }
+
+            // blocks at heights without a known canonical block are kept, and pruning
+            // resumes from there once it's known
+            let canonical_hash = match self.get_canonical_hash_at_height(blockchain_length)? {
+                Some(canonical_hash) => canonical_hash,
+                None => {
+                    pruned_height = blockchain_length.checked_sub(1);
+                    break;
+                }
+            };
+
+            // the canonical block's transaction keys may coincide with the orphans'
+            let mut kept_tx_keys = HashSet::new();
+            if let Some(canonical_block) = self.get_block(&canonical_hash)? {
+                for command in canonical_block.commands() {
+                    kept_tx_keys.insert(tx_key(
+                        blockchain_length,
+                        canonical_block.timestamp(),
+                        &command,
+                    )?);
+                }
+            }
+
+            let state_hashes: Vec<BlockHash> = codec::decode(&value)?;
+            for state_hash in state_hashes.iter().filter(|hash| **hash != canonical_hash) {
+                let key = state_hash.0.as_bytes();
+                if let Some(block) = self.get_block(state_hash)? {
+                    self.batch_delete_block_indices(&mut batch, &block, &kept_tx_keys)?;
+                }
+                if let Some(header) = self.get_block_header(state_hash)? {
+                    for creator in [header.block_creator(), header.coinbase_receiver()] {
+                        batch.delete_cf("creators", keys::creator_key(&creator, state_hash));
+                    }
+                    batch.delete_cf(
+                        "block-bodies",
+                        keys::block_body_key(header.epoch(), state_hash),
+                    );
+                }
+                batch.delete_cf("blocks", key);
+                batch.delete_cf("block-bodies", key);
+                batch.delete_cf("block-metadata", key);
+                batch.delete_cf("block-commands", key);
+                batch.delete_cf("ancestry", key);
+                batch.delete_cf("ledger-diffs", key);
+                batch.put_cf(
+                    "canonicity",
+                    keys::block_canonicity_key(state_hash),
+                    self.value_codec.encode(&Canonicity::Orphaned)?,
+                );
+                num_pruned += 1;
+            }
+            batch.put_cf(
+                "heights",
+                key,
+                self.value_codec.encode(&vec![canonical_hash])?,
+            );
+        }
+
+        if let Some(pruned_height) = pruned_height {
+            batch.put(
+                ORPHANS_PRUNED_HEIGHT_KEY,
+                self.value_codec.encode(&pruned_height)?,
+            );
+        }
+        self.database.write(batch)?;
+        Ok(num_pruned)
+    }
+
+    /// Parent hash, length, and global slot of the block, without deserializing it
+    ///
+    /// Falls back to the stored block header if its ancestry entry is missing, e.g. in
+    /// stores which haven't been reindexed since the ancestry column family was added
+    fn get_block_ancestry(
+        &self,
+        state_hash: &BlockHash,
+    ) -> anyhow::Result<Option<BlockWithoutHeight>> {
+        self.database.try_catch_up_with_primary().ok();
+        let key = state_hash.0.as_bytes();
+        if let Some(bytes) = self.database.get_cf("ancestry", &key)? {
+            return Ok(Some(codec::decode(&bytes)?));
+        }
+        Ok(self
+            .get_block_header(state_hash)?
+            .map(|header| BlockWithoutHeight::from_header(&header)))
+    }
+
+    /// Ancestor of the block `generations` blocks back, if all blocks in between are stored
+    fn get_ancestor(
+        &self,
+        state_hash: &BlockHash,
+        generations: u32,
+    ) -> anyhow::Result<Option<BlockHash>> {
+        let mut curr_hash = state_hash.clone();
+        for _ in 0..generations {
+            match self.get_block_ancestry(&curr_hash)? {
+                Some(ancestry) => curr_hash = ancestry.parent_hash,
+                None => return Ok(None),
+            }
+        }
+        Ok(Some(curr_hash))
+    }
+
+    /// Lazily iterates over at most `limit` blocks, starting at length `start_height`
+    fn blocks_iter(&self, start_height: u32, limit: usize) -> BlockIterator<'_> {
+        Box::new(
+            self.block_hashes_from(start_height)
+                .take(limit)
+                .map(move |state_hash| self.get_stored_block(&state_hash?)),
+        )
+    }
+
+    /// Lazily iterates over the blocks following `state_hash`, i.e. the rest of its
+    /// length and all greater lengths, so iteration can be resumed from the last
+    /// block received
+    fn blocks_after(&self, state_hash: &BlockHash) -> anyhow::Result<BlockIterator<'_>> {
+        let start_height = self
+            .get_block_ancestry(state_hash)?
+            .ok_or_else(|| IndexerError::MissingBlock(state_hash.0.clone()))?
+            .blockchain_length
+            .unwrap_or(0);
+        let state_hash = state_hash.clone();
+        let mut found = false;
+        Ok(Box::new(
+            self.block_hashes_from(start_height)
+                .filter(move |entry| match entry {
+                    _ if found => true,
+                    Ok(curr_hash) => {
+                        found = *curr_hash == state_hash;
+                        false
+                    }
+                    Err(_) => true,
+                })
+                .map(move |state_hash| self.get_stored_block(&state_hash?)),
+        ))
+    }
+}
+
+impl IndexerStore {
+    /// State hashes of the blocks of length at least `start_height`, in the
+    /// order of the blocks-by-height index
+    fn block_hashes_from(
+        &self,
+        start_height: u32,
+    ) -> impl Iterator<Item = anyhow::Result<BlockHash>> + '_ {
+        self.database.try_catch_up_with_primary().ok();
+
+        // the genesis block has no precomputed block to iterate over
+        let genesis_hash = self
+            .get_genesis_block()
+            .ok()
+            .flatten()
+            .map(|genesis| genesis.state_hash);
+        self.database
+            .iterator_from_cf("heights", &start_height.to_be_bytes())
+            .flat_map(move |entry| {
+                let state_hashes =
+                    entry.and_then(|(_, value)| Ok(codec::decode::<Vec<BlockHash>>(&value)?));
+                match state_hashes {
+                    Ok(state_hashes) => state_hashes
+                        .into_iter()
+                        .filter(|state_hash| Some(state_hash) != genesis_hash.as_ref())
+                        .map(Ok)
+                        .collect(),
+                    Err(e) => vec![Err(e)],
+                }
+            })
+    }
+
+    /// Block which the blocks-by-height index points at, errors if it's missing
+    fn get_stored_block(&self, state_hash: &BlockHash) -> anyhow::Result<PrecomputedBlock> {
+        Ok(self
+            .get_block(state_hash)?
+            .ok_or_else(|| IndexerError::MissingBlock(state_hash.0.clone()))?)
+    }
+}
+
+impl CanonicityStore for IndexerStore {
+    fn set_canonicity(&self, state_hash: &BlockHash, canonicity: Canonicity) -> anyhow::Result<()> {
+        let mut batch = KvBatch::default();
+
+        // canonical blocks are also indexed by length
+        if canonicity == Canonicity::Canonical {
+            if let Some(BlockWithoutHeight {
+                blockchain_length: Some(blockchain_length),
+                ..
+            }) = self.get_block_ancestry(state_hash)?
+            {
+                let key = keys::canonical_height_key(blockchain_length);
+                let value = self.value_codec.encode(state_hash)?;
+                batch.put_cf("canonicity", key, value);
+            }
+        }
+
+        // only actual changes are logged
+        if self.get_canonicity(state_hash)?.as_ref() != Some(&canonicity) {
+            // blocks are counted in the account aggregates once, as they become canonical
+            if canonicity == Canonicity::Canonical {
+                if let Some(block) = self.get_block(state_hash)? {
+                    self.batch_account_aggregates(&mut batch, &block);
+                }
+            }
+            self.batch_events(
+                &mut batch,
+                &[IndexerEvent::CanonicityChanged {
+                    state_hash: state_hash.clone(),
+                    canonicity: canonicity.clone(),
+                }],
+            )?;
+        }
+
+        let key = keys::block_canonicity_key(state_hash);
+        let value = self.value_codec.encode(&canonicity)?;
+        batch.put_cf("canonicity", key, value);
+        self.database.write(batch)?;
+        Ok(())
+    }
+
+    fn get_canonicity(&self, state_hash: &BlockHash) -> anyhow::Result<Option<Canonicity>> {
+        let mut canonicity = None;
+        self.database.try_catch_up_with_primary().ok();
+        let key = keys::block_canonicity_key(state_hash);
+        if let Some(bytes) = self.database.get_cf("canonicity", &key)? {
+            canonicity = Some(codec::decode(&bytes)?);
+        }
+        Ok(canonicity)
+    }
+
+    fn get_canonical_hash_at_height(&self, height: u32) -> anyhow::Result<Option<BlockHash>> {
+        let mut state_hash = None;
+        self.database.try_catch_up_with_primary().ok();
+        let key = keys::canonical_height_key(height);
+        if let Some(bytes) = self.database.get_cf("canonicity", &key)? {
+            state_hash = Some(codec::decode(&bytes)?);
+        }
+        Ok(state_hash)
+    }
+
+    fn rollback_canonical(
+        &self,
+        from_length: u32,
+        updates: &[(BlockHash, Canonicity)],
+        ledger: (&BlockHash, &Ledger),
+    ) -> anyhow::Result<Vec<BlockHash>> {
+        let mut batch = KvBatch::default();
+        let mut events = vec![];
+        let mut rolled_back = vec![];
+        let mut aggregates: HashMap<String, AccountAggregate> = HashMap::new();
+        let mut update_aggregates =
+            |block: &PrecomputedBlock, becomes_canonical: bool| -> anyhow::Result<()> {
+                for (pk, delta) in AccountAggregate::from_precomputed_block(block) {
+                    let address = pk.to_address();
+                    let aggregate = match aggregates.remove(&address) {
+                        Some(aggregate) => aggregate,
+                        None => self.get_account_aggregate(&pk)?,
+                    };
+                    let aggregate = if becomes_canonical {
+                        aggregate.saturating_add(&delta)
+                    } else {
+                        aggregate.saturating_sub(&delta)
+                    };
+                    aggregates.insert(address, aggregate);
+                }
+                Ok(())
+            };
+
+        for entry in self
+            .database
+            .iterator_from_cf("canonicity", &keys::canonical_height_key(from_length))
+        {
+            let (key, value) = entry?;
+            if !key.starts_with(keys::CANONICAL_HEIGHT_PREFIX) {
+                break;
+            }
+            let state_hash: BlockHash = codec::decode(&value)?;
+            batch.delete_cf("canonicity", &key);
+            batch.put_cf(
+                "canonicity",
+                keys::block_canonicity_key(&state_hash),
+                self.value_codec.encode(&Canonicity::Orphaned)?,
+            );
+            batch.delete_cf("ledgers", state_hash.0.as_bytes());
+
+            // the block's increments are taken back out of the account aggregates
+            if let Some(block) = self.get_block(&state_hash)? {
+                update_aggregates(&block, false)?;
+            }
+            events.push(IndexerEvent::CanonicityChanged {
+                state_hash: state_hash.clone(),
+                canonicity: Canonicity::Orphaned,
+            });
+            rolled_back.push(state_hash);
+        }
+
+        // the updates apply on top of the rollback, the batch is written in order
+        for (state_hash, canonicity) in updates {
+            let previous = if rolled_back.contains(state_hash) {
+                Some(Canonicity::Orphaned)
+            } else {
+                self.get_canonicity(state_hash)?
+            };
+            if previous.as_ref() == Some(canonicity) {
+                continue;
+            }
+            if *canonicity == Canonicity::Canonical {
+                if let Some(BlockWithoutHeight {
+                    blockchain_length: Some(blockchain_length),
+                    ..
+                }) = self.get_block_ancestry(state_hash)?
+                {
+                    batch.put_cf(
+                        "canonicity",
+                        keys::canonical_height_key(blockchain_length),
+                        self.value_codec.encode(state_hash)?,
+                    );
+                }
+                if let Some(block) = self.get_block(state_hash)? {
+                    update_aggregates(&block, true)?;
+                }
+            }
+            batch.put_cf(
+                "canonicity",
+                keys::block_canonicity_key(state_hash),
+                self.value_codec.encode(canonicity)?,
+            );
+            events.push(IndexerEvent::CanonicityChanged {
+                state_hash: state_hash.clone(),
+                canonicity: canonicity.clone(),
+            });
+        }
+        for (address, aggregate) in aggregates {
+            batch.put_cf(
+                "account-aggregates",
+                address.into_bytes(),
+                aggregate.to_counters(),
+            );
+        }
+
+        let (ledger_hash, ledger) = ledger;
+        batch.put_cf(
+            "ledgers",
+            ledger_hash.0.as_bytes(),
+            self.value_codec.encode(ledger)?,
+        );
+        events.push(IndexerEvent::LedgerSnapshotTaken {
+            state_hash: ledger_hash.clone(),
+        });
+
+        self.batch_events(&mut batch, &events)?;
+        self.database.write(batch)?;
+        Ok(rolled_back)
+    }
+}
+
+/// Keys of the metadata column family
+const BEST_TIP_KEY: &[u8] = b"best-tip";
+const CANONICAL_TIP_KEY: &[u8] = b"canonical-tip";
+const MAX_CANONICAL_HEIGHT_KEY: &[u8] = b"max-canonical-height";
+const BLOCKS_PROCESSED_KEY: &[u8] = b"blocks-processed";
+const WITNESS_TREE_KEY: &[u8] = b"witness-tree";
+
+impl MetadataStore for IndexerStore {
+    fn set_metadata(&self, metadata: &StoreMetadata) -> anyhow::Result<()> {
+        let mut batch = KvBatch::default();
+        if self.get_metadata()?.map(|stored| stored.best_tip).as_ref() != Some(&metadata.best_tip) {
+            self.batch_events(
+                &mut batch,
+                &[IndexerEvent::BestTipChanged {
+                    state_hash: metadata.best_tip.clone(),
+                }],
+            )?;
+        }
+        batch.put_cf(
+            "metadata",
+            BEST_TIP_KEY,
+            self.value_codec.encode(&metadata.best_tip)?,
+        );
+        batch.put_cf(
+            "metadata",
+            CANONICAL_TIP_KEY,
+            self.value_codec.encode(&metadata.canonical_tip)?,
+        );
+        batch.put_cf(
+            "metadata",
+            MAX_CANONICAL_HEIGHT_KEY,
+            self.value_codec.encode(&metadata.max_canonical_height)?,
+        );
+        batch.put_cf(
+            "metadata",
+            BLOCKS_PROCESSED_KEY,
+            self.value_codec.encode(&metadata.blocks_processed)?,
+        );
+        self.database.write(batch)?;
+        Ok(())
+    }
+
+    fn get_metadata(&self) -> anyhow::Result<Option<StoreMetadata>> {
+        self.database.try_catch_up_with_primary().ok();
+        let get = |key| self.database.get_cf("metadata", key);
+        match (
+            get(BEST_TIP_KEY)?,
+            get(CANONICAL_TIP_KEY)?,
+            get(MAX_CANONICAL_HEIGHT_KEY)?,
+            get(BLOCKS_PROCESSED_KEY)?,
+        ) {
+            (
+                Some(best_tip),
+                Some(canonical_tip),
+                Some(max_canonical_height),
+                Some(blocks_processed),
+            ) => Ok(Some(StoreMetadata {
+                best_tip: codec::decode(&best_tip)?,
+                canonical_tip: codec::decode(&canonical_tip)?,
+                max_canonical_height: codec::decode(&max_canonical_height)?,
+                blocks_processed: codec::decode(&blocks_processed)?,
+            })),
+            _ => Ok(None),
+        }
+    }
+
+    fn set_witness_tree(&self, witness_tree: &PersistedWitnessTree) -> anyhow::Result<()> {
+        self.database.put_cf(
+            "metadata",
+            WITNESS_TREE_KEY,
+            &self.value_codec.encode(witness_tree)?,
+        )?;
+        Ok(())
+    }
+
+    fn get_witness_tree(&self) -> anyhow::Result<Option<PersistedWitnessTree>> {
+        self.database.try_catch_up_with_primary().ok();
+        match self.database.get_cf("metadata", WITNESS_TREE_KEY)? {
+            Some(bytes) => Ok(Some(codec::decode(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+}
+
+impl AccountAggregateStore for IndexerStore {
+    fn get_account_aggregate(&self, pk: &PublicKey) -> anyhow::Result<AccountAggregate> {
+        self.database.try_catch_up_with_primary().ok();
+        let key = pk.to_address().into_bytes();
+        match self.database.get_cf("account-aggregates", &key)? {
+            Some(bytes) => AccountAggregate::from_counters(&bytes),
+            None => Ok(AccountAggregate::default()),
+        }
+    }
+}
+
+impl IndexerStore {
+    /// Adds merges of the block's increments of the account aggregates to the batch
+    fn batch_account_aggregates(&self, batch: &mut KvBatch, block: &PrecomputedBlock) {
+        for (pk, increment) in AccountAggregate::from_precomputed_block(block) {
+            batch.merge_cf(
+                "account-aggregates",
+                pk.to_address().into_bytes(),
+                increment.to_counters(),
+            );
+        }
+    }
+}
+
+impl EventStore for IndexerStore {
+    fn get_event(&self, seq_num: u64) -> anyhow::Result<Option<IndexerEvent>> {
+        let mut event = None;
+        self.database.try_catch_up_with_primary().ok();
+        let key = seq_num.to_be_bytes();
+        if let Some(bytes) = self.database.get_cf("events", &key)? {
+            event = Some(codec::decode(&bytes)?);
+        }
+        Ok(event)
+    }
+
+    fn get_events_from(
+        &self,
+        seq_num: u64,
+        limit: usize,
+    ) -> anyhow::Result<Vec<(u64, IndexerEvent)>> {
+        let mut events = vec![];
+        self.database.try_catch_up_with_primary().ok();
+        for entry in self
+            .database
+            .iterator_from_cf("events", &seq_num.to_be_bytes())
+            .take(limit)
+        {
+            let (key, value) = entry?;
+            let seq_num = u64::from_be_bytes(key[..8].try_into()?);
+            events.push((seq_num, codec::decode(&value)?));
+        }
+        Ok(events)
+    }
+
+    fn get_next_event_seq_num(&self) -> anyhow::Result<u64> {
+        self.database.try_catch_up_with_primary().ok();
+        match self.database.get(NEXT_EVENT_SEQ_NUM_KEY)? {
+            Some(bytes) => Ok(codec::decode(&bytes)?),
+            None => Ok(0),
+        }
+    }
+}
+
+impl IndexerStore {
+    /// Appends the events to the event log in the batch, in order
+    ///
+    /// Sequence numbers are read from the store, so the batch must be written
+    /// before any other events are logged
+    fn batch_events(&self, batch: &mut KvBatch, events: &[IndexerEvent]) -> anyhow::Result<()> {
+        let mut seq_num = self.get_next_event_seq_num()?;
+        for event in events {
+            batch.put_cf(
+                "events",
+                seq_num.to_be_bytes(),
+                self.value_codec.encode(event)?,
+            );
+            seq_num += 1;
+        }
+        batch.put(NEXT_EVENT_SEQ_NUM_KEY, self.value_codec.encode(&seq_num)?);
+        Ok(())
+    }
+}
+
+impl LedgerStore for IndexerStore {
+    fn add_ledger(&self, state_hash: &BlockHash, ledger: Ledger) -> anyhow::Result<()> {
+        let mut batch = KvBatch::default();
+        let key = state_hash.0.as_bytes();
+        let value = self.value_codec.encode(&ledger)?;
+        batch.put_cf("ledgers", key, value);
+        self.batch_events(
+            &mut batch,
+            &[IndexerEvent::LedgerSnapshotTaken {
+                state_hash: state_hash.clone(),
+            }],
+        )?;
+        self.database.write(batch)?;
+        Ok(())
+    }
+
+    fn get_ledger(&self, state_hash: &BlockHash) -> anyhow::Result<Option<Ledger>> {
+        self.database.try_catch_up_with_primary().ok();
+
+        // walk back to the last reconstructed ledger or the nearest snapshot, collecting diffs
+        let mut diffs = vec![];
+        let mut curr_hash = state_hash.clone();
+        loop {
+            let last_ledger = match self.last_ledger.lock().unwrap().as_ref() {
+                Some((last_hash, ledger)) if last_hash == &curr_hash => Some(ledger.clone()),
+                _ => None,
+            };
+            let ledger = match last_ledger {
+                Some(ledger) => Some(ledger),
+                None => self.get_ledger_snapshot(&curr_hash)?,
+            };
+            if let Some(mut ledger) = ledger {
+                for diff in diffs.iter().rev() {
+                    ledger.apply_diff(diff)?;
+                }
+                *self.last_ledger.lock().unwrap() = Some((state_hash.clone(), ledger.clone()));
+                return Ok(Some(ledger));
+            }
+
+            match self.get_ledger_diff(&curr_hash)? {
+                Some((parent_hash, diff)) if parent_hash != curr_hash => {
+                    diffs.push(diff);
+                    curr_hash = parent_hash;
+                }
+                _ => return Ok(None),
+            }
+        }
+    }
+
+    fn add_ledger_diff(
+        &self,
+        state_hash: &BlockHash,
+        parent_hash: &BlockHash,
+        diff: &LedgerDiff,
+    ) -> anyhow::Result<()> {
+        let mut batch = KvBatch::default();
+        self.batch_ledger_diff(&mut batch, state_hash, parent_hash, diff)?;
+        self.database.write(batch)?;
+        Ok(())
+    }
+
+    fn get_ledger_diff(
+        &self,
+        state_hash: &BlockHash,
+    ) -> anyhow::Result<Option<(BlockHash, LedgerDiff)>> {
+        let mut diff = None;
+        let key = state_hash.0.as_bytes();
+        if let Some(bytes) = self.database.get_cf("ledger-diffs", &key)? {
+            diff = Some(codec::decode(&bytes)?);
+        }
+        Ok(diff)
+    }
+
+    fn add_spilled_diff(&self, state_hash: &BlockHash, diff: &LedgerDiff) -> anyhow::Result<()> {
+        let key = state_hash.0.as_bytes();
+        let value = self.value_codec.encode(diff)?;
+        self.database.put_cf("diffs", key, &value)?;
+        Ok(())
+    }
+
+    fn get_spilled_diff(&self, state_hash: &BlockHash) -> anyhow::Result<Option<LedgerDiff>> {
+        let mut diff = None;
+        let key = state_hash.0.as_bytes();
+        if let Some(bytes) = self.database.get_cf("diffs", key)? {
+            diff = Some(codec::decode(&bytes)?);
+        }
+        Ok(diff)
+    }
+
+    fn remove_spilled_diff(&self, state_hash: &BlockHash) -> anyhow::Result<()> {
+        let mut batch = KvBatch::default();
+        batch.delete_cf("diffs", state_hash.0.as_bytes());
+        self.database.write(batch)?;
+        Ok(())
+    }
+
+    fn add_staking_ledger(&self, epoch: u32, ledger: Ledger) -> anyhow::Result<()> {
+        let key = epoch.to_be_bytes();
+        let value = self.value_codec.encode(&ledger)?;
+        self.database.put_cf("staking-ledgers", &key, &value)?;
+        Ok(())
+    }
+
+    fn get_staking_ledger(&self, epoch: u32) -> anyhow::Result<Option<Ledger>> {
+        let mut ledger = None;
+        self.database.try_catch_up_with_primary().ok();
+        let key = epoch.to_be_bytes();
+        if let Some(bytes) = self.database.get_cf("staking-ledgers", &key)? {
+            ledger = Some(codec::decode(&bytes)?);
+        }
+        Ok(ledger)
+    }
+}
+
+impl IndexerStore {
+    /// Full ledger snapshot of the block, if one was stored
+    fn get_ledger_snapshot(&self, state_hash: &BlockHash) -> anyhow::Result<Option<Ledger>> {
+        let mut ledger = None;
+        let key = state_hash.0.as_bytes();
+        if let Some(bytes) = self.database.get_cf("ledgers", &key)? {
+            ledger = Some(codec::decode(&bytes)?);
+        }
+        Ok(ledger)
+    }
+}
+
+impl IndexerStore {
+    /// Adds the block's header and body to the batch, bodies are partitioned by epoch
+    fn batch_block(&self, batch: &mut KvBatch, block: &PrecomputedBlock) -> anyhow::Result<()> {
+        let state_hash = BlockHash(block.state_hash.clone());
+        batch.put_cf(
+            "blocks",
+            state_hash.0.as_bytes(),
+            self.value_codec.encode(&block.header())?,
+        );
+        batch.put_cf(
+            "block-bodies",
+            keys::block_body_key(block.epoch(), &state_hash),
+            self.value_codec.encode(&block.body_ref())?,
+        );
+        Ok(())
+    }
+
+    /// Adds the data derived from a block to the batch: ancestry, block metadata,
+    /// transaction indices, internal commands, SNARK work, balances, and the ledger diff
+    fn batch_derived_data(
+        &self,
+        batch: &mut KvBatch,
+        block: &PrecomputedBlock,
+    ) -> anyhow::Result<()> {
+        let ancestry = BlockWithoutHeight::from_precomputed(block);
+        batch.put_cf(
+            "ancestry",
+            block.state_hash.as_bytes(),
+            self.value_codec.encode(&ancestry)?,
+        );
+        batch.put_cf(
+            "block-metadata",
+            block.state_hash.as_bytes(),
+            self.value_codec
+                .encode(&BlockMetadata::from_precomputed_block(block))?,
+        );
+
+        if let Some(height) = block.blockchain_length {
+            let timestamp = block.timestamp();
+            for cmd in block.commands() {
+                self.batch_tx(batch, height, timestamp, cmd)?;
+            }
+        }
+        self.batch_commands(batch, block)?;
+        self.batch_internal_commands(batch, block)?;
+        self.batch_snark_work(batch, block)?;
+        self.batch_balances(batch, block)?;
+        self.batch_ledger_diff(
+            batch,
+            &BlockHash(block.state_hash.clone()),
+            &BlockHash::previous_state_hash(block),
+            &LedgerDiff::from_precomputed_block(block),
+        )
+    }
+
+    /// Adds the block's user commands, their commands-by-account index entries,
+    /// and the block's command hashes to the batch
+    fn batch_commands(&self, batch: &mut KvBatch, block: &PrecomputedBlock) -> anyhow::Result<()> {
+        let state_hash = BlockHash(block.state_hash.clone());
+        let mut command_hashes = vec![];
+
+        for command in block.commands() {
+            let command = UserCommandWithStatus(command);
+            let UserCommand::SignedCommand(signed_command) = command.clone().data();
+            let command_hash = SignedCommand(signed_command).hash_signed_command()?;
+            let value = self.value_codec.encode(&CommandWithStateHash {
+                command: command.clone(),
+                state_hash: state_hash.clone(),
+                blockchain_length: block.blockchain_length,
+            })?;
+            batch.put_cf(
+                "commands",
+                keys::command_key(&command_hash, &state_hash),
+                &value,
+            );
+
+            // commands-by-account index, both sides of the command
+            let height = block.blockchain_length.unwrap_or_default();
+            let signed_command = signed_command::SignedCommand::from_user_command(command.clone());
+            let mut pks = vec![signed_command.source_pk(), signed_command.receiver_pk()];
+            pks.dedup();
+            for pk in pks {
+                let key = keys::account_key(&pk, height, &state_hash, command_hash.as_bytes());
+                batch.put_cf("account-commands", key, &value);
+            }
+            command_hashes.push(command_hash);
+        }
+
+        // commands-by-block index
+        batch.put_cf(
+            "block-commands",
+            state_hash.0.as_bytes(),
+            self.value_codec.encode(&command_hashes)?,
+        );
+        Ok(())
+    }
+
+    /// Adds the block's internal commands and their by-recipient index entries to the batch
+    fn batch_internal_commands(
+        &self,
+        batch: &mut KvBatch,
+        block: &PrecomputedBlock,
+    ) -> anyhow::Result<()> {
+        let state_hash = BlockHash(block.state_hash.clone());
+        let height = block.blockchain_length.unwrap_or_default();
+
+        let internal_commands: Vec<InternalCommandWithStateHash> =
+            InternalCommand::from_precomputed_block(block)
+                .into_iter()
+                .map(|command| InternalCommandWithStateHash {
+                    command,
+                    state_hash: state_hash.clone(),
+                    blockchain_length: block.blockchain_length,
+                })
+                .collect();
+
+        // internal-commands-by-recipient index
+        for (n, internal_command) in internal_commands.iter().enumerate() {
+            let key = keys::account_key(
+                &internal_command.command.receiver(),
+                height,
+                &state_hash,
+                &(n as u32).to_be_bytes(),
+            );
+            batch.put_cf(
+                "recipient-internal-commands",
+                key,
+                self.value_codec.encode(internal_command)?,
+            );
+        }
+
+        let key = state_hash.0.as_bytes();
+        let value = self.value_codec.encode(&internal_commands)?;
+        batch.put_cf("internal-commands", key, value);
+        Ok(())
+    }
+
+    /// Adds the block's SNARK work and its by-prover index entries to the batch
+    fn batch_snark_work(
+        &self,
+        batch: &mut KvBatch,
+        block: &PrecomputedBlock,
+    ) -> anyhow::Result<()> {
+        let state_hash = BlockHash(block.state_hash.clone());
+        let height = block.blockchain_length.unwrap_or_default();
+        let snark_work = SnarkWorkSummary::from_precomputed_block(block);
+
+        // SNARK-work-by-prover index
+        for (n, summary) in snark_work.iter().enumerate() {
+            let key = keys::account_key(
+                &summary.prover,
+                height,
+                &state_hash,
+                &(n as u32).to_be_bytes(),
+            );
+            let value = self
+                .value_codec
+                .encode(&SnarkWorkSummaryWithStateHash::from_summary(
+                    summary.clone(),
+                    &state_hash,
+                    block.blockchain_length,
+                ))?;
+            batch.put_cf("prover-snarks", key, value);
+        }
+
+        let key = state_hash.0.as_bytes();
+        let value = self.value_codec.encode(&snark_work)?;
+        batch.put_cf("snarks", key, value);
+        Ok(())
+    }
+
+    /// Adds the block's balance history entries to the batch
+    fn batch_balances(&self, batch: &mut KvBatch, block: &PrecomputedBlock) -> anyhow::Result<()> {
+        for (pk, entry) in BalanceHistoryEntry::from_precomputed_block(block) {
+            let key = keys::account_key(&pk, entry.blockchain_length, &entry.state_hash, &[]);
+            batch.put_cf("balances", key, self.value_codec.encode(&entry)?);
+        }
+        Ok(())
+    }
+
+    /// Adds the ledger diff of a block to the batch
+    fn batch_ledger_diff(
+        &self,
+        batch: &mut KvBatch,
+        state_hash: &BlockHash,
+        parent_hash: &BlockHash,
+        diff: &LedgerDiff,
+    ) -> anyhow::Result<()> {
+        let key = state_hash.0.as_bytes();
+        let value = self.value_codec.encode(&(parent_hash, diff))?;
+        batch.put_cf("ledger-diffs", key, value);
+        Ok(())
+    }
+
+    /// Adds a user command to the batch under its transaction key
+    fn batch_tx(
+        &self,
+        batch: &mut KvBatch,
+        height: u32,
+        timestamp: u64,
+        tx: UserCommandWithStatusV1,
+    ) -> anyhow::Result<()> {
+        let key = tx_key(height, timestamp, &tx)?;
+        let value = self.value_codec.encode(&tx)?;
+        batch.put_cf("tx", key, value);
+        Ok(())
+    }
+
+    /// Adds the deletion of the block's entries in the command, internal command,
+    /// SNARK work, balance, and transaction indices to the batch, except the transaction
+    /// keys in `kept_tx_keys`
+    fn batch_delete_block_indices(
+        &self,
+        batch: &mut KvBatch,
+        block: &PrecomputedBlock,
+        kept_tx_keys: &HashSet<Vec<u8>>,
+    ) -> anyhow::Result<()> {
+        let state_hash = BlockHash(block.state_hash.clone());
+        let height = block.blockchain_length.unwrap_or_default();
+
+        for command in block.commands() {
+            let key = tx_key(height, block.timestamp(), &command)?;
+            if !kept_tx_keys.contains(&key) {
+                batch.delete_cf("tx", key);
+            }
+
+            let command = UserCommandWithStatus(command);
+            let UserCommand::SignedCommand(signed_command) = command.clone().data();
+            let command_hash = SignedCommand(signed_command).hash_signed_command()?;
+            batch.delete_cf("commands", keys::command_key(&command_hash, &state_hash));
+            let signed_command = signed_command::SignedCommand::from_user_command(command);
+            for pk in [signed_command.source_pk(), signed_command.receiver_pk()] {
+                let key = keys::account_key(&pk, height, &state_hash, command_hash.as_bytes());
+                batch.delete_cf("account-commands", key);
+            }
+        }
+        for (n, internal_command) in InternalCommand::from_precomputed_block(block)
+            .iter()
+            .enumerate()
+        {
+            let key = keys::account_key(
+                &internal_command.receiver(),
+                height,
+                &state_hash,
+                &(n as u32).to_be_bytes(),
+            );
+            batch.delete_cf("recipient-internal-commands", key);
+        }
+        for (n, summary) in SnarkWorkSummary::from_precomputed_block(block)
+            .iter()
+            .enumerate()
+        {
+            let key = keys::account_key(
+                &summary.prover,
+                height,
+                &state_hash,
+                &(n as u32).to_be_bytes(),
+            );
+            batch.delete_cf("prover-snarks", key);
+        }
+        for (pk, entry) in BalanceHistoryEntry::from_precomputed_block(block) {
+            let key = keys::account_key(&pk, entry.blockchain_length, &entry.state_hash, &[]);
+            batch.delete_cf("balances", key);
+        }
+        batch.delete_cf("internal-commands", state_hash.0.as_bytes());
+        batch.delete_cf("snarks", state_hash.0.as_bytes());
+        Ok(())
+    }
+}
+
+/// Key of the user command in the transaction index
+fn tx_key(height: u32, timestamp: u64, tx: &UserCommandWithStatusV1) -> anyhow::Result<Vec<u8>> {
+    match tx.clone().inner().data.inner().inner() {
+        UserCommand::SignedCommand(cmd) => {
+            let json_sig = SignatureJson::from(cmd.inner().inner().signature);
+            let sig = serde_json::to_string(&json_sig)?;
+            Ok(TransactionKey::new(height, timestamp, sig).bytes())
+        }
+    }
+}
+
+impl CommandStore for IndexerStore {
+    fn add_commands(&self, block: &PrecomputedBlock) -> anyhow::Result<()> {
+        let mut batch = KvBatch::default();
+        self.batch_commands(&mut batch, block)?;
+        self.database.write(batch)?;
+        Ok(())
+    }
+
+    /// A command included in several blocks, e.g. on different forks, is returned with
+    /// its canonical block, if any, otherwise with the longest one
+    fn get_command_by_hash(
+        &self,
+        command_hash: &str,
+    ) -> anyhow::Result<Option<CommandWithStateHash>> {
+        self.database.try_catch_up_with_primary().ok();
+        let prefix = command_hash.as_bytes();
+        let mut command: Option<CommandWithStateHash> = None;
+        for entry in self.database.prefix_iterator_cf("commands", prefix) {
+            let (key, bytes) = entry?;
+            let included: CommandWithStateHash = codec::decode(&bytes)?;
+            // a longer hash sharing the prefix
+            if &key[prefix.len()..] != included.state_hash.0.as_bytes() {
+                continue;
+            }
+            if self.get_canonicity(&included.state_hash)? == Some(Canonicity::Canonical) {
+                return Ok(Some(included));
+            }
+            let longer = match &command {
+                Some(command) => command.blockchain_length < included.blockchain_length,
+                None => true,
+            };
+            if longer {
+                command = Some(included);
+            }
+        }
+        Ok(command)
+    }
+
+    fn get_command_hashes_in_block(&self, state_hash: &BlockHash) -> anyhow::Result<Vec<String>> {
+        let mut command_hashes = vec![];
+        self.database.try_catch_up_with_primary().ok();
+        let key = state_hash.0.as_bytes();
+        if let Some(bytes) = self.database.get_cf("block-commands", &key)? {
+            command_hashes = codec::decode(&bytes)?;
+        }
+        Ok(command_hashes)
+    }
+
+    fn get_commands_in_block(
+        &self,
+        state_hash: &BlockHash,
+    ) -> anyhow::Result<Vec<CommandWithStateHash>> {
+        let mut commands = vec![];
+        for command_hash in self.get_command_hashes_in_block(state_hash)? {
+            let key = keys::command_key(&command_hash, state_hash);
+            match self.database.get_cf("commands", &key)? {
+                Some(bytes) => commands.push(codec::decode(&bytes)?),
+                None => {
+                    return Err(anyhow::Error::msg(format!(
+                        "Missing command {command_hash} of block {}",
+                        state_hash.0
+                    )))
+                }
+            }
+        }
+        Ok(commands)
+    }
+
+    fn get_commands_for_public_key(
+        &self,
+        pk: &PublicKey,
+    ) -> anyhow::Result<Vec<CommandWithStateHash>> {
+        self.get_commands_for_public_key_page(pk, 0, usize::MAX)
+    }
+
+    /// Only the commands of the page are decoded
+    fn get_commands_for_public_key_page(
+        &self,
+        pk: &PublicKey,
+        offset: usize,
+        limit: usize,
+    ) -> anyhow::Result<Vec<CommandWithStateHash>> {
+        let mut commands = vec![];
+        self.database.try_catch_up_with_primary().ok();
+        let prefix = keys::account_prefix(pk);
+        for entry in self
+            .database
+            .prefix_iterator_cf("account-commands", &prefix)
+            .skip(offset)
+            .take(limit)
+        {
+            let (_, value) = entry?;
+            commands.push(codec::decode(&value)?);
+        }
+        Ok(commands)
+    }
+}
+
+impl InternalCommandStore for IndexerStore {
+    fn add_internal_commands(&self, block: &PrecomputedBlock) -> anyhow::Result<()> {
+        let mut batch = KvBatch::default();
+        self.batch_internal_commands(&mut batch, block)?;
+        self.database.write(batch)?;
+        Ok(())
+    }
+
+    fn get_internal_commands(
+        &self,
+        state_hash: &BlockHash,
+    ) -> anyhow::Result<Vec<InternalCommandWithStateHash>> {
+        let mut internal_commands = vec![];
+        self.database.try_catch_up_with_primary().ok();
+        let key = state_hash.0.as_bytes();
+        if let Some(bytes) = self.database.get_cf("internal-commands", &key)? {
+            internal_commands = codec::decode(&bytes)?;
+        }
+        Ok(internal_commands)
+    }
+
+    fn get_internal_commands_for_public_key(
+        &self,
+        pk: &PublicKey,
+    ) -> anyhow::Result<Vec<InternalCommandWithStateHash>> {
+        let mut internal_commands = vec![];
+        self.database.try_catch_up_with_primary().ok();
+        let prefix = keys::account_prefix(pk);
+        for entry in self
+            .database
+            .prefix_iterator_cf("recipient-internal-commands", &prefix)
+        {
+            let (_, value) = entry?;
+            internal_commands.push(codec::decode(&value)?);
+        }
+        Ok(internal_commands)
+    }
+}
+
+impl SnarkStore for IndexerStore {
+    fn add_snark_work(&self, block: &PrecomputedBlock) -> anyhow::Result<()> {
+        let mut batch = KvBatch::default();
+        self.batch_snark_work(&mut batch, block)?;
+        self.database.write(batch)?;
+        Ok(())
+    }
+
+    fn get_snark_work_in_block(
+        &self,
+        state_hash: &BlockHash,
+    ) -> anyhow::Result<Vec<SnarkWorkSummary>> {
+        let mut snark_work = vec![];
+        self.database.try_catch_up_with_primary().ok();
+        let key = state_hash.0.as_bytes();
+        if let Some(bytes) = self.database.get_cf("snarks", &key)? {
+            snark_work = codec::decode(&bytes)?;
+        }
+        Ok(snark_work)
+    }
+
+    fn get_snark_work_by_prover(
+        &self,
+        prover: &PublicKey,
+    ) -> anyhow::Result<Vec<SnarkWorkSummaryWithStateHash>> {
+        let mut snark_work = vec![];
+        self.database.try_catch_up_with_primary().ok();
+        let prefix = keys::account_prefix(prover);
+        for entry in self.database.prefix_iterator_cf("prover-snarks", &prefix) {
+            let (_, value) = entry?;
+            snark_work.push(codec::decode(&value)?);
+        }
+        Ok(snark_work)
+    }
+}
+
+impl BalanceStore for IndexerStore {
+    fn add_balances(&self, block: &PrecomputedBlock) -> anyhow::Result<()> {
+        let mut batch = KvBatch::default();
+        self.batch_balances(&mut batch, block)?;
+        self.database.write(batch)?;
+        Ok(())
+    }
+
+    fn get_balance_history(&self, pk: &PublicKey) -> anyhow::Result<Vec<BalanceHistoryEntry>> {
+        let mut history = vec![];
+        self.database.try_catch_up_with_primary().ok();
+        let prefix = keys::account_prefix(pk);
+        for entry in self.database.prefix_iterator_cf("balances", &prefix) {
+            let (_, value) = entry?;
+            history.push(codec::decode(&value)?);
+        }
+        Ok(history)
+    }
+
+    fn get_balance_at_height(
+        &self,
+        pk: &PublicKey,
+        blockchain_length: u32,
+    ) -> anyhow::Result<Option<BalanceHistoryEntry>> {
+        let mut balance = None;
+        self.database.try_catch_up_with_primary().ok();
+        for entry in self.iter_account_heights_cf("balances", pk, 0, blockchain_length) {
+            let (_, value) = entry?;
+            let entry: BalanceHistoryEntry = codec::decode(&value)?;
+            if self.get_canonicity(&entry.state_hash)? == Some(Canonicity::Canonical) {
+                balance = Some(entry);
+            }
+        }
+        Ok(balance)
+    }
+}
+
+impl IndexerStore {
+    /// Rebuilds ancestry, block metadata, canonicity, account aggregates, ledger diffs and
+    /// snapshots, and the transaction indices from the stored blocks
+    ///
+    /// The canonical chain is the chain of the best stored block by Mina's fork choice,
+    /// see [Block]'s ordering, its blocks `canonical_threshold` deep and below are canonical.
+    /// Ledgers are rebuilt by applying its canonical blocks to `root_ledger`, the ledger of
+    /// `root_hash`
+    ///
+    /// Returns the number of blocks reindexed, errors if any epoch has been dropped
+    pub fn reindex(
+        &self,
+        root_hash: &BlockHash,
+        root_ledger: Ledger,
+        canonical_threshold: u32,
+    ) -> anyhow::Result<u32> {
+        let dropped_epochs = self.get_dropped_epochs()?;
+        if !dropped_epochs.is_empty() {
+            return Err(anyhow::Error::msg(format!(
+                "Cannot reindex, the block bodies of epochs {dropped_epochs:?} have been dropped"
+            )));
+        }
+
+        // state hash -> (parent hash, blockchain length)
+        let mut blocks: HashMap<BlockHash, (BlockHash, u32)> = HashMap::new();
+        let mut best_tip: Option<Block> = None;
+
+        for entry in self.database.iterator_from_cf("blocks", &[]) {
+            let (key, _) = entry?;
+            let state_hash = BlockHash(String::from_utf8(key.to_vec())?);
+            let block = self.get_stored_block(&state_hash)?;
+            let length = block.blockchain_length.unwrap_or(0);
+
+            // blocks stored whole or with unpartitioned bodies are split into a
+            // header and a body of their epoch, then transaction indices, derived
+            // data, and ledger diffs
+            let mut batch = KvBatch::default();
+            let body_key = keys::block_body_key(block.epoch(), &state_hash);
+            if !self.database.contains_cf("block-bodies", &body_key)? {
+                self.batch_block(&mut batch, &block)?;
+                batch.delete_cf("block-bodies", &key);
+            }
+            self.batch_derived_data(&mut batch, &block)?;
+            self.database.write(batch)?;
+
+            // forks are chosen between like the witness tree does, with lengths for heights
+            let tip = Block::from_precomputed(&block, length);
+            if best_tip.as_ref().map_or(true, |best_tip| tip > *best_tip) {
+                best_tip = Some(tip);
+            }
+            blocks.insert(state_hash, (BlockHash::previous_state_hash(&block), length));
+        }
+        info!("Rebuilt the transaction index of {} blocks", blocks.len());
+
+        // walk back from the best tip
+        let mut chain = vec![];
+        if let Some(best_tip) = best_tip {
+            let mut curr_hash = best_tip.state_hash;
+            while let Some((parent_hash, _)) = blocks.get(&curr_hash) {
+                chain.push(curr_hash.clone());
+                if parent_hash == &curr_hash || chain.len() > blocks.len() {
+                    break;
+                }
+                curr_hash = parent_hash.clone();
+            }
+        }
+
+        // canonicity, dropping entries keyed by the raw state hash or length
+        let mut batch = KvBatch::default();
+        for entry in self.database.iterator_from_cf("canonicity", &[]) {
+            let (key, _) = entry?;
+            if !key.starts_with(keys::BLOCK_CANONICITY_PREFIX)
+                && !key.starts_with(keys::CANONICAL_HEIGHT_PREFIX)
+            {
+                batch.delete_cf("canonicity", key);
+            }
+        }
+        self.database.write(batch)?;
+
+        let threshold = canonical_threshold as usize;
+        let canonical_tip_length = chain
+            .get(threshold)
+            .and_then(|state_hash| blocks.get(state_hash))
+            .map(|(_, length)| *length);
+        let canonical_hashes: HashSet<&BlockHash> = chain.iter().skip(threshold).collect();
+
+        for (state_hash, (_, length)) in blocks.iter() {
+            let canonicity = if canonical_hashes.contains(state_hash) {
+                Canonicity::Canonical
+            } else if canonical_tip_length.map_or(false, |tip_length| *length <= tip_length) {
+                Canonicity::Orphaned
+            } else {
+                Canonicity::Pending
+            };
+            self.set_canonicity(state_hash, canonicity)?;
+        }
+        info!(
+            "Rebuilt canonicity, {} canonical blocks",
+            canonical_hashes.len()
+        );
+
+        // account aggregates, from scratch since they're only ever incremented
+        let mut batch = KvBatch::default();
+        for entry in self.database.iterator_from_cf("account-aggregates", &[]) {
+            let (key, _) = entry?;
+            batch.delete_cf("account-aggregates", key);
+        }
+        for state_hash in &canonical_hashes {
+            if let Some(block) = self.get_block(state_hash)? {
+                self.batch_account_aggregates(&mut batch, &block);
+            }
+        }
+        self.database.write(batch)?;
+
+        // ledgers
+        let mut canonical_chain: Vec<BlockHash> = chain.iter().skip(threshold).cloned().collect();
+        canonical_chain.reverse();
+
+        let ledger_chain = match canonical_chain.iter().position(|hash| hash == root_hash) {
+            Some(root_idx) => &canonical_chain[(root_idx + 1)..],
+            None => match canonical_chain.first().and_then(|hash| blocks.get(hash)) {
+                Some((parent_hash, _)) if parent_hash == root_hash => &canonical_chain[..],
+                None => &canonical_chain[..],
+                Some(_) => {
+                    warn!(
+                        "Canonical chain does not connect to root {}, skipping ledgers",
+                        root_hash.0
+                    );
+                    return Ok(blocks.len() as u32);
+                }
+            },
+        };
+
+        let mut ledger = root_ledger;
+        self.add_ledger(root_hash, ledger.clone())?;
+
+        for (n, state_hash) in ledger_chain.iter().enumerate() {
+            if let Some((_, diff)) = self.get_ledger_diff(state_hash)? {
+                ledger.apply_diff(&diff)?;
+            }
+
+            // snapshot at the usual interval, and the canonical tip
+            if (n + 1) % LEDGER_SNAPSHOT_INTERVAL_DEFAULT as usize == 0
+                || n + 1 == ledger_chain.len()
+            {
+                self.add_ledger(state_hash, ledger.clone())?;
+            }
+        }
+        info!("Rebuilt ledgers of {} canonical blocks", ledger_chain.len());
+
+        Ok(blocks.len() as u32)
+    }
+
+    /// Compacts the full key range of the given column family, or of every
+    /// column family if none is given, to reclaim space after large deletes
+    pub fn compact(&self, column_family: Option<&str>) -> anyhow::Result<()> {
+        let column_families = match column_family {
+            Some(name) => vec![name],
+            None => [DEFAULT_NAMESPACE]
+                .into_iter()
+                .chain(COLUMN_FAMILIES)
+                .collect(),
+        };
+
+        for name in column_families {
+            info!("Compacting column family {name}");
+            self.database.compact_cf(name)?;
+        }
+        Ok(())
+    }
+
+    /// Syncs the write-ahead log and persists the memtables of every column
+    /// family, e.g. before the server exits
+    pub fn flush(&self) -> anyhow::Result<()> {
+        self.database.flush_wal()?;
+        for name in [DEFAULT_NAMESPACE].into_iter().chain(COLUMN_FAMILIES) {
+            self.database.flush_cf(name)?;
+        }
+        info!("Flushed {}", self.db_path.display());
+        Ok(())
+    }
+
+    /// Creates a new backup of the database in `backup_dir`, keeping only the
+    /// `num_backups_to_keep` most recent backups
+    pub fn backup(&self, backup_dir: &Path, num_backups_to_keep: usize) -> anyhow::Result<()> {
+        self.database.backup(backup_dir, num_backups_to_keep)?;
+        info!(
+            "Backed up {} to {}",
+            self.db_path.display(),
+            backup_dir.display()
+        );
+        Ok(())
+    }
+
+    /// Drops the bodies of the blocks of `epoch` with a single range delete, e.g.
+    /// after archiving a checkpoint of the database
+    ///
+    /// Block headers and derived data are kept, the blocks themselves can no
+    /// longer be read
+    pub fn drop_epoch(&self, epoch: u32) -> anyhow::Result<()> {
+        let from = keys::epoch_prefix(epoch);
+        let to = keys::epoch_prefix(epoch.saturating_add(1));
+
+        let mut dropped_epochs = self.get_dropped_epochs()?;
+        if !dropped_epochs.contains(&epoch) {
+            dropped_epochs.push(epoch);
+            dropped_epochs.sort();
+        }
+
+        let mut batch = KvBatch::default();
+        batch.delete_range_cf("block-bodies", &from, &to);
+        batch.put(
+            DROPPED_EPOCHS_KEY,
+            self.value_codec.encode(&dropped_epochs)?,
+        );
+        self.database.write(batch)?;
+
+        // SST files of the epoch are deleted outright
+        self.database.compact_range_cf("block-bodies", &from, &to)?;
+        info!("Dropped the block bodies of epoch {epoch}");
+        Ok(())
+    }
+
+    /// Blocks produced per block creator in the range, most canonical blocks first,
+    /// from the blocks-by-creator index
+    pub fn get_block_production(&self, range: &BlockRange) -> anyhow::Result<Vec<BlockProduction>> {
+        let mut production: HashMap<String, BlockProduction> = HashMap::new();
+        self.database.try_catch_up_with_primary().ok();
+        for entry in self.database.iterator_from_cf("creators", &[]) {
+            let (key, _) = entry?;
+            if key.len() <= keys::ADDRESS_LEN {
+                continue;
+            }
+            let (address, state_hash) = key.split_at(keys::ADDRESS_LEN);
+            let state_hash = BlockHash(std::str::from_utf8(state_hash)?.to_string());
+            let header = match self.get_block_header(&state_hash)? {
+                Some(header) => header,
+                None => continue,
+            };
+
+            // the index also holds the blocks paying their coinbase to the account
+            let creator = header.block_creator().to_address();
+            if creator.as_bytes() != address || !range.contains(&header) {
+                continue;
+            }
+            let creator_production = production
+                .entry(creator.clone())
+                .or_insert_with(|| BlockProduction::new(creator));
+            match self.get_canonicity(&state_hash)? {
+                Some(Canonicity::Canonical) => creator_production.canonical += 1,
+                Some(Canonicity::Orphaned) => creator_production.orphaned += 1,
+                Some(Canonicity::Pending) | None => creator_production.pending += 1,
+            }
+        }
+
+        let mut production: Vec<BlockProduction> = production.into_values().collect();
+        production.sort_by(|a, b| {
+            b.canonical
+                .cmp(&a.canonical)
+                .then_with(|| b.total().cmp(&a.total()))
+                .then_with(|| a.creator.cmp(&b.creator))
+        });
+        Ok(production)
+    }
+
+    /// Canonical blocks and skipped slots per epoch of the canonical chain, of all epochs if
+    /// `epoch` is `None`, listing the skipped slots if `list_skipped`
+    ///
+    /// Only the slots between consecutive canonical blocks are counted, so slots before the
+    /// lowest canonical block or across gaps in the canonical chain aren't counted as skipped.
+    /// The canonical chain of a single epoch is read from the canonical block before it on
+    pub fn get_epoch_slots(
+        &self,
+        epoch: Option<u32>,
+        list_skipped: bool,
+    ) -> anyhow::Result<Vec<EpochSlots>> {
+        let slots_per_epoch = self.network()?.unwrap_or_default().slots_per_epoch();
+        let in_range = |slot: u32| epoch.map_or(true, |epoch| slot / slots_per_epoch == epoch);
+        let mut epochs: BTreeMap<u32, EpochSlots> = BTreeMap::new();
+        let mut previous: Option<(u32, u32)> = None;
+        self.database.try_catch_up_with_primary().ok();
+
+        // canonical blocks are in slot order, the lowest length whose next canonical block
+        // is in or after the epoch is found by bisection
+        let start_length = match epoch {
+            Some(epoch) => {
+                let epoch_start = epoch.saturating_mul(slots_per_epoch);
+                let (mut low, mut high) = (0, u32::MAX);
+                while low < high {
+                    let mid = low + (high - low) / 2;
+                    match self.next_canonical_slot(mid)? {
+                        Some(slot) if slot < epoch_start => low = mid + 1,
+                        _ => high = mid,
+                    }
+                }
+                low.saturating_sub(1)
+            }
+            None => 0,
+        };
+        for entry in self
+            .database
+            .iterator_from_cf("canonicity", &keys::canonical_height_key(start_length))
+        {
+            let (key, value) = entry?;
+            if !key.starts_with(keys::CANONICAL_HEIGHT_PREFIX) {
+                break;
+            }
+            let blockchain_length =
+                u32::from_be_bytes(key[keys::CANONICAL_HEIGHT_PREFIX.len()..].try_into()?);
+            let state_hash: BlockHash = codec::decode(&value)?;
+            let slot = match self.get_block_ancestry(&state_hash)? {
+                Some(ancestry) => ancestry.global_slot_since_genesis,
+                None => {
+                    previous = None;
+                    continue;
+                }
+            };
+
+            // the slots since the parent had no canonical block
+            if let Some((previous_length, previous_slot)) = previous {
+                if previous_length + 1 == blockchain_length {
+                    for skipped_slot in
+                        (previous_slot + 1..slot).filter(|skipped_slot| in_range(*skipped_slot))
+                    {
+                        let epoch_slots = epochs
+                            .entry(skipped_slot / slots_per_epoch)
+                            .or_insert_with(|| EpochSlots::new(skipped_slot / slots_per_epoch));
+                        epoch_slots.skipped += 1;
+                        if list_skipped {
+                            epoch_slots.skipped_slots.push(skipped_slot);
+                        }
+                    }
+                }
+            }
+            if in_range(slot) {
+                epochs
+                    .entry(slot / slots_per_epoch)
+                    .or_insert_with(|| EpochSlots::new(slot / slots_per_epoch))
+                    .canonical += 1;
+            }
+
+            // canonical blocks are in slot order, none of the later ones are in the epoch
+            if epoch.map_or(false, |epoch| slot / slots_per_epoch > epoch) {
+                break;
+            }
+            previous = Some((blockchain_length, slot));
+        }
+        Ok(epochs.into_values().collect())
+    }
+
+    /// Global slot of the lowest canonical block of at least the given length, if any
+    fn next_canonical_slot(&self, blockchain_length: u32) -> anyhow::Result<Option<u32>> {
+        for entry in self
+            .database
+            .iterator_from_cf("canonicity", &keys::canonical_height_key(blockchain_length))
+        {
+            let (key, value) = entry?;
+            if !key.starts_with(keys::CANONICAL_HEIGHT_PREFIX) {
+                break;
+            }
+            let state_hash: BlockHash = codec::decode(&value)?;
+            if let Some(ancestry) = self.get_block_ancestry(&state_hash)? {
+                return Ok(Some(ancestry.global_slot_since_genesis));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Completed SNARK work of `prover` included in canonical blocks, with its fees per epoch
+    pub fn get_canonical_snark_work_by_prover(
+        &self,
+        prover: &PublicKey,
+    ) -> anyhow::Result<ProverSnarkWork> {
+        let mut snark_work = vec![];
+        let mut epoch_fees: BTreeMap<u32, EpochSnarkFees> = BTreeMap::new();
+
+        // a block usually includes several works of the prover
+        let mut block_epochs: HashMap<BlockHash, Option<u32>> = HashMap::new();
+        for work in self.get_snark_work_by_prover(prover)? {
+            let epoch = match block_epochs.get(&work.state_hash) {
+                Some(epoch) => *epoch,
+                None => {
+                    let epoch = match self.get_canonicity(&work.state_hash)? {
+                        Some(Canonicity::Canonical) => self
+                            .get_block_header(&work.state_hash)?
+                            .map(|header| header.epoch()),
+                        _ => None,
+                    };
+                    block_epochs.insert(work.state_hash.clone(), epoch);
+                    epoch
+                }
+            };
+
+            if let Some(epoch) = epoch {
+                let fees = epoch_fees.entry(epoch).or_insert(EpochSnarkFees {
+                    epoch,
+                    num_works: 0,
+                    total_fees: 0,
+                });
+                fees.num_works += 1;
+                fees.total_fees += work.fee.0;
+                snark_work.push(work);
+            }
+        }
+
+        Ok(ProverSnarkWork {
+            prover: prover.clone(),
+            snark_work,
+            epoch_fees: epoch_fees.into_values().collect(),
+        })
+    }
+
+    /// Epochs whose block bodies have been dropped, in increasing order
+    pub fn get_dropped_epochs(&self) -> anyhow::Result<Vec<u32>> {
+        self.database.try_catch_up_with_primary().ok();
+        match self.database.get(DROPPED_EPOCHS_KEY)? {
+            Some(bytes) => codec::decode(&bytes),
+            None => Ok(vec![]),
+        }
+    }
+
+    /// Creates a crash-consistent copy of the live database at `dst_path`, without
+    /// stopping writes, which can be opened as a database directory of its own
+    ///
+    /// The copy is written to a uniquely named scratch directory next to `dst_path` and
+    /// moved into place once complete, a failed checkpoint leaves nothing behind
+    pub fn checkpoint(&self, dst_path: &Path) -> anyhow::Result<()> {
+        if dst_path.exists() {
+            return Err(anyhow::Error::msg(format!(
+                "Checkpoint destination {} already exists",
+                dst_path.display()
+            )));
+        }
+        let file_name = dst_path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .ok_or_else(|| {
+                anyhow::Error::msg(format!(
+                    "Checkpoint destination {} has no file name",
+                    dst_path.display()
+                ))
+            })?;
+        let scratch_path = dst_path.with_file_name(format!(".{file_name}.{}.tmp", Uuid::new_v4()));
+        if let Err(e) = self
+            .database
+            .checkpoint(&scratch_path)
+            .and_then(|()| Ok(std::fs::rename(&scratch_path, dst_path)?))
+        {
+            if scratch_path.exists() {
+                std::fs::remove_dir_all(&scratch_path)?;
+            }
+            return Err(e);
+        }
+        info!(
+            "Checkpointed {} to {}",
+            self.db_path.display(),
+            dst_path.display()
+        );
+        Ok(())
+    }
+
+    /// Exports the stored blocks to the new directory `dst_dir`, as one SST file per column
+    /// family of [BLOCK_COLUMN_FAMILIES], returns the number of blocks
+    ///
+    /// Derived data isn't exported, the importing database is reindexed instead
+    pub fn export_blocks(&self, dst_dir: &Path) -> anyhow::Result<u64> {
+        if dst_dir.exists() {
+            return Err(anyhow::Error::msg(format!(
+                "Export destination {} already exists",
+                dst_dir.display()
+            )));
+        }
+
+        let dropped_epochs = self.get_dropped_epochs()?;
+        if !dropped_epochs.is_empty() {
+            return Err(anyhow::Error::msg(format!(
+                "Cannot export blocks, the block bodies of epochs {dropped_epochs:?} have been dropped"
+            )));
+        }
+
+        std::fs::create_dir_all(dst_dir)?;
+        let mut num_blocks = 0;
+        for cf in BLOCK_COLUMN_FAMILIES {
+            let num_entries = self
+                .database
+                .export_cf(cf, &exported_blocks_file(dst_dir, cf))?;
+            if cf == "blocks" {
+                num_blocks = num_entries;
+            }
+        }
+        info!(
+            "Exported {num_blocks} blocks from {} to {}",
+            self.db_path.display(),
+            dst_dir.display()
+        );
+        Ok(num_blocks)
+    }
+
+    /// Bulk loads the blocks exported by [IndexerStore::export_blocks] to `src_dir`,
+    /// replacing stored blocks with the same state hash
+    ///
+    /// The database must be reindexed afterwards to derive the data of the imported blocks
+    pub fn import_blocks(&self, src_dir: &Path) -> anyhow::Result<()> {
+        let mut num_files = 0;
+        for cf in BLOCK_COLUMN_FAMILIES {
+            let file = exported_blocks_file(src_dir, cf);
+            if file.exists() {
+                self.database.ingest_cf(cf, &[file])?;
+                num_files += 1;
+            }
+        }
+
+        if num_files == 0 {
+            return Err(anyhow::Error::msg(format!(
+                "No exported blocks found in {}",
+                src_dir.display()
+            )));
+        }
+        info!(
+            "Imported the blocks in {} into {}",
+            src_dir.display(),
+            self.db_path.display()
+        );
+        Ok(())
+    }
+
+    /// Restores the latest backup in `backup_dir` into the database directory `path`,
+    /// overwriting any existing database there
+    ///
+    /// The database must not be open
+    pub fn restore_from_latest_backup(backup_dir: &Path, path: &Path) -> anyhow::Result<()> {
+        RocksDbStore::restore_from_latest_backup(backup_dir, path)?;
+        info!("Restored {} from {}", path.display(), backup_dir.display());
+        Ok(())
+    }
+
+    /// Backs up the database every `interval`, failed backups are logged and retried at the next interval
+    pub async fn run_backups(
+        self: Arc<Self>,
+        backup_dir: PathBuf,
+        interval: Duration,
+        num_backups_to_keep: usize,
+    ) {
+        let mut interval = tokio::time::interval(interval);
+        // the first tick completes immediately
+        interval.tick().await;
+
+        loop {
+            interval.tick().await;
+
+            let store = self.clone();
+            let backup_dir = backup_dir.clone();
+            match tokio::task::spawn_blocking(move || {
+                store.backup(&backup_dir, num_backups_to_keep)
+            })
+            .await
+            {
+                Ok(Ok(())) => (),
+                Ok(Err(e)) => error!("Backup failed: {e}"),
+                Err(e) => error!("Backup task failed: {e}"),
+            }
+        }
+    }
+
+    /// Removes data left behind by blocks older than `max_age` which didn't make it
+    /// into the canonical chain: pending canonicity entries and ledger snapshots of
+    /// orphaned blocks, along with leftover read-only instance directories
+    ///
+    /// Returns the number of entries and directories removed
+    pub fn remove_stale_data(&self, max_age: Duration) -> anyhow::Result<u32> {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?;
+        let cutoff_millis = now.saturating_sub(max_age).as_millis() as u64;
+        let is_stale = |state_hash: &BlockHash| -> anyhow::Result<bool> {
+            Ok(self
+                .get_block_header(state_hash)?
+                .map_or(true, |header| header.timestamp() < cutoff_millis))
+        };
+
+        let mut batch = KvBatch::default();
+
+        // pending canonicity entries
+        let mut num_pending = 0;
+        for entry in self
+            .database
+            .prefix_iterator_cf("canonicity", keys::BLOCK_CANONICITY_PREFIX)
+        {
+            let (key, value) = entry?;
+            if codec::decode::<Canonicity>(&value)? != Canonicity::Pending {
+                continue;
+            }
+            let state_hash = std::str::from_utf8(&key[keys::BLOCK_CANONICITY_PREFIX.len()..])?;
+            if is_stale(&BlockHash(state_hash.to_string()))? {
+                batch.delete_cf("canonicity", key);
+                num_pending += 1;
+            }
+        }
+
+        // ledger snapshots on orphaned forks
+        let mut num_snapshots = 0;
+        for entry in self.database.iterator_from_cf("ledgers", &[]) {
+            let (key, _) = entry?;
+            let state_hash = BlockHash(String::from_utf8(key.to_vec())?);
+            if self.get_canonicity(&state_hash)? == Some(Canonicity::Orphaned)
+                && is_stale(&state_hash)?
+            {
+                batch.delete_cf("ledgers", key);
+                num_snapshots += 1;
+            }
+        }
+        self.database.write(batch)?;
+
+        let num_secondaries = self.remove_stale_secondaries(max_age)?;
+        debug!(
+            "Removed {num_pending} pending canonicity entries, {num_snapshots} orphaned ledger snapshots, and {num_secondaries} read-only instance directories"
+        );
+        Ok(num_pending + num_snapshots + num_secondaries)
+    }
+
+    /// Removes read-only instance directories in the database directory which
+    /// haven't been modified for `max_age`, e.g. per-connection instances left
+    /// behind by a crash of an earlier version
+    fn remove_stale_secondaries(&self, max_age: Duration) -> anyhow::Result<u32> {
+        let mut num_removed = 0;
+        let entries = match std::fs::read_dir(&self.db_path) {
+            Ok(entries) => entries,
+            // e.g. an in-memory store
+            Err(_) => return Ok(0),
+        };
+
+        for entry in entries {
+            let entry = entry?;
+            let metadata = entry.metadata()?;
+            let is_secondary = metadata.is_dir()
+                && entry
+                    .file_name()
+                    .to_str()
+                    .map_or(false, |name| Uuid::parse_str(name).is_ok());
+            if is_secondary && metadata.modified()?.elapsed().unwrap_or_default() > max_age {
+                std::fs::remove_dir_all(entry.path())?;
+                num_removed += 1;
+            }
+        }
+        Ok(num_removed)
+    }
+
+    /// Removes stale data every `interval`, failures are logged and retried at the next interval
+    pub async fn run_cleanup(self: Arc<Self>, interval: Duration, max_age: Duration) {
+        let mut interval = tokio::time::interval(interval);
+        loop {
+            interval.tick().await;
+
+            let store = self.clone();
+            match tokio::task::spawn_blocking(move || store.remove_stale_data(max_age)).await {
+                Ok(Ok(num_removed)) => info!("Removed {num_removed} stale entries"),
+                Ok(Err(e)) => error!("Stale data cleanup failed: {e}"),
+                Err(e) => error!("Stale data cleanup task failed: {e}"),
+            }
+        }
+    }
+
+    /// Checks that every stored block deserializes, that block index entries and the
+    /// commands, SNARK work, balances, diffs, and ledgers of blocks point at stored
+    /// blocks, and that the canonical chain is contiguous
+    ///
+    /// Returns a description of each problem found
+    pub fn verify(&self) -> anyhow::Result<Vec<String>> {
+        let mut problems = vec![];
+
+        // blocks, only the headers of blocks of dropped epochs
+        let dropped_epochs = self.get_dropped_epochs()?;
+        let mut num_blocks = 0;
+        for entry in self.database.iterator_from_cf("blocks", &[]) {
+            let (key, _) = entry?;
+            let key = String::from_utf8_lossy(&key).to_string();
+            let state_hash = BlockHash(key.clone());
+            let header = match self.get_block_header(&state_hash) {
+                Ok(Some(header)) if dropped_epochs.contains(&header.epoch()) => Ok(Some(header)),
+                Ok(_) => self
+                    .get_block(&state_hash)
+                    .map(|block| block.as_ref().map(PrecomputedBlock::header)),
+                Err(e) => Err(e),
+            };
+            match header {
+                Ok(Some(header)) if header.state_hash == key => num_blocks += 1,
+                Ok(Some(header)) => problems.push(format!(
+                    "Block stored at {key} has state hash {}",
+                    header.state_hash
+                )),
+                Ok(None) => (),
+                Err(e) => problems.push(format!("Block {key} does not deserialize: {e}")),
+            }
+        }
+        info!("Verified {num_blocks} blocks");
+
+        // the genesis block has no precomputed block, only its ancestry
+        let genesis_hash = self.get_genesis_block()?.map(|genesis| genesis.state_hash);
+
+        // blocks-by-height index
+        for entry in self.database.iterator_from_cf("heights", &[]) {
+            let (key, value) = entry?;
+            let blockchain_length = u32::from_be_bytes(key[..].try_into()?);
+            let state_hashes: Vec<BlockHash> = match codec::decode(&value) {
+                Ok(state_hashes) => state_hashes,
+                Err(e) => {
+                    problems.push(format!(
+                        "Height index entry {blockchain_length} does not deserialize: {e}"
+                    ));
+                    continue;
+                }
+            };
+            for state_hash in state_hashes {
+                if Some(&state_hash) == genesis_hash.as_ref() {
+                    continue;
+                }
+                match self.get_block_header(&state_hash).ok().flatten() {
+                    None => problems.push(format!(
+                        "Height index entry {blockchain_length} points at missing block {state_hash:?}"
+                    )),
+                    Some(header) if header.blockchain_length != Some(blockchain_length) => {
+                        problems.push(format!(
+                            "Height index entry {blockchain_length} points at block {state_hash:?} of length {:?}",
+                            header.blockchain_length
+                        ))
+                    }
+                    Some(_) => (),
+                }
+            }
+        }
+
+        // blocks-by-creator index
+        for entry in self.database.iterator_from_cf("creators", &[]) {
+            let (key, _) = entry?;
+            let state_hash = BlockHash(
+                String::from_utf8_lossy(&key[key.len().saturating_sub(keys::STATE_HASH_LEN)..])
+                    .to_string(),
+            );
+            if !self.exists(&state_hash).unwrap_or(false) {
+                problems.push(format!(
+                    "Creator index entry {} points at missing block {state_hash:?}",
+                    String::from_utf8_lossy(&key)
+                ));
+            }
+        }
+
+        // entries of a block, keyed by its state hash or including it
+        for cf in [
+            "commands",
+            "account-commands",
+            "internal-commands",
+            "recipient-internal-commands",
+            "snarks",
+            "prover-snarks",
+            "balances",
+            "diffs",
+            "ledgers",
+        ] {
+            for entry in self.database.iterator_from_cf(cf, &[]) {
+                let (key, _) = entry?;
+                let start = match cf {
+                    "commands" => key.len().saturating_sub(keys::STATE_HASH_LEN),
+                    "internal-commands" | "snarks" | "diffs" | "ledgers" => 0,
+                    _ => keys::ADDRESS_LEN + 4,
+                };
+                let end = (start + keys::STATE_HASH_LEN).min(key.len());
+                let state_hash =
+                    BlockHash(String::from_utf8_lossy(&key[start.min(end)..end]).to_string());
+                if Some(&state_hash) != genesis_hash.as_ref()
+                    && !self.exists(&state_hash).unwrap_or(false)
+                {
+                    problems.push(format!(
+                        "Entry {} of {cf} points at missing block {state_hash:?}",
+                        String::from_utf8_lossy(&key)
+                    ));
+                }
+            }
+        }
+
+        // ancestry
+        for entry in self.database.iterator_from_cf("ancestry", &[]) {
+            let (key, value) = entry?;
+            let state_hash = BlockHash(String::from_utf8_lossy(&key).to_string());
+            let ancestry: BlockWithoutHeight = match codec::decode(&value) {
+                Ok(ancestry) => ancestry,
+                Err(e) => {
+                    problems.push(format!(
+                        "Ancestry entry {state_hash:?} does not deserialize: {e}"
+                    ));
+                    continue;
+                }
+            };
+            match self.get_block_header(&state_hash).ok().flatten() {
+                None if Some(&state_hash) == genesis_hash.as_ref() => (),
+                None => problems.push(format!(
+                    "Ancestry entry {state_hash:?} points at missing block"
+                )),
+                Some(header) if BlockWithoutHeight::from_header(&header) != ancestry => problems
+                    .push(format!(
+                        "Ancestry entry {state_hash:?} does not match its block: {ancestry:?}"
+                    )),
+                Some(_) => (),
+            }
+        }
+
+        // canonical chain
+        let mut previous: Option<(u32, BlockHash)> = None;
+        for entry in self
+            .database
+            .prefix_iterator_cf("canonicity", keys::CANONICAL_HEIGHT_PREFIX)
+        {
+            let (key, value) = entry?;
+            let blockchain_length =
+                u32::from_be_bytes(key[keys::CANONICAL_HEIGHT_PREFIX.len()..].try_into()?);
+            let state_hash: BlockHash = match codec::decode(&value) {
+                Ok(state_hash) => state_hash,
+                Err(e) => {
+                    problems.push(format!(
+                        "Canonical chain entry {blockchain_length} does not deserialize: {e}"
+                    ));
+                    previous = None;
+                    continue;
+                }
+            };
+            let ancestry = if Some(&state_hash) == genesis_hash.as_ref() {
+                self.get_block_ancestry(&state_hash).ok().flatten()
+            } else {
+                self.get_block_header(&state_hash)
+                    .ok()
+                    .flatten()
+                    .map(|header| BlockWithoutHeight::from_header(&header))
+            };
+            if ancestry.is_none() {
+                problems.push(format!(
+                    "Canonical block {state_hash:?} of length {blockchain_length} is missing"
+                ));
+            }
+
+            if let Some((previous_length, previous_hash)) = previous {
+                if previous_length + 1 != blockchain_length {
+                    problems.push(format!(
+                        "Canonical chain has a gap between lengths {previous_length} and {blockchain_length}"
+                    ));
+                } else if let Some(ancestry) = &ancestry {
+                    let parent_hash = ancestry.parent_hash.clone();
+                    if parent_hash != previous_hash {
+                        problems.push(format!(
+                            "Canonical block {state_hash:?} of length {blockchain_length} has parent {parent_hash:?}, expected {previous_hash:?}"
+                        ));
+                    }
+                }
+            }
+            previous = Some((blockchain_length, state_hash));
+        }
+
+        Ok(problems)
+    }
+
+    /// Re-applies the ledger diff of every canonical block, starting from the lowest
+    /// canonical ledger snapshot, and checks the replayed ledger against each later
+    /// snapshot and the balances recorded for each block
+    ///
+    /// The coinbase receiver's balance isn't checked since its recorded balance
+    /// precedes the block's fees and coinbase. Replay resumes from the stored
+    /// snapshot after a mismatch. Returns a description of each problem found
+    pub fn replay_ledgers(&self) -> anyhow::Result<Vec<String>> {
+        let mut problems = vec![];
+        let mut ledger: Option<Ledger> = None;
+        let mut previous_hash: Option<BlockHash> = None;
+        let mut num_replayed = 0;
+        for entry in self
+            .database
+            .prefix_iterator_cf("canonicity", keys::CANONICAL_HEIGHT_PREFIX)
+        {
+            let (key, value) = entry?;
+            let blockchain_length =
+                u32::from_be_bytes(key[keys::CANONICAL_HEIGHT_PREFIX.len()..].try_into()?);
+            let state_hash: BlockHash = codec::decode(&value)?;
+            let snapshot = self.get_ledger_snapshot(&state_hash)?;
+
+            let mut replayed = match ledger.take() {
+                // look for the first snapshot to replay from
+                None => {
+                    ledger = snapshot;
+                    previous_hash = Some(state_hash);
+                    continue;
+                }
+                Some(replayed) => replayed,
+            };
+            match self.get_ledger_diff(&state_hash)? {
+                Some((parent_hash, diff)) if previous_hash.as_ref() == Some(&parent_hash) => {
+                    replayed.apply_diff(&diff)?;
+                    num_replayed += 1;
+                }
+                Some((parent_hash, _)) => {
+                    problems.push(format!(
+                        "Canonical block {state_hash:?} of length {blockchain_length} has ledger diff parent {parent_hash:?}, expected {previous_hash:?}"
+                    ));
+                    ledger = snapshot;
+                    previous_hash = Some(state_hash);
+                    continue;
+                }
+                None => {
+                    problems.push(format!(
+                        "Canonical block {state_hash:?} of length {blockchain_length} has no ledger diff"
+                    ));
+                    ledger = snapshot;
+                    previous_hash = Some(state_hash);
+                    continue;
+                }
+            }
+
+            // recorded balances
+            let coinbase_receiver = self
+                .get_block_header(&state_hash)?
+                .map(|header| header.coinbase_receiver());
+            for (pk, account) in &replayed.accounts {
+                if coinbase_receiver.as_ref() == Some(pk) {
+                    continue;
+                }
+                let key = keys::account_key(pk, blockchain_length, &state_hash, &[]);
+                if let Some(bytes) = self.database.get_cf("balances", &key)? {
+                    let entry: BalanceHistoryEntry = codec::decode(&bytes)?;
+                    if entry.balance != account.balance {
+                        problems.push(format!(
+                            "Balance of {} recorded at canonical block {state_hash:?} of length {blockchain_length} is {}, replayed {}",
+                            pk.to_address(),
+                            entry.balance.0,
+                            account.balance.0
+                        ));
+                    }
+                }
+            }
+
+            // stored snapshot
+            if let Some(snapshot) = snapshot {
+                let mut mismatched: Vec<String> = replayed
+                    .accounts
+                    .iter()
+                    .filter(|(pk, account)| snapshot.accounts.get(*pk) != Some(*account))
+                    .map(|(pk, _)| pk.to_address())
+                    .chain(
+                        snapshot
+                            .accounts
+                            .keys()
+                            .filter(|pk| !replayed.accounts.contains_key(*pk))
+                            .map(PublicKey::to_address),
+                    )
+                    .collect();
+                if !mismatched.is_empty() {
+                    mismatched.sort();
+                    problems.push(format!(
+                        "Ledger snapshot of canonical block {state_hash:?} of length {blockchain_length} differs from the replayed ledger in {} accounts: {}",
+                        mismatched.len(),
+                        mismatched.join(", ")
+                    ));
+                }
+                replayed = snapshot;
+            }
+            ledger = Some(replayed);
+            previous_hash = Some(state_hash);
+        }
+
+        if previous_hash.is_some() && ledger.is_none() && num_replayed == 0 {
+            problems.push("No ledger snapshot on the canonical chain to replay from".into());
+        }
+        info!("Replayed {num_replayed} canonical ledger diffs");
+        Ok(problems)
+    }
+
+    pub fn test_conn(&mut self) -> anyhow::Result<()> {
+        self.database.put(b"test", b"value")?;
+        self.database.delete(b"test")?;
+        Ok(())
+    }
+
+    /// Database statistics, if the backend provides them
+    pub fn stats(&self) -> anyhow::Result<Option<DbStats>> {
+        let mut column_families = vec![];
+        for cf in std::iter::once(&DEFAULT_NAMESPACE).chain(COLUMN_FAMILIES.iter()) {
+            if let Some(cf_stats) = self.cf_stats(cf)? {
+                column_families.push(cf_stats);
+            }
+        }
+        if column_families.is_empty() {
+            return Ok(None);
+        }
+
+        let num_running_compactions = self
+            .database
+            .property_int_value("rocksdb.num-running-compactions")?
+            .unwrap_or_default();
+        Ok(Some(DbStats {
+            memtables_size: column_families.iter().map(|cf| cf.memtables_size).sum(),
+            live_data_size: column_families.iter().map(|cf| cf.live_data_size).sum(),
+            sst_files_size: column_families.iter().map(|cf| cf.sst_files_size).sum(),
+            num_keys: column_families.iter().map(|cf| cf.num_keys).sum(),
+            pending_compaction_bytes: column_families
+                .iter()
+                .map(|cf| cf.pending_compaction_bytes)
+                .sum(),
+            num_running_compactions,
+            column_families,
+        }))
+    }
+
+    /// Counters and latencies of the reads and writes of each column family
+    /// since the store was opened
+    pub fn operation_stats(&self) -> StoreOperationStats {
+        self.metrics.stats()
+    }
+
+    /// Statistics of the column family `cf`, if the backend provides them
+    fn cf_stats(&self, cf: &str) -> anyhow::Result<Option<ColumnFamilyStats>> {
+        let property = |name| self.database.property_int_value_cf(cf, name);
+        let num_keys = match property("rocksdb.estimate-num-keys")? {
+            Some(num_keys) => num_keys,
+            None => return Ok(None),
+        };
+
+        Ok(Some(ColumnFamilyStats {
+            name: cf.to_string(),
+            memtables_size: property("rocksdb.cur-size-all-mem-tables")?.unwrap_or_default(),
+            live_data_size: property("rocksdb.estimate-live-data-size")?.unwrap_or_default(),
+            sst_files_size: property("rocksdb.total-sst-files-size")?.unwrap_or_default(),
+            num_keys,
+            pending_compaction_bytes: property("rocksdb.estimate-pending-compaction-bytes")?
+                .unwrap_or_default(),
+        }))
+    }
+}
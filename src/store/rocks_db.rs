@@ -0,0 +1,345 @@
+use super::{
+    keys::{ACCOUNT_KEYED_COLUMN_FAMILIES, ADDRESS_LEN},
+    kv::{merge_counters, KvBatch, KvIterator, KvStore, KvWrite},
+};
+use rocksdb::{
+    backup::{BackupEngine, BackupEngineOptions, RestoreOptions},
+    checkpoint::Checkpoint,
+    merge_operator::MergeOperands,
+    BlockBasedOptions, Cache, ColumnFamily, ColumnFamilyDescriptor, Direction, Env, IteratorMode,
+    ReadOptions, SliceTransform, SstFileWriter, WriteBatch, DB,
+};
+use serde_derive::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Tuning options of the primary RocksDB instance, any option missing from
+/// the configuration file keeps its default
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct RocksDbTuningConfiguration {
+    /// Size of a column family's memtable in bytes
+    pub write_buffer_size: usize,
+    /// Max number of memtables per column family
+    pub max_write_buffer_number: i32,
+    /// Target size of level 1 SST files in bytes
+    pub target_file_size_base: u64,
+    /// Size of the block cache shared by all column families in bytes
+    pub block_cache_size: usize,
+    /// Bits per key of the bloom filters
+    pub bloom_filter_bits_per_key: f64,
+    /// Max number of concurrent flushes and compactions
+    pub max_background_jobs: i32,
+    /// Max number of open files, -1 keeps all files open
+    pub max_open_files: i32,
+}
+
+impl Default for RocksDbTuningConfiguration {
+    fn default() -> Self {
+        Self {
+            write_buffer_size: 64 * 1024 * 1024,
+            max_write_buffer_number: 16,
+            target_file_size_base: 64 * 1024 * 1024,
+            block_cache_size: 32 * 1024 * 1024,
+            bloom_filter_bits_per_key: 10.0,
+            max_background_jobs: 2,
+            max_open_files: -1,
+        }
+    }
+}
+
+impl RocksDbTuningConfiguration {
+    /// Reads the configuration from a JSON file
+    pub fn from_file(path: &Path) -> anyhow::Result<Self> {
+        let contents = std::fs::read_to_string(path)?;
+        serde_json::from_str(&contents).map_err(|e| {
+            anyhow::Error::msg(format!(
+                "Invalid RocksDB configuration {}: {e}",
+                path.display()
+            ))
+        })
+    }
+}
+
+/// Smallest key greater than every key starting with `prefix`, if there is one
+fn prefix_upper_bound(prefix: &[u8]) -> Option<Vec<u8>> {
+    let mut upper_bound = prefix.to_vec();
+    while let Some(last) = upper_bound.pop() {
+        if last < u8::MAX {
+            upper_bound.push(last + 1);
+            return Some(upper_bound);
+        }
+    }
+    None
+}
+
+/// Merges counter increments, see [merge_counters]
+fn counters_merge_operator(
+    _key: &[u8],
+    existing: Option<&[u8]>,
+    operands: &MergeOperands,
+) -> Option<Vec<u8>> {
+    let mut value = existing.map(<[u8]>::to_vec);
+    for increments in operands.iter() {
+        value = Some(merge_counters(value.as_deref(), increments));
+    }
+    value
+}
+
+/// Descriptors of `column_families`, the primary and its secondaries need the same merge
+/// operator and prefix extractors to read the column families
+fn column_family_descriptors(
+    column_families: &[&str],
+    tuning: &RocksDbTuningConfiguration,
+) -> anyhow::Result<Vec<ColumnFamilyDescriptor>> {
+    // bloom filters let key lookups skip SST files which don't contain the key
+    let mut table_opts = BlockBasedOptions::default();
+    table_opts.set_bloom_filter(tuning.bloom_filter_bits_per_key, false);
+    table_opts.set_block_cache(&Cache::new_lru_cache(tuning.block_cache_size)?);
+
+    let mut cf_opts = rocksdb::Options::default();
+    cf_opts.set_write_buffer_size(tuning.write_buffer_size);
+    cf_opts.set_max_write_buffer_number(tuning.max_write_buffer_number);
+    cf_opts.set_target_file_size_base(tuning.target_file_size_base);
+    cf_opts.set_block_based_table_factory(&table_opts);
+    cf_opts.set_merge_operator_associative("counters", counters_merge_operator);
+
+    Ok(column_families
+        .iter()
+        .map(|name| {
+            let mut cf_opts = cf_opts.clone();
+            // account prefix scans only consult the memtables and SST files which may contain the account
+            if ACCOUNT_KEYED_COLUMN_FAMILIES.contains(name) {
+                cf_opts.set_prefix_extractor(SliceTransform::create_fixed_prefix(ADDRESS_LEN));
+                cf_opts.set_memtable_prefix_bloom_ratio(0.1);
+            }
+            ColumnFamilyDescriptor::new(*name, cf_opts)
+        })
+        .collect())
+}
+
+/// RocksDB storage backend, namespaces are column families
+#[derive(Debug)]
+pub struct RocksDbStore {
+    database: DB,
+}
+
+impl RocksDbStore {
+    /// Opens the primary instance at `path`, creating the database and any
+    /// missing column families
+    pub fn open(
+        path: &Path,
+        column_families: &[&str],
+        tuning: &RocksDbTuningConfiguration,
+    ) -> anyhow::Result<Self> {
+        let mut database_opts = rocksdb::Options::default();
+        database_opts.create_missing_column_families(true);
+        database_opts.create_if_missing(true);
+        database_opts.set_max_background_jobs(tuning.max_background_jobs);
+        database_opts.set_max_open_files(tuning.max_open_files);
+        let database = rocksdb::DBWithThreadMode::open_cf_descriptors(
+            &database_opts,
+            path,
+            column_family_descriptors(column_families, tuning)?,
+        )?;
+        Ok(Self { database })
+    }
+
+    /// Opens a secondary instance of the primary at `path`, with its own info logs in `secondary`
+    pub fn open_as_secondary(
+        path: &Path,
+        secondary: &Path,
+        column_families: &[&str],
+    ) -> anyhow::Result<Self> {
+        let database_opts = rocksdb::Options::default();
+        let database = rocksdb::DBWithThreadMode::open_cf_descriptors_as_secondary(
+            &database_opts,
+            path,
+            secondary,
+            column_family_descriptors(column_families, &RocksDbTuningConfiguration::default())?,
+        )?;
+        Ok(Self { database })
+    }
+
+    /// Restores the latest backup in `backup_dir` into the database directory `path`
+    pub fn restore_from_latest_backup(backup_dir: &Path, path: &Path) -> anyhow::Result<()> {
+        let backup_opts = BackupEngineOptions::new(backup_dir)?;
+        let mut backup_engine = BackupEngine::open(&backup_opts, &Env::new()?)?;
+        if backup_engine.get_backup_info().is_empty() {
+            return Err(anyhow::Error::msg(format!(
+                "No backups found in {}",
+                backup_dir.display()
+            )));
+        }
+
+        backup_engine.restore_from_latest_backup(path, path, &RestoreOptions::default())?;
+        Ok(())
+    }
+
+    fn cf_handle(&self, cf: &str) -> &ColumnFamily {
+        self.database.cf_handle(cf).expect("column family exists")
+    }
+}
+
+impl KvStore for RocksDbStore {
+    fn get_cf(&self, cf: &str, key: &[u8]) -> anyhow::Result<Option<Vec<u8>>> {
+        Ok(self
+            .database
+            .get_pinned_cf(self.cf_handle(cf), key)?
+            .map(|bytes| bytes.to_vec()))
+    }
+
+    /// Checks the memtables and bloom filters first, the value is only read,
+    /// without being copied, if the key may exist
+    fn contains_cf(&self, cf: &str, key: &[u8]) -> anyhow::Result<bool> {
+        let cf_handle = self.cf_handle(cf);
+        if !self.database.key_may_exist_cf(cf_handle, key) {
+            return Ok(false);
+        }
+        Ok(self.database.get_pinned_cf(cf_handle, key)?.is_some())
+    }
+
+    fn iterator_from_cf<'a>(&'a self, cf: &str, from: &[u8]) -> KvIterator<'a> {
+        // prefix extractors don't apply to iteration across prefixes
+        let mut read_opts = ReadOptions::default();
+        read_opts.set_total_order_seek(true);
+        Box::new(
+            self.database
+                .iterator_cf_opt(
+                    self.cf_handle(cf),
+                    read_opts,
+                    IteratorMode::From(from, Direction::Forward),
+                )
+                .map(|entry| entry.map_err(anyhow::Error::from)),
+        )
+    }
+
+    /// Iteration stops at the upper bound of the prefix, without reading the next entry
+    fn prefix_iterator_cf<'a>(&'a self, cf: &str, prefix: &[u8]) -> KvIterator<'a> {
+        let mut read_opts = ReadOptions::default();
+        read_opts.set_total_order_seek(
+            !ACCOUNT_KEYED_COLUMN_FAMILIES.contains(&cf) || prefix.len() < ADDRESS_LEN,
+        );
+        if let Some(upper_bound) = prefix_upper_bound(prefix) {
+            read_opts.set_iterate_upper_bound(upper_bound);
+        }
+        Box::new(
+            self.database
+                .iterator_cf_opt(
+                    self.cf_handle(cf),
+                    read_opts,
+                    IteratorMode::From(prefix, Direction::Forward),
+                )
+                .map(|entry| entry.map_err(anyhow::Error::from)),
+        )
+    }
+
+    fn write(&self, batch: KvBatch) -> anyhow::Result<()> {
+        let mut write_batch = WriteBatch::default();
+        for write in batch.into_writes() {
+            match write {
+                KvWrite::Put { cf, key, value } => {
+                    write_batch.put_cf(self.cf_handle(&cf), key, value)
+                }
+                KvWrite::Delete { cf, key } => write_batch.delete_cf(self.cf_handle(&cf), key),
+                KvWrite::DeleteRange { cf, from, to } => {
+                    write_batch.delete_range_cf(self.cf_handle(&cf), from, to)
+                }
+                KvWrite::Merge { cf, key, value } => {
+                    write_batch.merge_cf(self.cf_handle(&cf), key, value)
+                }
+            }
+        }
+        self.database.write(write_batch)?;
+        Ok(())
+    }
+
+    fn try_catch_up_with_primary(&self) -> anyhow::Result<()> {
+        self.database.try_catch_up_with_primary()?;
+        Ok(())
+    }
+
+    fn flush_wal(&self) -> anyhow::Result<()> {
+        self.database.flush_wal(true)?;
+        Ok(())
+    }
+
+    /// Memtables are written to SST files, so reopening doesn't replay the WAL
+    fn flush_cf(&self, cf: &str) -> anyhow::Result<()> {
+        self.database.flush_cf(self.cf_handle(cf))?;
+        Ok(())
+    }
+
+    fn compact_cf(&self, cf: &str) -> anyhow::Result<()> {
+        let cf_handle = self
+            .database
+            .cf_handle(cf)
+            .ok_or_else(|| anyhow::Error::msg(format!("Unknown column family: {cf}")))?;
+        self.database
+            .compact_range_cf(cf_handle, None::<&[u8]>, None::<&[u8]>);
+        Ok(())
+    }
+
+    /// SST files entirely within the range, e.g. after a range delete, are dropped
+    fn compact_range_cf(&self, cf: &str, from: &[u8], to: &[u8]) -> anyhow::Result<()> {
+        self.database
+            .compact_range_cf(self.cf_handle(cf), Some(from), Some(to));
+        Ok(())
+    }
+
+    fn backup(&self, backup_dir: &Path, num_backups_to_keep: usize) -> anyhow::Result<()> {
+        let backup_opts = BackupEngineOptions::new(backup_dir)?;
+        let mut backup_engine = BackupEngine::open(&backup_opts, &Env::new()?)?;
+
+        // flush memtables so the backup doesn't rely on the WAL
+        backup_engine.create_new_backup_flush(&self.database, true)?;
+        backup_engine.purge_old_backups(num_backups_to_keep)?;
+        Ok(())
+    }
+
+    /// SST files are hard linked when `dst` is on the same filesystem, so checkpoints are cheap
+    fn checkpoint(&self, dst: &Path) -> anyhow::Result<()> {
+        Checkpoint::new(&self.database)?.create_checkpoint(dst)?;
+        Ok(())
+    }
+
+    fn export_cf(&self, cf: &str, dst: &Path) -> anyhow::Result<u64> {
+        let mut entries = self.iterator_from_cf(cf, &[]).peekable();
+        if entries.peek().is_none() {
+            // SST files can't be empty
+            return Ok(0);
+        }
+
+        let sst_opts = rocksdb::Options::default();
+        let mut writer = SstFileWriter::create(&sst_opts);
+        writer.open(dst)?;
+        let mut num_entries = 0;
+        for entry in entries {
+            let (key, value) = entry?;
+            writer.put(key, value)?;
+            num_entries += 1;
+        }
+        writer.finish()?;
+        Ok(num_entries)
+    }
+
+    /// Files are copied into the database, leaving `files` in place
+    fn ingest_cf(&self, cf: &str, files: &[PathBuf]) -> anyhow::Result<()> {
+        self.database
+            .ingest_external_file_cf(self.cf_handle(cf), files.to_vec())?;
+        Ok(())
+    }
+
+    fn property_value(&self, name: &str) -> anyhow::Result<Option<String>> {
+        Ok(self.database.property_value(name)?)
+    }
+
+    fn property_int_value(&self, name: &str) -> anyhow::Result<Option<u64>> {
+        Ok(self.database.property_int_value(name)?)
+    }
+
+    fn property_int_value_cf(&self, cf: &str, name: &str) -> anyhow::Result<Option<u64>> {
+        Ok(self
+            .database
+            .property_int_value_cf(self.cf_handle(cf), name)?)
+    }
+}
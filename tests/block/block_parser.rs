@@ -1,7 +1,7 @@
-use std::path::PathBuf;
+use std::{path::PathBuf, time::Duration};
 
 use mina_indexer::block::parser::BlockParser;
-use tokio::time::Instant;
+use tokio::{sync::watch, time::Instant};
 
 #[tokio::test]
 async fn representative_bench() {
@@ -54,3 +54,22 @@ async fn get_global_slot_since_genesis() {
     );
     assert_eq!(block.global_slot_since_genesis(), 155140);
 }
+
+#[tokio::test]
+async fn paused_parsing() {
+    let sample_dir = PathBuf::from("./tests/data/sequential_blocks");
+    let (paused_tx, paused) = watch::channel(true);
+    let mut block_parser = BlockParser::new_testing(&sample_dir)
+        .unwrap()
+        .with_pause(paused);
+
+    // nothing is parsed while paused
+    assert!(
+        tokio::time::timeout(Duration::from_millis(100), block_parser.next())
+            .await
+            .is_err()
+    );
+
+    paused_tx.send_replace(false);
+    assert!(block_parser.next().await.unwrap().is_some());
+}
@@ -73,6 +73,119 @@ async fn detects_new_block_copied() {
     posttest(TEST_DIR, success).await;
 }
 
+#[tokio::test]
+async fn detects_new_block_in_second_directory() {
+    const TEST_DIR_0: &'static str = "./receiver_multiple_test_0";
+    const TEST_DIR_1: &'static str = "./receiver_multiple_test_1";
+    const TEST_BLOCK: &'static str = include_str!(
+        "../data/beautified_logs/mainnet-2-3NLyWnjZqUECniE1q719CoLmes6WDQAod4vrTeLfN7XXJbHv6EHH.json"
+    );
+
+    let timeout = Duration::new(5, 0);
+    let mut success = false;
+
+    tokio::time::timeout(timeout, async {
+        let test_dir_paths = vec![PathBuf::from(TEST_DIR_0), PathBuf::from(TEST_DIR_1)];
+        let mut test_block_path = test_dir_paths[1].clone();
+        test_block_path.push("mainnet-2-3NLyWnjZqUECniE1q719CoLmes6WDQAod4vrTeLfN7XXJbHv6EHH.json");
+
+        pretest(TEST_DIR_0).await;
+        pretest(TEST_DIR_1).await;
+
+        let mut block_receiver = BlockReceiver::new().await.unwrap();
+        block_receiver
+            .load_directories(&test_dir_paths)
+            .await
+            .unwrap();
+        assert_eq!(block_receiver.watched_directories(), &test_dir_paths[..]);
+
+        let mut file = File::create(test_block_path.clone()).await.unwrap();
+        file.write_all(TEST_BLOCK.as_bytes()).await.unwrap();
+
+        block_receiver.recv().await.unwrap().unwrap();
+        success = true;
+    })
+    .await
+    .unwrap();
+
+    posttest(TEST_DIR_0, true).await;
+    posttest(TEST_DIR_1, success).await;
+}
+
+#[tokio::test]
+async fn receives_block_in_two_directories_once() {
+    const TEST_DIRS: [&str; 2] = ["./receiver_duplicate_test_0", "./receiver_duplicate_test_1"];
+    const TEST_BLOCK: &'static str = include_str!(
+        "../data/beautified_logs/mainnet-2-3NLyWnjZqUECniE1q719CoLmes6WDQAod4vrTeLfN7XXJbHv6EHH.json"
+    );
+
+    let timeout = Duration::new(5, 0);
+    let mut success = false;
+
+    for dir in TEST_DIRS {
+        pretest(dir).await;
+    }
+    let test_dir_paths: Vec<PathBuf> = TEST_DIRS.iter().map(PathBuf::from).collect();
+    let mut block_receiver = BlockReceiver::new().await.unwrap();
+    block_receiver
+        .load_directories(&test_dir_paths)
+        .await
+        .unwrap();
+
+    tokio::time::timeout(timeout, async {
+        for test_dir_path in &test_dir_paths {
+            let test_block_path = test_dir_path
+                .join("mainnet-2-3NLyWnjZqUECniE1q719CoLmes6WDQAod4vrTeLfN7XXJbHv6EHH.json");
+            let mut file = File::create(test_block_path).await.unwrap();
+            file.write_all(TEST_BLOCK.as_bytes()).await.unwrap();
+        }
+
+        block_receiver.recv().await.unwrap().unwrap();
+        success = true;
+    })
+    .await
+    .unwrap();
+
+    // the copy in the other directory isn't received again
+    assert!(
+        tokio::time::timeout(Duration::new(2, 0), block_receiver.recv())
+            .await
+            .is_err()
+    );
+
+    for dir in TEST_DIRS {
+        posttest(dir, success).await;
+    }
+}
+
+#[tokio::test]
+async fn replaces_watched_directories() {
+    const TEST_DIRS: [&str; 3] = [
+        "./receiver_replace_test_0",
+        "./receiver_replace_test_1",
+        "./receiver_replace_test_2",
+    ];
+    for dir in TEST_DIRS {
+        pretest(dir).await;
+    }
+    let test_dir_paths: Vec<PathBuf> = TEST_DIRS.iter().map(PathBuf::from).collect();
+
+    let mut block_receiver = BlockReceiver::new().await.unwrap();
+    block_receiver
+        .load_directories(&test_dir_paths[..2])
+        .await
+        .unwrap();
+    block_receiver
+        .set_directories(&test_dir_paths[1..])
+        .await
+        .unwrap();
+    assert_eq!(block_receiver.watched_directories(), &test_dir_paths[1..]);
+
+    for dir in TEST_DIRS {
+        posttest(dir, true).await;
+    }
+}
+
 async fn pretest(path: &str) {
     if metadata(path).await.is_ok() {
         remove_dir_all(path).await.unwrap();
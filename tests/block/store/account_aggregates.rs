@@ -0,0 +1,54 @@
+use std::{collections::HashMap, path::PathBuf};
+
+use mina_indexer::{
+    block::{parser::BlockParser, store::BlockStore, BlockHash},
+    state::{
+        canonicity::store::CanonicityStore,
+        ledger::{
+            account_aggregate::{store::AccountAggregateStore, AccountAggregate},
+            public_key::PublicKey,
+        },
+        Canonicity,
+    },
+    store::IndexerStore,
+};
+
+#[tokio::test]
+async fn merged_account_aggregates() {
+    let store_dir = &PathBuf::from("./account-aggregates-store-test");
+    let log_dir = &PathBuf::from("./tests/data/sequential_blocks");
+
+    let db = IndexerStore::new(store_dir).unwrap();
+    let mut bp = BlockParser::new(log_dir).unwrap();
+    while let Some(block) = bp.next().await.unwrap() {
+        db.add_block(&block).unwrap();
+    }
+
+    // canonical chain of mainnet-105498-3NKbLiBHzQrAimK7AkP8qAfQpHnezkdsSm8mkt2TzsbjsLN8Axmt.json
+    let best_tip = BlockHash("3NKbLiBHzQrAimK7AkP8qAfQpHnezkdsSm8mkt2TzsbjsLN8Axmt".to_string());
+    let mut expected: HashMap<String, AccountAggregate> = HashMap::new();
+    for generations in 0..10 {
+        let state_hash = db.get_ancestor(&best_tip, generations).unwrap().unwrap();
+        let block = db.get_block(&state_hash).unwrap().unwrap();
+        for (pk, increment) in AccountAggregate::from_precomputed_block(&block) {
+            let aggregate = expected.entry(pk.to_address()).or_default();
+            aggregate.total_received += increment.total_received;
+            aggregate.total_fees_paid += increment.total_fees_paid;
+            aggregate.num_commands += increment.num_commands;
+        }
+
+        // blocks are only counted once
+        db.set_canonicity(&state_hash, Canonicity::Canonical)
+            .unwrap();
+        db.set_canonicity(&state_hash, Canonicity::Canonical)
+            .unwrap();
+    }
+    assert!(!expected.is_empty());
+
+    for (address, aggregate) in expected {
+        let pk = PublicKey::from_address(&address).unwrap();
+        assert_eq!(db.get_account_aggregate(&pk).unwrap(), aggregate);
+    }
+
+    tokio::fs::remove_dir_all(store_dir).await.unwrap();
+}
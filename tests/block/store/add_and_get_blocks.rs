@@ -1,7 +1,7 @@
 use std::{collections::HashMap, path::PathBuf};
 
 use mina_indexer::{
-    block::{parser::BlockParser, store::BlockStore},
+    block::{parser::BlockParser, store::BlockStore, BlockHash},
     store::IndexerStore,
 };
 use tokio::time::Instant;
@@ -32,12 +32,26 @@ async fn rocksdb() {
         assert_eq!(block, blocks.get(state_hash).unwrap());
     }
 
+    let fetch_time = fetching.elapsed();
+
+    let checking = Instant::now();
+    for state_hash in blocks.keys() {
+        assert!(db.exists(&BlockHash(state_hash.clone())).unwrap());
+    }
+    let check_time = checking.elapsed();
+    assert!(!db
+        .exists(&BlockHash(
+            "3NKeMoncuHab5ScarV5ViyF16cJPT4taWNSaTLS64Dp67wuXigPZ".to_string()
+        ))
+        .unwrap());
+
     println!("\n~~~~~~~~~~~~~~~~~~");
     println!("~~~ Benchmarks ~~~");
     println!("~~~~~~~~~~~~~~~~~~");
     println!("Number of blocks: {n}");
     println!("To insert all:    {add_time:?}");
-    println!("To fetch all:     {:?}\n", fetching.elapsed());
+    println!("To fetch all:     {fetch_time:?}");
+    println!("To check all:     {check_time:?}\n");
 
     tokio::fs::remove_dir_all(store_dir).await.unwrap();
 }
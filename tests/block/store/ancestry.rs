@@ -0,0 +1,50 @@
+use std::path::PathBuf;
+
+use mina_indexer::{
+    block::{parser::BlockParser, store::BlockStore, BlockHash, BlockWithoutHeight},
+    store::IndexerStore,
+};
+
+#[tokio::test]
+async fn block_ancestry() {
+    let store_dir = &PathBuf::from("./ancestry-store-test");
+    let log_dir = &PathBuf::from("./tests/data/sequential_blocks");
+
+    let db = IndexerStore::new(store_dir).unwrap();
+    let mut bp = BlockParser::new(log_dir).unwrap();
+
+    let mut blocks = vec![];
+    while let Some(block) = bp.next().await.unwrap() {
+        db.add_block(&block).unwrap();
+        blocks.push(block);
+    }
+
+    for block in &blocks {
+        let state_hash = BlockHash(block.state_hash.clone());
+        assert_eq!(
+            db.get_block_ancestry(&state_hash).unwrap(),
+            Some(BlockWithoutHeight::from_precomputed(block))
+        );
+    }
+
+    let best_tip = BlockHash("3NKbLiBHzQrAimK7AkP8qAfQpHnezkdsSm8mkt2TzsbjsLN8Axmt".to_string());
+    let parent = BlockHash("3NKjngJTXJzRUXF3uH2nK19iYUVtYBFjLhezSrMMFVQyEGwqEi3c".to_string());
+    assert_eq!(
+        db.get_ancestor(&best_tip, 0).unwrap(),
+        Some(best_tip.clone())
+    );
+    assert_eq!(db.get_ancestor(&best_tip, 1).unwrap(), Some(parent));
+
+    // 105498 -> 105489 is the whole chain, its parent isn't stored
+    let oldest = db.get_ancestor(&best_tip, 9).unwrap().unwrap();
+    assert_eq!(
+        db.get_block_ancestry(&oldest)
+            .unwrap()
+            .unwrap()
+            .blockchain_length,
+        Some(105489)
+    );
+    assert_eq!(db.get_ancestor(&best_tip, 11).unwrap(), None);
+
+    tokio::fs::remove_dir_all(store_dir).await.unwrap();
+}
@@ -0,0 +1,72 @@
+use std::path::PathBuf;
+
+use mina_indexer::{
+    block::{parser::BlockParser, store::BlockStore, BlockHash},
+    store::IndexerStore,
+};
+
+#[tokio::test]
+async fn backup_with_retention() {
+    let store_dir = &PathBuf::from("./backup-store-test");
+    let backup_dir = &PathBuf::from("./backup-store-test-backups");
+    let log_dir = &PathBuf::from("./tests/data/sequential_blocks");
+
+    let db = IndexerStore::new(store_dir).unwrap();
+    let mut bp = BlockParser::new(log_dir).unwrap();
+
+    while let Some(block) = bp.next().await.unwrap() {
+        db.add_block(&block).unwrap();
+        db.backup(backup_dir, 2).unwrap();
+    }
+
+    // only the 2 most recent backups are kept
+    let mut backups = std::fs::read_dir(backup_dir.join("meta"))
+        .unwrap()
+        .flatten()
+        .map(|entry| {
+            entry
+                .file_name()
+                .into_string()
+                .unwrap()
+                .parse::<u32>()
+                .unwrap()
+        })
+        .collect::<Vec<_>>();
+    backups.sort();
+    assert_eq!(backups, vec![23, 24]);
+
+    tokio::fs::remove_dir_all(store_dir).await.unwrap();
+    tokio::fs::remove_dir_all(backup_dir).await.unwrap();
+}
+
+#[tokio::test]
+async fn restore_from_latest_backup() {
+    let store_dir = &PathBuf::from("./restore-store-test");
+    let backup_dir = &PathBuf::from("./restore-store-test-backups");
+    let restore_dir = &PathBuf::from("./restore-store-test-restored");
+    let log_dir = &PathBuf::from("./tests/data/sequential_blocks");
+
+    assert!(IndexerStore::restore_from_latest_backup(backup_dir, restore_dir).is_err());
+
+    let mut state_hashes = vec![];
+    {
+        let db = IndexerStore::new(store_dir).unwrap();
+        let mut bp = BlockParser::new(log_dir).unwrap();
+
+        while let Some(block) = bp.next().await.unwrap() {
+            db.add_block(&block).unwrap();
+            state_hashes.push(BlockHash(block.state_hash));
+        }
+        db.backup(backup_dir, 1).unwrap();
+    }
+
+    IndexerStore::restore_from_latest_backup(backup_dir, restore_dir).unwrap();
+    let db = IndexerStore::new(restore_dir).unwrap();
+    for state_hash in state_hashes {
+        assert!(db.get_block(&state_hash).unwrap().is_some());
+    }
+
+    tokio::fs::remove_dir_all(store_dir).await.unwrap();
+    tokio::fs::remove_dir_all(backup_dir).await.unwrap();
+    tokio::fs::remove_dir_all(restore_dir).await.unwrap();
+}
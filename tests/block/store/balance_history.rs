@@ -0,0 +1,60 @@
+use std::path::PathBuf;
+
+use mina_indexer::{
+    block::{parser::BlockParser, BlockHash},
+    state::{
+        canonicity::store::CanonicityStore,
+        ledger::{balance_history::store::BalanceStore, public_key::PublicKey},
+        Canonicity,
+    },
+    store::IndexerStore,
+};
+
+#[tokio::test]
+async fn balance_at_height() {
+    let store_dir = &PathBuf::from("./balance-history-test");
+    let log_dir = &PathBuf::from("./tests/data/sequential_blocks");
+
+    let db = IndexerStore::new(store_dir).unwrap();
+    let mut bp = BlockParser::new(log_dir).unwrap();
+
+    while let Some(block) = bp.next().await.unwrap() {
+        db.add_balances(&block).unwrap();
+    }
+
+    // receiver of a payment in
+    // mainnet-105498-3NKbLiBHzQrAimK7AkP8qAfQpHnezkdsSm8mkt2TzsbjsLN8Axmt.json
+    let receiver =
+        PublicKey::from_address("B62qpRkv1YNgKwLEp1A4wRbvoHVJf6uSgX4aJgpXDqojsZbvCDyuWD5").unwrap();
+    let history = db.get_balance_history(&receiver).unwrap();
+    assert!(history
+        .windows(2)
+        .all(|entries| entries[0].blockchain_length <= entries[1].blockchain_length));
+
+    let canonical_hash =
+        BlockHash("3NKbLiBHzQrAimK7AkP8qAfQpHnezkdsSm8mkt2TzsbjsLN8Axmt".to_string());
+    assert_eq!(db.get_balance_at_height(&receiver, 105498).unwrap(), None);
+    db.set_canonicity(&canonical_hash, Canonicity::Canonical)
+        .unwrap();
+
+    let entry = db
+        .get_balance_at_height(&receiver, 105498)
+        .unwrap()
+        .unwrap();
+    assert_eq!(entry.balance.0, 7855833100);
+    assert_eq!(entry.state_hash, canonical_hash);
+    assert_eq!(db.get_balance_at_height(&receiver, 1).unwrap(), None);
+
+    // balances of blocks which aren't canonical, e.g. of forks, are skipped
+    for pending in history
+        .iter()
+        .filter(|entry| entry.state_hash != canonical_hash)
+    {
+        let entry = db
+            .get_balance_at_height(&receiver, pending.blockchain_length)
+            .unwrap();
+        assert!(entry.map_or(true, |entry| entry.state_hash != pending.state_hash));
+    }
+
+    tokio::fs::remove_dir_all(store_dir).await.unwrap();
+}
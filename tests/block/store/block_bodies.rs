@@ -0,0 +1,38 @@
+use std::path::PathBuf;
+
+use mina_indexer::{
+    block::{parser::BlockParser, store::BlockStore, BlockHash},
+    store::IndexerStore,
+};
+
+#[tokio::test]
+async fn headers_and_bodies() {
+    let store_dir = &PathBuf::from("./block-bodies-test");
+    let log_dir = &PathBuf::from("./tests/data/sequential_blocks");
+
+    let db = IndexerStore::new(store_dir).unwrap();
+    let mut bp = BlockParser::new(log_dir).unwrap();
+
+    let mut blocks = vec![];
+    while let Some(block) = bp.next().await.unwrap() {
+        db.add_block(&block).unwrap();
+        blocks.push(block);
+    }
+
+    for block in blocks.iter() {
+        let state_hash = BlockHash(block.state_hash.clone());
+        let header = db.get_block_header(&state_hash).unwrap().unwrap();
+        assert_eq!(header, block.header());
+        assert_eq!(header.parent_hash(), BlockHash::previous_state_hash(block));
+        assert_eq!(header.block_creator(), block.block_creator());
+        assert_eq!(header.timestamp(), block.timestamp());
+
+        // reassembled from the header and body
+        assert_eq!(&db.get_block(&state_hash).unwrap().unwrap(), block);
+    }
+
+    let missing = BlockHash("3NKeMoncuHab5ScarV5ViyF16cJPT4taWNSaTLS64Dp67wuXigPZ".to_string());
+    assert_eq!(db.get_block_header(&missing).unwrap(), None);
+
+    tokio::fs::remove_dir_all(store_dir).await.unwrap();
+}
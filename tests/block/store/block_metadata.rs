@@ -0,0 +1,53 @@
+use std::path::PathBuf;
+
+use mina_indexer::{
+    block::{metadata::BlockMetadata, parser::BlockParser, store::BlockStore, BlockHash},
+    store::IndexerStore,
+};
+
+#[tokio::test]
+async fn per_block_totals() {
+    let store_dir = &PathBuf::from("./block-metadata-test");
+    let log_dir = &PathBuf::from("./tests/data/sequential_blocks");
+
+    let db = IndexerStore::new(store_dir).unwrap();
+    let mut bp = BlockParser::new(log_dir).unwrap();
+
+    let mut state_hashes = vec![];
+    while let Some(block) = bp.next().await.unwrap() {
+        db.add_block(&block).unwrap();
+        state_hashes.push(BlockHash(block.state_hash));
+    }
+    for state_hash in &state_hashes {
+        assert!(db.get_block_metadata(state_hash).unwrap().is_some());
+    }
+
+    // 2 payments, no SNARK work
+    let state_hash = BlockHash("3NKbLiBHzQrAimK7AkP8qAfQpHnezkdsSm8mkt2TzsbjsLN8Axmt".to_string());
+    assert_eq!(
+        db.get_block_metadata(&state_hash).unwrap(),
+        Some(BlockMetadata {
+            num_commands: 2,
+            total_fees: 11_000_000,
+            coinbase_amount: 720_000_000_000,
+            num_snarks: 0,
+        })
+    );
+
+    // supercharged coinbase, 100 SNARK works
+    let state_hash = BlockHash("3NKAqzELKDp2BbdKKwdRWEoMNehyMrxJGCoGCyH1t1PyyH7VQMgk".to_string());
+    assert_eq!(
+        db.get_block_metadata(&state_hash).unwrap(),
+        Some(BlockMetadata {
+            num_commands: 3,
+            total_fees: 12_000_000,
+            coinbase_amount: 1_440_000_000_000,
+            num_snarks: 100,
+        })
+    );
+
+    let missing = BlockHash("3NKeMoncuHab5ScarV5ViyF16cJPT4taWNSaTLS64Dp67wuXigPZ".to_string());
+    assert_eq!(db.get_block_metadata(&missing).unwrap(), None);
+
+    tokio::fs::remove_dir_all(store_dir).await.unwrap();
+}
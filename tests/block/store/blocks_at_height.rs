@@ -0,0 +1,39 @@
+use std::path::PathBuf;
+
+use mina_indexer::{
+    block::{parser::BlockParser, store::BlockStore, BlockHash},
+    store::IndexerStore,
+};
+
+#[tokio::test]
+async fn indexes_blocks_by_height() {
+    let store_dir = &PathBuf::from("./blocks-at-height-test");
+    let log_dir = &PathBuf::from("./tests/data/sequential_blocks");
+
+    let db = IndexerStore::new(store_dir).unwrap();
+    let mut bp = BlockParser::new(log_dir).unwrap();
+
+    let mut num_blocks = 0;
+    while let Some(block) = bp.next().await.unwrap() {
+        db.add_block(&block).unwrap();
+        // adding a block twice does not duplicate the index entry
+        db.add_block(&block).unwrap();
+        num_blocks += 1;
+    }
+
+    let num_indexed: usize = (105489..=105498)
+        .map(|length| db.get_blocks_at_height(length).unwrap().len())
+        .sum();
+    assert_eq!(num_indexed, num_blocks);
+
+    // mainnet-105498-3NKbLiBHzQrAimK7AkP8qAfQpHnezkdsSm8mkt2TzsbjsLN8Axmt.json
+    assert_eq!(
+        db.get_blocks_at_height(105498).unwrap(),
+        vec![BlockHash(
+            "3NKbLiBHzQrAimK7AkP8qAfQpHnezkdsSm8mkt2TzsbjsLN8Axmt".to_string()
+        )]
+    );
+    assert!(db.get_blocks_at_height(1).unwrap().is_empty());
+
+    tokio::fs::remove_dir_all(store_dir).await.unwrap();
+}
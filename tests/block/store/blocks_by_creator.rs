@@ -0,0 +1,82 @@
+use std::path::PathBuf;
+
+use mina_indexer::{
+    block::{
+        parser::BlockParser,
+        production::{BlockProduction, BlockRange},
+        store::BlockStore,
+    },
+    state::ledger::public_key::PublicKey,
+    store::IndexerStore,
+};
+
+#[tokio::test]
+async fn indexes_blocks_by_creator() {
+    let store_dir = &PathBuf::from("./blocks-by-creator-test");
+    let log_dir = &PathBuf::from("./tests/data/sequential_blocks");
+
+    let db = IndexerStore::new(store_dir).unwrap();
+    let mut bp = BlockParser::new(log_dir).unwrap();
+
+    while let Some(block) = bp.next().await.unwrap() {
+        db.add_block(&block).unwrap();
+    }
+
+    // creator of 5 blocks at height 105496
+    let creator =
+        PublicKey::from_address("B62qq3TQ8AP7MFYPVtMx5tZGF3kWLJukfwG1A1RGvaBW1jfTPTkDBW6").unwrap();
+    let state_hashes = db.get_blocks_by_creator(&creator).unwrap();
+    assert_eq!(state_hashes.len(), 5);
+    for state_hash in state_hashes {
+        let block = db.get_block(&state_hash).unwrap().unwrap();
+        assert_eq!(block.blockchain_length, Some(105496));
+        assert_eq!(block.block_creator(), creator);
+    }
+
+    // coinbase receiver of 4 blocks at height 105492
+    let coinbase_receiver =
+        PublicKey::from_address("B62qk9WYHu2PBYv4EyEubnVQURcwpiV2ysuYYoMdwi8YTnwZQ7H4bLM").unwrap();
+    assert_eq!(
+        db.get_blocks_by_creator(&coinbase_receiver).unwrap().len(),
+        4
+    );
+
+    tokio::fs::remove_dir_all(store_dir).await.unwrap();
+}
+
+#[tokio::test]
+async fn block_production() {
+    let store_dir = &PathBuf::from("./block-production-test");
+    let log_dir = &PathBuf::from("./tests/data/sequential_blocks");
+
+    let db = IndexerStore::new(store_dir).unwrap();
+    let mut bp = BlockParser::new(log_dir).unwrap();
+
+    while let Some(block) = bp.next().await.unwrap() {
+        db.add_block(&block).unwrap();
+    }
+
+    // only block creators are counted, none of the blocks have a canonicity yet
+    let production = db
+        .get_block_production(&BlockRange::Heights {
+            start: 105496,
+            end: 105496,
+        })
+        .unwrap();
+    let creator = "B62qq3TQ8AP7MFYPVtMx5tZGF3kWLJukfwG1A1RGvaBW1jfTPTkDBW6";
+    assert_eq!(production.len(), 3);
+    assert_eq!(production[0].creator, creator);
+    assert_eq!(production[0].pending, 5);
+    assert_eq!(production[0].total(), 5);
+    assert_eq!(
+        production.iter().map(BlockProduction::total).sum::<u32>(),
+        7
+    );
+
+    let production = db
+        .get_block_production(&BlockRange::Heights { start: 0, end: 1 })
+        .unwrap();
+    assert!(production.is_empty());
+
+    tokio::fs::remove_dir_all(store_dir).await.unwrap();
+}
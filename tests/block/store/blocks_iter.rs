@@ -0,0 +1,68 @@
+use std::path::PathBuf;
+
+use mina_indexer::{
+    block::{parser::BlockParser, store::BlockStore, BlockHash},
+    store::IndexerStore,
+};
+
+#[tokio::test]
+async fn paginated_blocks() {
+    let store_dir = &PathBuf::from("./blocks-iter-store-test");
+    let log_dir = &PathBuf::from("./tests/data/sequential_blocks");
+
+    let db = IndexerStore::new(store_dir).unwrap();
+    let mut bp = BlockParser::new(log_dir).unwrap();
+
+    let mut block_count = 0;
+    while let Some(block) = bp.next().await.unwrap() {
+        db.add_block(&block).unwrap();
+        block_count += 1;
+    }
+
+    // all blocks, in order of increasing length
+    let blocks: Vec<_> = db.blocks_iter(0, usize::MAX).map(Result::unwrap).collect();
+    assert_eq!(blocks.len(), block_count);
+    assert!(blocks
+        .windows(2)
+        .all(|pair| pair[0].blockchain_length <= pair[1].blockchain_length));
+
+    let blocks_at_105496: Vec<_> = db.blocks_iter(105496, 5).map(Result::unwrap).collect();
+    assert_eq!(blocks_at_105496.len(), 5);
+    assert!(blocks_at_105496
+        .iter()
+        .all(|block| block.blockchain_length == Some(105496)));
+
+    // resume pages of 5 from the last block received
+    let mut paged = db.blocks_iter(0, 5).map(Result::unwrap).collect::<Vec<_>>();
+    while let Some(last) = paged.last() {
+        let state_hash = BlockHash(last.state_hash.clone());
+        let page: Vec<_> = db
+            .blocks_after(&state_hash)
+            .unwrap()
+            .take(5)
+            .map(Result::unwrap)
+            .collect();
+        if page.is_empty() {
+            break;
+        }
+        paged.extend(page);
+    }
+    assert_eq!(
+        paged
+            .iter()
+            .map(|block| block.state_hash.clone())
+            .collect::<Vec<_>>(),
+        blocks
+            .iter()
+            .map(|block| block.state_hash.clone())
+            .collect::<Vec<_>>()
+    );
+
+    let best_tip = BlockHash("3NKbLiBHzQrAimK7AkP8qAfQpHnezkdsSm8mkt2TzsbjsLN8Axmt".to_string());
+    assert_eq!(db.blocks_after(&best_tip).unwrap().count(), 0);
+    assert!(db
+        .blocks_after(&BlockHash("not-a-state-hash".to_string()))
+        .is_err());
+
+    tokio::fs::remove_dir_all(store_dir).await.unwrap();
+}
@@ -0,0 +1,50 @@
+use std::path::PathBuf;
+
+use mina_indexer::{
+    block::{parser::BlockParser, store::BlockStore, BlockHash},
+    state::{canonicity::store::CanonicityStore, Canonicity},
+    store::IndexerStore,
+};
+
+#[tokio::test]
+async fn canonical_hash_at_height() {
+    let store_dir = &PathBuf::from("./canonicity-store-test");
+    let log_dir = &PathBuf::from("./tests/data/sequential_blocks");
+
+    let db = IndexerStore::new(store_dir).unwrap();
+    let mut bp = BlockParser::new(log_dir).unwrap();
+
+    while let Some(block) = bp.next().await.unwrap() {
+        db.add_block(&block).unwrap();
+    }
+
+    // mainnet-105497-3NKjngJTXJzRUXF3uH2nK19iYUVtYBFjLhezSrMMFVQyEGwqEi3c.json
+    let canonical_hash =
+        BlockHash("3NKjngJTXJzRUXF3uH2nK19iYUVtYBFjLhezSrMMFVQyEGwqEi3c".to_string());
+    // mainnet-105498-3NKbLiBHzQrAimK7AkP8qAfQpHnezkdsSm8mkt2TzsbjsLN8Axmt.json
+    let pending_hash =
+        BlockHash("3NKbLiBHzQrAimK7AkP8qAfQpHnezkdsSm8mkt2TzsbjsLN8Axmt".to_string());
+
+    assert_eq!(db.get_canonicity(&canonical_hash).unwrap(), None);
+
+    db.set_canonicity(&canonical_hash, Canonicity::Canonical)
+        .unwrap();
+    db.set_canonicity(&pending_hash, Canonicity::Pending)
+        .unwrap();
+
+    assert_eq!(
+        db.get_canonicity(&canonical_hash).unwrap(),
+        Some(Canonicity::Canonical)
+    );
+    assert_eq!(
+        db.get_canonicity(&pending_hash).unwrap(),
+        Some(Canonicity::Pending)
+    );
+    assert_eq!(
+        db.get_canonical_hash_at_height(105497).unwrap(),
+        Some(canonical_hash)
+    );
+    assert_eq!(db.get_canonical_hash_at_height(105498).unwrap(), None);
+
+    tokio::fs::remove_dir_all(store_dir).await.unwrap();
+}
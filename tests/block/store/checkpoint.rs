@@ -0,0 +1,88 @@
+use std::path::PathBuf;
+
+use mina_indexer::{
+    block::{parser::BlockParser, store::BlockStore, BlockHash},
+    server::checkpoint_path,
+    store::IndexerStore,
+};
+
+#[tokio::test]
+async fn checkpoint_live_store() {
+    let store_dir = &PathBuf::from("./checkpoint-store-test");
+    let checkpoint_dir = &PathBuf::from("./checkpoint-store-test-checkpoint");
+    let log_dir = &PathBuf::from("./tests/data/sequential_blocks");
+
+    let db = IndexerStore::new(store_dir).unwrap();
+    let mut bp = BlockParser::new(log_dir).unwrap();
+
+    let mut state_hashes = vec![];
+    while let Some(block) = bp.next().await.unwrap() {
+        db.add_block(&block).unwrap();
+        state_hashes.push(BlockHash(block.state_hash));
+    }
+
+    db.checkpoint(checkpoint_dir).unwrap();
+    // the destination must not exist
+    assert!(db.checkpoint(checkpoint_dir).is_err());
+
+    // the checkpoint opens as a database of its own, while the store stays open
+    let checkpoint = IndexerStore::new(checkpoint_dir).unwrap();
+    for state_hash in &state_hashes {
+        assert_eq!(
+            checkpoint.get_block(state_hash).unwrap(),
+            db.get_block(state_hash).unwrap()
+        );
+    }
+
+    // in-memory stores can't be checkpointed, and leave nothing behind
+    assert!(IndexerStore::new_in_memory()
+        .checkpoint(&PathBuf::from("./checkpoint-in-memory-test"))
+        .is_err());
+    assert!(std::fs::read_dir(".").unwrap().all(|entry| !entry
+        .unwrap()
+        .file_name()
+        .to_string_lossy()
+        .contains("checkpoint-in-memory-test")));
+
+    tokio::fs::remove_dir_all(store_dir).await.unwrap();
+    tokio::fs::remove_dir_all(checkpoint_dir).await.unwrap();
+}
+
+#[tokio::test]
+async fn failed_checkpoint_rename() {
+    let store_dir = &PathBuf::from("./checkpoint-rename-test");
+    let dst_dir = &PathBuf::from("./checkpoint-rename-test-dst");
+    let db = IndexerStore::new(store_dir).unwrap();
+
+    // a dangling symlink isn't an existing destination, but a directory can't be
+    // renamed onto it
+    std::os::unix::fs::symlink("./checkpoint-rename-test-missing", dst_dir).unwrap();
+    assert!(db.checkpoint(dst_dir).is_err());
+
+    // the scratch directory is removed
+    assert!(std::fs::read_dir(".").unwrap().all(|entry| !entry
+        .unwrap()
+        .file_name()
+        .to_string_lossy()
+        .starts_with(".checkpoint-rename-test-dst.")));
+
+    tokio::fs::remove_file(dst_dir).await.unwrap();
+    tokio::fs::remove_dir_all(store_dir).await.unwrap();
+}
+
+#[test]
+fn checkpoint_paths_stay_in_the_checkpoint_dir() {
+    let checkpoint_dir = PathBuf::from("/var/lib/mina-indexer/checkpoints");
+    assert_eq!(
+        checkpoint_path(&checkpoint_dir, &PathBuf::from("daily/2023-07-01")).unwrap(),
+        checkpoint_dir.join("daily/2023-07-01")
+    );
+    for path in [
+        "/tmp/checkpoint",
+        "../checkpoint",
+        "daily/../../checkpoint",
+        "",
+    ] {
+        assert!(checkpoint_path(&checkpoint_dir, &PathBuf::from(path)).is_err());
+    }
+}
@@ -0,0 +1,231 @@
+use std::path::PathBuf;
+
+use mina_indexer::{
+    block::{parser::BlockParser, BlockHash},
+    server::rest::account_transactions,
+    state::{
+        canonicity::{store::CanonicityStore, Canonicity},
+        ledger::{
+            command::{store::CommandStore, SignedCommand, UserCommandWithStatus},
+            public_key::PublicKey,
+        },
+    },
+    store::IndexerStore,
+};
+use mina_serialization_types::staged_ledger_diff::UserCommand;
+
+#[tokio::test]
+async fn add_and_get_by_hash() {
+    let store_dir = &PathBuf::from("./command-store-test");
+    let log_dir = &PathBuf::from("./tests/data/beautified_logs");
+
+    let db = IndexerStore::new(store_dir).unwrap();
+    let mut bp = BlockParser::new(log_dir).unwrap();
+    let block = bp
+        .get_precomputed_block("3NL4HLb7MQrxmAqVw8D4vEXCj2tdT8zgP9DFWGRoDxP72b4wxyUw")
+        .await
+        .unwrap();
+
+    db.add_commands(&block).unwrap();
+
+    assert!(!block.commands().is_empty());
+    for command in block.commands() {
+        let command = UserCommandWithStatus(command);
+        let UserCommand::SignedCommand(signed_command) = command.clone().data();
+        let hash = SignedCommand(signed_command).hash_signed_command().unwrap();
+        let stored = db.get_command_by_hash(&hash).unwrap().unwrap();
+
+        assert_eq!(stored.command, command);
+        assert_eq!(stored.state_hash.0, block.state_hash);
+        assert_eq!(stored.blockchain_length, block.blockchain_length);
+    }
+    assert_eq!(db.get_command_by_hash("not-a-hash").unwrap(), None);
+
+    tokio::fs::remove_dir_all(store_dir).await.unwrap();
+}
+
+#[tokio::test]
+async fn transaction_hashes() {
+    let log_dir = &PathBuf::from("./tests/data/beautified_logs");
+    let mut bp = BlockParser::new(log_dir).unwrap();
+    let block = bp
+        .get_precomputed_block("3NL4HLb7MQrxmAqVw8D4vEXCj2tdT8zgP9DFWGRoDxP72b4wxyUw")
+        .await
+        .unwrap();
+
+    let hashes: Vec<String> = block
+        .commands()
+        .into_iter()
+        .map(|command| {
+            let UserCommand::SignedCommand(signed_command) = UserCommandWithStatus(command).data();
+            SignedCommand(signed_command).hash_signed_command().unwrap()
+        })
+        .collect();
+    assert!(hashes.iter().all(|hash| hash.starts_with("Ckp")));
+
+    let mut unique = hashes.clone();
+    unique.sort();
+    unique.dedup();
+    assert_eq!(unique.len(), hashes.len());
+}
+
+#[tokio::test]
+async fn commands_included_in_several_blocks() {
+    let store_dir = &PathBuf::from("./forked-commands-test");
+    let log_dir = &PathBuf::from("./tests/data/beautified_logs");
+
+    let db = IndexerStore::new(store_dir).unwrap();
+    let mut bp = BlockParser::new(log_dir).unwrap();
+    let block = bp
+        .get_precomputed_block("3NL4HLb7MQrxmAqVw8D4vEXCj2tdT8zgP9DFWGRoDxP72b4wxyUw")
+        .await
+        .unwrap();
+
+    // the same commands in a longer block of another fork
+    let mut fork_block = block.clone();
+    fork_block.state_hash = "3NKn7ZtT6Axw3hK3HpyUGRxmirkuUhtR4cYzWFk75NCgmjCcqPby".to_string();
+    fork_block.blockchain_length = block.blockchain_length.map(|length| length + 1);
+
+    db.add_commands(&block).unwrap();
+    db.add_commands(&fork_block).unwrap();
+
+    let UserCommand::SignedCommand(signed_command) =
+        UserCommandWithStatus(block.commands()[0].clone()).data();
+    let hash = SignedCommand(signed_command).hash_signed_command().unwrap();
+
+    // both inclusions are kept
+    for state_hash in [&block.state_hash, &fork_block.state_hash] {
+        let state_hash = BlockHash(state_hash.clone());
+        assert_eq!(
+            db.get_commands_in_block(&state_hash).unwrap().len(),
+            block.commands().len()
+        );
+    }
+
+    // the longest inclusion, unless one is canonical
+    assert_eq!(
+        db.get_command_by_hash(&hash).unwrap().unwrap().state_hash.0,
+        fork_block.state_hash
+    );
+    db.set_canonicity(&BlockHash(block.state_hash.clone()), Canonicity::Canonical)
+        .unwrap();
+    assert_eq!(
+        db.get_command_by_hash(&hash).unwrap().unwrap().state_hash.0,
+        block.state_hash
+    );
+
+    tokio::fs::remove_dir_all(store_dir).await.unwrap();
+}
+
+#[tokio::test]
+async fn commands_for_public_key() {
+    let store_dir = &PathBuf::from("./account-commands-test");
+    let log_dir = &PathBuf::from("./tests/data/beautified_logs");
+
+    let db = IndexerStore::new(store_dir).unwrap();
+    let mut bp = BlockParser::new(log_dir).unwrap();
+    let block = bp
+        .get_precomputed_block("3NL4HLb7MQrxmAqVw8D4vEXCj2tdT8zgP9DFWGRoDxP72b4wxyUw")
+        .await
+        .unwrap();
+
+    db.add_commands(&block).unwrap();
+
+    // sender of 3 payments
+    let sender =
+        PublicKey::from_address("B62qmPc8Ziq7txW48YPf4qtavcD5mcQVjEAGo9LEZD8DeaNsNthYLsz").unwrap();
+    let sent = db.get_commands_for_public_key(&sender).unwrap();
+    assert_eq!(sent.len(), 3);
+    assert!(sent.iter().all(|cmd| cmd.state_hash.0 == block.state_hash));
+
+    // receiver of 2 payments
+    let receiver =
+        PublicKey::from_address("B62qjYanmV7y9njVeH5UHkz3GYBm7xKir1rAnoY4KsEYUGLMiU45FSM").unwrap();
+    assert_eq!(db.get_commands_for_public_key(&receiver).unwrap().len(), 2);
+
+    tokio::fs::remove_dir_all(store_dir).await.unwrap();
+}
+
+#[tokio::test]
+async fn account_transactions_pages() {
+    let store_dir = &PathBuf::from("./account-transactions-test");
+    let log_dir = &PathBuf::from("./tests/data/beautified_logs");
+
+    let db = IndexerStore::new(store_dir).unwrap();
+    let mut bp = BlockParser::new(log_dir).unwrap();
+    let block = bp
+        .get_precomputed_block("3NL4HLb7MQrxmAqVw8D4vEXCj2tdT8zgP9DFWGRoDxP72b4wxyUw")
+        .await
+        .unwrap();
+
+    db.add_commands(&block).unwrap();
+
+    // sender of 3 payments, in pages of 2
+    let sender =
+        PublicKey::from_address("B62qmPc8Ziq7txW48YPf4qtavcD5mcQVjEAGo9LEZD8DeaNsNthYLsz").unwrap();
+    let all = db.get_commands_for_public_key(&sender).unwrap();
+    let first = db.get_commands_for_public_key_page(&sender, 0, 2).unwrap();
+    let second = db.get_commands_for_public_key_page(&sender, 2, 2).unwrap();
+    assert_eq!(first, all[..2]);
+    assert_eq!(second, all[2..]);
+
+    let transactions = account_transactions(&db, &sender, 0, 10).unwrap();
+    assert_eq!(transactions.len(), 3);
+    assert!(transactions
+        .iter()
+        .all(|tx| tx.sent && tx.kind == "payment" && tx.state_hash == block.state_hash));
+
+    tokio::fs::remove_dir_all(store_dir).await.unwrap();
+}
+
+#[tokio::test]
+async fn commands_in_block() {
+    let store_dir = &PathBuf::from("./block-commands-test");
+    let log_dir = &PathBuf::from("./tests/data/beautified_logs");
+
+    let db = IndexerStore::new(store_dir).unwrap();
+    let mut bp = BlockParser::new(log_dir).unwrap();
+    let block = bp
+        .get_precomputed_block("3NL4HLb7MQrxmAqVw8D4vEXCj2tdT8zgP9DFWGRoDxP72b4wxyUw")
+        .await
+        .unwrap();
+
+    db.add_commands(&block).unwrap();
+
+    // block order
+    let state_hash = BlockHash(block.state_hash.clone());
+    let command_hashes: Vec<String> = block
+        .commands()
+        .into_iter()
+        .map(|command| {
+            let UserCommand::SignedCommand(signed_command) = UserCommandWithStatus(command).data();
+            SignedCommand(signed_command).hash_signed_command().unwrap()
+        })
+        .collect();
+    assert!(!command_hashes.is_empty());
+    assert_eq!(
+        db.get_command_hashes_in_block(&state_hash).unwrap(),
+        command_hashes
+    );
+
+    let commands = db.get_commands_in_block(&state_hash).unwrap();
+    assert_eq!(
+        commands
+            .iter()
+            .map(|command| command.command.clone())
+            .collect::<Vec<_>>(),
+        block
+            .commands()
+            .into_iter()
+            .map(UserCommandWithStatus)
+            .collect::<Vec<_>>()
+    );
+    assert!(commands
+        .iter()
+        .all(|command| command.state_hash == state_hash));
+
+    let missing = BlockHash("3NKeMoncuHab5ScarV5ViyF16cJPT4taWNSaTLS64Dp67wuXigPZ".to_string());
+    assert!(db.get_commands_in_block(&missing).unwrap().is_empty());
+
+    tokio::fs::remove_dir_all(store_dir).await.unwrap();
+}
@@ -0,0 +1,32 @@
+use std::path::PathBuf;
+
+use mina_indexer::{
+    block::{parser::BlockParser, store::BlockStore, BlockHash},
+    store::IndexerStore,
+};
+
+#[tokio::test]
+async fn compact_column_families() {
+    let store_dir = &PathBuf::from("./compaction-store-test");
+    let log_dir = &PathBuf::from("./tests/data/sequential_blocks");
+
+    let db = IndexerStore::new(store_dir).unwrap();
+    let mut bp = BlockParser::new(log_dir).unwrap();
+
+    let mut state_hashes = vec![];
+    while let Some(block) = bp.next().await.unwrap() {
+        db.add_block(&block).unwrap();
+        state_hashes.push(BlockHash(block.state_hash));
+    }
+
+    db.compact(Some("blocks")).unwrap();
+    db.compact(None).unwrap();
+    assert!(db.compact(Some("not-a-column-family")).is_err());
+
+    // compaction preserves the stored data
+    for state_hash in state_hashes {
+        assert!(db.get_block(&state_hash).unwrap().is_some());
+    }
+
+    tokio::fs::remove_dir_all(store_dir).await.unwrap();
+}
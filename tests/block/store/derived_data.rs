@@ -0,0 +1,39 @@
+use std::path::PathBuf;
+
+use mina_indexer::{
+    block::{parser::BlockParser, store::BlockStore, BlockHash},
+    state::ledger::{
+        internal_command::store::InternalCommandStore, snark_work::store::SnarkStore,
+        store::LedgerStore,
+    },
+    store::IndexerStore,
+};
+
+#[tokio::test]
+async fn add_block_writes_derived_data() {
+    let store_dir = &PathBuf::from("./derived-data-store-test");
+    let log_dir = &PathBuf::from("./tests/data/sequential_blocks");
+
+    let db = IndexerStore::new(store_dir).unwrap();
+    let mut bp = BlockParser::new(log_dir).unwrap();
+
+    // mainnet-105493-3NKakum3B2Tigw9TSsxwvXvV3x8L2LvrJ3yXFLEAJDMZu2vkn7db.json
+    let block = bp
+        .get_precomputed_block("3NKakum3B2Tigw9TSsxwvXvV3x8L2LvrJ3yXFLEAJDMZu2vkn7db")
+        .await
+        .unwrap();
+    db.add_block(&block).unwrap();
+
+    // the block's derived data is written along with it
+    let state_hash = BlockHash(block.state_hash.clone());
+    assert_eq!(db.get_snark_work_in_block(&state_hash).unwrap().len(), 28);
+    assert!(!db.get_internal_commands(&state_hash).unwrap().is_empty());
+    assert_eq!(
+        db.get_ledger_diff(&state_hash)
+            .unwrap()
+            .map(|(parent, _)| parent),
+        Some(BlockHash::previous_state_hash(&block))
+    );
+
+    tokio::fs::remove_dir_all(store_dir).await.unwrap();
+}
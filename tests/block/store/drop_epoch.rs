@@ -0,0 +1,50 @@
+use std::path::PathBuf;
+
+use mina_indexer::{
+    block::{parser::BlockParser, store::BlockStore, BlockHash},
+    store::IndexerStore,
+};
+
+#[tokio::test]
+async fn drop_block_bodies_of_epoch() {
+    let store_dir = &PathBuf::from("./drop-epoch-test");
+    let log_dir = &PathBuf::from("./tests/data/sequential_blocks");
+
+    let db = IndexerStore::new(store_dir).unwrap();
+    let mut bp = BlockParser::new(log_dir).unwrap();
+
+    let mut blocks = vec![];
+    while let Some(block) = bp.next().await.unwrap() {
+        db.add_block(&block).unwrap();
+        blocks.push(block);
+    }
+
+    // all blocks are in the same epoch
+    let epoch = blocks[0].epoch();
+    assert!(blocks.iter().all(|block| block.epoch() == epoch));
+    assert_eq!(db.get_dropped_epochs().unwrap(), vec![]);
+
+    // other epochs are unaffected
+    db.drop_epoch(epoch + 1).unwrap();
+    for block in &blocks {
+        let state_hash = BlockHash(block.state_hash.clone());
+        assert_eq!(db.get_block(&state_hash).unwrap().as_ref(), Some(block));
+    }
+
+    db.drop_epoch(epoch).unwrap();
+    assert_eq!(db.get_dropped_epochs().unwrap(), vec![epoch, epoch + 1]);
+    for block in &blocks {
+        // headers and derived data are kept
+        let state_hash = BlockHash(block.state_hash.clone());
+        assert!(db.get_block(&state_hash).is_err());
+        assert!(db.exists(&state_hash).unwrap());
+        assert_eq!(
+            db.get_block_header(&state_hash).unwrap(),
+            Some(block.header())
+        );
+        assert!(db.get_block_ancestry(&state_hash).unwrap().is_some());
+    }
+    assert_eq!(db.verify().unwrap(), Vec::<String>::new());
+
+    tokio::fs::remove_dir_all(store_dir).await.unwrap();
+}
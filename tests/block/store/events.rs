@@ -0,0 +1,62 @@
+use mina_indexer::{
+    block::{parser::BlockParser, store::BlockStore, BlockHash},
+    state::{
+        canonicity::store::CanonicityStore,
+        event::{store::EventStore, IndexerEvent},
+        ledger::{store::LedgerStore, Ledger},
+        Canonicity,
+    },
+    store::IndexerStore,
+};
+use std::path::PathBuf;
+
+#[tokio::test]
+async fn event_log() {
+    let log_dir = &PathBuf::from("./tests/data/sequential_blocks");
+    let db = IndexerStore::new_in_memory();
+    let mut bp = BlockParser::new(log_dir).unwrap();
+
+    let mut expected = vec![];
+    while let Some(block) = bp.next().await.unwrap() {
+        db.add_block(&block).unwrap();
+        expected.push(IndexerEvent::BlockAdded {
+            state_hash: BlockHash(block.state_hash.clone()),
+            blockchain_length: block.blockchain_length,
+        });
+    }
+
+    // canonicity changes are logged once
+    let state_hash = BlockHash("3NKbLiBHzQrAimK7AkP8qAfQpHnezkdsSm8mkt2TzsbjsLN8Axmt".to_string());
+    db.set_canonicity(&state_hash, Canonicity::Pending).unwrap();
+    db.set_canonicity(&state_hash, Canonicity::Pending).unwrap();
+    db.set_canonicity(&state_hash, Canonicity::Canonical)
+        .unwrap();
+    expected.push(IndexerEvent::CanonicityChanged {
+        state_hash: state_hash.clone(),
+        canonicity: Canonicity::Pending,
+    });
+    expected.push(IndexerEvent::CanonicityChanged {
+        state_hash: state_hash.clone(),
+        canonicity: Canonicity::Canonical,
+    });
+
+    db.add_ledger(&state_hash, Ledger::new()).unwrap();
+    expected.push(IndexerEvent::LedgerSnapshotTaken {
+        state_hash: state_hash.clone(),
+    });
+
+    assert_eq!(db.get_next_event_seq_num().unwrap(), expected.len() as u64);
+    assert_eq!(db.get_event(0).unwrap(), expected.first().cloned());
+    assert_eq!(db.get_event(expected.len() as u64).unwrap(), None);
+
+    let events = db.get_events_from(0, usize::MAX).unwrap();
+    assert_eq!(
+        events,
+        expected
+            .into_iter()
+            .enumerate()
+            .map(|(n, event)| (n as u64, event))
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(db.get_events_from(3, 2).unwrap(), events[3..5].to_vec());
+}
@@ -0,0 +1,39 @@
+use std::path::PathBuf;
+
+use mina_indexer::{
+    block::{parser::BlockParser, store::BlockStore, BlockHash},
+    store::IndexerStore,
+};
+
+#[tokio::test]
+async fn flush_persists_memtables() {
+    let store_dir = &PathBuf::from("./flush-store-test");
+    let log_dir = &PathBuf::from("./tests/data/sequential_blocks");
+
+    let mut state_hashes = vec![];
+    {
+        let db = IndexerStore::new(store_dir).unwrap();
+        let mut bp = BlockParser::new(log_dir).unwrap();
+        while let Some(block) = bp.next().await.unwrap() {
+            db.add_block(&block).unwrap();
+            state_hashes.push(BlockHash(block.state_hash));
+        }
+
+        db.flush().unwrap();
+        let stats = db.stats().unwrap().unwrap();
+        let blocks = stats
+            .column_families
+            .iter()
+            .find(|cf| cf.name == "blocks")
+            .unwrap();
+        assert!(blocks.sst_files_size > 0);
+    }
+
+    // the flushed blocks are there after reopening
+    let db = IndexerStore::new(store_dir).unwrap();
+    for state_hash in state_hashes {
+        assert!(db.get_block(&state_hash).unwrap().is_some());
+    }
+
+    tokio::fs::remove_dir_all(store_dir).await.unwrap();
+}
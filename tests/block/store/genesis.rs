@@ -0,0 +1,49 @@
+use mina_indexer::{
+    block::{store::BlockStore, BlockHash},
+    state::{branch::Branch, canonicity::store::CanonicityStore, Canonicity},
+    store::IndexerStore,
+    MAINNET_GENESIS_HASH,
+};
+
+/// The genesis block is the canonical block of length 1, without a precomputed block
+#[test]
+fn genesis_block_is_canonical() {
+    let db = IndexerStore::new_in_memory();
+    let genesis_hash = BlockHash(MAINNET_GENESIS_HASH.to_string());
+    let genesis_branch = Branch::new_genesis(genesis_hash.clone()).unwrap();
+    db.add_genesis_block(genesis_branch.root_block().unwrap())
+        .unwrap();
+
+    assert_eq!(
+        db.get_genesis_block()
+            .unwrap()
+            .map(|genesis| genesis.state_hash),
+        Some(genesis_hash.clone())
+    );
+    assert_eq!(
+        db.get_blocks_at_height(1).unwrap(),
+        vec![genesis_hash.clone()]
+    );
+    assert_eq!(
+        db.get_canonicity(&genesis_hash).unwrap(),
+        Some(Canonicity::Canonical)
+    );
+    assert_eq!(
+        db.get_canonical_hash_at_height(1).unwrap(),
+        Some(genesis_hash.clone())
+    );
+
+    let ancestry = db.get_block_ancestry(&genesis_hash).unwrap().unwrap();
+    assert_eq!(ancestry.parent_hash, genesis_hash);
+    assert_eq!(ancestry.blockchain_length, Some(1));
+    assert_eq!(ancestry.global_slot_since_genesis, 0);
+
+    // recording it again doesn't duplicate the height index entry
+    db.add_genesis_block(genesis_branch.root_block().unwrap())
+        .unwrap();
+    assert_eq!(db.get_blocks_at_height(1).unwrap().len(), 1);
+
+    // it has no precomputed block, so block iteration skips it
+    assert_eq!(db.blocks_iter(0, 10).count(), 0);
+    assert!(db.verify().unwrap().is_empty());
+}
@@ -0,0 +1,50 @@
+use std::path::PathBuf;
+
+use mina_indexer::{
+    block::{parser::BlockParser, store::BlockStore, BlockHash},
+    state::{
+        canonicity::store::CanonicityStore,
+        ledger::{public_key::PublicKey, snark_work::store::SnarkStore},
+        Canonicity,
+    },
+    store::IndexerStore,
+};
+
+#[tokio::test]
+async fn in_memory_store() {
+    let log_dir = &PathBuf::from("./tests/data/sequential_blocks");
+
+    let db = IndexerStore::new_in_memory();
+    let mut bp = BlockParser::new(log_dir).unwrap();
+
+    let mut state_hashes = vec![];
+    while let Some(block) = bp.next().await.unwrap() {
+        db.add_block(&block).unwrap();
+        state_hashes.push(BlockHash(block.state_hash));
+    }
+
+    for state_hash in &state_hashes {
+        assert!(db.get_block(state_hash).unwrap().is_some());
+    }
+    assert_eq!(db.get_blocks_at_height(105496).unwrap().len(), 7);
+
+    // prefix scans stop at the end of the prefix
+    let prover =
+        PublicKey::from_address("B62qs2Lw5WZNSjd8eHBUZXFYyRjV8oKtrZMFDn1S1Ye62G71xCQJMYM").unwrap();
+    assert!(db
+        .get_snark_work_by_prover(&prover)
+        .unwrap()
+        .iter()
+        .all(|work| work.prover == prover));
+
+    // mainnet-105497-3NKjngJTXJzRUXF3uH2nK19iYUVtYBFjLhezSrMMFVQyEGwqEi3c.json
+    let canonical_hash =
+        BlockHash("3NKjngJTXJzRUXF3uH2nK19iYUVtYBFjLhezSrMMFVQyEGwqEi3c".to_string());
+    db.set_canonicity(&canonical_hash, Canonicity::Canonical)
+        .unwrap();
+    assert_eq!(
+        db.get_canonical_hash_at_height(105497).unwrap(),
+        Some(canonical_hash)
+    );
+    assert!(db.verify().unwrap().is_empty());
+}
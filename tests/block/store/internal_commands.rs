@@ -0,0 +1,63 @@
+use std::path::PathBuf;
+
+use mina_indexer::{
+    block::{parser::BlockParser, BlockHash},
+    state::ledger::{
+        internal_command::{store::InternalCommandStore, InternalCommand},
+        public_key::PublicKey,
+    },
+    store::IndexerStore,
+};
+
+#[tokio::test]
+async fn add_and_get_internal_commands() {
+    let store_dir = &PathBuf::from("./internal-command-store-test");
+    let log_dir = &PathBuf::from("./tests/data/sequential_blocks");
+
+    let db = IndexerStore::new(store_dir).unwrap();
+    let mut bp = BlockParser::new(log_dir).unwrap();
+
+    // mainnet-105498-3NKbLiBHzQrAimK7AkP8qAfQpHnezkdsSm8mkt2TzsbjsLN8Axmt.json
+    let block = bp
+        .get_precomputed_block("3NKbLiBHzQrAimK7AkP8qAfQpHnezkdsSm8mkt2TzsbjsLN8Axmt")
+        .await
+        .unwrap();
+    db.add_internal_commands(&block).unwrap();
+
+    let producer =
+        PublicKey::from_address("B62qpKyT3YHnWvQ2MXbUuZZyYVCBe6kWohFtM52QTLJ6torYXJhQajE").unwrap();
+    let internal_commands: Vec<InternalCommand> = db
+        .get_internal_commands(&BlockHash(block.state_hash.clone()))
+        .unwrap()
+        .into_iter()
+        .map(|internal_command| internal_command.command)
+        .collect();
+
+    // 720 MINA coinbase and 0.011 MINA of transaction fees, no SNARK work
+    assert_eq!(
+        internal_commands,
+        vec![
+            InternalCommand::Coinbase {
+                receiver: producer.clone(),
+                amount: 720_000_000_000.into(),
+            },
+            InternalCommand::FeeTransfer {
+                sender: producer.clone(),
+                receiver: producer.clone(),
+                amount: 11_000_000.into(),
+            },
+        ]
+    );
+    assert_eq!(
+        db.get_internal_commands_for_public_key(&producer)
+            .unwrap()
+            .len(),
+        2
+    );
+    assert_eq!(
+        internal_commands[0].to_string(),
+        format!("Coinbase of 720000000000 to {}", producer.to_address())
+    );
+
+    tokio::fs::remove_dir_all(store_dir).await.unwrap();
+}
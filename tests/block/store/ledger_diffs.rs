@@ -0,0 +1,100 @@
+use std::path::PathBuf;
+
+use mina_indexer::{
+    block::{parser::BlockParser, BlockHash},
+    state::ledger::{diff::LedgerDiff, store::LedgerStore, Ledger},
+    store::IndexerStore,
+};
+
+#[tokio::test]
+async fn reconstructs_ledger_from_snapshot_and_diffs() {
+    let store_dir = &PathBuf::from("./ledger-diffs-test");
+    let log_dir = &PathBuf::from("./tests/data/sequential_blocks");
+
+    let db = IndexerStore::new(store_dir).unwrap();
+    let mut bp = BlockParser::new(log_dir).unwrap();
+
+    // mainnet-105497-3NKjngJTXJzRUXF3uH2nK19iYUVtYBFjLhezSrMMFVQyEGwqEi3c.json
+    // mainnet-105498-3NKbLiBHzQrAimK7AkP8qAfQpHnezkdsSm8mkt2TzsbjsLN8Axmt.json
+    let parent = bp
+        .get_precomputed_block("3NKjngJTXJzRUXF3uH2nK19iYUVtYBFjLhezSrMMFVQyEGwqEi3c")
+        .await
+        .unwrap();
+    let child = bp
+        .get_precomputed_block("3NKbLiBHzQrAimK7AkP8qAfQpHnezkdsSm8mkt2TzsbjsLN8Axmt")
+        .await
+        .unwrap();
+
+    // snapshot at the parent's parent only
+    let root_hash = BlockHash::previous_state_hash(&parent);
+    db.add_ledger(&root_hash, Ledger::new()).unwrap();
+
+    let mut expected = Ledger::new();
+    for block in [&parent, &child] {
+        let diff = LedgerDiff::from_precomputed_block(block);
+        expected.apply_diff(&diff).unwrap();
+        db.add_ledger_diff(
+            &BlockHash(block.state_hash.clone()),
+            &BlockHash::previous_state_hash(block),
+            &diff,
+        )
+        .unwrap();
+    }
+
+    let child_hash = BlockHash(child.state_hash.clone());
+    assert_eq!(db.get_ledger(&child_hash).unwrap(), Some(expected));
+
+    // no snapshot to reconstruct from
+    let unknown_hash =
+        BlockHash("3NKXsaznJ6WdyA4PHfXxn25RzVanzQsNMZrxjidbhoBug8R4LZDy".to_string());
+    assert_eq!(db.get_ledger(&unknown_hash).unwrap(), None);
+
+    tokio::fs::remove_dir_all(store_dir).await.unwrap();
+}
+
+#[tokio::test]
+async fn reconstructs_ledger_from_the_last_one() {
+    let log_dir = &PathBuf::from("./tests/data/sequential_blocks");
+    let db = IndexerStore::new_in_memory();
+    let mut bp = BlockParser::new(log_dir).unwrap();
+
+    // mainnet-105497-3NKjngJTXJzRUXF3uH2nK19iYUVtYBFjLhezSrMMFVQyEGwqEi3c.json
+    // mainnet-105498-3NKbLiBHzQrAimK7AkP8qAfQpHnezkdsSm8mkt2TzsbjsLN8Axmt.json
+    let parent = bp
+        .get_precomputed_block("3NKjngJTXJzRUXF3uH2nK19iYUVtYBFjLhezSrMMFVQyEGwqEi3c")
+        .await
+        .unwrap();
+    let child = bp
+        .get_precomputed_block("3NKbLiBHzQrAimK7AkP8qAfQpHnezkdsSm8mkt2TzsbjsLN8Axmt")
+        .await
+        .unwrap();
+    let root_hash = BlockHash::previous_state_hash(&parent);
+    db.add_ledger(&root_hash, Ledger::new()).unwrap();
+
+    let mut expected = Ledger::new();
+    for block in [&parent, &child] {
+        let diff = LedgerDiff::from_precomputed_block(block);
+        expected.apply_diff(&diff).unwrap();
+        db.add_ledger_diff(
+            &BlockHash(block.state_hash.clone()),
+            &BlockHash::previous_state_hash(block),
+            &diff,
+        )
+        .unwrap();
+    }
+
+    let parent_hash = BlockHash(parent.state_hash.clone());
+    let child_hash = BlockHash(child.state_hash.clone());
+    db.get_ledger(&parent_hash).unwrap().unwrap();
+    let diff_gets = |db: &IndexerStore| {
+        db.operation_stats()
+            .column_family("ledger-diffs")
+            .map(|cf| cf.gets.count)
+            .unwrap_or(0)
+    };
+    let before = diff_gets(&db);
+
+    // only the child's diff is applied to the parent's ledger
+    assert_eq!(db.get_ledger(&child_hash).unwrap(), Some(expected));
+    assert_eq!(diff_gets(&db), before + 1);
+}
@@ -1 +1,34 @@
+mod account_aggregates;
 mod add_and_get_blocks;
+mod ancestry;
+mod backup;
+mod balance_history;
+mod block_bodies;
+mod block_metadata;
+mod blocks_at_height;
+mod blocks_by_creator;
+mod blocks_iter;
+mod canonicity;
+mod checkpoint;
+mod commands;
+mod compaction;
+mod derived_data;
+mod drop_epoch;
+mod events;
+mod flush;
+mod genesis;
+mod in_memory;
+mod internal_commands;
+mod ledger_diffs;
+mod operation_stats;
+mod prefix_keys;
+mod prune_orphans;
+mod reindex;
+mod skipped_slots;
+mod snark_work;
+mod sst_export;
+mod stale_data;
+mod stats;
+mod tuning;
+mod value_codec;
+mod verify;
@@ -0,0 +1,45 @@
+use mina_indexer::{
+    block::{parser::BlockParser, store::BlockStore, BlockHash},
+    store::IndexerStore,
+};
+use std::path::PathBuf;
+
+#[tokio::test]
+async fn operation_stats() {
+    let log_dir = &PathBuf::from("./tests/data/sequential_blocks");
+
+    let db = IndexerStore::new_in_memory();
+    let mut bp = BlockParser::new(log_dir).unwrap();
+
+    let mut block_count = 0;
+    let mut state_hash = None;
+    while let Some(block) = bp.next().await.unwrap() {
+        db.add_block(&block).unwrap();
+        state_hash = Some(BlockHash(block.state_hash.clone()));
+        block_count += 1;
+    }
+
+    let stats = db.operation_stats();
+    let blocks = stats.column_family("blocks").unwrap();
+    assert_eq!(blocks.puts, block_count);
+    assert!(blocks.bytes_written > 0);
+    assert!(stats.writes.count >= block_count);
+    assert_eq!(stats.writes.count, stats.writes.buckets.iter().sum::<u64>());
+
+    // reads are counted per column family
+    let gets = blocks.gets.count;
+    db.get_block(&state_hash.unwrap()).unwrap();
+    let stats = db.operation_stats();
+    let blocks = stats.column_family("blocks").unwrap();
+    assert!(blocks.gets.count > gets);
+    assert!(blocks.bytes_read > 0);
+
+    // iterations are recorded once the iterator is dropped
+    let iterations = blocks.iterations.count;
+    let entries_iterated = blocks.entries_iterated;
+    let entries = db.iter_prefix_cf("blocks", b"").count() as u64;
+    let stats = db.operation_stats();
+    let blocks = stats.column_family("blocks").unwrap();
+    assert_eq!(blocks.iterations.count, iterations + 1);
+    assert_eq!(blocks.entries_iterated, entries_iterated + entries);
+}
@@ -0,0 +1,80 @@
+use std::path::PathBuf;
+
+use mina_indexer::{
+    block::{parser::BlockParser, store::BlockStore, BlockHash},
+    state::{
+        canonicity::store::CanonicityStore,
+        ledger::{balance_history::store::BalanceStore, public_key::PublicKey},
+        Canonicity,
+    },
+    store::{keys, IndexerStore},
+};
+
+#[tokio::test]
+async fn account_height_range() {
+    let store_dir = &PathBuf::from("./prefix-keys-test");
+    let log_dir = &PathBuf::from("./tests/data/sequential_blocks");
+
+    let db = IndexerStore::new(store_dir).unwrap();
+    let mut bp = BlockParser::new(log_dir).unwrap();
+
+    while let Some(block) = bp.next().await.unwrap() {
+        db.add_block(&block).unwrap();
+    }
+
+    // receiver of a payment in
+    // mainnet-105498-3NKbLiBHzQrAimK7AkP8qAfQpHnezkdsSm8mkt2TzsbjsLN8Axmt.json
+    let receiver =
+        PublicKey::from_address("B62qpRkv1YNgKwLEp1A4wRbvoHVJf6uSgX4aJgpXDqojsZbvCDyuWD5").unwrap();
+    let history = db.get_balance_history(&receiver).unwrap();
+    assert!(!history.is_empty());
+
+    for (start, end) in [(105489, 105498), (105495, 105497), (105498, 105498), (0, 1)] {
+        let keys: Vec<Box<[u8]>> = db
+            .iter_account_heights_cf("balances", &receiver, start, end)
+            .map(|entry| entry.unwrap().0)
+            .collect();
+        let expected: Vec<Vec<u8>> = history
+            .iter()
+            .filter(|entry| (start..=end).contains(&entry.blockchain_length))
+            .map(|entry| {
+                keys::account_key(&receiver, entry.blockchain_length, &entry.state_hash, &[])
+            })
+            .collect();
+        assert_eq!(
+            keys.iter().map(|key| key.to_vec()).collect::<Vec<_>>(),
+            expected
+        );
+    }
+
+    // prefix scans don't run into the next account
+    let prefix = keys::account_prefix(&receiver);
+    assert!(db
+        .iter_prefix_cf("balances", &prefix)
+        .all(|entry| entry.unwrap().0.starts_with(&prefix)));
+    assert_eq!(
+        db.iter_prefix_cf("balances", &prefix).count(),
+        history.len()
+    );
+
+    tokio::fs::remove_dir_all(store_dir).await.unwrap();
+}
+
+#[tokio::test]
+async fn canonicity_prefixes() {
+    let db = IndexerStore::new_in_memory();
+    let state_hash = BlockHash("3NKbLiBHzQrAimK7AkP8qAfQpHnezkdsSm8mkt2TzsbjsLN8Axmt".to_string());
+
+    db.set_canonicity(&state_hash, Canonicity::Pending).unwrap();
+    assert_eq!(
+        db.iter_prefix_cf("canonicity", keys::BLOCK_CANONICITY_PREFIX)
+            .map(|entry| entry.unwrap().0.to_vec())
+            .collect::<Vec<_>>(),
+        vec![keys::block_canonicity_key(&state_hash)]
+    );
+    assert_eq!(
+        db.iter_prefix_cf("canonicity", keys::CANONICAL_HEIGHT_PREFIX)
+            .count(),
+        0
+    );
+}
@@ -0,0 +1,121 @@
+use std::path::PathBuf;
+
+use mina_indexer::{
+    block::{parser::BlockParser, precomputed::PrecomputedBlock, store::BlockStore, BlockHash},
+    state::{
+        canonicity::store::CanonicityStore,
+        ledger::{
+            command::{store::CommandStore, SignedCommand, UserCommandWithStatus},
+            internal_command::store::InternalCommandStore,
+            snark_work::{store::SnarkStore, SnarkWorkSummary},
+        },
+        Canonicity,
+    },
+    store::IndexerStore,
+};
+use mina_serialization_types::staged_ledger_diff::UserCommand;
+
+#[tokio::test]
+async fn prune_orphaned_blocks() {
+    let store_dir = &PathBuf::from("./prune-orphans-store-test");
+    let log_dir = &PathBuf::from("./tests/data/sequential_blocks");
+
+    let db = IndexerStore::new(store_dir).unwrap();
+    let mut bp = BlockParser::new(log_dir).unwrap();
+
+    let mut blocks = vec![];
+    while let Some(block) = bp.next().await.unwrap() {
+        db.add_block(&block).unwrap();
+        blocks.push(block);
+    }
+
+    // mark the ancestors of 3NKbLiBHzQrAimK7AkP8qAfQpHnezkdsSm8mkt2TzsbjsLN8Axmt canonical
+    let mut canonical_hashes = vec![];
+    let mut state_hash =
+        BlockHash("3NKbLiBHzQrAimK7AkP8qAfQpHnezkdsSm8mkt2TzsbjsLN8Axmt".to_string());
+    while let Some(block) = db.get_block(&state_hash).unwrap() {
+        db.set_canonicity(&state_hash, Canonicity::Canonical)
+            .unwrap();
+        canonical_hashes.push(state_hash);
+        state_hash = BlockHash::previous_state_hash(&block);
+    }
+
+    // 14 orphaned blocks of length at most 105496
+    assert_eq!(db.prune_orphaned_blocks(105496).unwrap(), 14);
+    assert_eq!(db.prune_orphaned_blocks(105496).unwrap(), 0);
+
+    for block in &blocks {
+        let state_hash = BlockHash(block.state_hash.clone());
+        if canonical_hashes.contains(&state_hash) {
+            assert!(db.get_block(&state_hash).unwrap().is_some());
+        } else {
+            assert!(db.get_block(&state_hash).unwrap().is_none());
+            assert_eq!(
+                db.get_canonicity(&state_hash).unwrap(),
+                Some(Canonicity::Orphaned)
+            );
+            assert_no_indexed_data(&db, block);
+        }
+    }
+    for height in 105489..=105496 {
+        assert_eq!(db.get_blocks_at_height(height).unwrap().len(), 1);
+    }
+
+    tokio::fs::remove_dir_all(store_dir).await.unwrap();
+}
+
+#[tokio::test]
+async fn prune_stops_at_missing_canonical_block() {
+    let store_dir = &PathBuf::from("./prune-orphans-gap-store-test");
+    let log_dir = &PathBuf::from("./tests/data/sequential_blocks");
+
+    let db = IndexerStore::new(store_dir).unwrap();
+    let mut bp = BlockParser::new(log_dir).unwrap();
+    while let Some(block) = bp.next().await.unwrap() {
+        db.add_block(&block).unwrap();
+    }
+
+    // the canonical block of length 105492 isn't known yet
+    let mut gap = None;
+    let mut state_hash =
+        BlockHash("3NKbLiBHzQrAimK7AkP8qAfQpHnezkdsSm8mkt2TzsbjsLN8Axmt".to_string());
+    while let Some(block) = db.get_block(&state_hash).unwrap() {
+        if block.blockchain_length == Some(105492) {
+            gap = Some(state_hash.clone());
+        } else {
+            db.set_canonicity(&state_hash, Canonicity::Canonical)
+                .unwrap();
+        }
+        state_hash = BlockHash::previous_state_hash(&block);
+    }
+
+    // the orphans above the gap are pruned once it's filled
+    let num_pruned = db.prune_orphaned_blocks(105496).unwrap();
+    assert!(db.get_blocks_at_height(105495).unwrap().len() > 1);
+
+    db.set_canonicity(&gap.unwrap(), Canonicity::Canonical)
+        .unwrap();
+    assert_eq!(num_pruned + db.prune_orphaned_blocks(105496).unwrap(), 14);
+    for height in 105489..=105496 {
+        assert_eq!(db.get_blocks_at_height(height).unwrap().len(), 1);
+    }
+
+    tokio::fs::remove_dir_all(store_dir).await.unwrap();
+}
+
+/// The pruned block's commands, internal commands and SNARK work are gone
+fn assert_no_indexed_data(db: &IndexerStore, block: &PrecomputedBlock) {
+    let state_hash = BlockHash(block.state_hash.clone());
+    for command in block.commands() {
+        let UserCommand::SignedCommand(signed_command) = UserCommandWithStatus(command).data();
+        let hash = SignedCommand(signed_command).hash_signed_command().unwrap();
+        if let Some(command) = db.get_command_by_hash(&hash).unwrap() {
+            assert_ne!(command.state_hash, state_hash);
+        }
+    }
+    assert!(db.get_internal_commands(&state_hash).unwrap().is_empty());
+    assert_eq!(
+        db.get_snark_work_in_block(&state_hash).unwrap(),
+        Vec::<SnarkWorkSummary>::new()
+    );
+}
@@ -0,0 +1,91 @@
+use std::path::PathBuf;
+
+use mina_indexer::{
+    block::{parser::BlockParser, store::BlockStore, BlockHash},
+    state::{canonicity::store::CanonicityStore, ledger::Ledger, Canonicity},
+    store::IndexerStore,
+};
+
+#[tokio::test]
+async fn rebuilds_canonicity() {
+    let store_dir = &PathBuf::from("./reindex-test");
+    let log_dir = &PathBuf::from("./tests/data/sequential_blocks");
+
+    let db = IndexerStore::new(store_dir).unwrap();
+    let mut bp = BlockParser::new(log_dir).unwrap();
+
+    let mut state_hashes = vec![];
+    while let Some(block) = bp.next().await.unwrap() {
+        db.add_block(&block).unwrap();
+        state_hashes.push(BlockHash(block.state_hash.clone()));
+    }
+
+    let root_hash = BlockHash("3NKeMoncuHab5ScarV5ViyF16cJPT4taWNSaTLS64Dp67wuXigPZ".to_string());
+    let num_blocks = db.reindex(&root_hash, Ledger::new(), 3).unwrap();
+    assert_eq!(num_blocks as usize, state_hashes.len());
+
+    // every block is assigned a canonicity
+    for state_hash in &state_hashes {
+        assert!(db.get_canonicity(state_hash).unwrap().is_some());
+    }
+
+    // best tip = mainnet-105498-3NKbLiBHzQrAimK7AkP8qAfQpHnezkdsSm8mkt2TzsbjsLN8Axmt.json
+    assert_eq!(
+        db.get_canonicity(&BlockHash(
+            "3NKbLiBHzQrAimK7AkP8qAfQpHnezkdsSm8mkt2TzsbjsLN8Axmt".to_string()
+        ))
+        .unwrap(),
+        Some(Canonicity::Pending)
+    );
+    assert!(state_hashes
+        .iter()
+        .any(|state_hash| db.get_canonicity(state_hash).unwrap() == Some(Canonicity::Canonical)));
+
+    tokio::fs::remove_dir_all(store_dir).await.unwrap();
+}
+
+/// Forks of equal length are chosen between by Mina's fork choice, not by state hash
+#[tokio::test]
+async fn chooses_forks_by_fork_choice() {
+    let store_dir = &PathBuf::from("./reindex-fork-choice-test");
+    let log_dir = &PathBuf::from("./tests/data/sequential_blocks");
+
+    let db = IndexerStore::new(store_dir).unwrap();
+    let mut bp = BlockParser::new(log_dir).unwrap();
+
+    // only the forks at length 105489
+    while let Some(block) = bp.next().await.unwrap() {
+        if block.blockchain_length == Some(105489) {
+            db.add_block(&block).unwrap();
+        }
+    }
+
+    // the forks don't connect to the root, so no ledgers are rebuilt
+    let root_hash = BlockHash("3NKeMoncuHab5ScarV5ViyF16cJPT4taWNSaTLS64Dp67wuXigPZ".to_string());
+    assert_eq!(db.reindex(&root_hash, Ledger::new(), 0).unwrap(), 3);
+
+    // the network built on the fork with the greatest last VRF output digest, which
+    // doesn't have the greatest state hash
+    for (state_hash, canonicity) in [
+        (
+            "3NK4huLvUDiL4XuCUcyrWCKynmvhqfKsx5h2MfBXVVUq2Qwzi5uT",
+            Canonicity::Canonical,
+        ),
+        (
+            "3NLFXtdzaFW2WX6KgrxMjL4enE4pCa9hAsVUPm47PT6337SXgBGh",
+            Canonicity::Orphaned,
+        ),
+        (
+            "3NLUfaHDcyt9KsYxi1xsSdYE369GAduLxVgRUDE7RuFgSXQBphDK",
+            Canonicity::Orphaned,
+        ),
+    ] {
+        assert_eq!(
+            db.get_canonicity(&BlockHash(state_hash.to_string()))
+                .unwrap(),
+            Some(canonicity)
+        );
+    }
+
+    tokio::fs::remove_dir_all(store_dir).await.unwrap();
+}
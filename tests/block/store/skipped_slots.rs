@@ -0,0 +1,60 @@
+use std::path::PathBuf;
+
+use mina_indexer::{
+    block::{parser::BlockParser, store::BlockStore, BlockHash},
+    state::{canonicity::store::CanonicityStore, Canonicity},
+    store::IndexerStore,
+};
+
+#[tokio::test]
+async fn skipped_slots_per_epoch() {
+    let log_dir = &PathBuf::from("./tests/data/sequential_blocks");
+
+    let db = IndexerStore::new_in_memory();
+    let mut bp = BlockParser::new(log_dir).unwrap();
+
+    while let Some(block) = bp.next().await.unwrap() {
+        db.add_block(&block).unwrap();
+    }
+    assert!(db.get_epoch_slots(None, true).unwrap().is_empty());
+
+    // global slots of the ancestors of 3NKbLiBHzQrAimK7AkP8qAfQpHnezkdsSm8mkt2TzsbjsLN8Axmt
+    let mut canonical_blocks = vec![];
+    let mut state_hash =
+        BlockHash("3NKbLiBHzQrAimK7AkP8qAfQpHnezkdsSm8mkt2TzsbjsLN8Axmt".to_string());
+    while let Some(block) = db.get_block(&state_hash).unwrap() {
+        canonical_blocks.push((state_hash, block.global_slot_since_genesis()));
+        state_hash = BlockHash::previous_state_hash(&block);
+    }
+    canonical_blocks.reverse();
+    for (state_hash, _) in &canonical_blocks {
+        db.set_canonicity(state_hash, Canonicity::Canonical)
+            .unwrap();
+    }
+
+    let slots: Vec<u32> = canonical_blocks.iter().map(|(_, slot)| *slot).collect();
+    let skipped_slots: Vec<u32> = slots
+        .windows(2)
+        .flat_map(|pair| pair[0] + 1..pair[1])
+        .collect();
+    let epoch = slots[0] / 7140;
+    assert_eq!(slots.last().unwrap() / 7140, epoch);
+
+    let epoch_slots = db.get_epoch_slots(None, true).unwrap();
+    assert_eq!(epoch_slots.len(), 1);
+    assert_eq!(epoch_slots[0].epoch, epoch);
+    assert_eq!(epoch_slots[0].canonical, slots.len() as u32);
+    assert_eq!(epoch_slots[0].skipped, skipped_slots.len() as u32);
+    assert_eq!(epoch_slots[0].skipped_slots, skipped_slots);
+
+    // skipped slots are only listed on request
+    let epoch_slots = db.get_epoch_slots(Some(epoch), false).unwrap();
+    assert_eq!(epoch_slots[0].skipped, skipped_slots.len() as u32);
+    assert!(epoch_slots[0].skipped_slots.is_empty());
+    for other_epoch in [epoch - 1, epoch + 1] {
+        assert!(db
+            .get_epoch_slots(Some(other_epoch), false)
+            .unwrap()
+            .is_empty());
+    }
+}
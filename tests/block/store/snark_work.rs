@@ -0,0 +1,83 @@
+use std::path::PathBuf;
+
+use mina_indexer::{
+    block::{parser::BlockParser, store::BlockStore, BlockHash},
+    state::{
+        canonicity::store::CanonicityStore,
+        ledger::{public_key::PublicKey, snark_work::store::SnarkStore},
+        Canonicity,
+    },
+    store::IndexerStore,
+};
+
+#[tokio::test]
+async fn add_and_get_snark_work() {
+    let store_dir = &PathBuf::from("./snark-store-test");
+    let log_dir = &PathBuf::from("./tests/data/sequential_blocks");
+
+    let db = IndexerStore::new(store_dir).unwrap();
+    let mut bp = BlockParser::new(log_dir).unwrap();
+
+    // mainnet-105493-3NKakum3B2Tigw9TSsxwvXvV3x8L2LvrJ3yXFLEAJDMZu2vkn7db.json
+    let block = bp
+        .get_precomputed_block("3NKakum3B2Tigw9TSsxwvXvV3x8L2LvrJ3yXFLEAJDMZu2vkn7db")
+        .await
+        .unwrap();
+    db.add_snark_work(&block).unwrap();
+
+    let state_hash = BlockHash(block.state_hash.clone());
+    assert_eq!(db.get_snark_work_in_block(&state_hash).unwrap().len(), 28);
+
+    // prover of 19 of the block's SNARK works
+    let prover =
+        PublicKey::from_address("B62qs2Lw5WZNSjd8eHBUZXFYyRjV8oKtrZMFDn1S1Ye62G71xCQJMYM").unwrap();
+    let snark_work = db.get_snark_work_by_prover(&prover).unwrap();
+    assert_eq!(snark_work.len(), 19);
+    assert!(snark_work.iter().all(|work| work.state_hash == state_hash
+        && work.prover == prover
+        && work.blockchain_length == Some(105493)));
+
+    tokio::fs::remove_dir_all(store_dir).await.unwrap();
+}
+
+#[tokio::test]
+async fn canonical_snark_work_by_prover() {
+    let store_dir = &PathBuf::from("./canonical-snark-work-test");
+    let log_dir = &PathBuf::from("./tests/data/sequential_blocks");
+
+    let db = IndexerStore::new(store_dir).unwrap();
+    let mut bp = BlockParser::new(log_dir).unwrap();
+
+    // mainnet-105493-3NKakum3B2Tigw9TSsxwvXvV3x8L2LvrJ3yXFLEAJDMZu2vkn7db.json
+    let block = bp
+        .get_precomputed_block("3NKakum3B2Tigw9TSsxwvXvV3x8L2LvrJ3yXFLEAJDMZu2vkn7db")
+        .await
+        .unwrap();
+    db.add_block(&block).unwrap();
+
+    // only SNARK work of canonical blocks is counted
+    let prover =
+        PublicKey::from_address("B62qs2Lw5WZNSjd8eHBUZXFYyRjV8oKtrZMFDn1S1Ye62G71xCQJMYM").unwrap();
+    let snark_work = db.get_canonical_snark_work_by_prover(&prover).unwrap();
+    assert!(snark_work.snark_work.is_empty());
+    assert!(snark_work.epoch_fees.is_empty());
+
+    let state_hash = BlockHash(block.state_hash.clone());
+    db.set_canonicity(&state_hash, Canonicity::Canonical)
+        .unwrap();
+    let snark_work = db.get_canonical_snark_work_by_prover(&prover).unwrap();
+    assert_eq!(snark_work.snark_work.len(), 19);
+    assert_eq!(snark_work.epoch_fees.len(), 1);
+    assert_eq!(snark_work.epoch_fees[0].epoch, block.epoch());
+    assert_eq!(snark_work.epoch_fees[0].num_works, 19);
+    assert_eq!(
+        snark_work.epoch_fees[0].total_fees,
+        snark_work
+            .snark_work
+            .iter()
+            .map(|work| work.fee.0)
+            .sum::<u64>()
+    );
+
+    tokio::fs::remove_dir_all(store_dir).await.unwrap();
+}
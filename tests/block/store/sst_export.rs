@@ -0,0 +1,54 @@
+use std::path::PathBuf;
+
+use mina_indexer::{
+    block::{parser::BlockParser, store::BlockStore, BlockHash},
+    state::{canonicity::store::CanonicityStore, ledger::Ledger},
+    store::IndexerStore,
+};
+
+#[tokio::test]
+async fn export_and_import_blocks() {
+    let store_dir = &PathBuf::from("./sst-export-store-test");
+    let import_dir = &PathBuf::from("./sst-export-import-store-test");
+    let export_dir = &PathBuf::from("./sst-export-test");
+    let log_dir = &PathBuf::from("./tests/data/sequential_blocks");
+
+    let db = IndexerStore::new(store_dir).unwrap();
+    let mut bp = BlockParser::new(log_dir).unwrap();
+
+    let mut state_hashes = vec![];
+    while let Some(block) = bp.next().await.unwrap() {
+        db.add_block(&block).unwrap();
+        state_hashes.push(BlockHash(block.state_hash));
+    }
+
+    let num_blocks = db.export_blocks(export_dir).unwrap();
+    assert_eq!(num_blocks as usize, state_hashes.len());
+    // the destination must not exist
+    assert!(db.export_blocks(export_dir).is_err());
+
+    let imported = IndexerStore::new(import_dir).unwrap();
+    imported.import_blocks(export_dir).unwrap();
+    for state_hash in &state_hashes {
+        assert_eq!(
+            imported.get_block(state_hash).unwrap(),
+            db.get_block(state_hash).unwrap()
+        );
+        // derived data is only rebuilt by reindexing
+        assert_eq!(imported.get_canonicity(state_hash).unwrap(), None);
+    }
+
+    let root_hash = BlockHash("3NKeMoncuHab5ScarV5ViyF16cJPT4taWNSaTLS64Dp67wuXigPZ".to_string());
+    let num_reindexed = imported.reindex(&root_hash, Ledger::new(), 3).unwrap();
+    assert_eq!(num_reindexed as usize, state_hashes.len());
+    for state_hash in &state_hashes {
+        assert!(imported.get_canonicity(state_hash).unwrap().is_some());
+    }
+
+    // nothing to import from a directory without exported blocks
+    assert!(imported.import_blocks(store_dir).is_err());
+
+    tokio::fs::remove_dir_all(store_dir).await.unwrap();
+    tokio::fs::remove_dir_all(import_dir).await.unwrap();
+    tokio::fs::remove_dir_all(export_dir).await.unwrap();
+}
@@ -0,0 +1,65 @@
+use mina_indexer::{
+    block::{parser::BlockParser, store::BlockStore, BlockHash},
+    state::{
+        canonicity::store::CanonicityStore,
+        ledger::{store::LedgerStore, Ledger},
+        Canonicity,
+    },
+    store::IndexerStore,
+};
+use std::{path::PathBuf, time::Duration};
+
+#[tokio::test]
+async fn remove_stale_data() {
+    let store_dir = &PathBuf::from("./stale-data-store-test");
+    let log_dir = &PathBuf::from("./tests/data/sequential_blocks");
+
+    let db = IndexerStore::new(store_dir).unwrap();
+    let mut bp = BlockParser::new(log_dir).unwrap();
+    while let Some(block) = bp.next().await.unwrap() {
+        db.add_block(&block).unwrap();
+    }
+
+    // mainnet-105498-3NKbLiBHzQrAimK7AkP8qAfQpHnezkdsSm8mkt2TzsbjsLN8Axmt.json
+    let pending = BlockHash("3NKbLiBHzQrAimK7AkP8qAfQpHnezkdsSm8mkt2TzsbjsLN8Axmt".to_string());
+    // mainnet-105497-3NKjngJTXJzRUXF3uH2nK19iYUVtYBFjLhezSrMMFVQyEGwqEi3c.json
+    let canonical = BlockHash("3NKjngJTXJzRUXF3uH2nK19iYUVtYBFjLhezSrMMFVQyEGwqEi3c".to_string());
+    // mainnet-105489-3NK4huLvUDiL4XuCUcyrWCKynmvhqfKsx5h2MfBXVVUq2Qwzi5uT.json
+    let orphaned = BlockHash("3NK4huLvUDiL4XuCUcyrWCKynmvhqfKsx5h2MfBXVVUq2Qwzi5uT".to_string());
+
+    db.set_canonicity(&pending, Canonicity::Pending).unwrap();
+    db.set_canonicity(&canonical, Canonicity::Canonical)
+        .unwrap();
+    db.set_canonicity(&orphaned, Canonicity::Orphaned).unwrap();
+    db.add_ledger(&canonical, Ledger::new()).unwrap();
+    db.add_ledger(&orphaned, Ledger::new()).unwrap();
+
+    // leftover read-only instance directory
+    let secondary_dir = store_dir.join("5b1d6e0c-0d5e-4a8e-9d4f-7a1c2b3d4e5f");
+    std::fs::create_dir(&secondary_dir).unwrap();
+
+    // nothing is old enough
+    let one_hundred_years = Duration::from_secs(100 * 365 * 24 * 60 * 60);
+    assert_eq!(db.remove_stale_data(one_hundred_years).unwrap(), 0);
+    assert!(secondary_dir.exists());
+
+    // the test blocks are long past any small max age
+    std::thread::sleep(Duration::from_millis(10));
+    assert_eq!(db.remove_stale_data(Duration::from_millis(1)).unwrap(), 3);
+    assert_eq!(db.get_canonicity(&pending).unwrap(), None);
+    assert!(db.get_ledger(&orphaned).unwrap().is_none());
+    assert!(!secondary_dir.exists());
+
+    // canonical data is kept
+    assert_eq!(
+        db.get_canonicity(&canonical).unwrap(),
+        Some(Canonicity::Canonical)
+    );
+    assert!(db.get_ledger(&canonical).unwrap().is_some());
+    assert_eq!(
+        db.get_canonicity(&orphaned).unwrap(),
+        Some(Canonicity::Orphaned)
+    );
+
+    tokio::fs::remove_dir_all(store_dir).await.unwrap();
+}
@@ -0,0 +1,41 @@
+use std::path::PathBuf;
+
+use mina_indexer::{
+    block::parser::BlockParser,
+    store::{IndexerStore, COLUMN_FAMILIES},
+};
+
+#[tokio::test]
+async fn typed_db_stats() {
+    let store_dir = &PathBuf::from("./stats-store-test");
+    let log_dir = &PathBuf::from("./tests/data/sequential_blocks");
+
+    let db = IndexerStore::new(store_dir).unwrap();
+    let mut bp = BlockParser::new(log_dir).unwrap();
+
+    let mut block_count = 0;
+    while let Some(block) = bp.next().await.unwrap() {
+        db.add_block(&block).unwrap();
+        block_count += 1;
+    }
+
+    let stats = db.stats().unwrap().unwrap();
+    assert_eq!(stats.column_families.len(), COLUMN_FAMILIES.len() + 1);
+    assert_eq!(
+        stats.num_keys,
+        stats.column_families.iter().map(|cf| cf.num_keys).sum()
+    );
+
+    let blocks = stats
+        .column_families
+        .iter()
+        .find(|cf| cf.name == "blocks")
+        .unwrap();
+    assert_eq!(blocks.num_keys, block_count);
+    assert!(blocks.memtables_size > 0);
+
+    // the in-memory backend doesn't provide stats
+    assert_eq!(IndexerStore::new_in_memory().stats().unwrap(), None);
+
+    tokio::fs::remove_dir_all(store_dir).await.unwrap();
+}
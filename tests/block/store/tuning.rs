@@ -0,0 +1,45 @@
+use std::path::PathBuf;
+
+use mina_indexer::{
+    block::{parser::BlockParser, store::BlockStore, BlockHash},
+    store::{rocks_db::RocksDbTuningConfiguration, IndexerStore},
+};
+
+#[tokio::test]
+async fn tuned_store() {
+    let store_dir = &PathBuf::from("./tuning-store-test");
+    let config_path = &PathBuf::from("./tuning-store-test.json");
+    let log_dir = &PathBuf::from("./tests/data/sequential_blocks");
+
+    // options left out keep their defaults
+    tokio::fs::write(
+        config_path,
+        r#"{ "block_cache_size": 1048576, "max_background_jobs": 4 }"#,
+    )
+    .await
+    .unwrap();
+    let tuning = RocksDbTuningConfiguration::from_file(config_path).unwrap();
+    assert_eq!(
+        tuning,
+        RocksDbTuningConfiguration {
+            block_cache_size: 1024 * 1024,
+            max_background_jobs: 4,
+            ..Default::default()
+        }
+    );
+
+    let db = IndexerStore::new_with_tuning(store_dir, &tuning).unwrap();
+    let mut bp = BlockParser::new(log_dir).unwrap();
+    while let Some(block) = bp.next().await.unwrap() {
+        db.add_block(&block).unwrap();
+        assert!(db.exists(&BlockHash(block.state_hash)).unwrap());
+    }
+
+    tokio::fs::write(config_path, r#"{ "max_open_files": "all" }"#)
+        .await
+        .unwrap();
+    assert!(RocksDbTuningConfiguration::from_file(config_path).is_err());
+
+    tokio::fs::remove_file(config_path).await.unwrap();
+    tokio::fs::remove_dir_all(store_dir).await.unwrap();
+}
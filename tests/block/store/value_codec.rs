@@ -0,0 +1,69 @@
+use std::path::PathBuf;
+
+use mina_indexer::{
+    block::{parser::BlockParser, store::BlockStore, BlockHash},
+    state::{canonicity::store::CanonicityStore, Canonicity},
+    store::{
+        codec::{self, ValueCodec},
+        IndexerStore,
+    },
+};
+
+#[test]
+fn envelopes() {
+    let value = (
+        BlockHash("3NKbLiBHzQrAimK7AkP8qAfQpHnezkdsSm8mkt2TzsbjsLN8Axmt".to_string()),
+        105498u32,
+    );
+    for value_codec in [ValueCodec::Bcs, ValueCodec::Bincode, ValueCodec::Cbor] {
+        let bytes = value_codec.encode(&value).unwrap();
+        assert_eq!(codec::decode::<(BlockHash, u32)>(&bytes).unwrap(), value);
+    }
+
+    // values written before envelopes were introduced
+    let bytes = bcs::to_bytes(&value).unwrap();
+    assert_eq!(codec::decode::<(BlockHash, u32)>(&bytes).unwrap(), value);
+
+    // unknown format versions are rejected
+    let mut bytes = ValueCodec::Bcs.encode(&Canonicity::Canonical).unwrap();
+    bytes[1] = codec::VALUE_FORMAT_VERSION + 1;
+    assert!(codec::decode::<Canonicity>(&bytes).is_err());
+}
+
+#[tokio::test]
+async fn switch_codecs() {
+    let store_dir = &PathBuf::from("./value-codec-test");
+    let log_dir = &PathBuf::from("./tests/data/sequential_blocks");
+
+    let mut bp = BlockParser::new(log_dir).unwrap();
+    let mut blocks = vec![];
+    while let Some(block) = bp.next().await.unwrap() {
+        blocks.push(block);
+    }
+
+    // each third of the blocks is written with a different codec
+    let value_codecs = [ValueCodec::Bcs, ValueCodec::Bincode, ValueCodec::Cbor];
+    for (n, chunk) in blocks.chunks(blocks.len() / 3 + 1).enumerate() {
+        let db = IndexerStore::new(store_dir)
+            .unwrap()
+            .with_value_codec(value_codecs[n]);
+        for block in chunk {
+            let state_hash = BlockHash(block.state_hash.clone());
+            db.add_block(block).unwrap();
+            db.set_canonicity(&state_hash, Canonicity::Pending).unwrap();
+        }
+    }
+
+    let db = IndexerStore::new(store_dir).unwrap();
+    for block in &blocks {
+        let state_hash = BlockHash(block.state_hash.clone());
+        assert_eq!(db.get_block(&state_hash).unwrap().as_ref(), Some(block));
+        assert_eq!(
+            db.get_canonicity(&state_hash).unwrap(),
+            Some(Canonicity::Pending)
+        );
+    }
+    assert!(db.verify().unwrap().is_empty());
+
+    tokio::fs::remove_dir_all(store_dir).await.unwrap();
+}
@@ -0,0 +1,118 @@
+use std::path::PathBuf;
+
+use mina_indexer::{
+    block::{parser::BlockParser, store::BlockStore, BlockHash},
+    state::{
+        canonicity::store::CanonicityStore,
+        ledger::{
+            account::Amount, balance_history::store::BalanceStore, command::store::CommandStore,
+            internal_command::store::InternalCommandStore, snark_work::store::SnarkStore,
+            store::LedgerStore, Ledger,
+        },
+        Canonicity,
+    },
+    store::IndexerStore,
+};
+
+#[tokio::test]
+async fn verify_store() {
+    let store_dir = &PathBuf::from("./verify-store-test");
+    let log_dir = &PathBuf::from("./tests/data/sequential_blocks");
+
+    let db = IndexerStore::new(store_dir).unwrap();
+    let mut bp = BlockParser::new(log_dir).unwrap();
+
+    while let Some(block) = bp.next().await.unwrap() {
+        db.add_block(&block).unwrap();
+    }
+
+    // ancestors of 3NKbLiBHzQrAimK7AkP8qAfQpHnezkdsSm8mkt2TzsbjsLN8Axmt
+    let mut canonical_blocks = vec![];
+    let mut state_hash =
+        BlockHash("3NKbLiBHzQrAimK7AkP8qAfQpHnezkdsSm8mkt2TzsbjsLN8Axmt".to_string());
+    while let Some(block) = db.get_block(&state_hash).unwrap() {
+        canonical_blocks.push((state_hash, block.blockchain_length.unwrap()));
+        state_hash = BlockHash::previous_state_hash(&block);
+    }
+
+    // skip the canonical block of length 105495
+    for (state_hash, _) in canonical_blocks
+        .iter()
+        .filter(|(_, length)| *length != 105495)
+    {
+        db.set_canonicity(state_hash, Canonicity::Canonical)
+            .unwrap();
+    }
+    assert_eq!(
+        db.verify().unwrap(),
+        vec!["Canonical chain has a gap between lengths 105494 and 105496".to_string()]
+    );
+
+    // fill the gap
+    for (state_hash, _) in &canonical_blocks {
+        db.set_canonicity(state_hash, Canonicity::Canonical)
+            .unwrap();
+    }
+    assert!(db.verify().unwrap().is_empty());
+
+    // replay needs a snapshot to start from
+    assert_eq!(
+        db.replay_ledgers().unwrap(),
+        vec!["No ledger snapshot on the canonical chain to replay from".to_string()]
+    );
+
+    // snapshot the root and a corrupted tip ledger
+    let (tip_hash, tip_length) = canonical_blocks.first().cloned().unwrap();
+    let (root_hash, _) = canonical_blocks.last().cloned().unwrap();
+    db.add_ledger(&root_hash, Ledger::new()).unwrap();
+
+    let mut tip_ledger = db.get_ledger(&tip_hash).unwrap().unwrap();
+    let account = tip_ledger.accounts.values_mut().next().unwrap();
+    let address = account.public_key.to_address();
+    account.balance = Amount(account.balance.0 + 1);
+    db.add_ledger(&tip_hash, tip_ledger).unwrap();
+
+    let snapshot_problems: Vec<String> = db
+        .replay_ledgers()
+        .unwrap()
+        .into_iter()
+        .filter(|problem| problem.starts_with("Ledger snapshot"))
+        .collect();
+    assert_eq!(
+        snapshot_problems,
+        vec![format!(
+            "Ledger snapshot of canonical block {tip_hash:?} of length {tip_length} differs from the replayed ledger in 1 accounts: {address}"
+        )]
+    );
+
+    tokio::fs::remove_dir_all(store_dir).await.unwrap();
+}
+
+#[tokio::test]
+async fn verify_dangling_block_data() {
+    let log_dir = &PathBuf::from("./tests/data/sequential_blocks");
+    let db = IndexerStore::new_in_memory();
+    let mut bp = BlockParser::new(log_dir).unwrap();
+    let block = bp.next().await.unwrap().unwrap();
+    db.add_block(&block).unwrap();
+    assert!(db.verify().unwrap().is_empty());
+
+    // data of a block which isn't stored
+    let mut missing_block = block.clone();
+    missing_block.state_hash = "3NKn7ZtT6Axw3hK3HpyUGRxmirkuUhtR4cYzWFk75NCgmjCcqPby".to_string();
+    let missing_hash = BlockHash(missing_block.state_hash.clone());
+    db.add_commands(&missing_block).unwrap();
+    db.add_internal_commands(&missing_block).unwrap();
+    db.add_snark_work(&missing_block).unwrap();
+    db.add_balances(&missing_block).unwrap();
+    db.add_ledger(&missing_hash, Ledger::new()).unwrap();
+
+    let problems = db.verify().unwrap();
+    let missing = format!("points at missing block {missing_hash:?}");
+    assert!(problems.iter().all(|problem| problem.ends_with(&missing)));
+    for cf in ["internal-commands", "snarks", "ledgers"] {
+        assert!(problems
+            .iter()
+            .any(|problem| problem.contains(&format!(" of {cf} points"))));
+    }
+}
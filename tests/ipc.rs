@@ -0,0 +1,399 @@
+use futures::io::{BufReader, Cursor};
+use interprocess::local_socket::tokio::LocalSocketListener;
+use mina_indexer::{
+    client::Client,
+    error::IndexerError,
+    ipc::{
+        auth_token_matches, read_frame, read_frame_payload, read_request_payload, refuse_conn,
+        write_frame, BlockId, ConnLimits, IpcRequest, IpcRequestFrame, IpcResponse,
+        IPC_PROTOCOL_VERSION, MAX_FRAME_LEN, MAX_REQUEST_FRAME_LEN,
+    },
+};
+use std::{
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+use tokio::net::{TcpListener, TcpStream};
+use tokio_util::compat::{TokioAsyncReadCompatExt, TokioAsyncWriteCompatExt};
+
+#[tokio::test]
+async fn frames_round_trip() {
+    let requests = vec![
+        IpcRequest::BestLedger {
+            // arguments may contain spaces
+            path: PathBuf::from("/tmp/best ledger.txt"),
+        },
+        IpcRequest::Compact {
+            column_family: None,
+        },
+        IpcRequest::Summary { verbosity: 2 },
+        IpcRequest::Subscribe,
+    ];
+
+    let mut buffer = Cursor::new(vec![]);
+    for request in &requests {
+        write_frame(&mut buffer, request).await.unwrap();
+    }
+
+    buffer.set_position(0);
+    for request in &requests {
+        let read: IpcRequest = read_frame(&mut buffer).await.unwrap().unwrap();
+        assert_eq!(&read, request);
+    }
+    // end of stream between frames
+    assert_eq!(
+        read_frame::<_, IpcRequest>(&mut buffer).await.unwrap(),
+        None
+    );
+}
+
+#[tokio::test]
+async fn malformed_frames() {
+    // truncated payload
+    let mut buffer = Cursor::new(vec![0, 0, 0, 8, 1]);
+    assert!(read_frame::<_, IpcRequest>(&mut buffer).await.is_err());
+
+    // unknown request variant
+    let mut buffer = Cursor::new(vec![0, 0, 0, 1, 99]);
+    assert!(read_frame::<_, IpcRequest>(&mut buffer).await.is_err());
+
+    // frames longer than the max length aren't read
+    let mut buffer = Cursor::new((MAX_FRAME_LEN + 1).to_be_bytes().to_vec());
+    assert!(read_frame::<_, IpcRequest>(&mut buffer).await.is_err());
+
+    // requests are held to a much smaller max length than responses
+    let mut frame = (MAX_REQUEST_FRAME_LEN + 1).to_be_bytes().to_vec();
+    frame.resize(frame.len() + MAX_REQUEST_FRAME_LEN as usize + 1, 0);
+    assert!(read_request_payload(&mut Cursor::new(frame.clone()))
+        .await
+        .is_err());
+    assert!(read_frame_payload(&mut Cursor::new(frame))
+        .await
+        .unwrap()
+        .is_some());
+}
+
+#[tokio::test]
+async fn requests_share_a_connection() {
+    let socket_name = "@mina-indexer-test-keep-alive.sock";
+    let listener = LocalSocketListener::bind(socket_name).unwrap();
+
+    // answers each request with its debug output until the client hangs up
+    let server = tokio::spawn(async move {
+        let (reader, mut writer) = listener.accept().await.unwrap().into_split();
+        let mut reader = BufReader::new(reader);
+        let mut num_requests = 0;
+        while let Some(frame) = read_frame::<_, IpcRequestFrame>(&mut reader).await.unwrap() {
+            assert_eq!(frame.auth_token, None);
+            let response = IpcResponse::Message(format!("{:?}", frame.request));
+            write_frame(&mut writer, &response).await.unwrap();
+            num_requests += 1;
+        }
+        num_requests
+    });
+
+    let mut client = Client::connect(socket_name).await.unwrap();
+    for num in 0..3 {
+        let request = IpcRequest::BestChain { num };
+        match client.request(&request).await.unwrap() {
+            IpcResponse::Message(msg) => assert_eq!(msg, format!("{request:?}")),
+            response => panic!("Unexpected response {response:?}"),
+        }
+    }
+    drop(client);
+    assert_eq!(server.await.unwrap(), 3);
+}
+
+#[tokio::test]
+async fn requests_carry_the_auth_token() {
+    let socket_name = "@mina-indexer-test-auth-token.sock";
+    let listener = LocalSocketListener::bind(socket_name).unwrap();
+
+    let server = tokio::spawn(async move {
+        let (reader, mut writer) = listener.accept().await.unwrap().into_split();
+        let mut reader = BufReader::new(reader);
+        let frame: IpcRequestFrame = read_frame(&mut reader).await.unwrap().unwrap();
+        write_frame(&mut writer, &IpcResponse::Message(String::new()))
+            .await
+            .unwrap();
+        frame.auth_token
+    });
+
+    let mut client = Client::connect(socket_name)
+        .await
+        .unwrap()
+        .with_auth_token("secret".to_string());
+    client.request(&IpcRequest::Pause).await.unwrap();
+    assert_eq!(server.await.unwrap(), Some("secret".to_string()));
+}
+
+#[tokio::test]
+async fn tcp_requests_are_authenticated() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+
+    // answers requests only when they carry the token, as the server does
+    let server = tokio::spawn(async move {
+        for _ in 0..2 {
+            let (reader, writer) = listener.accept().await.unwrap().0.into_split();
+            let mut reader = BufReader::new(reader.compat());
+            let mut writer = writer.compat_write();
+            let frame: IpcRequestFrame = read_frame(&mut reader).await.unwrap().unwrap();
+            let response = if auth_token_matches("secret", frame.auth_token.as_deref()) {
+                IpcResponse::Message(format!("{:?}", frame.request))
+            } else {
+                IpcResponse::Error("Unauthorized request".to_string())
+            };
+            write_frame(&mut writer, &response).await.unwrap();
+        }
+    });
+
+    let mut client = Client::connect_tcp(address)
+        .await
+        .unwrap()
+        .with_auth_token("secret".to_string());
+    match client.request(&IpcRequest::Pause).await.unwrap() {
+        IpcResponse::Message(msg) => assert_eq!(msg, format!("{:?}", IpcRequest::Pause)),
+        response => panic!("Unexpected response {response:?}"),
+    }
+
+    let mut client = Client::connect_tcp(address).await.unwrap();
+    assert!(matches!(
+        client.request(&IpcRequest::Pause).await.unwrap(),
+        IpcResponse::Error(_)
+    ));
+    server.await.unwrap();
+}
+
+/// Answers each request of the connection with its debug output, paced and within the
+/// max number of connections as the server does
+async fn serve_limited_conn(stream: TcpStream, limits: ConnLimits) {
+    let (reader, writer) = stream.into_split();
+    let mut reader = BufReader::new(reader.compat());
+    let mut writer = writer.compat_write();
+    let _permit = match limits.try_open() {
+        Some(permit) => permit,
+        None => return refuse_conn(&mut reader, &mut writer).await,
+    };
+    let mut request_ticks = limits.request_ticks();
+    while let Some(frame) = read_frame::<_, IpcRequestFrame>(&mut reader).await.unwrap() {
+        if let Some(request_ticks) = request_ticks.as_mut() {
+            request_ticks.tick().await;
+        }
+        let response = IpcResponse::Message(format!("{:?}", frame.request));
+        write_frame(&mut writer, &response).await.unwrap();
+    }
+}
+
+#[tokio::test]
+async fn connections_beyond_the_max_are_refused() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    let max_connections = 2;
+    let limits = ConnLimits::new(max_connections, None);
+    tokio::spawn(async move {
+        loop {
+            let (stream, _) = listener.accept().await.unwrap();
+            tokio::spawn(serve_limited_conn(stream, limits.clone()));
+        }
+    });
+
+    let mut clients = vec![];
+    for _ in 0..max_connections {
+        let mut client = Client::connect_tcp(address).await.unwrap();
+        assert!(matches!(
+            client.request(&IpcRequest::Health).await.unwrap(),
+            IpcResponse::Message(_)
+        ));
+        clients.push(client);
+    }
+    let mut client = Client::connect_tcp(address).await.unwrap();
+    match client.request(&IpcRequest::Health).await.unwrap() {
+        IpcResponse::Error(msg) => assert_eq!(msg, "Too many connections, try again later"),
+        response => panic!("Unexpected response {response:?}"),
+    }
+
+    // a closed connection makes room for another, once the server notices
+    drop(clients.pop());
+    let mut accepted = false;
+    for _ in 0..50 {
+        let mut client = Client::connect_tcp(address).await.unwrap();
+        if let IpcResponse::Message(_) = client.request(&IpcRequest::Health).await.unwrap() {
+            accepted = true;
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    assert!(accepted);
+}
+
+#[tokio::test]
+async fn requests_are_rate_limited() {
+    assert_eq!(ConnLimits::min_request_interval(0), None);
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    let max_requests_per_sec = 20;
+    let limits = ConnLimits::new(1, ConnLimits::min_request_interval(max_requests_per_sec));
+    tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        serve_limited_conn(stream, limits).await;
+    });
+
+    // the first request is answered at once, each later one a tick after the previous one
+    let num_requests = 11;
+    let mut client = Client::connect_tcp(address).await.unwrap();
+    let start = Instant::now();
+    for _ in 0..num_requests {
+        assert!(matches!(
+            client.request(&IpcRequest::Health).await.unwrap(),
+            IpcResponse::Message(_)
+        ));
+    }
+    assert!(start.elapsed() >= Duration::from_secs(1) / max_requests_per_sec * (num_requests - 1));
+}
+
+#[tokio::test]
+async fn protocol_versions() {
+    // the version is read without the rest of the frame
+    let frame = IpcRequestFrame {
+        protocol_version: 0,
+        auth_token: Some("secret".to_string()),
+        request: IpcRequest::Health,
+    };
+    let mut buffer = Cursor::new(vec![]);
+    write_frame(&mut buffer, &frame).await.unwrap();
+    buffer.set_position(0);
+    let payload = read_frame_payload(&mut buffer).await.unwrap().unwrap();
+    assert_eq!(IpcRequestFrame::protocol_version(&payload).unwrap(), 0);
+    assert!(IpcRequestFrame::protocol_version(&payload[..3]).is_err());
+
+    // the client sends its version and reports the server's refusal
+    let socket_name = "@mina-indexer-test-protocol-version.sock";
+    let listener = LocalSocketListener::bind(socket_name).unwrap();
+    let server = tokio::spawn(async move {
+        let (reader, mut writer) = listener.accept().await.unwrap().into_split();
+        let mut reader = BufReader::new(reader);
+        let payload = read_frame_payload(&mut reader).await.unwrap().unwrap();
+        let client_version = IpcRequestFrame::protocol_version(&payload).unwrap();
+        let response = IpcResponse::UnsupportedVersion {
+            server_version: client_version + 1,
+            client_version,
+        };
+        write_frame(&mut writer, &response).await.unwrap();
+        client_version
+    });
+
+    let mut client = Client::connect(socket_name).await.unwrap();
+    let err = client.request(&IpcRequest::Health).await.unwrap_err();
+    assert!(err.to_string().contains("upgrade your client"));
+    assert_eq!(server.await.unwrap(), IPC_PROTOCOL_VERSION);
+}
+
+#[test]
+fn auth_tokens() {
+    assert!(auth_token_matches("secret", Some("secret")));
+    assert!(!auth_token_matches("secret", Some("secreT")));
+    assert!(!auth_token_matches("secret", Some("secret1")));
+    assert!(!auth_token_matches("secret", None));
+}
+
+#[test]
+fn block_ids() {
+    assert_eq!("105489".parse::<BlockId>(), Ok(BlockId::Length(105489)));
+    assert_eq!(
+        "3NK4huLvUDiL4XuCUcyrWCKynmvhqfKsx5h2MfBXVVUq2Qwzi5uT".parse::<BlockId>(),
+        Ok(BlockId::StateHash(
+            "3NK4huLvUDiL4XuCUcyrWCKynmvhqfKsx5h2MfBXVVUq2Qwzi5uT".to_string()
+        ))
+    );
+}
+
+#[tokio::test]
+async fn streamed_responses() {
+    let socket_name = "@mina-indexer-test-stream.sock";
+    let listener = LocalSocketListener::bind(socket_name).unwrap();
+
+    // answers with chunks, then the end of the stream
+    tokio::spawn(async move {
+        let (reader, mut writer) = listener.accept().await.unwrap().into_split();
+        let mut reader = BufReader::new(reader);
+        read_frame::<_, IpcRequestFrame>(&mut reader)
+            .await
+            .unwrap()
+            .unwrap();
+        for _ in 0..3 {
+            write_frame(&mut writer, &IpcResponse::BestChain(vec![]))
+                .await
+                .unwrap();
+        }
+        write_frame(&mut writer, &IpcResponse::EndOfStream)
+            .await
+            .unwrap();
+    });
+
+    let mut client = Client::connect(socket_name).await.unwrap();
+    let mut num_chunks = 0;
+    client
+        .request_stream(&IpcRequest::BestChain { num: 500 }, |chunk| {
+            assert!(matches!(chunk, IpcResponse::BestChain(_)));
+            num_chunks += 1;
+            Ok(())
+        })
+        .await
+        .unwrap();
+    assert_eq!(num_chunks, 3);
+}
+
+#[tokio::test]
+async fn failed_requests() {
+    let socket_name = "@mina-indexer-test-failed.sock";
+    let listener = LocalSocketListener::bind(socket_name).unwrap();
+
+    // a chunk, then the stream fails
+    tokio::spawn(async move {
+        let (reader, mut writer) = listener.accept().await.unwrap().into_split();
+        let mut reader = BufReader::new(reader);
+        read_frame::<_, IpcRequestFrame>(&mut reader)
+            .await
+            .unwrap()
+            .unwrap();
+        write_frame(&mut writer, &IpcResponse::BestChain(vec![]))
+            .await
+            .unwrap();
+        let failed = IpcResponse::Failed {
+            error: IndexerError::MissingBlock("3NKunknown".to_string()),
+            request_id: "1.1".to_string(),
+        };
+        write_frame(&mut writer, &failed).await.unwrap();
+    });
+
+    // the client gets the typed error
+    let mut client = Client::connect(socket_name).await.unwrap();
+    let err = client
+        .request_stream(&IpcRequest::BestChain { num: 500 }, |_| Ok(()))
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err.downcast_ref::<IndexerError>(),
+        Some(&IndexerError::MissingBlock("3NKunknown".to_string()))
+    );
+    assert!(format!("{err:#}").contains("Request 1.1 failed: Block 3NKunknown not found"));
+}
+
+#[test]
+fn typed_errors() {
+    // typed errors survive being passed around as anyhow errors
+    let err: anyhow::Error = IndexerError::MissingStakingLedger(42).into();
+    assert_eq!(
+        IndexerError::from(err),
+        IndexerError::MissingStakingLedger(42)
+    );
+
+    // other errors keep their message
+    let err = anyhow::Error::msg("Database unavailable");
+    assert_eq!(
+        IndexerError::from(err),
+        IndexerError::Other("Database unavailable".to_string())
+    );
+}
@@ -1,2 +1,5 @@
 mod block;
+mod ipc;
+mod server;
 mod state;
+mod support;
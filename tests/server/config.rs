@@ -0,0 +1,160 @@
+use clap::Parser;
+use mina_indexer::{
+    block::{parser::BlockParser, store::BlockStore},
+    network::Network,
+    server::{
+        check_database_network, handle_command_line_arguments, set_socket_permissions, ServerArgs,
+    },
+    store::IndexerStore,
+};
+use std::{ffi::OsString, os::unix::fs::PermissionsExt, path::PathBuf};
+
+#[tokio::test]
+async fn command_line_overrides_config_file() {
+    let test_dir = PathBuf::from("./config-file-test");
+    tokio::fs::create_dir_all(&test_dir).await.unwrap();
+    let config_path = test_dir.join("config.toml");
+    tokio::fs::write(
+        &config_path,
+        r#"
+ledger = "./tests/data/genesis_ledgers/mainnet.json"
+database_dir = "./config-file-test/file-database"
+startup-dir = "./config-file-test/startup-blocks"
+watch_dir = ["./config-file-test/watch-blocks"]
+log_dir = "./config-file-test/logs"
+in_memory = true
+prune_interval = 20
+"#,
+    )
+    .await
+    .unwrap();
+
+    let cli_args: Vec<OsString> = [
+        "--config",
+        config_path.to_str().unwrap(),
+        "--database-dir",
+        "./config-file-test/cli-database",
+    ]
+    .into_iter()
+    .map(OsString::from)
+    .collect();
+    let args = ServerArgs::try_parse_from(
+        std::iter::once(OsString::from("server")).chain(cli_args.clone()),
+    )
+    .unwrap()
+    .with_config_file(cli_args)
+    .unwrap();
+
+    let config = handle_command_line_arguments(args).await.unwrap();
+    assert_eq!(
+        config.database_dir,
+        PathBuf::from("./config-file-test/cli-database")
+    );
+    assert!(config.in_memory);
+    assert!(test_dir.join("watch-blocks").exists());
+
+    tokio::fs::remove_dir_all(&test_dir).await.unwrap();
+}
+
+#[tokio::test]
+async fn ledger_required_for_non_genesis_ledger() {
+    // the network's genesis ledger is the default
+    assert!(ServerArgs::try_parse_from(["server"]).is_ok());
+
+    let args = ServerArgs::try_parse_from(["server", "--non-genesis-ledger"]).unwrap();
+    assert!(handle_command_line_arguments(args).await.is_err());
+}
+
+#[tokio::test]
+async fn db_override_removes_the_database() {
+    let test_dir = PathBuf::from("./db-override-test");
+    let database_dir = test_dir.join("database");
+    tokio::fs::create_dir_all(&database_dir).await.unwrap();
+    tokio::fs::write(database_dir.join("CURRENT"), "MANIFEST-000001")
+        .await
+        .unwrap();
+
+    let args = |db_override: bool| {
+        let mut args = vec![
+            "server",
+            "--ledger",
+            "./tests/data/genesis_ledgers/mainnet.json",
+            "--database-dir",
+            "./db-override-test/database",
+            "--startup-dir",
+            "./db-override-test/startup-blocks",
+            "--watch-dir",
+            "./db-override-test/watch-blocks",
+            "--log-dir",
+            "./db-override-test/logs",
+            "--checkpoint-dir",
+            "./db-override-test/checkpoints",
+        ];
+        if db_override {
+            args.push("--db-override");
+        }
+        ServerArgs::try_parse_from(args).unwrap()
+    };
+
+    // the database is kept without the flag
+    handle_command_line_arguments(args(false)).await.unwrap();
+    assert!(database_dir.join("CURRENT").exists());
+
+    handle_command_line_arguments(args(true)).await.unwrap();
+    assert!(!database_dir.exists());
+
+    tokio::fs::remove_dir_all(&test_dir).await.unwrap();
+}
+
+#[tokio::test]
+async fn database_of_another_network_refused() {
+    let database_dir = PathBuf::from("./network-mismatch-test");
+    let indexer_store = IndexerStore::new(&database_dir).unwrap();
+
+    // a fresh database belongs to any network
+    assert!(check_database_network(&indexer_store, &database_dir, &Network::Devnet).is_ok());
+
+    let mut block_parser =
+        BlockParser::new(&PathBuf::from("./tests/data/sequential_blocks")).unwrap();
+    let block = block_parser.next().await.unwrap().unwrap();
+    indexer_store.add_block(&block).unwrap();
+
+    assert!(check_database_network(&indexer_store, &database_dir, &Network::Mainnet).is_ok());
+    assert!(check_database_network(&indexer_store, &database_dir, &Network::Devnet).is_err());
+
+    drop(indexer_store);
+    tokio::fs::remove_dir_all(&database_dir).await.unwrap();
+}
+
+#[tokio::test]
+async fn listen_requires_auth_token() {
+    let args = ServerArgs::try_parse_from(["server", "--listen", "127.0.0.1:8082"]).unwrap();
+    assert!(handle_command_line_arguments(args).await.is_err());
+}
+
+#[tokio::test]
+async fn socket_permissions() {
+    assert!(ServerArgs::try_parse_from(["server", "--socket-mode", "660"]).is_ok());
+    assert!(ServerArgs::try_parse_from(["server", "--socket-mode", "968"]).is_err());
+    assert!(ServerArgs::try_parse_from(["server", "--socket-mode", "1777"]).is_err());
+
+    // namespaced sockets have no file to set them on
+    let args = ServerArgs::try_parse_from(["server", "--socket-mode", "660"]).unwrap();
+    assert!(handle_command_line_arguments(args).await.is_err());
+
+    let test_dir = PathBuf::from("./socket-permissions-test");
+    tokio::fs::create_dir_all(&test_dir).await.unwrap();
+    let socket_path = test_dir.join("mina-indexer.sock");
+    let socket_name = socket_path.to_str().unwrap();
+    tokio::fs::write(&socket_path, "").await.unwrap();
+
+    set_socket_permissions(socket_name, Some(0o640), None).unwrap();
+    let mode = std::fs::metadata(&socket_path)
+        .unwrap()
+        .permissions()
+        .mode();
+    assert_eq!(mode & 0o777, 0o640);
+    assert!(set_socket_permissions(socket_name, None, Some("no-such-group-here")).is_err());
+
+    tokio::fs::remove_dir_all(test_dir).await.unwrap();
+}
@@ -0,0 +1,34 @@
+use clap::Parser;
+use mina_indexer::server::{daemon, ServerArgs, ServerCommand};
+use std::{path::PathBuf, time::Duration};
+
+#[test]
+fn stop_command() {
+    let args =
+        ServerArgs::try_parse_from(["server", "stop", "--pid-file", "./indexer.pid"]).unwrap();
+    assert!(matches!(args.command, Some(ServerCommand::Stop(_))));
+
+    // the server's own args are left as they were
+    let args = ServerArgs::try_parse_from(["server", "--daemonize"]).unwrap();
+    assert!(args.command.is_none());
+}
+
+#[test]
+fn stale_pid_file() {
+    let test_dir = PathBuf::from("./daemon-test");
+    std::fs::create_dir_all(&test_dir).unwrap();
+    let pid_file = test_dir.join("mina-indexer.pid");
+
+    std::fs::write(&pid_file, "not a pid\n").unwrap();
+    assert!(daemon::read_pid(&pid_file).is_err());
+
+    // no process has the max pid, its pid file is removed
+    std::fs::write(&pid_file, format!("{}\n", i32::MAX)).unwrap();
+    assert_eq!(daemon::read_pid(&pid_file).unwrap().as_raw(), i32::MAX);
+    assert!(daemon::stop(&pid_file, Duration::from_secs(1)).is_err());
+    assert!(!pid_file.exists());
+
+    // without a pid file there's no daemon to stop
+    assert!(daemon::stop(&pid_file, Duration::from_secs(1)).is_err());
+    std::fs::remove_dir_all(test_dir).unwrap();
+}
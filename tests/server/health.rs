@@ -0,0 +1,28 @@
+use mina_indexer::{server::health::HealthMonitor, state::IndexerPhase, store::IndexerStore};
+use std::path::PathBuf;
+
+#[test]
+fn ready_once_watching() {
+    let db = IndexerStore::new_in_memory();
+    let monitor = HealthMonitor::new(
+        Some(PathBuf::from(".")),
+        IndexerPhase::InitializingFromBlockDir,
+    );
+
+    let report = monitor.report(&db);
+    assert!(!report.ready);
+    assert!(report.store_connected);
+    assert_eq!(
+        report.phase,
+        IndexerPhase::InitializingFromBlockDir.to_string()
+    );
+    assert_eq!(report.last_block_received, None);
+    assert!(report.disk_available_bytes.is_some());
+
+    monitor.set_phase(IndexerPhase::Watching);
+    monitor.block_received();
+    let report = monitor.report(&db);
+    assert!(report.ready);
+    assert!(report.last_block_received.is_some());
+    assert_eq!(report.seconds_since_last_block, Some(0));
+}
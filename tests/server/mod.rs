@@ -0,0 +1,8 @@
+mod config;
+mod daemon;
+mod health;
+mod rest;
+mod rosetta;
+mod runtime;
+mod snapshot;
+mod websocket;
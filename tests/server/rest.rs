@@ -0,0 +1,46 @@
+use std::path::PathBuf;
+
+use mina_indexer::{
+    block::{metadata::BlockMetadata, parser::BlockParser, BlockHash},
+    server::rest::block_json,
+    state::{canonicity::store::CanonicityStore, Canonicity},
+    store::IndexerStore,
+};
+
+#[tokio::test]
+async fn block_json_from_store() {
+    let log_dir = &PathBuf::from("./tests/data/sequential_blocks");
+
+    let db = IndexerStore::new_in_memory();
+    let mut bp = BlockParser::new(log_dir).unwrap();
+    while let Some(block) = bp.next().await.unwrap() {
+        db.add_block(&block).unwrap();
+    }
+
+    // mainnet-105498-3NKbLiBHzQrAimK7AkP8qAfQpHnezkdsSm8mkt2TzsbjsLN8Axmt.json
+    let state_hash = BlockHash("3NKbLiBHzQrAimK7AkP8qAfQpHnezkdsSm8mkt2TzsbjsLN8Axmt".to_string());
+    db.set_canonicity(&state_hash, Canonicity::Pending).unwrap();
+
+    let block = block_json(&db, &state_hash).unwrap().unwrap();
+    assert_eq!(block.state_hash, state_hash.0);
+    assert_eq!(block.blockchain_length, Some(105498));
+    assert_eq!(block.canonicity, Some(Canonicity::Pending));
+    assert_eq!(block.command_hashes.len(), 2);
+    assert_eq!(
+        block.metadata,
+        Some(BlockMetadata {
+            num_commands: 2,
+            total_fees: 11_000_000,
+            coinbase_amount: 720_000_000_000,
+            num_snarks: 0,
+        })
+    );
+
+    // serialized with plain JSON types
+    let json = serde_json::to_value(&block).unwrap();
+    assert_eq!(json["state_hash"], state_hash.0);
+    assert!(json["block_creator"].as_str().unwrap().starts_with("B62q"));
+
+    let unknown = BlockHash("3NKunknown".to_string());
+    assert_eq!(block_json(&db, &unknown).unwrap(), None);
+}
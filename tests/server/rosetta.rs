@@ -0,0 +1,66 @@
+use std::path::PathBuf;
+
+use mina_indexer::{
+    block::parser::BlockParser,
+    server::rosetta::{self, PartialBlockIdentifier},
+    store::IndexerStore,
+};
+
+#[tokio::test]
+async fn rosetta_block() {
+    let log_dir = &PathBuf::from("./tests/data/sequential_blocks");
+
+    let db = IndexerStore::new_in_memory();
+    let mut bp = BlockParser::new(log_dir).unwrap();
+    while let Some(block) = bp.next().await.unwrap() {
+        db.add_block(&block).unwrap();
+    }
+
+    // mainnet-105498-3NKbLiBHzQrAimK7AkP8qAfQpHnezkdsSm8mkt2TzsbjsLN8Axmt.json
+    let block_identifier = PartialBlockIdentifier {
+        index: None,
+        hash: Some("3NKbLiBHzQrAimK7AkP8qAfQpHnezkdsSm8mkt2TzsbjsLN8Axmt".to_string()),
+    };
+    let block = rosetta::block(&db, &block_identifier).unwrap();
+    assert_eq!(block.block_identifier.index, 105498);
+    assert_eq!(block.parent_block_identifier.index, 105497);
+
+    // 2 user commands, then the coinbase and fee transfers
+    let user_commands = &block.transactions[..2];
+    for transaction in user_commands {
+        let operation_types: Vec<&str> = transaction
+            .operations
+            .iter()
+            .map(|operation| operation.operation_type.as_str())
+            .collect();
+        assert_eq!(operation_types[0], "fee_payer_dec");
+        assert!(transaction.operations[0]
+            .amount
+            .as_ref()
+            .unwrap()
+            .value
+            .starts_with('-'));
+    }
+    let credits: i128 = block.transactions[2..]
+        .iter()
+        .flat_map(|transaction| &transaction.operations)
+        .map(|operation| {
+            operation
+                .amount
+                .as_ref()
+                .unwrap()
+                .value
+                .parse::<i128>()
+                .unwrap()
+        })
+        .sum();
+    // coinbase and transaction fees
+    assert_eq!(credits, 720_000_000_000 + 11_000_000);
+
+    // the index must match the block's length
+    let mismatched = PartialBlockIdentifier {
+        index: Some(105497),
+        ..block_identifier
+    };
+    assert_eq!(rosetta::block(&db, &mismatched).unwrap_err().code, 2);
+}
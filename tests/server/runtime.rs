@@ -0,0 +1,36 @@
+use clap::Parser;
+use mina_indexer::server::{
+    runtime::{QueryPool, RuntimeConfiguration},
+    ServerArgs,
+};
+
+#[test]
+fn query_pool_runs_apart_from_ingestion() {
+    let config = RuntimeConfiguration {
+        worker_threads: Some(1),
+        query_threads: 1,
+    };
+    let runtime = config.build_runtime().unwrap();
+    let query_runtime = config.build_query_runtime().unwrap();
+    let query_pool = QueryPool::new(query_runtime.handle().clone());
+
+    let thread_name = runtime
+        .block_on(query_pool.run(|| std::thread::current().name().map(str::to_string)))
+        .unwrap();
+    assert_eq!(thread_name.as_deref(), Some("mina-indexer-query"));
+}
+
+#[test]
+fn thread_counts_are_positive() {
+    let args = ServerArgs::try_parse_from(["server", "--worker-threads", "2"]).unwrap();
+    assert_eq!(
+        args.runtime_configuration().unwrap(),
+        RuntimeConfiguration {
+            worker_threads: Some(2),
+            query_threads: mina_indexer::QUERY_THREADS_DEFAULT,
+        }
+    );
+
+    let args = ServerArgs::try_parse_from(["server", "--query-threads", "0"]).unwrap();
+    assert!(args.runtime_configuration().is_err());
+}
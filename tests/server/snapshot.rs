@@ -0,0 +1,152 @@
+use mina_indexer::{
+    block::{parser::BlockParser, store::BlockStore, BlockHash},
+    server::snapshot::StateSnapshot,
+    state::{ledger::Ledger, IndexerState},
+    store::IndexerStore,
+};
+use std::{path::PathBuf, sync::Arc};
+use tokio::sync::mpsc;
+
+/// A snapshot answers as of the state it was taken of, not of later blocks
+#[tokio::test]
+async fn answers_as_of_the_snapshot() {
+    let log_dir = PathBuf::from("./tests/data/sequential_blocks");
+    let mut block_parser = BlockParser::new_testing(&log_dir).unwrap();
+
+    // root_block = mainnet-105489-3NK4huLvUDiL4XuCUcyrWCKynmvhqfKsx5h2MfBXVVUq2Qwzi5uT.json
+    let root_block = block_parser
+        .get_precomputed_block("3NK4huLvUDiL4XuCUcyrWCKynmvhqfKsx5h2MfBXVVUq2Qwzi5uT")
+        .await
+        .unwrap();
+
+    let mut state = IndexerState::new_testing(
+        &root_block,
+        Some(Ledger::new()),
+        Some(IndexerStore::new_in_memory()),
+        None,
+    )
+    .unwrap();
+    state
+        .indexer_store
+        .as_ref()
+        .unwrap()
+        .add_block(&root_block)
+        .unwrap();
+
+    let snapshot = StateSnapshot::new(&state);
+    while let Some(block) = block_parser.next().await.unwrap() {
+        if block.state_hash != root_block.state_hash {
+            state.add_block(&block).unwrap();
+        }
+    }
+
+    assert_eq!(snapshot.best_tip.0, root_block.state_hash);
+    assert_eq!(snapshot.best_chain.len(), 1);
+    assert_eq!(snapshot.blocks_processed, 0);
+
+    let snapshot = snapshot.next(&state);
+    assert_eq!(snapshot.best_tip, state.best_tip.state_hash);
+    assert_eq!(*snapshot.best_chain, state.root_branch.longest_chain());
+    assert_eq!(snapshot.blocks_processed, state.blocks_processed);
+
+    // the best tip has no blocks above it
+    let best_tip_status = snapshot.block_status(&snapshot.best_tip).unwrap().unwrap();
+    assert_eq!(best_tip_status.confirmations, Some(0));
+    assert_eq!(
+        snapshot
+            .block_status(&BlockHash("3NKunknown".to_string()))
+            .unwrap(),
+        None
+    );
+}
+
+/// Subscribers are pushed the tips of a snapshot whenever they differ from the last ones
+#[tokio::test]
+async fn tips_of_the_snapshot() {
+    let log_dir = PathBuf::from("./tests/data/sequential_blocks");
+    let mut block_parser = BlockParser::new_testing(&log_dir).unwrap();
+
+    // root_block = mainnet-105489-3NK4huLvUDiL4XuCUcyrWCKynmvhqfKsx5h2MfBXVVUq2Qwzi5uT.json
+    let root_block = block_parser
+        .get_precomputed_block("3NK4huLvUDiL4XuCUcyrWCKynmvhqfKsx5h2MfBXVVUq2Qwzi5uT")
+        .await
+        .unwrap();
+    let mut state =
+        IndexerState::new_testing(&root_block, Some(Ledger::new()), None, None).unwrap();
+
+    let tips = StateSnapshot::new(&state).tips();
+    assert_eq!(tips.best_tip.0, root_block.state_hash);
+    assert_eq!(tips.canonical_tip.0, root_block.state_hash);
+    assert_eq!(tips.best_tip_length, 105489);
+    assert_eq!(tips, StateSnapshot::new(&state).tips());
+
+    while let Some(block) = block_parser.next().await.unwrap() {
+        if block.state_hash != root_block.state_hash {
+            state.add_block(&block).unwrap();
+        }
+    }
+
+    let new_tips = StateSnapshot::new(&state).tips();
+    assert_ne!(new_tips, tips);
+    assert_eq!(new_tips.best_tip, state.best_tip.state_hash);
+    assert_eq!(new_tips.canonical_tip, state.canonical_tip.state_hash);
+    assert!(new_tips.canonical_tip_length <= new_tips.best_tip_length);
+}
+
+/// Snapshots share the chains of the previous one until the tips move, summaries are built
+/// from the snapshot and the witness tree taken with it
+#[tokio::test]
+async fn snapshots_share_chains() {
+    let log_dir = PathBuf::from("./tests/data/sequential_blocks");
+    let mut block_parser = BlockParser::new_testing(&log_dir).unwrap();
+
+    // root_block = mainnet-105489-3NK4huLvUDiL4XuCUcyrWCKynmvhqfKsx5h2MfBXVVUq2Qwzi5uT.json
+    let root_block = block_parser
+        .get_precomputed_block("3NK4huLvUDiL4XuCUcyrWCKynmvhqfKsx5h2MfBXVVUq2Qwzi5uT")
+        .await
+        .unwrap();
+    let mut state =
+        IndexerState::new_testing(&root_block, Some(Ledger::new()), None, None).unwrap();
+
+    let snapshot = StateSnapshot::new(&state);
+    let unchanged = snapshot.next(&state);
+    assert!(Arc::ptr_eq(&snapshot.best_chain, &unchanged.best_chain));
+    assert!(Arc::ptr_eq(
+        &snapshot.witness_canonical_chain,
+        &unchanged.witness_canonical_chain
+    ));
+
+    while let Some(block) = block_parser.next().await.unwrap() {
+        if block.state_hash != root_block.state_hash {
+            state.add_block(&block).unwrap();
+        }
+    }
+    let moved = unchanged.next(&state);
+    assert!(!Arc::ptr_eq(&unchanged.best_chain, &moved.best_chain));
+    assert_eq!(*moved.best_chain, state.root_branch.longest_chain());
+    assert_eq!(
+        *moved.witness_canonical_chain,
+        state.witness_canonical_chain()
+    );
+
+    // the indexer state isn't asked for the witness tree taken with the snapshot
+    let (state_requests, _) = mpsc::channel(1);
+    let summary = StateSnapshot::new(&state)
+        .with_witness_tree(&state)
+        .summary(&state_requests)
+        .await
+        .unwrap();
+    assert_eq!(summary.blocks_processed, state.blocks_processed);
+    assert_eq!(
+        summary.witness_tree.best_tip_hash,
+        state.best_tip.state_hash.0
+    );
+    assert_eq!(
+        summary.witness_tree.num_leaves,
+        state.root_branch.leaves().len() as u32
+    );
+    assert!(StateSnapshot::new(&state)
+        .summary(&state_requests)
+        .await
+        .is_err());
+}
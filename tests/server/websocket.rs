@@ -0,0 +1,36 @@
+use mina_indexer::{
+    block::BlockHash,
+    server::websocket::EventMessage,
+    state::{event::IndexerEvent, Canonicity},
+};
+
+#[test]
+fn event_message_json() {
+    let message = EventMessage {
+        seq_num: 7,
+        event: IndexerEvent::CanonicityChanged {
+            state_hash: BlockHash(
+                "3NKbLiBHzQrAimK7AkP8qAfQpHnezkdsSm8mkt2TzsbjsLN8Axmt".to_string(),
+            ),
+            canonicity: Canonicity::Canonical,
+        },
+    };
+
+    let json = serde_json::to_value(&message).unwrap();
+    assert_eq!(json["seq_num"], 7);
+    assert_eq!(
+        json["event"]["CanonicityChanged"]["state_hash"],
+        "3NKbLiBHzQrAimK7AkP8qAfQpHnezkdsSm8mkt2TzsbjsLN8Axmt"
+    );
+    assert_eq!(
+        json["event"]["CanonicityChanged"]["canonicity"],
+        "Canonical"
+    );
+
+    // subscribers can deserialize the messages with the same types
+    let text = serde_json::to_string(&message).unwrap();
+    assert_eq!(
+        serde_json::from_str::<EventMessage>(&text).unwrap(),
+        message
+    );
+}
@@ -0,0 +1,89 @@
+use mina_indexer::{
+    block::{parser::BlockParser, BlockHash},
+    state::{branch::Branch, ledger::Ledger, IndexerState},
+    store::IndexerStore,
+};
+use std::path::PathBuf;
+
+/// A block lacking its length gets one more than its parent's
+#[tokio::test]
+async fn inferred_from_parent() {
+    let log_dir = PathBuf::from("./tests/data/sequential_blocks");
+    let mut block_parser = BlockParser::new_testing(&log_dir).unwrap();
+
+    // root_block = mainnet-105489-3NK4huLvUDiL4XuCUcyrWCKynmvhqfKsx5h2MfBXVVUq2Qwzi5uT.json
+    let root_block = block_parser
+        .get_precomputed_block("3NK4huLvUDiL4XuCUcyrWCKynmvhqfKsx5h2MfBXVVUq2Qwzi5uT")
+        .await
+        .unwrap();
+    let mut state = IndexerState::new_testing(
+        &root_block,
+        Some(Ledger::new()),
+        Some(IndexerStore::new_in_memory()),
+        None,
+    )
+    .unwrap();
+
+    // child_block = mainnet-105490-3NKxEA9gztvEGxL4uk4eTncZAxuRmMsB8n81UkeAMevUjMbLHmkC.json
+    let mut child_block = block_parser
+        .get_precomputed_block("3NKxEA9gztvEGxL4uk4eTncZAxuRmMsB8n81UkeAMevUjMbLHmkC")
+        .await
+        .unwrap();
+    child_block.blockchain_length = None;
+    state.add_block(&child_block).unwrap();
+
+    let child = state
+        .root_branch
+        .block(&BlockHash(child_block.state_hash.clone()))
+        .unwrap();
+    assert_eq!(child.blockchain_length, Some(105490));
+    assert_eq!(state.best_tip_block().blockchain_length, Some(105490));
+}
+
+/// A merged branch's blocks lacking lengths get them from the junction
+#[tokio::test]
+async fn backfilled_on_merge() {
+    let log_dir = PathBuf::from("./tests/data/sequential_blocks");
+    let mut block_parser = BlockParser::new_testing(&log_dir).unwrap();
+
+    // root_block = mainnet-105489-3NK4huLvUDiL4XuCUcyrWCKynmvhqfKsx5h2MfBXVVUq2Qwzi5uT.json
+    let root_block = block_parser
+        .get_precomputed_block("3NK4huLvUDiL4XuCUcyrWCKynmvhqfKsx5h2MfBXVVUq2Qwzi5uT")
+        .await
+        .unwrap();
+    let mut root_branch = Branch::new(&root_block).unwrap();
+
+    // middle_block = mainnet-105490-3NKxEA9gztvEGxL4uk4eTncZAxuRmMsB8n81UkeAMevUjMbLHmkC.json
+    let mut middle_block = block_parser
+        .get_precomputed_block("3NKxEA9gztvEGxL4uk4eTncZAxuRmMsB8n81UkeAMevUjMbLHmkC")
+        .await
+        .unwrap();
+    middle_block.blockchain_length = None;
+
+    // leaf_block = mainnet-105491-3NKizDx3nnhXha2WqHDNUvJk9jW7GsonsEGYs26tCPW2Wow1ZoR3.json
+    let mut leaf_block = block_parser
+        .get_precomputed_block("3NKizDx3nnhXha2WqHDNUvJk9jW7GsonsEGYs26tCPW2Wow1ZoR3")
+        .await
+        .unwrap();
+    leaf_block.blockchain_length = None;
+
+    let mut dangling_branch = Branch::new(&middle_block).unwrap();
+    dangling_branch
+        .simple_extension(&leaf_block)
+        .unwrap()
+        .unwrap();
+    assert_eq!(dangling_branch.best_tip().unwrap().blockchain_length, None);
+
+    let root_id = root_branch.root.clone();
+    root_branch
+        .merge_on(&root_id, &mut dangling_branch)
+        .unwrap();
+
+    for (state_hash, length) in [
+        (&middle_block.state_hash, 105490),
+        (&leaf_block.state_hash, 105491),
+    ] {
+        let block = root_branch.block(&BlockHash(state_hash.clone())).unwrap();
+        assert_eq!(block.blockchain_length, Some(length));
+    }
+}
@@ -0,0 +1,101 @@
+use mina_indexer::{
+    block::{parser::BlockParser, store::BlockStore, BlockHash},
+    state::{
+        canonicity::{chain::CanonicalChain, store::CanonicityStore},
+        ledger::Ledger,
+        Canonicity, IndexerState,
+    },
+    store::IndexerStore,
+};
+use std::path::PathBuf;
+
+/// The chain crosses from the canonicity store into the witness tree's canonical blocks
+#[tokio::test]
+async fn spans_store_and_witness_tree() {
+    let log_dir = PathBuf::from("./tests/data/sequential_blocks");
+    let mut block_parser = BlockParser::new_testing(&log_dir).unwrap();
+    let indexer_store = IndexerStore::new_in_memory();
+
+    // mainnet-105489-3NK4huLvUDiL4XuCUcyrWCKynmvhqfKsx5h2MfBXVVUq2Qwzi5uT.json
+    // mainnet-105490-3NKxEA9gztvEGxL4uk4eTncZAxuRmMsB8n81UkeAMevUjMbLHmkC.json
+    // mainnet-105491-3NKizDx3nnhXha2WqHDNUvJk9jW7GsonsEGYs26tCPW2Wow1ZoR3.json
+    let mut chain = vec![];
+    for state_hash in [
+        "3NK4huLvUDiL4XuCUcyrWCKynmvhqfKsx5h2MfBXVVUq2Qwzi5uT",
+        "3NKxEA9gztvEGxL4uk4eTncZAxuRmMsB8n81UkeAMevUjMbLHmkC",
+        "3NKizDx3nnhXha2WqHDNUvJk9jW7GsonsEGYs26tCPW2Wow1ZoR3",
+    ] {
+        let block = block_parser
+            .get_precomputed_block(state_hash)
+            .await
+            .unwrap();
+        indexer_store.add_block(&block).unwrap();
+        chain.push((
+            block.blockchain_length.unwrap(),
+            BlockHash(block.state_hash.clone()),
+        ));
+    }
+
+    // the first two are in the store, the last only in the witness tree
+    for (_, state_hash) in &chain[..2] {
+        indexer_store
+            .set_canonicity(state_hash, Canonicity::Canonical)
+            .unwrap();
+    }
+    let witness_chain = chain[1..].to_vec();
+
+    let canonical: Vec<(u32, BlockHash)> =
+        CanonicalChain::new(Some(&indexer_store), witness_chain.clone(), 105489)
+            .collect::<anyhow::Result<_>>()
+            .unwrap();
+    assert_eq!(canonical, chain);
+
+    // starting part way up
+    let canonical: Vec<(u32, BlockHash)> =
+        CanonicalChain::new(Some(&indexer_store), witness_chain.clone(), 105491)
+            .collect::<anyhow::Result<_>>()
+            .unwrap();
+    assert_eq!(canonical, chain[2..]);
+
+    // nothing at unknown lengths
+    assert!(
+        CanonicalChain::new(Some(&indexer_store), witness_chain, 105492)
+            .next()
+            .is_none()
+    );
+}
+
+/// Without canonical blocks in the store, the chain ends at the canonical tip
+#[tokio::test]
+async fn ends_at_canonical_tip() {
+    let log_dir = PathBuf::from("./tests/data/sequential_blocks");
+    let mut block_parser = BlockParser::new_testing(&log_dir).unwrap();
+
+    let root_block = block_parser.next().await.unwrap().unwrap();
+    let mut state = IndexerState::new_testing(
+        &root_block,
+        Some(Ledger::new()),
+        Some(IndexerStore::new_in_memory()),
+        None,
+    )
+    .unwrap();
+    while let Some(precomputed_block) = block_parser.next().await.unwrap() {
+        state.add_block(&precomputed_block).unwrap();
+    }
+
+    let root_length = state
+        .root_branch
+        .root_block()
+        .unwrap()
+        .blockchain_length
+        .unwrap();
+    let canonical: Vec<(u32, BlockHash)> = state
+        .canonical_chain(root_length)
+        .collect::<anyhow::Result<_>>()
+        .unwrap();
+    assert_eq!(canonical, state.witness_canonical_chain());
+    assert_eq!(
+        canonical.last().map(|(_, state_hash)| state_hash),
+        Some(&state.canonical_tip.state_hash)
+    );
+}
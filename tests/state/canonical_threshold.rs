@@ -0,0 +1,45 @@
+use crate::support::dag::BlockDag;
+use mina_indexer::{
+    block::parser::BlockParser,
+    state::{ledger::Ledger, IndexerState},
+};
+use std::path::PathBuf;
+
+/// The canonical tip is always at least the configured number of confirmations deep
+#[tokio::test]
+async fn confirmation_depth() {
+    let log_dir = PathBuf::from("./tests/data/sequential_blocks");
+    let mut block_parser = BlockParser::new_testing(&log_dir).unwrap();
+
+    // template = mainnet-105489-3NK4huLvUDiL4XuCUcyrWCKynmvhqfKsx5h2MfBXVVUq2Qwzi5uT.json
+    let template = block_parser
+        .get_precomputed_block("3NK4huLvUDiL4XuCUcyrWCKynmvhqfKsx5h2MfBXVVUq2Qwzi5uT")
+        .await
+        .unwrap();
+    let mut dag = BlockDag::new(&template, 0);
+    dag.chain(&dag.root_hash(), 40);
+    let root_length = template.blockchain_length.unwrap();
+
+    let mut canonical_lengths = vec![];
+    for canonical_threshold in [5, 20] {
+        let mut state =
+            IndexerState::new_testing(dag.root(), Some(Ledger::new()), None, None).unwrap();
+        state.canonical_threshold = canonical_threshold;
+
+        for block in dag.blocks() {
+            state.add_block(&block).unwrap();
+
+            let best_length = state.best_tip_block().blockchain_length.unwrap();
+            let canonical_length = state.canonical_tip_block().blockchain_length.unwrap();
+            assert!(
+                canonical_length == root_length
+                    || canonical_length + canonical_threshold <= best_length
+            );
+        }
+        canonical_lengths.push(state.canonical_tip_block().blockchain_length.unwrap());
+    }
+
+    // deeper confirmation holds the canonical tip back
+    assert!(canonical_lengths[0] > canonical_lengths[1]);
+    assert!(canonical_lengths[1] > root_length);
+}
@@ -108,3 +108,95 @@ async fn extension() {
         assert_eq!(longest_chain.len(), 10);
     }
 }
+
+#[tokio::test]
+async fn verbose_summary() {
+    let log_dir = PathBuf::from("./tests/data/sequential_blocks");
+    let mut block_parser = BlockParser::new_testing(&log_dir).unwrap();
+
+    let precomputed_block = block_parser.next().await.unwrap().unwrap();
+    let mut state = IndexerState::new_testing(&precomputed_block, None, None, None).unwrap();
+    while let Some(precomputed_block) = block_parser.next().await.unwrap() {
+        state.add_block(&precomputed_block).unwrap();
+    }
+
+    // one block per dangling branch
+    let summary = state.summary_verbose();
+    assert_eq!(summary.witness_tree.dangling_branches.len(), 2);
+    for branch in &summary.witness_tree.dangling_branches {
+        assert_eq!(branch.len, 1);
+        assert_eq!(branch.root_hash, branch.tip_hash);
+    }
+
+    // the rendering starts at most 10 blocks below the best tip
+    let witness_tree = summary.clone().with_verbosity(2).witness_tree.witness_tree;
+    let best_tip_hash = &summary.witness_tree.best_tip_hash[0..12];
+    assert!(witness_tree.unwrap().contains(best_tip_hash));
+    assert!(summary
+        .with_verbosity(1)
+        .witness_tree
+        .witness_tree
+        .is_none());
+}
+
+#[tokio::test]
+async fn consensus_time_summary() {
+    let log_dir = PathBuf::from("./tests/data/sequential_blocks");
+    let mut block_parser = BlockParser::new_testing(&log_dir).unwrap();
+
+    let precomputed_block = block_parser.next().await.unwrap().unwrap();
+    let mut state = IndexerState::new_testing(&precomputed_block, None, None, None).unwrap();
+    while let Some(precomputed_block) = block_parser.next().await.unwrap() {
+        state.add_block(&precomputed_block).unwrap();
+    }
+
+    let consensus_time = state.summary_verbose().consensus_time;
+    let slots_per_epoch = state.network.slots_per_epoch();
+    assert_eq!(
+        consensus_time.global_slot,
+        state.best_tip_block().global_slot_since_genesis
+    );
+    assert_eq!(
+        consensus_time.epoch * slots_per_epoch + consensus_time.slot_in_epoch,
+        consensus_time.global_slot
+    );
+    assert!(consensus_time.slot_in_epoch < slots_per_epoch);
+
+    // mainnet's genesis timestamp is known, the blocks are long in the past
+    assert!(consensus_time.wall_clock_slot.unwrap() > consensus_time.global_slot);
+    assert!(consensus_time.slot_lag().unwrap() > 0);
+
+    let genesis_ms = state.network.slot_timestamp_ms(0).unwrap();
+    assert_eq!(state.network.global_slot_at(genesis_ms - 1), Some(0));
+    assert_eq!(
+        state
+            .network
+            .global_slot_at(state.network.slot_timestamp_ms(42).unwrap()),
+        Some(42)
+    );
+    assert_eq!(state.network.global_slot_at(genesis_ms), Some(0));
+}
+
+#[tokio::test]
+async fn short_summary_lists_dangling_branches() {
+    let log_dir = PathBuf::from("./tests/data/sequential_blocks");
+    let mut block_parser = BlockParser::new_testing(&log_dir).unwrap();
+
+    let precomputed_block = block_parser.next().await.unwrap().unwrap();
+    let mut state = IndexerState::new_testing(&precomputed_block, None, None, None).unwrap();
+    while let Some(precomputed_block) = block_parser.next().await.unwrap() {
+        state.add_block(&precomputed_block).unwrap();
+    }
+
+    // the root block's siblings share its missing parent
+    let summary = state.summary_short();
+    let root_parent_hash = &state.root_branch.root_block().unwrap().parent_hash.0;
+    assert_eq!(summary.witness_tree.dangling_branches.len(), 2);
+    for branch in &summary.witness_tree.dangling_branches {
+        assert_eq!(branch.root_length, 105489);
+        assert_eq!(&branch.missing_parent_hash, root_parent_hash);
+        assert_eq!(branch.tip_hash, branch.root_hash);
+        assert_eq!(branch.len, 1);
+        assert!(summary.to_string().contains(&branch.root_hash));
+    }
+}
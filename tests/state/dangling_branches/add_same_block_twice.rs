@@ -1,14 +1,13 @@
 use mina_indexer::{
     block::parser::BlockParser,
     state::{ExtensionType, IndexerState},
+    store::IndexerStore,
 };
 use std::path::PathBuf;
-use tokio::fs::remove_dir_all;
 
 /// Adds the same block twice, second time fails
 #[tokio::test]
 async fn test() {
-    let block_store_dir = PathBuf::from("./test_block_store");
     let log_dir = PathBuf::from("./tests/data/sequential_blocks");
     let mut block_parser = BlockParser::new_testing(&log_dir).unwrap();
 
@@ -41,7 +40,8 @@ async fn test() {
 
     // initialize state
     let mut state =
-        IndexerState::new_testing(&root_block, None, Some(&block_store_dir), None).unwrap();
+        IndexerState::new_testing(&root_block, None, Some(IndexerStore::new_in_memory()), None)
+            .unwrap();
 
     // add block for the first time
     let extension_type = state.add_block(&block0).unwrap();
@@ -72,6 +72,4 @@ async fn test() {
     assert_eq!(state.root_branch.len(), 1);
     assert_eq!(state.dangling_branches.len(), 1);
     assert_eq!(state.dangling_branches.get(0).unwrap().len(), 1);
-
-    remove_dir_all(block_store_dir).await.unwrap();
 }
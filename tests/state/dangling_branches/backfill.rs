@@ -0,0 +1,48 @@
+use mina_indexer::{
+    block::parser::BlockParser,
+    state::{ledger::Ledger, IndexerState},
+    store::IndexerStore,
+};
+use std::path::PathBuf;
+
+/// A dangling branch taller than the transition frontier is flagged and connected by
+/// backfilling its missing ancestors from the blocks directory
+#[tokio::test]
+async fn backfills_deep_branch() {
+    let log_dir = PathBuf::from("./tests/data/sequential_blocks");
+    let mut block_parser = BlockParser::new_testing(&log_dir).unwrap();
+
+    // mainnet-105490-3NKxEA9gztvEGxL4uk4eTncZAxuRmMsB8n81UkeAMevUjMbLHmkC.json
+    // mainnet-105491-3NKizDx3nnhXha2WqHDNUvJk9jW7GsonsEGYs26tCPW2Wow1ZoR3.json
+    let missing = [
+        "3NKxEA9gztvEGxL4uk4eTncZAxuRmMsB8n81UkeAMevUjMbLHmkC",
+        "3NKizDx3nnhXha2WqHDNUvJk9jW7GsonsEGYs26tCPW2Wow1ZoR3",
+    ];
+
+    let root_block = block_parser.next().await.unwrap().unwrap();
+    let mut state = IndexerState::new_testing(
+        &root_block,
+        Some(Ledger::new()),
+        Some(IndexerStore::new_in_memory()),
+        Some(2),
+    )
+    .unwrap();
+    while let Some(precomputed_block) = block_parser.next().await.unwrap() {
+        if !missing.contains(&precomputed_block.state_hash.as_str()) {
+            state.add_block(&precomputed_block).unwrap();
+        }
+    }
+
+    assert_eq!(state.deep_dangling_branches().count(), 1);
+    assert_eq!(state.summary_short().witness_tree.num_deep_dangling, 1);
+    assert!(state
+        .summary_verbose()
+        .witness_tree
+        .dangling_branches
+        .iter()
+        .any(|branch| branch.deep));
+
+    assert_eq!(state.backfill_dangling_branches(&log_dir).await.unwrap(), 2);
+    assert_eq!(state.deep_dangling_branches().count(), 0);
+    assert_eq!(state.best_tip_block().blockchain_length, Some(105498));
+}
@@ -143,7 +143,7 @@ async fn extension() {
 
     // after extension quantities
     let root_branch = state.root_branch;
-    let root = root_branch.root_block();
+    let root = root_branch.root_block().unwrap();
     let branches = root_branch.clone().branches;
     let branch_root = branches
         .get(&branches.root_node_id().unwrap())
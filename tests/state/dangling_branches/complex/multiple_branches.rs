@@ -144,7 +144,7 @@ async fn merge() {
 
     // after extension quantities
     let root_branch = state.root_branch.clone();
-    let root0 = state.root_branch.root_block();
+    let root0 = state.root_branch.root_block().unwrap();
     let branches0 = state.root_branch.clone().branches;
     let branch_root0 = branches0
         .get(&branches0.root_node_id().unwrap())
@@ -127,7 +127,7 @@ async fn extension() {
     });
 
     // after extension quantities
-    let root = state.root_branch.root_block();
+    let root = state.root_branch.root_block().unwrap();
     let branches = state.root_branch.clone().branches;
     let branch_root = branches
         .get(&branches.root_node_id().unwrap())
@@ -0,0 +1,51 @@
+use mina_indexer::{
+    block::{parser::BlockParser, BlockHash},
+    state::{canonicity::store::CanonicityStore, ledger::Ledger, Canonicity, IndexerState},
+    store::IndexerStore,
+};
+use std::path::PathBuf;
+
+/// Dangling branches older than the max age are dropped and their blocks recorded as orphaned
+#[tokio::test]
+async fn drops_hopeless_branches() {
+    let log_dir = PathBuf::from("./tests/data/sequential_blocks");
+    let mut block_parser = BlockParser::new_testing(&log_dir).unwrap();
+
+    let root_block = block_parser.next().await.unwrap().unwrap();
+    let mut state = IndexerState::new_testing(
+        &root_block,
+        Some(Ledger::new()),
+        Some(IndexerStore::new_in_memory()),
+        None,
+    )
+    .unwrap();
+    while let Some(precomputed_block) = block_parser.next().await.unwrap() {
+        state.add_block(&precomputed_block).unwrap();
+    }
+
+    // the dangling branches are recent enough by default
+    assert_eq!(state.collect_dangling_branches().unwrap(), 0);
+    assert_eq!(state.dangling_branches.len(), 2);
+
+    let dangling_hashes: Vec<BlockHash> = state
+        .dangling_branches
+        .iter()
+        .flat_map(|branch| branch.blocks())
+        .map(|block| block.state_hash)
+        .collect();
+    let root_branch_len = state.root_branch.len();
+
+    state.dangling_max_age_slots = 0;
+    assert_eq!(state.collect_dangling_branches().unwrap(), 2);
+    assert!(state.dangling_branches.is_empty());
+    assert_eq!(state.root_branch.len(), root_branch_len);
+
+    let indexer_store = state.indexer_store.as_ref().unwrap();
+    for state_hash in &dangling_hashes {
+        assert!(!state.diffs_map.contains(state_hash));
+        assert_eq!(
+            indexer_store.get_canonicity(state_hash).unwrap(),
+            Some(Canonicity::Orphaned)
+        );
+    }
+}
@@ -1,4 +1,6 @@
 mod add_all_blocks;
 mod add_same_block_twice;
+mod backfill;
 mod complex;
+mod gc;
 mod simple;
@@ -65,7 +65,7 @@ async fn extensions() {
     assert_eq!(state.dangling_branches.len(), 0);
 
     // before extension quantities
-    let root0 = state.root_branch.root_block();
+    let root0 = state.root_branch.root_block().unwrap();
 
     let leaves = state.root_branch.leaves();
     let root_leaf0 = leaves.get(0).unwrap();
@@ -102,7 +102,7 @@ async fn extensions() {
     assert_eq!(state.dangling_branches.get(0).unwrap().leaves().len(), 1);
 
     // after extension quantities
-    let root1 = state.root_branch.root_block();
+    let root1 = state.root_branch.root_block().unwrap();
     let branches1 = &state.root_branch.branches;
     let branch_root1 = branches1
         .get(&branches1.root_node_id().unwrap())
@@ -64,7 +64,7 @@ async fn extension() {
     println!("=== Before Root Branch ===");
     println!("{:?}", state.root_branch.branches);
 
-    let before_root = state.root_branch.root_block().clone();
+    let before_root = state.root_branch.root_block().unwrap().clone();
 
     // root branch
     // - len = 2
@@ -104,7 +104,7 @@ async fn extension() {
     assert_eq!(state.dangling_branches.len(), 0);
 
     // after extension quantities
-    let after_root = state.root_branch.root_block().clone();
+    let after_root = state.root_branch.root_block().unwrap().clone();
     let branches1 = &state.root_branch.branches;
     let leaves1 = state.root_branch.leaves();
     let after_root_id = branches1.root_node_id().unwrap();
@@ -51,7 +51,13 @@ async fn extension() {
     assert_eq!(state.dangling_branches.get(0).unwrap().height(), 1);
 
     // before extension quantities
-    let before_root = state.dangling_branches.get(0).unwrap().root_block().clone();
+    let before_root = state
+        .dangling_branches
+        .get(0)
+        .unwrap()
+        .root_block()
+        .unwrap()
+        .clone();
     let before_leaves = state.dangling_branches.get(0).unwrap().leaves().clone();
     let before_leaf = before_leaves.get(0).unwrap().clone();
 
@@ -100,7 +106,13 @@ async fn extension() {
     let extension = state.add_block(&dangling_child_block).unwrap();
 
     // after extension quantities
-    let after_root = state.dangling_branches.get(0).unwrap().root_block().clone();
+    let after_root = state
+        .dangling_branches
+        .get(0)
+        .unwrap()
+        .root_block()
+        .unwrap()
+        .clone();
     let branches1 = state.dangling_branches.get(0).unwrap();
     let leaves1 = branches1.leaves();
     let after_root_id = branches1.branches.root_node_id().unwrap();
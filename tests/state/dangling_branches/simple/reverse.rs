@@ -63,7 +63,13 @@ async fn extension() {
     assert_eq!(extension_type, ExtensionType::DanglingNew);
 
     // before extension quantities
-    let before_root = state.dangling_branches.get(0).unwrap().root_block().clone();
+    let before_root = state
+        .dangling_branches
+        .get(0)
+        .unwrap()
+        .root_block()
+        .unwrap()
+        .clone();
     let before_leaves = state.dangling_branches.get(0).unwrap().leaves().clone();
     let before_root_leaf = state
         .dangling_branches
@@ -92,7 +98,7 @@ async fn extension() {
 
     // after extension quantities
     let after_branch = state.dangling_branches.get(0).unwrap();
-    let after_root = after_branch.root_block().clone();
+    let after_root = after_branch.root_block().unwrap().clone();
     let branches1 = &after_branch.branches;
     let leaves1 = &after_branch.leaves();
     let after_root_id = branches1.root_node_id().unwrap();
@@ -0,0 +1,63 @@
+use mina_indexer::{
+    block::BlockHash,
+    state::{
+        diffs::DiffsMap,
+        ledger::{diff::LedgerDiff, public_key::PublicKey},
+    },
+    store::IndexerStore,
+};
+use std::sync::Arc;
+
+fn diff(num_keys: usize) -> LedgerDiff {
+    LedgerDiff {
+        public_keys_seen: vec![
+            PublicKey::from_address(
+                "B62qre3erTHfzQckNuibViWQGyyKwZseztqrjPZBv6SQF384Rg6ESAy"
+            )
+            .unwrap();
+            num_keys
+        ],
+        account_diffs: vec![],
+    }
+}
+
+#[test]
+fn spills_oldest_diffs() {
+    let mut diffs_map = DiffsMap::new(Some(Arc::new(IndexerStore::new_in_memory())));
+    diffs_map.set_capacity(2).unwrap();
+
+    let hashes: Vec<BlockHash> = (0..4).map(|n| BlockHash(n.to_string())).collect();
+    for (n, state_hash) in hashes.iter().enumerate() {
+        diffs_map.insert(state_hash.clone(), diff(n)).unwrap();
+    }
+    assert_eq!(diffs_map.len(), 4);
+    assert_eq!(diffs_map.num_in_memory(), 2);
+    assert!(hashes
+        .iter()
+        .all(|state_hash| diffs_map.contains(state_hash)));
+
+    // spilled diffs are loaded back
+    assert_eq!(diffs_map.get(&hashes[0]).unwrap(), Some(diff(0)));
+    assert_eq!(diffs_map.get(&hashes[3]).unwrap(), Some(diff(3)));
+
+    diffs_map.remove(&hashes[0]).unwrap();
+    diffs_map.remove(&hashes[3]).unwrap();
+    assert_eq!(diffs_map.len(), 2);
+    assert!(!diffs_map.contains(&hashes[0]));
+    assert_eq!(diffs_map.get(&hashes[0]).unwrap(), None);
+
+    // shrinking the capacity spills right away
+    diffs_map.set_capacity(0).unwrap();
+    assert_eq!(diffs_map.num_in_memory(), 0);
+    assert_eq!(diffs_map.get(&hashes[2]).unwrap(), Some(diff(2)));
+}
+
+#[test]
+fn keeps_everything_without_a_store() {
+    let mut diffs_map = DiffsMap::new(None);
+    diffs_map.set_capacity(0).unwrap();
+    diffs_map
+        .insert(BlockHash("0".to_string()), diff(0))
+        .unwrap();
+    assert_eq!(diffs_map.num_in_memory(), 1);
+}
@@ -0,0 +1,106 @@
+use crate::support::dag::BlockDag;
+use mina_indexer::{
+    block::{parser::BlockParser, precomputed::PrecomputedBlock, BlockHash},
+    state::{ledger::Ledger, IndexerState},
+};
+use std::path::PathBuf;
+
+const NUM_SEEDS: u64 = 16;
+
+async fn template() -> PrecomputedBlock {
+    let log_dir = PathBuf::from("./tests/data/sequential_blocks");
+    let mut block_parser = BlockParser::new_testing(&log_dir).unwrap();
+
+    // template = mainnet-105489-3NK4huLvUDiL4XuCUcyrWCKynmvhqfKsx5h2MfBXVVUq2Qwzi5uT.json
+    block_parser
+        .get_precomputed_block("3NK4huLvUDiL4XuCUcyrWCKynmvhqfKsx5h2MfBXVVUq2Qwzi5uT")
+        .await
+        .unwrap()
+}
+
+fn state(dag: &BlockDag) -> IndexerState {
+    IndexerState::new_testing(dag.root(), Some(Ledger::new()), None, None).unwrap()
+}
+
+/// A chain delivered in any order ends up in the root branch
+#[tokio::test]
+async fn chain_in_any_order() {
+    let template = template().await;
+    for seed in 0..NUM_SEEDS {
+        let mut dag = BlockDag::new(&template, seed);
+        let chain = dag.chain(&dag.root_hash(), 20);
+        let mut state = state(&dag);
+
+        for block in dag.shuffled() {
+            state.add_block(&block).unwrap();
+        }
+
+        assert!(state.dangling_branches.is_empty(), "seed {seed}");
+        assert_eq!(state.root_branch.len(), 21, "seed {seed}");
+        assert_eq!(
+            &state.best_tip_block().state_hash,
+            chain.last().unwrap(),
+            "seed {seed}"
+        );
+    }
+}
+
+/// A forking DAG delivered in any order ends up in the root branch, with a longest tip
+/// as the best tip
+#[tokio::test]
+async fn forks_in_any_order() {
+    let template = template().await;
+    for seed in 0..NUM_SEEDS {
+        let mut dag = BlockDag::new(&template, seed);
+        dag.tree(40, 25);
+        let max_length = dag
+            .blocks()
+            .iter()
+            .filter_map(|block| block.blockchain_length)
+            .max();
+        let mut state = state(&dag);
+
+        for block in dag.shuffled() {
+            state.add_block(&block).unwrap();
+        }
+
+        assert!(state.dangling_branches.is_empty(), "seed {seed}");
+        assert_eq!(state.root_branch.len(), 41, "seed {seed}");
+        assert_eq!(
+            state.best_tip_block().blockchain_length,
+            max_length,
+            "seed {seed}"
+        );
+    }
+}
+
+/// Blocks above a missing parent dangle until it arrives
+#[tokio::test]
+async fn missing_parent() {
+    let template = template().await;
+    for seed in 0..NUM_SEEDS {
+        let mut dag = BlockDag::new(&template, seed);
+        let chain = dag.chain(&dag.root_hash(), 10);
+        let missing: BlockHash = chain[4].clone();
+        let mut state = state(&dag);
+
+        for block in dag
+            .shuffled()
+            .iter()
+            .filter(|block| block.state_hash != missing.0)
+        {
+            state.add_block(block).unwrap();
+        }
+
+        assert_eq!(state.dangling_branches.len(), 1, "seed {seed}");
+        assert_eq!(state.dangling_branches[0].len(), 5, "seed {seed}");
+        assert_eq!(state.root_branch.len(), 5, "seed {seed}");
+        assert_eq!(&state.best_tip_block().state_hash, &chain[3], "seed {seed}");
+
+        state.add_block(dag.block(&missing).unwrap()).unwrap();
+
+        assert!(state.dangling_branches.is_empty(), "seed {seed}");
+        assert_eq!(state.root_branch.len(), 11, "seed {seed}");
+        assert_eq!(&state.best_tip_block().state_hash, &chain[9], "seed {seed}");
+    }
+}
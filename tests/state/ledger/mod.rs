@@ -2,3 +2,4 @@ mod apply_diff;
 mod apply_post_balances;
 mod diff_from_precomputed;
 mod genesis;
+mod staking_ledger;
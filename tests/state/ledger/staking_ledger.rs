@@ -0,0 +1,103 @@
+use std::path::PathBuf;
+
+use mina_indexer::{
+    state::ledger::{staking::StakeDistribution, store::LedgerStore, Ledger},
+    store::IndexerStore,
+};
+
+#[tokio::test]
+async fn add_and_get_staking_ledger() {
+    let store_dir = &PathBuf::from("./staking-ledger-test");
+    let db = IndexerStore::new(store_dir).unwrap();
+
+    let ledger = Ledger::from(vec![
+        (
+            "B62qre3erTHfzQckNuibViWQGyyKwZseztqrjPZBv6SQF384Rg6ESAy",
+            947706514111,
+            Some(3),
+            None,
+        ),
+        (
+            "B62qjYanmV7y9njVeH5UHkz3GYBm7xKir1rAnoY4KsEYUGLMiU45FSM",
+            860273858,
+            None,
+            Some("B62qre3erTHfzQckNuibViWQGyyKwZseztqrjPZBv6SQF384Rg6ESAy"),
+        ),
+    ])
+    .unwrap();
+
+    db.add_staking_ledger(42, ledger.clone()).unwrap();
+
+    assert_eq!(db.get_staking_ledger(42).unwrap(), Some(ledger));
+    assert_eq!(db.get_staking_ledger(43).unwrap(), None);
+
+    tokio::fs::remove_dir_all(store_dir).await.unwrap();
+}
+
+#[test]
+fn stake_distribution() {
+    let delegate = "B62qre3erTHfzQckNuibViWQGyyKwZseztqrjPZBv6SQF384Rg6ESAy";
+    let ledger = Ledger::from(vec![
+        (delegate, 1000, None, None),
+        (
+            "B62qjYanmV7y9njVeH5UHkz3GYBm7xKir1rAnoY4KsEYUGLMiU45FSM",
+            500,
+            None,
+            Some(delegate),
+        ),
+        (
+            "B62qmPc8Ziq7txW48YPf4qtavcD5mcQVjEAGo9LEZD8DeaNsNthYLsz",
+            200,
+            None,
+            None,
+        ),
+    ])
+    .unwrap();
+
+    // undelegated accounts stake for themselves
+    let distribution = StakeDistribution::new(&ledger, Some(42));
+    assert_eq!(distribution.epoch, Some(42));
+    assert_eq!(distribution.total_stake, 1700);
+    assert_eq!(distribution.delegates.len(), 2);
+    assert_eq!(distribution.delegates[0].delegate, delegate);
+    assert_eq!(distribution.delegates[0].total_delegated, 1500);
+    assert_eq!(distribution.delegates[0].num_delegators, 2);
+    assert_eq!(distribution.delegates[1].total_delegated, 200);
+    assert_eq!(distribution.delegates[1].num_delegators, 1);
+}
+
+#[test]
+fn top_accounts() {
+    let ledger = Ledger::from(vec![
+        (
+            "B62qre3erTHfzQckNuibViWQGyyKwZseztqrjPZBv6SQF384Rg6ESAy",
+            200,
+            None,
+            None,
+        ),
+        (
+            "B62qjYanmV7y9njVeH5UHkz3GYBm7xKir1rAnoY4KsEYUGLMiU45FSM",
+            1000,
+            None,
+            None,
+        ),
+        (
+            "B62qmPc8Ziq7txW48YPf4qtavcD5mcQVjEAGo9LEZD8DeaNsNthYLsz",
+            500,
+            None,
+            None,
+        ),
+    ])
+    .unwrap();
+
+    let balances = |num| -> Vec<u64> {
+        ledger
+            .top_accounts(num)
+            .iter()
+            .map(|account| account.balance.0)
+            .collect()
+    };
+    assert_eq!(balances(2), vec![1000, 500]);
+    assert_eq!(balances(5), vec![1000, 500, 200]);
+    assert!(balances(0).is_empty());
+}
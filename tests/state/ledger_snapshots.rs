@@ -0,0 +1,93 @@
+use crate::support::dag::BlockDag;
+use mina_indexer::{
+    block::{parser::BlockParser, BlockHash},
+    state::{
+        ledger::{store::LedgerStore, Ledger},
+        observer::StateObserver,
+        IndexerState,
+    },
+    store::IndexerStore,
+};
+use std::{
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+
+/// Records the snapshotted ledgers
+#[derive(Default)]
+struct Recorder {
+    snapshots: Mutex<Vec<BlockHash>>,
+}
+
+impl StateObserver for Recorder {
+    fn ledger_snapshot_taken(&self, state_hash: &BlockHash) {
+        self.snapshots.lock().unwrap().push(state_hash.clone());
+    }
+}
+
+/// The canonical ledger is snapshotted every `ledger_snapshot_interval` heights, or on demand
+#[tokio::test]
+async fn snapshot_interval() {
+    let log_dir = PathBuf::from("./tests/data/sequential_blocks");
+    let mut block_parser = BlockParser::new_testing(&log_dir).unwrap();
+
+    // template = mainnet-105489-3NK4huLvUDiL4XuCUcyrWCKynmvhqfKsx5h2MfBXVVUq2Qwzi5uT.json
+    let template = block_parser
+        .get_precomputed_block("3NK4huLvUDiL4XuCUcyrWCKynmvhqfKsx5h2MfBXVVUq2Qwzi5uT")
+        .await
+        .unwrap();
+    let mut dag = BlockDag::new(&template, 0);
+    dag.chain(&dag.root_hash(), 40);
+
+    let mut state = IndexerState::new_testing(
+        dag.root(),
+        Some(Ledger::new()),
+        Some(IndexerStore::new_in_memory()),
+        None,
+    )
+    .unwrap();
+    state.ledger_snapshot_interval = 5;
+    let recorder = Arc::new(Recorder::default());
+    state.add_observer(recorder.clone());
+
+    let root_height = state.canonical_tip_block().height;
+    for block in dag.blocks() {
+        state.add_block(&block).unwrap();
+    }
+
+    // one snapshot per interval boundary the canonical tip passed
+    let canonical_height = state.canonical_tip_block().height;
+    assert!(canonical_height > root_height);
+    assert_eq!(
+        recorder.snapshots.lock().unwrap().len() as u32,
+        canonical_height / 5 - root_height / 5
+    );
+
+    // on demand
+    let canonical_tip = state.canonical_tip.state_hash.clone();
+    assert_eq!(
+        state.snapshot_canonical_ledger().unwrap(),
+        Some(canonical_tip.clone())
+    );
+    assert_eq!(
+        recorder.snapshots.lock().unwrap().last(),
+        Some(&canonical_tip)
+    );
+    let indexer_store = state.indexer_store.clone().unwrap();
+    assert!(indexer_store.get_ledger(&canonical_tip).unwrap().is_some());
+}
+
+/// Without a store, there's nothing to snapshot
+#[tokio::test]
+async fn snapshot_without_store() {
+    let log_dir = PathBuf::from("./tests/data/sequential_blocks");
+    let mut block_parser = BlockParser::new_testing(&log_dir).unwrap();
+
+    // root_block = mainnet-105489-3NK4huLvUDiL4XuCUcyrWCKynmvhqfKsx5h2MfBXVVUq2Qwzi5uT.json
+    let root_block = block_parser
+        .get_precomputed_block("3NK4huLvUDiL4XuCUcyrWCKynmvhqfKsx5h2MfBXVVUq2Qwzi5uT")
+        .await
+        .unwrap();
+    let state = IndexerState::new_testing(&root_block, None, None, None).unwrap();
+    assert_eq!(state.snapshot_canonical_ledger().unwrap(), None);
+}
@@ -0,0 +1,49 @@
+use mina_indexer::{
+    block::parser::BlockParser,
+    state::{
+        metadata::{store::MetadataStore, StoreMetadata},
+        IndexerState,
+    },
+    store::IndexerStore,
+};
+use std::path::PathBuf;
+
+/// The tips and blocks processed are recorded in the store on every update
+#[tokio::test]
+async fn metadata_recorded() {
+    let log_dir = PathBuf::from("./tests/data/sequential_blocks");
+    let mut block_parser = BlockParser::new_testing(&log_dir).unwrap();
+
+    // root_block = mainnet-105489-3NK4huLvUDiL4XuCUcyrWCKynmvhqfKsx5h2MfBXVVUq2Qwzi5uT.json
+    let root_block = block_parser
+        .get_precomputed_block("3NK4huLvUDiL4XuCUcyrWCKynmvhqfKsx5h2MfBXVVUq2Qwzi5uT")
+        .await
+        .unwrap();
+
+    let mut state =
+        IndexerState::new_testing(&root_block, None, Some(IndexerStore::new_in_memory()), None)
+            .unwrap();
+    let indexer_store = state.indexer_store.clone().unwrap();
+    assert_eq!(indexer_store.get_metadata().unwrap(), None);
+
+    while let Some(block) = block_parser.next().await.unwrap() {
+        if block.state_hash == root_block.state_hash {
+            continue;
+        }
+
+        state.add_block(&block).unwrap();
+        assert_eq!(
+            indexer_store.get_metadata().unwrap(),
+            Some(StoreMetadata {
+                best_tip: state.best_tip.state_hash.clone(),
+                canonical_tip: state.canonical_tip.state_hash.clone(),
+                max_canonical_height: state.canonical_tip_block().blockchain_length.unwrap(),
+                blocks_processed: state.blocks_processed,
+            })
+        );
+    }
+    assert_eq!(
+        indexer_store.get_metadata().unwrap().unwrap().best_tip.0,
+        "3NKbLiBHzQrAimK7AkP8qAfQpHnezkdsSm8mkt2TzsbjsLN8Axmt"
+    );
+}
@@ -1,3 +1,15 @@
+mod blockchain_length;
+mod canonical_chain;
+mod canonical_threshold;
 mod dangling_branches;
+mod diffs;
+mod generated_dags;
 mod ledger;
+mod ledger_snapshots;
+mod metadata;
+mod observer;
+mod progress;
+mod rejected_blocks;
+mod restore;
+mod rollback;
 mod root_branch;
@@ -0,0 +1,66 @@
+use mina_indexer::{
+    block::{parser::BlockParser, precomputed::PrecomputedBlock, Block, BlockHash},
+    state::{observer::StateObserver, IndexerState},
+    store::IndexerStore,
+};
+use std::{
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+
+/// Records the state hashes it's notified of
+#[derive(Default)]
+struct Recorder {
+    blocks_added: Mutex<Vec<BlockHash>>,
+    best_tips: Mutex<Vec<BlockHash>>,
+}
+
+impl StateObserver for Recorder {
+    fn block_added(&self, block: &PrecomputedBlock) {
+        self.blocks_added
+            .lock()
+            .unwrap()
+            .push(BlockHash(block.state_hash.clone()));
+    }
+
+    fn best_tip_changed(&self, best_tip: &Block) {
+        self.best_tips
+            .lock()
+            .unwrap()
+            .push(best_tip.state_hash.clone());
+    }
+}
+
+#[tokio::test]
+async fn observers_notified() {
+    let log_dir = PathBuf::from("./tests/data/sequential_blocks");
+    let mut block_parser = BlockParser::new_testing(&log_dir).unwrap();
+
+    // root_block = mainnet-105489-3NK4huLvUDiL4XuCUcyrWCKynmvhqfKsx5h2MfBXVVUq2Qwzi5uT.json
+    let root_block = block_parser
+        .get_precomputed_block("3NK4huLvUDiL4XuCUcyrWCKynmvhqfKsx5h2MfBXVVUq2Qwzi5uT")
+        .await
+        .unwrap();
+
+    let mut state =
+        IndexerState::new_testing(&root_block, None, Some(IndexerStore::new_in_memory()), None)
+            .unwrap();
+    let recorder = Arc::new(Recorder::default());
+    state.add_observer(recorder.clone());
+
+    let mut num_added = 0;
+    while let Some(block) = block_parser.next().await.unwrap() {
+        if block.state_hash == root_block.state_hash {
+            continue;
+        }
+        state.add_block(&block).unwrap();
+        num_added += 1;
+    }
+
+    // every block is added once, the best tip changes as the root branch grows
+    assert_eq!(recorder.blocks_added.lock().unwrap().len(), num_added);
+    let best_tips = recorder.best_tips.lock().unwrap();
+    assert!(!best_tips.is_empty());
+    assert_eq!(best_tips.last(), Some(&state.best_tip.state_hash));
+    assert!(best_tips.windows(2).all(|tips| tips[0] != tips[1]));
+}
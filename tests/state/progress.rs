@@ -0,0 +1,43 @@
+use mina_indexer::{block::parser::BlockParser, state::IndexerState, store::IndexerStore};
+use std::{
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+
+/// The progress hook observes the state after each block added during initialization
+#[tokio::test]
+async fn progress_reported_per_block() {
+    let log_dir = PathBuf::from("./tests/data/sequential_blocks");
+    let mut block_parser = BlockParser::new_testing(&log_dir).unwrap();
+
+    // root_block = mainnet-105489-3NK4huLvUDiL4XuCUcyrWCKynmvhqfKsx5h2MfBXVVUq2Qwzi5uT.json
+    let root_block = block_parser
+        .get_precomputed_block("3NK4huLvUDiL4XuCUcyrWCKynmvhqfKsx5h2MfBXVVUq2Qwzi5uT")
+        .await
+        .unwrap();
+
+    let mut state =
+        IndexerState::new_testing(&root_block, None, Some(IndexerStore::new_in_memory()), None)
+            .unwrap();
+    let observed = Arc::new(Mutex::new(vec![]));
+    let hook_observed = observed.clone();
+    state.set_progress_hook(Some(Box::new(move |state| {
+        hook_observed.lock().unwrap().push(state.blocks_processed);
+    })));
+
+    // the blocks above the root's length
+    let mut block_parser = BlockParser::new_with_min_length(&log_dir, 105490).unwrap();
+    let num_blocks = block_parser.total_num_blocks;
+    state.add_blocks(&mut block_parser, 0).await.unwrap();
+
+    {
+        let observed = observed.lock().unwrap();
+        assert_eq!(observed.len() as u32, num_blocks);
+        assert!(observed.windows(2).all(|pair| pair[0] <= pair[1]));
+        assert_eq!(observed.last(), Some(&state.blocks_processed));
+    }
+
+    // the hook is dropped once removed
+    state.set_progress_hook(None);
+    assert_eq!(Arc::strong_count(&observed), 1);
+}
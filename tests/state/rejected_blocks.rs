@@ -0,0 +1,138 @@
+use mina_indexer::{
+    block::{parser::BlockParser, precomputed::ProtocolVersion, BlockHash},
+    network::Network,
+    state::{ExtensionType, IndexerState},
+    store::IndexerStore,
+};
+use std::path::PathBuf;
+
+/// Blocks from another network or protocol version are recorded, not added
+#[tokio::test]
+async fn incompatible_blocks_rejected() {
+    let log_dir = PathBuf::from("./tests/data/sequential_blocks");
+    let mut block_parser = BlockParser::new_testing(&log_dir).unwrap();
+
+    // root_block = mainnet-105489-3NK4huLvUDiL4XuCUcyrWCKynmvhqfKsx5h2MfBXVVUq2Qwzi5uT.json
+    let root_block = block_parser
+        .get_precomputed_block("3NK4huLvUDiL4XuCUcyrWCKynmvhqfKsx5h2MfBXVVUq2Qwzi5uT")
+        .await
+        .unwrap();
+
+    // block = mainnet-105491-3NKizDx3nnhXha2WqHDNUvJk9jW7GsonsEGYs26tCPW2Wow1ZoR3.json
+    let block = block_parser
+        .get_precomputed_block("3NKizDx3nnhXha2WqHDNUvJk9jW7GsonsEGYs26tCPW2Wow1ZoR3")
+        .await
+        .unwrap();
+    let state_hash = BlockHash(block.state_hash.clone());
+
+    let mut state =
+        IndexerState::new_testing(&root_block, None, Some(IndexerStore::new_in_memory()), None)
+            .unwrap();
+
+    // future protocol version
+    let mut future_block = block.clone();
+    future_block.protocol_version = ProtocolVersion {
+        transaction: 3,
+        network: 0,
+        patch: 0,
+    };
+    assert_eq!(
+        state.add_block(&future_block).unwrap(),
+        ExtensionType::BlockNotAdded
+    );
+
+    // different network
+    let mut devnet_block = block.clone();
+    devnet_block.network = Network::Devnet;
+    assert_eq!(
+        state.add_block(&devnet_block).unwrap(),
+        ExtensionType::BlockNotAdded
+    );
+
+    let indexer_store = state.indexer_store.as_ref().unwrap();
+    assert!(indexer_store
+        .get_rejected_block(&state_hash)
+        .unwrap()
+        .is_some());
+    assert_eq!(state.root_branch.len(), 1);
+    assert!(state.dangling_branches.is_empty());
+
+    // the compatible block is still added
+    assert_eq!(state.add_block(&block).unwrap(), ExtensionType::DanglingNew);
+}
+
+/// Blocks whose length doesn't follow their parent's or precede their children's are
+/// recorded, not added
+#[tokio::test]
+async fn impossible_parents_rejected() {
+    let log_dir = PathBuf::from("./tests/data/sequential_blocks");
+    let mut block_parser = BlockParser::new_testing(&log_dir).unwrap();
+
+    // root_block = mainnet-105489-3NK4huLvUDiL4XuCUcyrWCKynmvhqfKsx5h2MfBXVVUq2Qwzi5uT.json
+    let root_block = block_parser
+        .get_precomputed_block("3NK4huLvUDiL4XuCUcyrWCKynmvhqfKsx5h2MfBXVVUq2Qwzi5uT")
+        .await
+        .unwrap();
+
+    // child_block = mainnet-105490-3NKxEA9gztvEGxL4uk4eTncZAxuRmMsB8n81UkeAMevUjMbLHmkC.json
+    let child_block = block_parser
+        .get_precomputed_block("3NKxEA9gztvEGxL4uk4eTncZAxuRmMsB8n81UkeAMevUjMbLHmkC")
+        .await
+        .unwrap();
+
+    // middle_block = mainnet-105491-3NKizDx3nnhXha2WqHDNUvJk9jW7GsonsEGYs26tCPW2Wow1ZoR3.json
+    let middle_block = block_parser
+        .get_precomputed_block("3NKizDx3nnhXha2WqHDNUvJk9jW7GsonsEGYs26tCPW2Wow1ZoR3")
+        .await
+        .unwrap();
+
+    // dangling_block = mainnet-105492-3NKAqzELKDp2BbdKKwdRWEoMNehyMrxJGCoGCyH1t1PyyH7VQMgk.json
+    let dangling_block = block_parser
+        .get_precomputed_block("3NKAqzELKDp2BbdKKwdRWEoMNehyMrxJGCoGCyH1t1PyyH7VQMgk")
+        .await
+        .unwrap();
+
+    let mut state =
+        IndexerState::new_testing(&root_block, None, Some(IndexerStore::new_in_memory()), None)
+            .unwrap();
+    let indexer_store = state.indexer_store.clone().unwrap();
+
+    // claims to be two above its parent, the root
+    let mut too_long = child_block.clone();
+    too_long.blockchain_length = Some(105491);
+    assert_eq!(
+        state.add_block(&too_long).unwrap(),
+        ExtensionType::BlockNotAdded
+    );
+    assert!(indexer_store
+        .get_rejected_block(&BlockHash(child_block.state_hash.clone()))
+        .unwrap()
+        .unwrap()
+        .contains("its parent"));
+    assert_eq!(state.root_branch.len(), 1);
+
+    // claims to be as long as its dangling child
+    assert_eq!(
+        state.add_block(&dangling_block).unwrap(),
+        ExtensionType::DanglingNew
+    );
+    let mut too_short = middle_block.clone();
+    too_short.blockchain_length = Some(105492);
+    assert_eq!(
+        state.add_block(&too_short).unwrap(),
+        ExtensionType::BlockNotAdded
+    );
+    assert!(indexer_store
+        .get_rejected_block(&BlockHash(middle_block.state_hash.clone()))
+        .unwrap()
+        .unwrap()
+        .contains("its child"));
+    assert_eq!(state.dangling_branches.len(), 1);
+    assert_eq!(state.dangling_branches[0].len(), 1);
+
+    // the consistent blocks are still added
+    state.add_block(&child_block).unwrap();
+    state.add_block(&middle_block).unwrap();
+    assert!(state.dangling_branches.is_empty());
+    assert_eq!(state.root_branch.len(), 4);
+}
@@ -0,0 +1,148 @@
+use mina_indexer::{
+    block::{parser::BlockParser, store::BlockStore},
+    state::{branch::Branch, ledger::Ledger, IndexerMode, IndexerState},
+    store::IndexerStore,
+    CANONICAL_UPDATE_THRESHOLD, PRUNE_INTERVAL_DEFAULT,
+};
+use std::path::PathBuf;
+
+/// The tips and blocks processed of a restored state match the state which recorded them
+#[tokio::test]
+async fn restore_from_db() {
+    let log_dir = PathBuf::from("./tests/data/sequential_blocks");
+    let mut block_parser = BlockParser::new_testing(&log_dir).unwrap();
+
+    // root_block = mainnet-105489-3NK4huLvUDiL4XuCUcyrWCKynmvhqfKsx5h2MfBXVVUq2Qwzi5uT.json
+    let root_block = block_parser
+        .get_precomputed_block("3NK4huLvUDiL4XuCUcyrWCKynmvhqfKsx5h2MfBXVVUq2Qwzi5uT")
+        .await
+        .unwrap();
+
+    let mut state = IndexerState::new_testing(
+        &root_block,
+        Some(Ledger::new()),
+        Some(IndexerStore::new_in_memory()),
+        None,
+    )
+    .unwrap();
+    let indexer_store = state.indexer_store.clone().unwrap();
+    indexer_store.add_block(&root_block).unwrap();
+
+    // nothing to restore from yet
+    assert!(IndexerState::new_from_db(
+        IndexerMode::Test,
+        root_block.network.clone(),
+        root_block.protocol_version,
+        indexer_store.clone(),
+        state.transition_frontier_length,
+        PRUNE_INTERVAL_DEFAULT,
+        CANONICAL_UPDATE_THRESHOLD,
+        root_block.network.canonical_threshold(),
+        false,
+    )
+    .is_err());
+
+    while let Some(block) = block_parser.next().await.unwrap() {
+        if block.state_hash != root_block.state_hash {
+            state.add_block(&block).unwrap();
+        }
+    }
+
+    let restored = IndexerState::new_from_db(
+        IndexerMode::Test,
+        root_block.network.clone(),
+        root_block.protocol_version,
+        indexer_store,
+        state.transition_frontier_length,
+        PRUNE_INTERVAL_DEFAULT,
+        CANONICAL_UPDATE_THRESHOLD,
+        root_block.network.canonical_threshold(),
+        false,
+    )
+    .unwrap();
+    assert_eq!(
+        restored.canonical_tip.state_hash,
+        state.canonical_tip.state_hash
+    );
+    assert_eq!(restored.best_tip.state_hash, state.best_tip.state_hash);
+    assert_eq!(restored.blocks_processed, state.blocks_processed);
+    assert_eq!(
+        restored.best_tip_block().blockchain_length,
+        state.best_tip_block().blockchain_length
+    );
+}
+
+/// A restored state picks up the persisted witness tree and the blocks added since
+#[tokio::test]
+async fn restore_persisted_witness_tree() {
+    let log_dir = PathBuf::from("./tests/data/sequential_blocks");
+    let mut block_parser = BlockParser::new_testing(&log_dir).unwrap();
+
+    // root_block = mainnet-105489-3NK4huLvUDiL4XuCUcyrWCKynmvhqfKsx5h2MfBXVVUq2Qwzi5uT.json
+    let root_block = block_parser
+        .get_precomputed_block("3NK4huLvUDiL4XuCUcyrWCKynmvhqfKsx5h2MfBXVVUq2Qwzi5uT")
+        .await
+        .unwrap();
+
+    let mut state = IndexerState::new_testing(
+        &root_block,
+        Some(Ledger::new()),
+        Some(IndexerStore::new_in_memory()),
+        None,
+    )
+    .unwrap();
+    state.witness_tree_interval = 5;
+    let indexer_store = state.indexer_store.clone().unwrap();
+    indexer_store.add_block(&root_block).unwrap();
+
+    while let Some(block) = block_parser.next().await.unwrap() {
+        if block.state_hash != root_block.state_hash {
+            state.add_block(&block).unwrap();
+        }
+    }
+
+    // branches round trip through their blocks
+    let blocks = state.root_branch.blocks();
+    assert_eq!(blocks[0], *state.root_branch.root_block().unwrap());
+    assert_eq!(Branch::from_blocks(&blocks).unwrap().blocks(), blocks);
+    assert!(Branch::from_blocks(&blocks[1..]).is_err());
+
+    let restored = IndexerState::new_from_db(
+        IndexerMode::Test,
+        root_block.network.clone(),
+        root_block.protocol_version,
+        indexer_store.clone(),
+        state.transition_frontier_length,
+        PRUNE_INTERVAL_DEFAULT,
+        CANONICAL_UPDATE_THRESHOLD,
+        root_block.network.canonical_threshold(),
+        false,
+    )
+    .unwrap();
+    assert_eq!(restored.blocks_processed, state.blocks_processed);
+    assert_eq!(
+        restored.best_tip_block().blockchain_length,
+        state.best_tip_block().blockchain_length
+    );
+
+    // the latest tree is persisted on demand, e.g. at shutdown
+    state.persist_witness_tree().unwrap();
+    let restored = IndexerState::new_from_db(
+        IndexerMode::Test,
+        root_block.network.clone(),
+        root_block.protocol_version,
+        indexer_store,
+        state.transition_frontier_length,
+        PRUNE_INTERVAL_DEFAULT,
+        CANONICAL_UPDATE_THRESHOLD,
+        root_block.network.canonical_threshold(),
+        false,
+    )
+    .unwrap();
+    assert_eq!(
+        restored.canonical_tip.state_hash,
+        state.canonical_tip.state_hash
+    );
+    assert_eq!(restored.root_branch.blocks(), state.root_branch.blocks());
+    assert_eq!(restored.diffs_map.len(), state.diffs_map.len());
+}
@@ -0,0 +1,113 @@
+use mina_indexer::{
+    block::{parser::BlockParser, store::BlockStore, BlockHash},
+    state::{
+        canonicity::store::CanonicityStore,
+        ledger::{
+            balance_history::{store::BalanceStore, BalanceHistoryEntry},
+            snark_work::SnarkWorkSummary,
+            store::LedgerStore,
+            Ledger,
+        },
+        Canonicity, IndexerState,
+    },
+    store::IndexerStore,
+};
+use std::path::PathBuf;
+
+/// A wrongly canonical fork is orphaned and replaced by the new canonical chain
+#[tokio::test]
+async fn rolls_back_wrong_fork() {
+    let log_dir = PathBuf::from("./tests/data/sequential_blocks");
+    let mut block_parser = BlockParser::new_testing(&log_dir).unwrap();
+
+    let root_block = block_parser.next().await.unwrap().unwrap();
+    let mut state = IndexerState::new_testing(
+        &root_block,
+        Some(Ledger::new()),
+        Some(IndexerStore::new_in_memory()),
+        None,
+    )
+    .unwrap();
+    while let Some(precomputed_block) = block_parser.next().await.unwrap() {
+        state.add_block(&precomputed_block).unwrap();
+    }
+    let indexer_store = state.indexer_store.clone().unwrap();
+
+    // the best chain's blocks of lengths 105490 to 105493 are canonical
+    let mut best_chain = state.root_branch.longest_chain();
+    best_chain.reverse();
+    for state_hash in &best_chain[1..=4] {
+        indexer_store
+            .set_canonicity(state_hash, Canonicity::Canonical)
+            .unwrap();
+    }
+
+    // but the short fork was wrongly made canonical above them
+    // mainnet-105494-3NKXsaznJ6WdyA4PHfXxn25RzVanzQsNMZrxjidbhoBug8R4LZDy.json
+    // mainnet-105495-3NL4zEKGtSokPMy29pGv7tm8uJt8GitM9JqrRg6Lkf3tRdnwrjpF.json
+    let wrong_fork = [
+        BlockHash("3NKXsaznJ6WdyA4PHfXxn25RzVanzQsNMZrxjidbhoBug8R4LZDy".to_string()),
+        BlockHash("3NL4zEKGtSokPMy29pGv7tm8uJt8GitM9JqrRg6Lkf3tRdnwrjpF".to_string()),
+    ];
+    for state_hash in &wrong_fork {
+        indexer_store
+            .set_canonicity(state_hash, Canonicity::Canonical)
+            .unwrap();
+    }
+
+    // mainnet-105494-3NKqd3XGqkLmZVmPC3iG6AnrwQoZdBKdmYTzEJT3vwwnn2H1Z4ww.json
+    // mainnet-105495-3NKmDYoFs5MRNE4PoGMkMT5udM4JrnB5NJYFLJcDUUob363aj5e9.json
+    let new_chain = [
+        BlockHash("3NKqd3XGqkLmZVmPC3iG6AnrwQoZdBKdmYTzEJT3vwwnn2H1Z4ww".to_string()),
+        BlockHash("3NKmDYoFs5MRNE4PoGMkMT5udM4JrnB5NJYFLJcDUUob363aj5e9".to_string()),
+    ];
+    assert_eq!(state.rollback_canonical(&new_chain[1]).unwrap(), 2);
+
+    for state_hash in &wrong_fork {
+        assert_eq!(
+            indexer_store.get_canonicity(state_hash).unwrap(),
+            Some(Canonicity::Orphaned)
+        );
+    }
+    for (length, state_hash) in [105494, 105495].into_iter().zip(&new_chain) {
+        assert_eq!(
+            indexer_store.get_canonicity(state_hash).unwrap(),
+            Some(Canonicity::Canonical)
+        );
+        assert_eq!(
+            indexer_store.get_canonical_hash_at_height(length).unwrap(),
+            Some(state_hash.clone())
+        );
+    }
+
+    // the wrong fork's SNARK work and balances are no longer canonical
+    for state_hash in &wrong_fork {
+        let block = indexer_store.get_block(state_hash).unwrap().unwrap();
+        for work in SnarkWorkSummary::from_precomputed_block(&block) {
+            assert!(indexer_store
+                .get_canonical_snark_work_by_prover(&work.prover)
+                .unwrap()
+                .snark_work
+                .iter()
+                .all(|work| !wrong_fork.contains(&work.state_hash)));
+        }
+        for (pk, entry) in BalanceHistoryEntry::from_precomputed_block(&block) {
+            let balance = indexer_store
+                .get_balance_at_height(&pk, entry.blockchain_length)
+                .unwrap();
+            assert!(balance.map_or(true, |balance| !wrong_fork.contains(&balance.state_hash)));
+        }
+    }
+
+    // the witness tree is rebuilt from the new canonical tip
+    assert_eq!(state.canonical_tip.state_hash, new_chain[1]);
+    assert_eq!(
+        state.root_branch.root_block().unwrap().state_hash,
+        new_chain[1]
+    );
+    assert_eq!(state.best_tip_block().blockchain_length, Some(105498));
+    assert!(indexer_store.get_ledger(&new_chain[1]).unwrap().is_some());
+
+    // already canonical
+    assert_eq!(state.rollback_canonical(&new_chain[0]).unwrap(), 0);
+}
@@ -0,0 +1,61 @@
+use mina_indexer::block::{parser::BlockParser, Block, BlockHash};
+use std::path::PathBuf;
+
+fn block(state_hash: &str, height: u32, last_vrf_output_digest: [u8; 32]) -> Block {
+    Block {
+        parent_hash: BlockHash("3NKeMoncuHab5ScarV5ViyF16cJPT4taWNSaTLS64Dp67wuXigPZ".into()),
+        state_hash: BlockHash(state_hash.into()),
+        height,
+        blockchain_length: Some(height + 1),
+        global_slot_since_genesis: height,
+        last_vrf_output_digest,
+    }
+}
+
+/// Longer chains win, then the greater last VRF output digest, then the greater state hash
+#[test]
+fn short_range_fork_choice() {
+    let longer = block("3NKA", 2, [0; 32]);
+    let greater_vrf = block("3NKB", 1, [1; 32]);
+    let lesser_vrf = block("3NKC", 1, [0; 32]);
+    let greater_hash = block("3NKD", 1, [0; 32]);
+
+    assert!(longer > greater_vrf);
+    assert!(greater_vrf > lesser_vrf);
+    assert!(greater_vrf > greater_hash);
+    assert!(greater_hash > lesser_vrf);
+    assert_eq!(
+        lesser_vrf.cmp(&lesser_vrf.clone()),
+        std::cmp::Ordering::Equal
+    );
+}
+
+#[tokio::test]
+async fn vrf_output_digests() {
+    let log_dir = PathBuf::from("./tests/data/sequential_blocks");
+    let mut block_parser = BlockParser::new_testing(&log_dir).unwrap();
+
+    // forks at length 105489, the network extended 3NK4hu...
+    // mainnet-105490-3NKxEA9gztvEGxL4uk4eTncZAxuRmMsB8n81UkeAMevUjMbLHmkC.json
+    let mut forks = vec![];
+    for state_hash in [
+        "3NK4huLvUDiL4XuCUcyrWCKynmvhqfKsx5h2MfBXVVUq2Qwzi5uT",
+        "3NLFXtdzaFW2WX6KgrxMjL4enE4pCa9hAsVUPm47PT6337SXgBGh",
+        "3NLUfaHDcyt9KsYxi1xsSdYE369GAduLxVgRUDE7RuFgSXQBphDK",
+    ] {
+        let block = block_parser
+            .get_precomputed_block(state_hash)
+            .await
+            .unwrap();
+        forks.push(Block::from_precomputed(&block, 1));
+    }
+
+    // the network's choice has the greatest last VRF output digest, not the greatest state hash
+    let best = forks.iter().max().unwrap();
+    assert_eq!(
+        best.state_hash.0,
+        "3NK4huLvUDiL4XuCUcyrWCKynmvhqfKsx5h2MfBXVVUq2Qwzi5uT"
+    );
+    assert_eq!(best.last_vrf_output_digest[..4], [0x9f, 0x0e, 0x20, 0x22]);
+    assert!(forks[1] > forks[2]);
+}
@@ -1,3 +1,4 @@
+mod fork_choice;
 mod prune;
 mod simple_improper;
 mod simple_proper;
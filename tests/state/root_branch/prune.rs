@@ -95,26 +95,28 @@ async fn transition_frontier() {
     // create the tree and add blocks
     let mut branch = Branch::new(&root_block).unwrap();
 
-    branch.simple_extension(&fork_block).unwrap();
-    branch.simple_extension(&main_1_block).unwrap();
-    branch.simple_extension(&main_2_block).unwrap();
-    branch.simple_extension(&main_3_block).unwrap();
-    branch.simple_extension(&main_4_block).unwrap();
-    let (best_tip_id, _) = branch.simple_extension(&main_5_block).unwrap();
+    branch.simple_extension(&fork_block).unwrap().unwrap();
+    branch.simple_extension(&main_1_block).unwrap().unwrap();
+    branch.simple_extension(&main_2_block).unwrap().unwrap();
+    branch.simple_extension(&main_3_block).unwrap().unwrap();
+    branch.simple_extension(&main_4_block).unwrap().unwrap();
+    let (best_tip_id, _) = branch.simple_extension(&main_5_block).unwrap().unwrap();
 
     println!("=== Before prune ===");
     println!("{branch:?}");
 
-    branch.prune_transition_frontier(
-        1,
-        &branch.branches.get(&best_tip_id).unwrap().data().clone(),
-    );
+    branch
+        .prune_transition_frontier(
+            1,
+            &branch.branches.get(&best_tip_id).unwrap().data().clone(),
+        )
+        .unwrap();
 
     println!("=== After prune ===");
     println!("{branch:?}");
 
     assert_eq!(
         Block::from_precomputed(&main_4_block, 0),
-        branch.root_block().clone()
+        branch.root_block().unwrap().clone()
     );
 }
@@ -28,7 +28,7 @@ async fn extension() {
     let root_tree = Branch::new(&root_block).unwrap();
 
     // before extension quantities
-    let before_root = root_tree.root_block();
+    let before_root = root_tree.root_block().unwrap();
     let before_branches = root_tree.branches.clone();
     let before_root_id = before_branches.root_node_id().unwrap();
     let before_root_leaf = before_branches.get(&before_root_id).unwrap().data();
@@ -49,10 +49,11 @@ async fn extension() {
     );
     tree2
         .simple_extension(&child_block)
+        .unwrap()
         .expect("new leaf should be inserted");
 
     // after extension quantities
-    let after_root = tree2.root_block();
+    let after_root = tree2.root_block().unwrap();
     let after_branches = tree2.branches.clone();
     let after_root_id = after_branches.root_node_id().unwrap();
     let after_root_leaf = after_branches.get(&after_root_id).unwrap().data();
@@ -0,0 +1,156 @@
+use blake2::{digest::consts::U32, Blake2b, Digest};
+use mina_indexer::block::{precomputed::PrecomputedBlock, BlockHash};
+use mina_serialization_types::v1::HashV1;
+use std::collections::HashMap;
+
+/// Deterministic generator of precomputed block DAGs for witness tree tests
+///
+/// Every block is a copy of a template block with a synthetic state hash, parent hash,
+/// length, and global slot. The same seed always generates the same DAG
+pub struct BlockDag {
+    template: PrecomputedBlock,
+    seed: u64,
+    rng: Rng,
+    /// Generated blocks, parents before children, the root first
+    blocks: Vec<PrecomputedBlock>,
+    /// Raw hashes of the generated blocks, to link children to them
+    hashes: HashMap<BlockHash, [u8; 32]>,
+}
+
+impl BlockDag {
+    /// Starts a DAG with a root of the template's length and global slot
+    pub fn new(template: &PrecomputedBlock, seed: u64) -> Self {
+        let mut dag = Self {
+            template: template.clone(),
+            seed,
+            rng: Rng::new(seed),
+            blocks: vec![],
+            hashes: HashMap::new(),
+        };
+        dag.push(None);
+        dag
+    }
+
+    pub fn root(&self) -> &PrecomputedBlock {
+        &self.blocks[0]
+    }
+
+    pub fn root_hash(&self) -> BlockHash {
+        BlockHash(self.root().state_hash.clone())
+    }
+
+    pub fn block(&self, state_hash: &BlockHash) -> Option<&PrecomputedBlock> {
+        self.blocks
+            .iter()
+            .find(|block| block.state_hash == state_hash.0)
+    }
+
+    /// The generated blocks except the root, parents before children
+    pub fn blocks(&self) -> Vec<PrecomputedBlock> {
+        self.blocks[1..].to_vec()
+    }
+
+    /// The generated blocks except the root, in a seeded random order
+    pub fn shuffled(&mut self) -> Vec<PrecomputedBlock> {
+        let mut blocks = self.blocks();
+        for i in (1..blocks.len()).rev() {
+            blocks.swap(i, self.rng.below(i as u64 + 1) as usize);
+        }
+        blocks
+    }
+
+    /// Adds a child of `parent`
+    pub fn extend(&mut self, parent: &BlockHash) -> BlockHash {
+        self.push(Some(parent))
+    }
+
+    /// Adds a chain of `length` blocks on top of `parent`, returns their hashes lowest first
+    pub fn chain(&mut self, parent: &BlockHash, length: usize) -> Vec<BlockHash> {
+        let mut chain: Vec<BlockHash> = vec![];
+        for _ in 0..length {
+            let parent = chain.last().unwrap_or(parent).clone();
+            chain.push(self.extend(&parent));
+        }
+        chain
+    }
+
+    /// Adds `num_blocks` blocks, each one extends the previous block, or forks off of a
+    /// random block with the given percent chance
+    pub fn tree(&mut self, num_blocks: usize, fork_percent: u64) -> Vec<BlockHash> {
+        let mut state_hashes = vec![];
+        for _ in 0..num_blocks {
+            let index = if self.rng.below(100) < fork_percent {
+                self.rng.below(self.blocks.len() as u64) as usize
+            } else {
+                self.blocks.len() - 1
+            };
+            let parent = BlockHash(self.blocks[index].state_hash.clone());
+            state_hashes.push(self.extend(&parent));
+        }
+        state_hashes
+    }
+
+    fn push(&mut self, parent: Option<&BlockHash>) -> BlockHash {
+        let mut digest = Blake2b::<U32>::new();
+        digest.update(self.seed.to_be_bytes());
+        digest.update((self.blocks.len() as u64).to_be_bytes());
+        let hash: [u8; 32] = digest.finalize().into();
+        let state_hash = BlockHash::from_hashv1(HashV1::from(hash));
+
+        let mut block = self.template.clone();
+        let (blockchain_length, global_slot) = match parent {
+            None => (
+                self.template.blockchain_length.unwrap_or_default(),
+                self.template.global_slot_since_genesis(),
+            ),
+            Some(parent_hash) => {
+                let parent = self.block(parent_hash).expect("parent is generated");
+                let parent_length = parent.blockchain_length.unwrap_or_default();
+                let parent_slot = parent.global_slot_since_genesis();
+                block.protocol_state.previous_state_hash = HashV1::from(self.hashes[parent_hash]);
+                (
+                    parent_length + 1,
+                    parent_slot + 1 + self.rng.below(3) as u32,
+                )
+            }
+        };
+        block.state_hash = state_hash.0.clone();
+        block.blockchain_length = Some(blockchain_length);
+        block
+            .protocol_state
+            .body
+            .t
+            .t
+            .consensus_state
+            .t
+            .t
+            .global_slot_since_genesis
+            .t
+            .t = global_slot;
+
+        self.hashes.insert(state_hash.clone(), hash);
+        self.blocks.push(block);
+        state_hash
+    }
+}
+
+/// xorshift64*, enough for shuffling and picking fork points
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        // the state must be nonzero
+        Self(seed ^ 0x9e37_79b9_7f4a_7c15)
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    fn below(&mut self, bound: u64) -> u64 {
+        self.next() % bound
+    }
+}